@@ -11,7 +11,7 @@ use strum::IntoEnumIterator;
 use crate::{
     aggregates::Bounded,
     core::ray::Ray,
-    math::{axis::Axis3, Number, Point3, Transform, Transformable, Vec3},
+    math::{axis::Axis3, Normed, Number, Point3, Transform, Transformable, Vec3},
     point3,
 };
 
@@ -36,6 +36,15 @@ impl<T: Number> Aabb<T> {
         }
     }
 
+    /// Center and radius of a sphere containing the box. Empty box has a zero sphere at the origin
+    pub fn bounding_sphere(&self) -> (Point3<T>, T) {
+        if self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z {
+            return (point3!(T::zero(), T::zero(), T::zero()), T::zero());
+        }
+        let center = self.center();
+        (center, (self.max - center).len())
+    }
+
     pub fn from_points(p1: Point3<T>, p2: Point3<T>) -> Self {
         Aabb::new(Point3::min_coords(p1, p2), Point3::max_coords(p1, p2))
     }
@@ -78,6 +87,32 @@ impl<T: Number> Aabb<T> {
         true
     }
 
+    /// Parametric range of `origin + t * dir` inside the box, clipped to [0; t_max]. `dir` doesn't have to be
+    /// normalized, which allows intersecting in scaled coordinates without changing `t`
+    pub fn intersect_range(&self, origin: Point3<T>, dir: Vec3<T>, t_max: T) -> Option<(T, T)> {
+        let mut t0 = T::zero();
+        let mut t1 = t_max;
+        for axis in Axis3::iter() {
+            let inv_dir = dir[axis].recip();
+            let mut t_near = (self.min[axis] - origin[axis]) * inv_dir;
+            let mut t_far = (self.max[axis] - origin[axis]) * inv_dir;
+            if t_near > t_far {
+                swap(&mut t_near, &mut t_far);
+            }
+            // NaN comes from a ray parallel to the axis starting on a slab boundary, keep the range then
+            if t_near > t0 {
+                t0 = t_near;
+            }
+            if t_far < t1 {
+                t1 = t_far;
+            }
+            if t0 > t1 {
+                return None;
+            }
+        }
+        Some((t0, t1))
+    }
+
     pub fn hit_fast(&self, ray: &Ray<T>, inv_dir: Vec3<T>, inv_bounds: Vec3<Sign>, ray_t_max: T) -> bool {
         let mut t_min = T::neg_infinity();
         let mut t_max = T::infinity();
@@ -218,7 +253,7 @@ mod tests {
     use approx::assert_abs_diff_eq;
 
     use super::*;
-    use crate::{ray, unit3};
+    use crate::{ray, unit3, vec3};
 
     #[test]
     fn test_aabb() {
@@ -233,6 +268,14 @@ mod tests {
 
         let ray = ray!(point3!(0., 1., 0.), unit3!(1., 0., 0.));
         assert!(bbox.hit(&ray, 10.));
+
+        let range = bbox.intersect_range(point3!(0., 0., 0.), vec3!(2., 0., 0.), 10.);
+        assert_eq!(range, Some((0.5, 1.)));
+        assert_eq!(
+            bbox.intersect_range(point3!(0., 0., 0.), vec3!(2., 0., 0.), 0.75),
+            Some((0.5, 0.75))
+        );
+        assert_eq!(bbox.intersect_range(point3!(0., 0., 0.), vec3!(-1., 0., 0.), 10.), None);
     }
 
     #[test]
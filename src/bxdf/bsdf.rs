@@ -71,12 +71,15 @@ impl<'a> BSDF<'a> {
             if sample.pdf == 0.0 || sample.incoming.z == 0.0 || sample.spectrum.is_zero() {
                 None
             } else {
-                Some(BSDFSample::new(
-                    sample.spectrum,
-                    self.shading_to_render(sample.incoming),
-                    sample.pdf,
-                    self.bxdf.flags(),
-                ))
+                Some(BSDFSample {
+                    spectrum: sample.spectrum,
+                    incoming: self.shading_to_render(sample.incoming),
+                    pdf: sample.pdf,
+                    eta: sample.eta,
+                    // Flags of the sampled lobe rather than all of the BxDF's, integrators tell reflection and
+                    // transmission apart by them
+                    flags: sample.flags,
+                })
             }
         } else {
             None
@@ -9,44 +9,90 @@ use crate::{
     bxdf::{
         bsdf::BSDFSample,
         bxdf::{BxDFFlags, Shading},
-        utils::abs_cos_theta,
+        microfacet::TrowbridgeReitzDistribution,
+        utils::{abs_cos_theta, reflect, same_hemisphere},
         BxDF,
     },
+    math::{dot, Normed},
     vec3, Point2f, SampledSpectrum, Vec3f,
 };
 
 #[derive(Debug, Copy, Clone)]
 #[derive(new)]
 pub struct ConductorBxDF {
+    distribution: TrowbridgeReitzDistribution,
     eta: SampledSpectrum,
     k: SampledSpectrum,
 }
 
 impl BxDF for ConductorBxDF {
-    fn flags(&self) -> BxDFFlags { BxDFFlags::SpecularReflection }
+    fn flags(&self) -> BxDFFlags {
+        if self.distribution.effectively_smooth() {
+            BxDFFlags::SpecularReflection
+        } else {
+            BxDFFlags::GlossyReflection
+        }
+    }
 
     fn eval(&self, incoming: Shading<Vec3f>, outgoing: Shading<Vec3f>) -> SampledSpectrum {
-        // if same_hemisphere(incoming, outgoing) {
-        //     // TODO: microfacet
-        //     colors::BLACK
-        // } else {
-        //     colors::BLACK
-        // }
-        SampledSpectrum::zero()
+        if !same_hemisphere(incoming, outgoing) || self.distribution.effectively_smooth() {
+            return SampledSpectrum::zero();
+        }
+        let cos_in = abs_cos_theta(incoming);
+        let cos_out = abs_cos_theta(outgoing);
+        if cos_in == 0. || cos_out == 0. {
+            return SampledSpectrum::zero();
+        }
+        let Some(wm) = half_vector(incoming, outgoing) else {
+            return SampledSpectrum::zero();
+        };
+
+        // Torrance-Sparrow model
+        let fresnel = fresnel_complex_im_re(dot(&*outgoing, &*wm).abs(), self.eta, self.k);
+        fresnel * self.distribution.d(wm) * self.distribution.g(outgoing, incoming) / (4. * cos_in * cos_out)
     }
 
     fn sample(&self, rnd_p: Point2f, rnd_c: f32, outgoing: Shading<Vec3f>) -> Option<BSDFSample<Shading<Vec3f>>> {
-        // TODO: flags, microfacet
-        let incoming: Shading<Vec3f> = vec3!(-outgoing.x, -outgoing.y, outgoing.z).into();
-        let cos_in = abs_cos_theta(incoming);
-        let mut spectrum = fresnel_complex_im_re(cos_in, self.eta, self.k) / cos_in;
-        Some(BSDFSample::new(spectrum, incoming, 1., self.flags()))
+        if self.distribution.effectively_smooth() {
+            let incoming: Shading<Vec3f> = vec3!(-outgoing.x, -outgoing.y, outgoing.z).into();
+            let cos_in = abs_cos_theta(incoming);
+            let spectrum = fresnel_complex_im_re(cos_in, self.eta, self.k) / cos_in;
+            return Some(BSDFSample::new(spectrum, incoming, 1., self.flags()));
+        }
+
+        if outgoing.z == 0. {
+            return None;
+        }
+        let wm = self.distribution.sample_wm(outgoing, rnd_p);
+        let incoming = reflect(outgoing, wm);
+        if !same_hemisphere(outgoing, incoming) {
+            return None;
+        }
+        // Change of variables from the microfacet normal to the reflected direction
+        let pdf = self.distribution.pdf(outgoing, wm) / (4. * dot(&*outgoing, &*wm).abs());
+        let spectrum = self.eval(incoming, outgoing);
+        Some(BSDFSample::new(spectrum, incoming, pdf, self.flags()))
     }
 
     fn pdf(&self, incoming: Shading<Vec3f>, outgoing: Shading<Vec3f>) -> f32 {
-        // TODO: microfacet
-        0.
+        if !same_hemisphere(incoming, outgoing) || self.distribution.effectively_smooth() {
+            return 0.;
+        }
+        let Some(wm) = half_vector(incoming, outgoing) else {
+            return 0.;
+        };
+        self.distribution.pdf(outgoing, wm) / (4. * dot(&*outgoing, &*wm).abs())
+    }
+}
+
+/// Microfacet normal reflecting `outgoing` into `incoming`, oriented towards the upper hemisphere
+fn half_vector(incoming: Shading<Vec3f>, outgoing: Shading<Vec3f>) -> Option<Shading<Vec3f>> {
+    let wm = *incoming + *outgoing;
+    if wm.len_squared() == 0. {
+        return None;
     }
+    let wm = *wm.to_unit();
+    Some(Shading::from(if wm.z < 0. { -wm } else { wm }))
 }
 
 /// https://graphics.stanford.edu/courses/cs148-10-summer/docs/2006--degreve--reflection_refraction.pdf
@@ -59,7 +105,7 @@ fn fresnel_complex(mut cos_theta_in: f32, eta: Complex32) -> f32 {
     let reflection_parallel = (eta * cos_theta_in - cos_theta_tr) / (eta * cos_theta_in + cos_theta_tr);
     let reflection_perpend = (cos_theta_in - eta * cos_theta_tr) / (cos_theta_in + eta * cos_theta_tr);
 
-    (reflection_parallel.norm() + reflection_perpend.norm()) / 2.
+    (reflection_parallel.norm_sqr() + reflection_perpend.norm_sqr()) / 2.
 }
 
 fn fresnel_complex_im_re(mut cos_theta_in: f32, eta: SampledSpectrum, k: SampledSpectrum) -> SampledSpectrum {
@@ -68,3 +114,56 @@ fn fresnel_complex_im_re(mut cos_theta_in: f32, eta: SampledSpectrum, k: Sampled
     //     fresnel_complex(cos_theta_in, Complex32::new(eta_i, k_i))
     // })
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::point2;
+
+    #[test]
+    fn test_fresnel_normal_incidence() {
+        for (eta, k) in [(1.5, 0.), (0.2, 3.9), (1.2, 2.1)] {
+            let expected = ((eta - 1.) * (eta - 1.) + k * k) / ((eta + 1.) * (eta + 1.) + k * k);
+            assert_relative_eq!(
+                fresnel_complex(1., Complex32::new(eta, k)),
+                expected,
+                max_relative = 1e-5
+            );
+        }
+        // Mapping of reflectance used by `Metal`
+        let reflectance = 0.7f32;
+        let k = 2. * reflectance.sqrt() / (1. - reflectance).sqrt();
+        assert_relative_eq!(
+            fresnel_complex(1., Complex32::new(1., k)),
+            reflectance,
+            max_relative = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_rough_sample_consistency() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let distribution = TrowbridgeReitzDistribution::new(0.2, 0.4);
+        let bxdf = ConductorBxDF::new(distribution, SampledSpectrum::from(0.2), SampledSpectrum::from(3.9));
+        let outgoing = Shading::from(*vec3!(0.3, -0.4, 0.5).to_unit());
+        for _ in 0..10_000 {
+            let Some(sample) = bxdf.sample(point2!(rng.gen(), rng.gen()), rng.gen(), outgoing) else {
+                continue;
+            };
+            assert!(sample.flags.contains(BxDFFlags::GlossyReflection));
+            assert_relative_eq!(sample.pdf, bxdf.pdf(sample.incoming, outgoing), max_relative = 1e-3);
+            assert_relative_eq!(
+                sample.spectrum[0],
+                bxdf.eval(sample.incoming, outgoing)[0],
+                max_relative = 1e-3
+            );
+        }
+        // Directions in the other hemisphere are never reflected into
+        let below = Shading::from(vec3!(0., 0., -1.));
+        assert_eq!(bxdf.pdf(below, outgoing), 0.);
+        assert!(bxdf.eval(below, outgoing).is_zero());
+    }
+}
@@ -9,10 +9,11 @@ use crate::{
     bxdf::{
         bsdf::BSDFSample,
         bxdf::{BxDFFlags, Shading},
-        utils::{abs_cos_theta, cos_theta},
+        microfacet::TrowbridgeReitzDistribution,
+        utils::{abs_cos_theta, cos_theta, reflect, same_hemisphere},
         BxDF,
     },
-    math::{utils::refract, Unit},
+    math::{dot, utils::refract, Normed, Unit},
     unit_normal3, unit_normal3_unchecked, vec3, Point2f, SampledSpectrum, Vec3f,
 };
 
@@ -22,18 +23,46 @@ use crate::{
 #[derive(new)]
 pub struct DielectricBxDF {
     eta: f32,
+    distribution: TrowbridgeReitzDistribution,
 }
 
 impl BxDF for DielectricBxDF {
-    fn flags(&self) -> BxDFFlags { BxDFFlags::SpecularTransmission | BxDFFlags::SpecularReflection }
+    fn flags(&self) -> BxDFFlags {
+        if self.distribution.effectively_smooth() {
+            BxDFFlags::SpecularTransmission | BxDFFlags::SpecularReflection
+        } else {
+            BxDFFlags::GlossyTransmission | BxDFFlags::GlossyReflection
+        }
+    }
 
     fn eval(&self, incoming: Shading<Vec3f>, outgoing: Shading<Vec3f>) -> SampledSpectrum {
-        // TODO: microfacet
-        SampledSpectrum::zero()
+        if self.eta == 1. || self.distribution.effectively_smooth() {
+            return SampledSpectrum::zero();
+        }
+        let Some((wm, rel_eta)) = self.half_vector(incoming, outgoing) else {
+            return SampledSpectrum::zero();
+        };
+
+        let fresnel = fresnel_dielectric(dot(&*outgoing, &*wm), self.eta);
+        let d = self.distribution.d(wm);
+        let g = self.distribution.g(outgoing, incoming);
+        let value = if same_hemisphere(incoming, outgoing) {
+            d * g * fresnel / (4. * cos_theta(incoming) * cos_theta(outgoing)).abs()
+        } else {
+            // Same convention as the specular case, see the TODO about transmission to a different medium
+            let denom = (dot(&*incoming, &*wm) + dot(&*outgoing, &*wm) / rel_eta).powi(2)
+                * cos_theta(incoming)
+                * cos_theta(outgoing);
+            d * (1. - fresnel) * g * (dot(&*incoming, &*wm) * dot(&*outgoing, &*wm) / denom).abs()
+        };
+        SampledSpectrum::from(value)
     }
 
     fn sample(&self, rnd_p: Point2f, rnd_c: f32, outgoing: Shading<Vec3f>) -> Option<BSDFSample<Shading<Vec3f>>> {
-        // TODO: flags, microfacet
+        if !self.distribution.effectively_smooth() {
+            return self.sample_rough(rnd_p, rnd_c, outgoing);
+        }
+        // TODO: flags
         // TODO: or use Schlick's approximation
         let prob_reflected = fresnel_dielectric(cos_theta(outgoing), self.eta);
 
@@ -46,8 +75,9 @@ impl BxDF for DielectricBxDF {
                 spectrum: color,
                 incoming,
                 pdf: prob_reflected,
-                eta: self.eta,
-                flags: self.flags(),
+                // Reflected light stays in the same medium
+                eta: 1.,
+                flags: BxDFFlags::SpecularReflection,
             })
         } else {
             // TODO: outgoing should be unit as a Ray.dir. Need to change BSDF.sample param to Unit<> and make Shading
@@ -70,7 +100,7 @@ impl BxDF for DielectricBxDF {
                     incoming,
                     pdf: prob_transmitted,
                     eta: rel_eta,
-                    flags: self.flags(),
+                    flags: BxDFFlags::SpecularTransmission,
                 })
             } else {
                 None
@@ -79,8 +109,101 @@ impl BxDF for DielectricBxDF {
     }
 
     fn pdf(&self, incoming: Shading<Vec3f>, outgoing: Shading<Vec3f>) -> f32 {
-        // TODO: microfacet
-        0.
+        if self.eta == 1. || self.distribution.effectively_smooth() {
+            return 0.;
+        }
+        let Some((wm, rel_eta)) = self.half_vector(incoming, outgoing) else {
+            return 0.;
+        };
+
+        let prob_reflected = fresnel_dielectric(dot(&*outgoing, &*wm), self.eta);
+        if same_hemisphere(incoming, outgoing) {
+            self.distribution.pdf(outgoing, wm) / (4. * dot(&*outgoing, &*wm).abs()) * prob_reflected
+        } else {
+            let denom = (dot(&*incoming, &*wm) + dot(&*outgoing, &*wm) / rel_eta).powi(2);
+            let dwm_dwi = dot(&*incoming, &*wm).abs() / denom;
+            self.distribution.pdf(outgoing, wm) * dwm_dwi * (1. - prob_reflected)
+        }
+    }
+}
+
+impl DielectricBxDF {
+    fn sample_rough(&self, rnd_p: Point2f, rnd_c: f32, outgoing: Shading<Vec3f>) -> Option<BSDFSample<Shading<Vec3f>>> {
+        if outgoing.z == 0. {
+            return None;
+        }
+        let wm = self.distribution.sample_wm(outgoing, rnd_p);
+        let prob_reflected = fresnel_dielectric(dot(&*outgoing, &*wm), self.eta);
+
+        if rnd_c < prob_reflected {
+            let incoming = reflect(outgoing, wm);
+            if !same_hemisphere(outgoing, incoming) {
+                return None;
+            }
+            let pdf = self.distribution.pdf(outgoing, wm) / (4. * dot(&*outgoing, &*wm).abs()) * prob_reflected;
+            let spectrum = SampledSpectrum::from(
+                self.distribution.d(wm) * self.distribution.g(outgoing, incoming) * prob_reflected
+                    / (4. * cos_theta(incoming) * cos_theta(outgoing)).abs(),
+            );
+            Some(BSDFSample {
+                spectrum,
+                incoming,
+                pdf,
+                eta: 1.,
+                flags: BxDFFlags::GlossyReflection,
+            })
+        } else {
+            let (incoming, rel_eta) = refract(Unit::from_unchecked(*outgoing), unit_normal3_unchecked!(*wm), self.eta)?;
+            let incoming = Shading::from(incoming);
+            if same_hemisphere(outgoing, incoming) || incoming.z == 0. {
+                return None;
+            }
+            // Change of variables from the microfacet normal to the refracted direction
+            let denom = (dot(&*incoming, &*wm) + dot(&*outgoing, &*wm) / rel_eta).powi(2);
+            let dwm_dwi = dot(&*incoming, &*wm).abs() / denom;
+            let pdf = self.distribution.pdf(outgoing, wm) * dwm_dwi * (1. - prob_reflected);
+            let spectrum = SampledSpectrum::from(
+                (1. - prob_reflected)
+                    * self.distribution.d(wm)
+                    * self.distribution.g(outgoing, incoming)
+                    * (dot(&*incoming, &*wm) * dot(&*outgoing, &*wm)
+                        / (cos_theta(incoming) * cos_theta(outgoing) * denom))
+                        .abs(),
+            );
+            Some(BSDFSample {
+                spectrum,
+                incoming,
+                pdf,
+                eta: rel_eta,
+                flags: BxDFFlags::GlossyTransmission,
+            })
+        }
+    }
+
+    /// Generalized half vector of a reflection or refraction between the directions, facing the upper hemisphere,
+    /// and the relative IOR of the interface. `None` for degenerate configurations and back-facing microfacets
+    fn half_vector(&self, incoming: Shading<Vec3f>, outgoing: Shading<Vec3f>) -> Option<(Shading<Vec3f>, f32)> {
+        let cos_in = cos_theta(incoming);
+        let cos_out = cos_theta(outgoing);
+        let rel_eta = if same_hemisphere(incoming, outgoing) {
+            1.
+        } else if cos_out > 0. {
+            self.eta
+        } else {
+            self.eta.recip()
+        };
+        let wm = *incoming * rel_eta + *outgoing;
+        if cos_in == 0. || cos_out == 0. || wm.len_squared() == 0. {
+            return None;
+        }
+        let mut wm = *wm.to_unit();
+        if wm.z < 0. {
+            wm = -wm;
+        }
+        if dot(&wm, &*incoming) * cos_in < 0. || dot(&wm, &*outgoing) * cos_out < 0. {
+            return None;
+        }
+        Some((Shading::from(wm), rel_eta))
     }
 }
 
@@ -107,3 +230,41 @@ fn fresnel_dielectric(mut cos_theta_in: f32, mut eta: f32) -> f32 {
 
     (reflection_parallel.powi(2) + reflection_perpend.powi(2)) / 2.
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::point2;
+
+    #[test]
+    fn test_rough_sample_consistency() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let bxdf = DielectricBxDF::new(1.5, TrowbridgeReitzDistribution::new(0.3, 0.5));
+        let mut albedo = 0.;
+        let n = 10_000;
+        // Both from outside and from inside of the object
+        for outgoing in [vec3!(0.3, 0.2, 0.9), vec3!(-0.5, 0.1, -0.6)] {
+            let outgoing = Shading::from(*outgoing.to_unit());
+            let mut transmitted = false;
+            for _ in 0..n {
+                let Some(sample) = bxdf.sample(point2!(rng.gen(), rng.gen()), rng.gen(), outgoing) else {
+                    continue;
+                };
+                transmitted |= sample.flags.contains(BxDFFlags::Transmission);
+                assert_relative_eq!(sample.pdf, bxdf.pdf(sample.incoming, outgoing), max_relative = 1e-3);
+                assert_relative_eq!(
+                    sample.spectrum[0],
+                    bxdf.eval(sample.incoming, outgoing)[0],
+                    max_relative = 1e-3
+                );
+                albedo += sample.spectrum[0] * abs_cos_theta(sample.incoming) / sample.pdf;
+            }
+            assert!(transmitted);
+        }
+        // Microfacet models lose energy to the ignored multiple scattering, but never gain it
+        assert!((0.8..=1.01).contains(&(albedo / (2 * n) as f32)));
+    }
+}
@@ -0,0 +1,170 @@
+use std::f32::consts::PI;
+
+use crate::{
+    bxdf::{
+        bxdf::Shading,
+        utils::{abs_cos_theta, cos2_theta, cos_phi, sin_phi, tan2_theta},
+    },
+    math::{cross, dot, utils::lerp, Normed},
+    samplers::utils::sample_uniform_disk_polar,
+    vec3, Point2f, Vec3f,
+};
+
+/// Trowbridge-Reitz (GGX) microfacet distribution. Anisotropic when `alpha_x` and `alpha_y` differ, `alpha_x` is
+/// along the shading dp/du
+#[derive(Debug, Copy, Clone)]
+pub struct TrowbridgeReitzDistribution {
+    alpha_x: f32,
+    alpha_y: f32,
+}
+
+impl TrowbridgeReitzDistribution {
+    pub fn new(alpha_x: f32, alpha_y: f32) -> Self {
+        let mut distribution = TrowbridgeReitzDistribution { alpha_x, alpha_y };
+        if !distribution.effectively_smooth() {
+            // Alphas just above the smoothness threshold are numerically unstable, PBRT clamps them the same way
+            distribution.alpha_x = alpha_x.max(1e-4);
+            distribution.alpha_y = alpha_y.max(1e-4);
+        }
+        distribution
+    }
+
+    /// Maps perceptually linear roughness in [0; 1] to alpha
+    pub fn roughness_to_alpha(roughness: f32) -> f32 { roughness.sqrt() }
+
+    /// Such surfaces are rendered as perfectly specular
+    pub fn effectively_smooth(&self) -> bool { self.alpha_x.max(self.alpha_y) < 1e-3 }
+
+    /// Differential area of microfacets with normal `wm`
+    pub fn d(&self, wm: Shading<Vec3f>) -> f32 {
+        let tan2_theta = tan2_theta(wm);
+        if tan2_theta.is_infinite() {
+            return 0.;
+        }
+        let cos4_theta = cos2_theta(wm).powi(2);
+        if cos4_theta < 1e-16 {
+            return 0.;
+        }
+        let e = tan2_theta * ((cos_phi(wm) / self.alpha_x).powi(2) + (sin_phi(wm) / self.alpha_y).powi(2));
+        1. / (PI * self.alpha_x * self.alpha_y * cos4_theta * (1. + e).powi(2))
+    }
+
+    /// Ratio of invisible to visible microfacet area for direction `w`
+    fn lambda(&self, w: Shading<Vec3f>) -> f32 {
+        let tan2_theta = tan2_theta(w);
+        if tan2_theta.is_infinite() {
+            return 0.;
+        }
+        let alpha2 = (cos_phi(w) * self.alpha_x).powi(2) + (sin_phi(w) * self.alpha_y).powi(2);
+        ((1. + alpha2 * tan2_theta).sqrt() - 1.) / 2.
+    }
+
+    /// Masking function, fraction of microfacets visible from `w`
+    pub fn g1(&self, w: Shading<Vec3f>) -> f32 { 1. / (1. + self.lambda(w)) }
+
+    /// Masking-shadowing function, fraction of microfacets visible from both directions
+    pub fn g(&self, outgoing: Shading<Vec3f>, incoming: Shading<Vec3f>) -> f32 {
+        1. / (1. + self.lambda(outgoing) + self.lambda(incoming))
+    }
+
+    /// Distribution of normals visible from `w`
+    pub fn d_visible(&self, w: Shading<Vec3f>, wm: Shading<Vec3f>) -> f32 {
+        self.g1(w) / abs_cos_theta(w) * self.d(wm) * dot(&*w, &*wm).abs()
+    }
+
+    /// Density of [Self::sample_wm]
+    pub fn pdf(&self, w: Shading<Vec3f>, wm: Shading<Vec3f>) -> f32 { self.d_visible(w, wm) }
+
+    /// Samples a microfacet normal visible from `w`
+    pub fn sample_wm(&self, w: Shading<Vec3f>, u: Point2f) -> Shading<Vec3f> {
+        // Transform to the hemispherical configuration
+        let mut wh = *vec3!(self.alpha_x * w.x, self.alpha_y * w.y, w.z).to_unit();
+        if wh.z < 0. {
+            wh = -wh;
+        }
+
+        // Orthonormal basis around wh
+        let t1 = if wh.z < 0.99999 {
+            *cross(&vec3!(0., 0., 1.), &wh).to_unit()
+        } else {
+            vec3!(1., 0., 0.)
+        };
+        let t2 = cross(&wh, &t1);
+
+        // Uniform disk sample warped to the projection of the visible hemisphere
+        let mut p = sample_uniform_disk_polar(u);
+        let h = (1. - p.x.powi(2)).sqrt();
+        p.y = lerp(h, p.y, (1. + wh.z) / 2.);
+
+        // Reproject to the hemisphere and back to the ellipsoid configuration
+        let pz = (1. - p.x.powi(2) - p.y.powi(2)).max(0.).sqrt();
+        let nh = t1 * p.x + t2 * p.y + wh * pz;
+        Shading::from(*vec3!(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).to_unit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        point2,
+        samplers::utils::{sample_uniform_hemisphere, uniform_hemisphere_pdf},
+    };
+
+    const N: usize = 200_000;
+
+    fn distributions() -> [TrowbridgeReitzDistribution; 3] {
+        [
+            TrowbridgeReitzDistribution::new(0.5, 0.5),
+            TrowbridgeReitzDistribution::new(0.2, 0.6),
+            TrowbridgeReitzDistribution::new(0.9, 0.1),
+        ]
+    }
+
+    #[test]
+    fn test_projected_area() {
+        // Projected microfacet area equals the macrosurface area
+        let mut rng = SmallRng::seed_from_u64(0);
+        for distribution in distributions() {
+            let sum: f32 = (0..N)
+                .map(|_| {
+                    let wm = Shading::from(*sample_uniform_hemisphere(point2!(rng.gen(), rng.gen())));
+                    distribution.d(wm) * wm.z / uniform_hemisphere_pdf()
+                })
+                .sum();
+            assert_abs_diff_eq!(sum / N as f32, 1., epsilon = 0.03);
+        }
+    }
+
+    #[test]
+    fn test_visible_normals() {
+        // Visible normal density is normalized and sampled normals follow it
+        let mut rng = SmallRng::seed_from_u64(1);
+        let w = Shading::from(*vec3!(0.6, -0.3, 0.4).to_unit());
+        for distribution in distributions() {
+            let mut integral = 0.;
+            let mut expected = vec3!(0., 0., 0.);
+            let mut sampled = vec3!(0., 0., 0.);
+            for _ in 0..N {
+                let wm = Shading::from(*sample_uniform_hemisphere(point2!(rng.gen(), rng.gen())));
+                // Back-facing microfacets are invisible
+                let pdf = if dot(&*w, &*wm) > 0. {
+                    distribution.pdf(w, wm) / uniform_hemisphere_pdf()
+                } else {
+                    0.
+                };
+                integral += pdf;
+                expected += *wm * pdf;
+
+                let wm = distribution.sample_wm(w, point2!(rng.gen(), rng.gen()));
+                assert!(wm.z > 0. && dot(&*w, &*wm) > -1e-4);
+                sampled += *wm;
+            }
+            assert_abs_diff_eq!(integral / N as f32, 1., epsilon = 0.03);
+            assert_abs_diff_eq!(expected / N as f32, sampled / N as f32, epsilon = 0.02);
+        }
+    }
+}
@@ -3,10 +3,12 @@ pub(crate) use bxdf::*;
 pub(crate) use conductor::*;
 pub(crate) use dielectric::*;
 pub(crate) use diffuse::*;
+pub(crate) use microfacet::*;
 
 mod bsdf;
 mod bxdf;
 mod conductor;
 mod dielectric;
 mod diffuse;
+mod microfacet;
 mod utils;
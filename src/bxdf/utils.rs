@@ -3,7 +3,8 @@ use std::f32::consts::{FRAC_1_PI, FRAC_2_PI, FRAC_PI_4, PI};
 use num_traits::{FloatConst, Zero};
 
 use crate::{
-    bxdf::bxdf::Shading, point2, samplers::utils::sample_uniform_disk_concentric, vec2, vec3, Normal3f, Point2f, Vec3f,
+    bxdf::bxdf::Shading, math::dot, point2, samplers::utils::sample_uniform_disk_concentric, vec2, vec3, Normal3f,
+    Point2f, Vec3f,
 };
 
 // TODO: should all of that be here?
@@ -17,8 +18,52 @@ pub(super) fn sample_cosine_hemisphere(u: Point2f) -> Shading<Vec3f> {
 
 pub(super) fn cosine_hemisphere_pdf(cos_theta: f32) -> f32 { cos_theta * FRAC_1_PI }
 
-pub(super) fn same_hemisphere(a: Shading<Vec3f>, b: Shading<Vec3f>) -> bool { a.z * a.z > 0.0 }
+pub(super) fn same_hemisphere(a: Shading<Vec3f>, b: Shading<Vec3f>) -> bool { a.z * b.z > 0.0 }
 
 pub(super) fn cos_theta(vec: Shading<Vec3f>) -> f32 { vec.z }
 
 pub(super) fn abs_cos_theta(vec: Shading<Vec3f>) -> f32 { f32::abs(cos_theta(vec)) }
+
+pub(super) fn cos2_theta(vec: Shading<Vec3f>) -> f32 { vec.z.powi(2) }
+
+pub(super) fn sin2_theta(vec: Shading<Vec3f>) -> f32 { (1. - cos2_theta(vec)).max(0.) }
+
+pub(super) fn tan2_theta(vec: Shading<Vec3f>) -> f32 { sin2_theta(vec) / cos2_theta(vec) }
+
+pub(super) fn cos_phi(vec: Shading<Vec3f>) -> f32 {
+    let sin_theta = sin2_theta(vec).sqrt();
+    if sin_theta == 0. {
+        1.
+    } else {
+        (vec.x / sin_theta).clamp(-1., 1.)
+    }
+}
+
+pub(super) fn sin_phi(vec: Shading<Vec3f>) -> f32 {
+    let sin_theta = sin2_theta(vec).sqrt();
+    if sin_theta == 0. {
+        0.
+    } else {
+        (vec.y / sin_theta).clamp(-1., 1.)
+    }
+}
+
+/// Mirrors `outgoing` around `normal`, both pointing away from the surface
+pub(super) fn reflect(outgoing: Shading<Vec3f>, normal: Shading<Vec3f>) -> Shading<Vec3f> {
+    Shading::from(-*outgoing + *normal * 2. * dot(&*outgoing, &*normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_hemisphere() {
+        let up = Shading::from(vec3!(0.3, 0., 0.5));
+        let down = Shading::from(vec3!(0.3, 0., -0.5));
+        assert!(same_hemisphere(up, up));
+        assert!(same_hemisphere(down, down));
+        assert!(!same_hemisphere(up, down));
+        assert!(!same_hemisphere(down, up));
+    }
+}
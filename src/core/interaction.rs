@@ -17,7 +17,8 @@ pub struct Interaction {
     pub t: f32,
     pub outgoing: Unit<Vec3f>,
     pub uv: Point2f,
-    // pub medium: Option<M>,
+    /// `normal` was flipped to face the ray and points against the orientation of the shape
+    pub flipped: bool,
 }
 
 impl Transformable<f32> for Interaction {
@@ -28,6 +29,7 @@ impl Transformable<f32> for Interaction {
             t: self.t,
             outgoing: self.outgoing.transform(trans).to_unit(),
             uv: self.uv,
+            flipped: self.flipped,
         }
     }
 
@@ -38,6 +40,7 @@ impl Transformable<f32> for Interaction {
             t: self.t,
             outgoing: self.outgoing.transform(trans).to_unit(),
             uv: self.uv,
+            flipped: self.flipped,
         }
     }
 }
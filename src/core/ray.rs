@@ -31,18 +31,48 @@ impl<T: Number> Ray<T> {
     pub fn at(&self, t: T) -> Point3<T> { self.origin + *self.dir * t }
 
     pub fn from_to(origin: Point3<T>, end: Point3<T>) -> Ray<T> { ray!(origin, (end - origin).to_unit()) }
+
+    /// Moves the differential rays closer to the main one, differentials generated by cameras are one pixel apart
+    pub fn scale_differentials(&mut self, scale: T) {
+        if let Some(diff) = &mut self.diff {
+            diff.rx_origin = self.origin + (diff.rx_origin - self.origin) * scale;
+            diff.ry_origin = self.origin + (diff.ry_origin - self.origin) * scale;
+            diff.rx_direction = (*self.dir + (*diff.rx_direction - *self.dir) * scale).to_unit();
+            diff.ry_direction = (*self.dir + (*diff.ry_direction - *self.dir) * scale).to_unit();
+        }
+    }
+}
+
+impl<T: Number> Transformable<T> for RayDifferential<T> {
+    fn transform(&self, trans: &Transform<T>) -> Self {
+        RayDifferential {
+            rx_origin: trans.apply_to(self.rx_origin),
+            ry_origin: trans.apply_to(self.ry_origin),
+            rx_direction: trans.apply_to(*self.rx_direction).to_unit(),
+            ry_direction: trans.apply_to(*self.ry_direction).to_unit(),
+        }
+    }
+
+    fn inv_transform(&self, trans: &Transform<T>) -> Self {
+        RayDifferential {
+            rx_origin: trans.apply_inv_to(self.rx_origin),
+            ry_origin: trans.apply_inv_to(self.ry_origin),
+            rx_direction: trans.apply_inv_to(*self.rx_direction).to_unit(),
+            ry_direction: trans.apply_inv_to(*self.ry_direction).to_unit(),
+        }
+    }
 }
 
 impl<T: Number> Transformable<T> for Ray<T> {
     fn transform(&self, trans: &Transform<T>) -> Self {
         let origin = trans.apply_to(self.origin);
         let dir = trans.apply_to(*self.dir).to_unit();
-        ray!(origin, dir)
+        Ray::new(origin, dir, self.diff.map(|diff| diff.transform(trans)))
     }
 
     fn inv_transform(&self, trans: &Transform<T>) -> Self {
         let origin = trans.apply_inv_to(self.origin);
         let dir = trans.apply_inv_to(*self.dir).to_unit();
-        ray!(origin, dir)
+        Ray::new(origin, dir, self.diff.map(|diff| diff.inv_transform(trans)))
     }
 }
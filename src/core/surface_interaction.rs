@@ -9,9 +9,10 @@ use crate::{
     core::{interaction::Interaction, Ray},
     light::{Light, LightEnum},
     material::{Material, MaterialsEnum},
-    math::{dot, Dot, Normed, Transform, Transformable, Unit},
+    math::{cross, dot, Dot, Normed, Transform, Transformable, Unit},
+    mediums::{MediumEnum, MediumInterface},
     ray,
-    samplers::SamplerType,
+    samplers::{Sampler, SamplerType},
     scene::cameras::{Camera, CameraType},
     Normal3f, SampledSpectrum, SampledWavelengths, Vec3f,
 };
@@ -50,6 +51,8 @@ pub struct SurfaceInteraction {
 
     pub material: Option<Arc<MaterialsEnum>>,
     pub area_light: Option<Arc<LightEnum>>,
+    /// Media on both sides of the surface. `None` if the surface doesn't change the medium a ray travels through
+    pub medium_interface: Option<MediumInterface>,
 }
 
 impl SurfaceInteraction {
@@ -87,6 +90,7 @@ impl SurfaceInteraction {
             dv_dy: 0.,
             material: None,
             area_light: None,
+            medium_interface: None,
         }
     }
 
@@ -102,11 +106,15 @@ impl SurfaceInteraction {
         sampler: &mut SamplerType,
         alloc: &'a mut Bump,
     ) -> Option<BSDF<'a>> {
-        // FIXME: not needed for now
-        // self.calculate_differentials(ray, camera, sampler.samples_per_pixel())
-
-        if let Some(material) = self.material.as_ref().map(|arc| arc.as_ref()) {
-            // TODO: [normal maps] [displacement maps]
+        self.calculate_differentials(ray, camera, sampler.samples_per_pixel());
+
+        if let Some(material) = self.material.clone() {
+            if let Some(bump) = material.bump() {
+                let (dp_du, dp_dv) = bump.shading_derivatives(self);
+                let normal = cross(&dp_du, &dp_dv).to_normal().to_unit();
+                let (dn_du, dn_dv) = (self.shading.dn_du, self.shading.dn_dv);
+                self.set_shading_geometry(normal, dp_du, dp_dv, dn_du, dn_dv);
+            }
             let bsdf: BSDF = material.get_bsdf(self, lambda, alloc);
             Some(bsdf)
         } else {
@@ -115,6 +123,29 @@ impl SurfaceInteraction {
         }
     }
 
+    /// Shading normal is flipped if needed to lie in the hemisphere of the geometric one
+    pub fn set_shading_geometry(
+        &mut self,
+        normal: Unit<Normal3f>,
+        dp_du: Vec3f,
+        dp_dv: Vec3f,
+        dn_du: Normal3f,
+        dn_dv: Normal3f,
+    ) {
+        let normal = if dot(&normal, &self.hit.normal) < 0. {
+            -normal
+        } else {
+            normal
+        };
+        self.shading = SurfaceShading {
+            normal,
+            dp_du,
+            dp_dv,
+            dn_du,
+            dn_dv,
+        };
+    }
+
     pub fn spawn_ray(&self, dir: Unit<Vec3f>) -> Ray {
         let scale = 1e-3_f32.copysign(dot(&dir, &self.hit.normal));
         let origin = self.hit.point + **self.hit.normal * scale;
@@ -122,26 +153,49 @@ impl SurfaceInteraction {
         ray!(origin, dir)
     }
 
-    pub fn set_material_properties(&mut self, material: &Arc<MaterialsEnum>, area_light: Option<&Arc<LightEnum>>) {
-        self.material = Some(material.clone());
+    pub fn set_material_properties(
+        &mut self,
+        material: Option<&Arc<MaterialsEnum>>,
+        area_light: Option<&Arc<LightEnum>>,
+    ) {
+        self.material = material.cloned();
         self.area_light = area_light.cloned()
     }
 
+    /// Medium a ray leaving the surface in `dir` travels through. Surfaces without a medium interface keep the
+    /// `current` medium
+    pub fn medium_towards(&self, dir: Vec3f, current: Option<&Arc<MediumEnum>>) -> Option<Arc<MediumEnum>> {
+        let Some(interface) = &self.medium_interface else {
+            return current.cloned();
+        };
+        let normal = if self.hit.flipped {
+            -*self.hit.normal
+        } else {
+            *self.hit.normal
+        };
+        if dot(&normal, &dir) > 0. {
+            interface.outside.clone()
+        } else {
+            interface.inside.clone()
+        }
+    }
+
     pub fn calculate_differentials(&mut self, ray: &Ray, camera: &dyn Camera, samples_per_pixel: u32) {
-        if let Some(diff) = ray.diff {
-            if dot(&self.hit.normal, &diff.rx_direction) != 0.0 && dot(&self.hit.normal, &diff.ry_direction) != 0.0 {
-                // Estimate screen-space change in intersection point using ray differentials
-
-                // Compute auxiliary intersection points with plane
-                let d = -dot(&self.hit.normal, &self.hit.point);
-                let tx = (-dot(&self.hit.normal, &diff.rx_origin) - d) / dot(&self.hit.normal, &diff.rx_direction);
-                let px = diff.rx_origin + tx * *diff.rx_direction;
-                let ty = (-dot(&self.hit.normal, &diff.ry_origin) - d) / dot(&self.hit.normal, &diff.ry_direction);
-                let py = diff.ry_origin + ty * *diff.ry_direction;
-
-                self.dp_dx = px - self.hit.point;
-                self.dp_dy = py - self.hit.point;
-            }
+        let diff = ray.diff.filter(|diff| {
+            dot(&self.hit.normal, &diff.rx_direction) != 0.0 && dot(&self.hit.normal, &diff.ry_direction) != 0.0
+        });
+        if let Some(diff) = diff {
+            // Estimate screen-space change in intersection point using ray differentials
+
+            // Compute auxiliary intersection points with plane
+            let d = -dot(&self.hit.normal, &self.hit.point);
+            let tx = (-dot(&self.hit.normal, &diff.rx_origin) - d) / dot(&self.hit.normal, &diff.rx_direction);
+            let px = diff.rx_origin + tx * *diff.rx_direction;
+            let ty = (-dot(&self.hit.normal, &diff.ry_origin) - d) / dot(&self.hit.normal, &diff.ry_direction);
+            let py = diff.ry_origin + ty * *diff.ry_direction;
+
+            self.dp_dx = px - self.hit.point;
+            self.dp_dy = py - self.hit.point;
         } else {
             let approx = camera.approximate_dp_dxy(self.hit.point, *self.hit.normal, samples_per_pixel);
             self.dp_dx = approx.0;
@@ -156,7 +210,7 @@ impl SurfaceInteraction {
         let ata01 = dot(&self.dp_du, &self.dp_dv);
         let ata11 = dot(&self.dp_dv, &self.dp_dv);
         let mut inv_det = 1. / (ata00 * ata11 - ata01 * ata01);
-        if !inv_det.is_infinite() {
+        if inv_det.is_infinite() {
             inv_det = 0.0
         };
 
@@ -198,7 +252,11 @@ impl SurfaceInteraction {
     /// MaterialsEnum may return an unset BSDF to indicate an interface between two scattering media that does not
     /// itself scatter light. In this case, it is necessary to spawn a new ray in the same direction, but past the
     /// intersection on the surface.
-    pub fn skip_interaction(ray: Ray, t: f32) { todo!() }
+    pub fn skip_interaction(&self, ray: &Ray) -> Ray {
+        let mut skipped = self.spawn_ray(ray.dir);
+        skipped.diff = ray.diff;
+        skipped
+    }
 }
 
 impl Transformable<f32> for SurfaceShading {
@@ -235,7 +293,7 @@ impl Transformable<f32> for SurfaceInteraction {
             shading: self.shading.transform(trans),
 
             dp_dx: self.dp_dx.transform(trans),
-            dp_dy: self.dp_dx.transform(trans),
+            dp_dy: self.dp_dy.transform(trans),
 
             du_dx: self.du_dx,
             dv_dx: self.dv_dx,
@@ -243,6 +301,7 @@ impl Transformable<f32> for SurfaceInteraction {
             dv_dy: self.dv_dy,
             material: self.material.clone(),
             area_light: self.area_light.clone(),
+            medium_interface: self.medium_interface.clone(),
         }
     }
 
@@ -258,7 +317,7 @@ impl Transformable<f32> for SurfaceInteraction {
             shading: self.shading.inv_transform(trans),
 
             dp_dx: self.dp_dx.inv_transform(trans),
-            dp_dy: self.dp_dx.inv_transform(trans),
+            dp_dy: self.dp_dy.inv_transform(trans),
 
             du_dx: self.du_dx,
             dv_dx: self.dv_dx,
@@ -266,6 +325,7 @@ impl Transformable<f32> for SurfaceInteraction {
             dv_dy: self.dv_dy,
             material: self.material.clone(),
             area_light: self.area_light.clone(),
+            medium_interface: self.medium_interface.clone(),
         }
     }
 }
@@ -9,9 +9,9 @@ use crate::{
     integrators::{
         ray::{RIState, RayIntegrator},
         tile::TIState,
-        IState, Integrator,
+        IState, Integrator, IntegratorConfig,
     },
-    samplers::{IndependentSampler, SamplerType},
+    samplers::SamplerType,
     scene::Scene,
     spectra::{
         rgb::{sRGB, RGBColorSpace, RGB},
@@ -44,15 +44,19 @@ impl RayIntegrator for DebugNormalIntegrator {
 }
 
 impl DebugNormalIntegrator {
-    pub fn new(scene: Scene) -> Self {
+    pub fn new(scene: Scene, config: IntegratorConfig) -> Self {
         DebugNormalIntegrator {
             color_space: sRGB.clone(),
             state: RIState {
                 max_depth: 1,
+                rr_depth: 1,
                 tile: TIState {
-                    base: IState { scene },
-                    sampler: SamplerType::Independent(IndependentSampler::new(1, 42)),
-                    save_intermediate: false,
+                    base: IState {
+                        scene,
+                        output: config.output,
+                    },
+                    sampler: config.sampler,
+                    save_intermediate: config.save_intermediate,
                 },
             },
         }
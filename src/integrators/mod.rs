@@ -1,13 +1,20 @@
 pub use debug_normal::DebugNormalIntegrator;
-use image::{ImageBuffer, Rgb};
+use image::{ImageBuffer, ImageResult, Rgb};
 pub use path::PathIntegrator;
 pub use random_walk::RandomWalkIntegrator;
 use rayon::iter::ParallelIterator;
 pub use simple_path::SimplePathIntegrator;
+pub use vol_path::VolPathIntegrator;
 
 use crate::{
+    light::LightSamplerKind,
     math::Point2,
-    scene::{cameras::Camera, film::Film, Scene},
+    samplers::SamplerType,
+    scene::{
+        cameras::Camera,
+        film::{Film, ImageOutput},
+        Scene,
+    },
     Int, Point2u,
 };
 
@@ -17,6 +24,7 @@ mod random_walk;
 mod ray;
 mod simple_path;
 mod tile;
+mod vol_path;
 
 // #[enum_delegate::implement(Integrator)]
 // pub enum Integrators{
@@ -24,19 +32,30 @@ mod tile;
 // }
 // #[enum_delegate::register]
 pub trait Integrator {
-    fn render(&mut self);
+    fn render(&mut self) -> ImageResult<()>;
     fn get_state(&self) -> &IState;
 
-    fn save_image(&self) {
-        self.get_state()
-            .scene
-            .camera
-            .get_film()
-            .write_image("./images/_image.png");
+    fn save_image(&self) -> ImageResult<()> {
+        let state = self.get_state();
+        state.scene.camera.get_film().write_image(&state.output)
     }
 }
 pub struct IState {
     pub scene: Scene,
+    pub output: ImageOutput,
+}
+
+/// Rendering options shared by all integrators
+pub struct IntegratorConfig {
+    pub max_depth: u32,
+    /// Path depth after which Russian roulette may terminate paths
+    pub rr_depth: u32,
+    /// How lights are chosen for direct lighting
+    pub light_sampler: LightSamplerKind,
+    pub sampler: SamplerType,
+    pub output: ImageOutput,
+    /// Save the image after each wave of samples
+    pub save_intermediate: bool,
 }
 
 // pub struct BaseIntegrator {
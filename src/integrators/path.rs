@@ -9,13 +9,13 @@ use crate::{
     bxdf::{BxDFFlags, BSDF},
     core::{Ray, SurfaceInteraction},
     integrators::{
-        ray::{RIState, RayIntegrator},
+        ray::{russian_roulette, RIState, RayIntegrator},
         tile::TIState,
-        IState, Integrator,
+        IState, Integrator, IntegratorConfig,
     },
-    light::{Light, LightEnum, LightSampler, UniformLightSampler},
+    light::{Light, LightEnum, LightSampler, LightSamplerType, LightType},
     math::{dot, utils::power_heuristic, Normed, Unit},
-    samplers::{Sampler, SamplerType, StratifiedSampler},
+    samplers::{Sampler, SamplerType},
     scene::Scene,
     SampledSpectrum, SampledWavelengths,
 };
@@ -25,7 +25,7 @@ pub struct PathIntegrator {
     state: RIState,
     #[borrows(state)]
     #[covariant]
-    light_sampler: UniformLightSampler<'this>,
+    light_sampler: LightSamplerType<'this>,
     regularize: bool,
 }
 
@@ -34,22 +34,22 @@ unsafe impl Send for PathIntegrator {}
 unsafe impl Sync for PathIntegrator {}
 
 impl PathIntegrator {
-    pub fn create(scene: Scene, max_depth: u32, samples_per_pixel: u32) -> Self {
-        let sqrt_spp = samples_per_pixel.isqrt();
+    pub fn create(scene: Scene, config: IntegratorConfig) -> Self {
         let state = RIState {
-            max_depth,
+            max_depth: config.max_depth,
+            rr_depth: config.rr_depth,
             tile: TIState {
-                base: IState { scene },
-                // sampler: IndependentSampler::new(samples_per_pixel, 42).into(),
-                sampler: StratifiedSampler::new(sqrt_spp, sqrt_spp, true, 42).into(),
-                save_intermediate: false,
+                base: IState {
+                    scene,
+                    output: config.output,
+                },
+                sampler: config.sampler,
+                save_intermediate: config.save_intermediate,
             },
         };
         PathIntegrator::new(
             state,
-            |state: &RIState| UniformLightSampler {
-                lights: &state.scene.lights,
-            },
+            |state: &RIState| LightSamplerType::new(config.light_sampler, &state.scene.lights),
             true,
         )
     }
@@ -61,8 +61,6 @@ impl PathIntegrator {
         lambda: &SampledWavelengths,
         sampler: &mut SamplerType,
     ) -> Option<SampledSpectrum> {
-        // TODO: [light sources emitting only on one side]
-
         // Choose a light source for the direct lighting calculation
         let rnd_c = sampler.get_1d();
         let rnd_p = sampler.get_2d();
@@ -76,7 +74,7 @@ impl PathIntegrator {
         {
             // Evaluate BSDF for light sample and check light visibility
             let cos = dot(&sample.incoming, &interaction.shading.normal).abs();
-            let reflected = bsdf.eval(*sample.incoming, *interaction.hit.outgoing);
+            let reflected = bsdf.eval(*sample.incoming, *interaction.hit.outgoing) * cos;
             let is_unoccluded =
                 self.borrow_state()
                     .scene
@@ -87,13 +85,14 @@ impl PathIntegrator {
 
             // Return light's contribution to reflected radiance
             let prob_light = sampled_light.prob * sample.pdf;
-            match sampled_light.light.as_ref() {
-                LightEnum::Point(l) => Some(sample.radiance * reflected / prob_light),
-                _ => {
-                    let prob_bsdf = bsdf.pdf(*sample.incoming, *interaction.hit.outgoing);
-                    let weight_light = power_heuristic(1, prob_light, 1, prob_bsdf);
-                    Some(weight_light * sample.radiance * reflected / prob_light)
-                }
+            let light_type = sampled_light.light.light_type();
+            if light_type.intersects(LightType::DeltaPosition | LightType::DeltaDirection) {
+                // Delta lights can't be hit by BSDF sampling
+                Some(sample.radiance * reflected / prob_light)
+            } else {
+                let prob_bsdf = bsdf.pdf(*sample.incoming, *interaction.hit.outgoing);
+                let weight_light = power_heuristic(1, prob_light, 1, prob_bsdf);
+                Some(weight_light * sample.radiance * reflected / prob_light)
             }
         } else {
             None
@@ -127,7 +126,20 @@ impl RayIntegrator for PathIntegrator {
             // Trace ray and find the closest path vertex and its BSDF
             let Some(mut interaction) = self.get_state().scene.cast_ray(&ray) else {
                 // Incorporate emission from infinite lights for escaped ray
-                // TODO: [infinite lights]
+                for light in self.get_state().scene.infinite_lights.iter() {
+                    let Some(emitted) = light.radiance_escaped(&ray, lambda) else {
+                        continue;
+                    };
+                    if depth == 0 || specular_bounce {
+                        radiance += throughput * emitted;
+                    } else {
+                        // Compute MIS weight for infinite light
+                        let prob_light = self.borrow_light_sampler().pmf(&prev_surf_int, light)
+                            * light.pdf_incoming(ray.dir, &prev_surf_int);
+                        let weight_bsdf = power_heuristic(1, prob_bsdf, 1, prob_light);
+                        radiance += throughput * weight_bsdf * emitted;
+                    }
+                }
                 break;
             };
 
@@ -148,7 +160,8 @@ impl RayIntegrator for PathIntegrator {
 
             let Some(bsdf) = interaction.get_bsdf(&ray, lambda, &self.borrow_state().scene.camera, sampler, alloc)
             else {
-                // TODO: medias
+                // Surface only separates two media, skip over it
+                ray = interaction.skip_interaction(&ray);
                 continue;
             };
 
@@ -171,8 +184,10 @@ impl RayIntegrator for PathIntegrator {
             };
 
             // Update path state variables after surface scattering
+            // Monte Carlo estimate of the scattered light, dividing by the pdf keeps it unbiased for BxDFs whose pdf
+            // doesn't cancel out with their value
             let cos = dot(&bsdf_sample.incoming, &interaction.shading.normal).abs();
-            throughput *= bsdf_sample.spectrum * cos;
+            throughput *= bsdf_sample.spectrum * cos / bsdf_sample.pdf;
             // TODO: [LayeredBxDF]
             prob_bsdf = bsdf_sample.pdf;
             specular_bounce = bsdf_sample.flags.contains(BxDFFlags::Specular);
@@ -185,7 +200,13 @@ impl RayIntegrator for PathIntegrator {
             // TODO:
             ray = interaction.spawn_ray(Unit::from_unchecked(bsdf_sample.incoming));
 
-            // TODO: Possibly terminate the path with Russian roulette
+            // Possibly terminate the path with Russian roulette
+            let rr_depth = self.borrow_state().rr_depth;
+            let Some(survival_prob) = russian_roulette(&throughput, eta_scale, depth, rr_depth, || sampler.get_1d())
+            else {
+                break;
+            };
+            throughput /= survival_prob;
         }
 
         radiance
@@ -2,18 +2,19 @@ use std::f32::consts::PI;
 
 use bumpalo::Bump;
 use image::Pixel;
-use num_traits::Zero;
+use num_traits::{One, Zero};
 
 use crate::{
     core::Ray,
     integrators::{
-        ray::{RIState, RayIntegrator},
+        ray::{russian_roulette, RIState, RayIntegrator},
         tile::TIState,
-        IState,
+        IState, IntegratorConfig,
     },
+    light::Light,
     math::dot,
     ray,
-    samplers::{utils::sample_uniform_sphere, Sampler, SamplerType, StratifiedSampler},
+    samplers::{utils::sample_uniform_sphere, Sampler, SamplerType},
     scene::Scene,
     SampledSpectrum, SampledWavelengths,
 };
@@ -34,23 +35,25 @@ impl RayIntegrator for RandomWalkIntegrator {
         sampler: &mut SamplerType,
         alloc: &mut Bump,
     ) -> SampledSpectrum {
-        self.random_walk(ray, lambda, 0, sampler, alloc)
+        self.random_walk(ray, lambda, 0, SampledSpectrum::one(), sampler, alloc)
     }
 
     fn get_ri_state(&self) -> &RIState { &self.state }
 }
 
 impl RandomWalkIntegrator {
-    pub fn new(scene: Scene, max_depth: u32, samples_per_pixel: u32) -> Self {
-        let sqrt_spp = samples_per_pixel.isqrt();
+    pub fn new(scene: Scene, config: IntegratorConfig) -> Self {
         RandomWalkIntegrator {
             state: RIState {
-                max_depth,
+                max_depth: config.max_depth,
+                rr_depth: config.rr_depth,
                 tile: TIState {
-                    base: IState { scene },
-                    // sampler: IndependentSampler::new(samples_per_pixel, 42).into(),
-                    sampler: StratifiedSampler::new(sqrt_spp, sqrt_spp, true, 42).into(),
-                    save_intermediate: false,
+                    base: IState {
+                        scene,
+                        output: config.output,
+                    },
+                    sampler: config.sampler,
+                    save_intermediate: config.save_intermediate,
                 },
             },
         }
@@ -61,6 +64,7 @@ impl RandomWalkIntegrator {
         ray: &Ray,
         lambda: &mut SampledWavelengths,
         depth: u32,
+        throughput: SampledSpectrum,
         sampler: &mut SamplerType,
         alloc: &mut Bump,
     ) -> SampledSpectrum {
@@ -73,25 +77,37 @@ impl RandomWalkIntegrator {
             }
 
             if let Some(bsdf) = interaction.get_bsdf(ray, lambda, &self.state.scene.camera, sampler, alloc) {
-                // todo: [infinite lights]
                 let incoming = sample_uniform_sphere(sampler.get_2d());
                 let cos_in_out = dot(&incoming, &interaction.hit.normal).abs();
-                let radiance = bsdf.eval(*incoming, *interaction.hit.outgoing) * cos_in_out;
+                let mut radiance = bsdf.eval(*incoming, *interaction.hit.outgoing) * cos_in_out * 4. * PI;
                 if radiance.is_zero() {
                     return emitted;
                 }
 
+                let Some(survival_prob) =
+                    russian_roulette(&(throughput * radiance), 1., depth, self.state.rr_depth, || {
+                        sampler.get_1d()
+                    })
+                else {
+                    return emitted;
+                };
+                radiance /= survival_prob;
+
                 // TODO: SI.spawn_ray
                 // TODO: ray offset
                 let incoming_ray = ray!(interaction.hit.point + **interaction.hit.normal * 1e-3, incoming);
-                let incoming_radiance = self.random_walk(&incoming_ray, lambda, depth + 1, sampler, alloc);
+                let incoming_radiance =
+                    self.random_walk(&incoming_ray, lambda, depth + 1, throughput * radiance, sampler, alloc);
 
-                radiance * incoming_radiance * 4. * PI + emitted
+                radiance * incoming_radiance + emitted
             } else {
                 emitted
             }
         } else {
-            SampledSpectrum::zero()
+            let infinite_lights = self.state.scene.infinite_lights.iter();
+            infinite_lights
+                .filter_map(|light| light.radiance_escaped(ray, lambda))
+                .fold(SampledSpectrum::zero(), |acc, x| acc + x)
         }
     }
 }
@@ -35,6 +35,28 @@ pub(super) struct RIState {
     pub(super) tile: TIState,
     // TODO: move to more concrete structs?
     pub(super) max_depth: u32,
+    /// Depth after which paths are randomly terminated
+    pub(super) rr_depth: u32,
+}
+
+/// Randomly terminates paths whose throughput dropped below one after `rr_depth` bounces. `eta_scale` undoes the
+/// radiance scaling by refraction, so paths leaving a dense medium aren't killed early. Returns the probability of the
+/// path surviving, the caller divides the throughput by it to keep the estimate unbiased, or `None` if the path is
+/// terminated. `rnd` is only called when the path may be terminated, so that sample dimensions aren't wasted on
+/// paths that always survive
+pub(super) fn russian_roulette(
+    throughput: &SampledSpectrum,
+    eta_scale: f32,
+    depth: u32,
+    rr_depth: u32,
+    rnd: impl FnOnce() -> f32,
+) -> Option<f32> {
+    let rr_throughput = (*throughput * eta_scale).max_value();
+    if depth <= rr_depth || rr_throughput >= 1. {
+        return Some(1.);
+    }
+    let survival_prob = rr_throughput.max(0.);
+    (rnd() < survival_prob).then_some(survival_prob)
 }
 
 impl<T> TileIntegrator for T
@@ -42,22 +64,53 @@ where T: RayIntegrator
 {
     fn evaluate_pixel(&self, pixel: Point2us, sampler: &mut SamplerType, alloc: &mut Bump) {
         let state = self.get_state();
-        let mut lambda = state.scene.camera.get_film().sample_wavelengths(sampler.get_1d());
-        let sample = CameraSample::new(pixel, sampler);
-
-        // TODO: [AA] should be diff ray.
-        //       [AA] something about diff scaling
-        //       [filters] should account for CameraRay weight
-        //       [realistic camera] need to know about wavelengths
-        let ray = state.scene.camera.generate_ray(sample);
+        let mut film = state.scene.camera.get_film();
+        let mut lambda = film.sample_wavelengths(sampler.get_1d());
+        let sample = CameraSample::new(pixel, sampler, film.filter());
+
+        // TODO: [realistic camera] need to know about wavelengths
+        let mut ray = state.scene.camera.generate_differential_ray(sample);
+        // Footprint of a single sample shrinks as samples get denser
+        ray.scale_differentials((sampler.samples_per_pixel() as f32).sqrt().recip().max(0.125));
         let spectrum = self.light_incoming(&ray, &mut lambda, sampler, alloc);
 
-        let mut arc_film = state.scene.camera.get_film();
-        unsafe {
-            let film = Arc::get_mut_unchecked(&mut arc_film);
-            film.add_sample(pixel, spectrum, lambda, 1.)
-        };
+        unsafe { Arc::get_mut_unchecked(&mut film).add_sample(pixel, spectrum, lambda, sample.filter_weight) };
     }
 
     fn get_ti_state(&self) -> &TIState { &self.get_ri_state().tile }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_russian_roulette() {
+        let bright = SampledSpectrum::from(2.);
+        let dim = SampledSpectrum::from(0.25);
+        // Never terminates before `rr_depth` or with high throughput
+        assert_eq!(russian_roulette(&dim, 1., 1, 1, || unreachable!()), Some(1.));
+        assert_eq!(russian_roulette(&bright, 1., 5, 1, || 0.99), Some(1.));
+        // Refraction into a dense medium shouldn't make the path more likely to terminate
+        assert_eq!(russian_roulette(&dim, 4., 5, 1, || 0.99), Some(1.));
+
+        assert_eq!(russian_roulette(&dim, 1., 5, 1, || 0.1), Some(0.25));
+        assert_eq!(russian_roulette(&dim, 1., 5, 1, || 0.3), None);
+    }
+
+    #[test]
+    fn test_russian_roulette_unbiased() {
+        // Survivors are divided by the survival probability, so the expected throughput is unchanged
+        let throughput = SampledSpectrum::from(0.25);
+        let n = 1000;
+        let mut sum = SampledSpectrum::from(0.);
+        for i in 0..n {
+            if let Some(survival_prob) = russian_roulette(&throughput, 1., 5, 1, || (i as f32 + 0.5) / n as f32) {
+                let mut survivor = throughput;
+                survivor /= survival_prob;
+                sum += survivor;
+            }
+        }
+        assert!((sum.max_value() / n as f32 - 0.25).abs() < 1e-3);
+    }
+}
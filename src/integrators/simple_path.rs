@@ -8,15 +8,15 @@ use crate::{
     bxdf::BxDFFlags,
     core::Ray,
     integrators::{
-        ray::{RIState, RayIntegrator},
+        ray::{russian_roulette, RIState, RayIntegrator},
         tile::TIState,
-        IState, Integrator,
+        IState, Integrator, IntegratorConfig,
     },
-    light::{Light, LightSampler, UniformLightSampler},
+    light::{Light, LightSampler, LightSamplerType},
     math::{dot, Normed, Unit},
     samplers::{
         utils::{sample_uniform_hemisphere, sample_uniform_sphere, uniform_hemisphere_pdf, uniform_sphere_pdf},
-        Sampler, SamplerType, StratifiedSampler,
+        Sampler, SamplerType,
     },
     scene::Scene,
     SampledSpectrum, SampledWavelengths,
@@ -28,7 +28,7 @@ pub struct SimplePathIntegrator {
     state: RIState,
     #[borrows(state)]
     #[covariant]
-    light_sampler: UniformLightSampler<'this>,
+    light_sampler: LightSamplerType<'this>,
     sample_lights: bool,
     sample_bsdf: bool,
 }
@@ -38,22 +38,22 @@ unsafe impl Send for SimplePathIntegrator {}
 unsafe impl Sync for SimplePathIntegrator {}
 
 impl SimplePathIntegrator {
-    pub fn create(scene: Scene, max_depth: u32, samples_per_pixel: u32) -> Self {
-        let sqrt_spp = samples_per_pixel.isqrt();
+    pub fn create(scene: Scene, config: IntegratorConfig) -> Self {
         let state = RIState {
-            max_depth,
+            max_depth: config.max_depth,
+            rr_depth: config.rr_depth,
             tile: TIState {
-                base: IState { scene },
-                // sampler: IndependentSampler::new(samples_per_pixel, 42).into(),
-                sampler: StratifiedSampler::new(sqrt_spp, sqrt_spp, true, 42).into(),
-                save_intermediate: false,
+                base: IState {
+                    scene,
+                    output: config.output,
+                },
+                sampler: config.sampler,
+                save_intermediate: config.save_intermediate,
             },
         };
         SimplePathIntegrator::new(
             state,
-            |state: &RIState| UniformLightSampler {
-                lights: &state.scene.lights,
-            },
+            |state: &RIState| LightSamplerType::new(config.light_sampler, &state.scene.lights),
             true,
             true,
         )
@@ -75,11 +75,19 @@ impl RayIntegrator for SimplePathIntegrator {
         // Fraction of radiance that arrives at the camera
         let mut throughput = SampledSpectrum::one();
         let mut specular_bounce = true;
+        let mut eta_scale = 1.;
 
         while !throughput.is_zero() {
             // Check for intersections with the scene
             let Some(mut interaction) = self.get_state().scene.cast_ray(&ray) else {
-                // TODO: [infinite lights]
+                // Account for infinite lights if the ray escaped
+                if !self.borrow_sample_lights() || specular_bounce {
+                    for light in self.get_state().scene.infinite_lights.iter() {
+                        if let Some(emitted) = light.radiance_escaped(&ray, lambda) {
+                            radiance += throughput * emitted;
+                        }
+                    }
+                }
                 break;
             };
 
@@ -131,6 +139,9 @@ impl RayIntegrator for SimplePathIntegrator {
 
                 throughput *= bsdf_sample.spectrum * cos;
                 specular_bounce = bsdf_sample.flags.contains(BxDFFlags::Specular);
+                if bsdf_sample.flags.contains(BxDFFlags::Transmission) {
+                    eta_scale *= bsdf_sample.eta.powi(2)
+                }
                 ray = interaction.spawn_ray(Unit::from_unchecked(bsdf_sample.incoming));
             } else {
                 // Uniformly sample sphere or hemisphere to get new path direction
@@ -156,6 +167,13 @@ impl RayIntegrator for SimplePathIntegrator {
                 specular_bounce = false;
                 ray = interaction.spawn_ray(incoming)
             }
+
+            let rr_depth = self.borrow_state().rr_depth;
+            let Some(survival_prob) = russian_roulette(&throughput, eta_scale, depth, rr_depth, || sampler.get_1d())
+            else {
+                break;
+            };
+            throughput /= survival_prob;
         }
         radiance
     }
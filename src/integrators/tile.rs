@@ -7,9 +7,9 @@ use std::{
 
 use bumpalo::Bump;
 use derive_more::Deref;
-use image::Rgb;
+use image::ImageResult;
 use itertools::{iproduct, Itertools};
-use log::{debug, info};
+use log::{debug, info, warn};
 use ndarray::iter::LanesMut;
 use rayon::{current_thread_index, prelude::*};
 use thread_local::ThreadLocal;
@@ -42,7 +42,7 @@ pub(super) trait TileIntegrator: Integrator {
 impl<T> Integrator for T
 where T: TileIntegrator + Sync + Send
 {
-    fn render(&mut self) {
+    fn render(&mut self) -> ImageResult<()> {
         // TODO: scratch buffer
         let spp = self.get_ti_state().sampler.samples_per_pixel();
         let mut start = 0;
@@ -89,8 +89,10 @@ where T: TileIntegrator + Sync + Send
                 start = till;
                 till = min(till * 2, spp);
 
-                if self.get_ti_state().save_intermediate {
-                    self.save_image()
+                if self.get_ti_state().save_intermediate
+                    && let Err(err) = self.save_image()
+                {
+                    warn!("Failed to save intermediate image: {err}")
                 }
             }
         });
@@ -0,0 +1,407 @@
+use std::sync::Arc;
+
+use bumpalo::Bump;
+use num_traits::{One, Zero};
+use ouroboros::self_referencing;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+    bxdf::{BxDFFlags, BSDF},
+    core::{Interaction, Ray, SurfaceInteraction},
+    integrators::{
+        ray::{russian_roulette, RIState, RayIntegrator},
+        tile::TIState,
+        IState, Integrator, IntegratorConfig,
+    },
+    light::{Light, LightSampler, LightSamplerType, LightType},
+    math::{dot, Normed, Unit},
+    mediums::{sample_majorant, HGPhaseFunction, MediumEnum},
+    ray,
+    samplers::{Sampler, SamplerType},
+    scene::{cameras::Camera, Scene},
+    SampledSpectrum, SampledWavelengths,
+};
+
+/// Path tracer accounting for participating media. Distances in media are sampled with delta tracking and shadow rays
+/// are attenuated with ratio tracking. Besides the throughput, paths carry the rescaled probabilities of sampling
+/// them with unidirectional (`r_u`) and light (`r_l`) sampling, which gives spectral MIS across the wavelengths for
+/// chromatic media
+#[self_referencing]
+pub struct VolPathIntegrator {
+    state: RIState,
+    #[borrows(state)]
+    #[covariant]
+    light_sampler: LightSamplerType<'this>,
+}
+
+unsafe impl Send for VolPathIntegrator {}
+
+unsafe impl Sync for VolPathIntegrator {}
+
+/// How light is scattered at a path vertex
+enum Scattering<'a, 'b> {
+    Surface(&'a BSDF<'b>),
+    Medium(HGPhaseFunction),
+}
+
+impl VolPathIntegrator {
+    pub fn create(scene: Scene, config: IntegratorConfig) -> Self {
+        let state = RIState {
+            max_depth: config.max_depth,
+            rr_depth: config.rr_depth,
+            tile: TIState {
+                base: IState {
+                    scene,
+                    output: config.output,
+                },
+                sampler: config.sampler,
+                save_intermediate: config.save_intermediate,
+            },
+        };
+        VolPathIntegrator::new(state, |state: &RIState| {
+            LightSamplerType::new(config.light_sampler, &state.scene.lights)
+        })
+    }
+
+    /// Light arriving at the vertex from a sampled light source, attenuated by the media in between. `throughput`
+    /// and `r_path` are the throughput and unidirectional rescaled probability of the path up to the vertex
+    #[allow(clippy::too_many_arguments)]
+    fn sample_direct_light(
+        &self,
+        interaction: &SurfaceInteraction,
+        scattering: Scattering,
+        medium: Option<&Arc<MediumEnum>>,
+        throughput: SampledSpectrum,
+        r_path: SampledSpectrum,
+        lambda: &SampledWavelengths,
+        sampler: &mut SamplerType,
+        rng: &mut SmallRng,
+    ) -> Option<SampledSpectrum> {
+        // Choose a light source and a point on it
+        let rnd_c = sampler.get_1d();
+        let rnd_p = sampler.get_2d();
+        let sampled_light = self.borrow_light_sampler().sample(interaction, rnd_c)?;
+        let sample = sampled_light
+            .light
+            .sample(interaction, lambda, rnd_p)
+            .filter(|x| x.pdf != 0. && !x.radiance.is_zero())?;
+        let prob_light = sampled_light.prob * sample.pdf;
+
+        // Evaluate the scattering function for the light sample
+        let outgoing = *interaction.hit.outgoing;
+        let (scattered, prob_scatter) = match scattering {
+            Scattering::Surface(bsdf) => {
+                let cos = dot(&sample.incoming, &interaction.shading.normal).abs();
+                (
+                    bsdf.eval(*sample.incoming, outgoing) * cos,
+                    bsdf.pdf(*sample.incoming, outgoing),
+                )
+            }
+            Scattering::Medium(phase) => {
+                let p = phase.p(outgoing, *sample.incoming);
+                (SampledSpectrum::from(p), phase.pdf(outgoing, *sample.incoming))
+            }
+        };
+        if scattered.is_zero() {
+            return None;
+        }
+
+        // Trace the shadow ray through media and interfaces, estimating transmittance with ratio tracking
+        let scene = &self.borrow_state().scene;
+        let mut light_ray = interaction.spawn_ray(sample.incoming);
+        let mut medium = medium.cloned();
+        let mut transmittance = SampledSpectrum::one();
+        let mut r_light = SampledSpectrum::one();
+        let mut r_unidirectional = SampledSpectrum::one();
+        loop {
+            let distance = (sample.point - light_ray.origin).len() * 0.99;
+            let hit = scene.cast_bounded_ray(&light_ray, distance);
+            // Surfaces that scatter light block it, interfaces between media don't
+            if hit.as_ref().is_some_and(|x| x.material.is_some()) {
+                return None;
+            }
+
+            if let Some(medium) = &medium {
+                let t_max = hit.as_ref().map_or(distance, |x| x.hit.t);
+                let u = rng.gen();
+                let t_maj = sample_majorant(
+                    medium,
+                    &light_ray,
+                    t_max,
+                    u,
+                    rng,
+                    lambda,
+                    |_, props, sigma_maj, t_maj, rng| {
+                        // Only null scattering lets light through
+                        let sigma_n = (sigma_maj - props.sigma_a - props.sigma_s).clamp(0., f32::INFINITY);
+                        let pdf = t_maj[0] * sigma_maj[0];
+                        transmittance *= t_maj * sigma_n / pdf;
+                        r_light *= t_maj * sigma_maj / pdf;
+                        r_unidirectional *= t_maj * sigma_n / pdf;
+
+                        // Randomly terminate rays with low transmittance
+                        let estimate = transmittance / (r_light + r_unidirectional).avg();
+                        if estimate.max_value() < 0.05 {
+                            if rng.gen::<f32>() < 0.75 {
+                                transmittance = SampledSpectrum::zero();
+                            } else {
+                                transmittance /= 0.25;
+                            }
+                        }
+                        !transmittance.is_zero()
+                    },
+                );
+                transmittance *= t_maj / t_maj[0];
+                r_light *= t_maj / t_maj[0];
+                r_unidirectional *= t_maj / t_maj[0];
+            }
+
+            if transmittance.is_zero() {
+                return None;
+            }
+            let Some(hit) = hit else {
+                break;
+            };
+            medium = hit.medium_towards(*light_ray.dir, medium.as_ref());
+            light_ray = hit.skip_interaction(&light_ray);
+        }
+
+        // Return the contribution weighted over both sampling strategies
+        r_light *= r_path * prob_light;
+        r_unidirectional *= r_path * prob_scatter;
+        let contribution = throughput * scattered * transmittance * sample.radiance;
+        if sampled_light
+            .light
+            .light_type()
+            .intersects(LightType::DeltaPosition | LightType::DeltaDirection)
+        {
+            // Delta lights can't be hit by scattering
+            Some(contribution / r_light.avg())
+        } else {
+            Some(contribution / (r_light + r_unidirectional).avg())
+        }
+    }
+}
+
+impl RayIntegrator for VolPathIntegrator {
+    fn light_incoming(
+        &self,
+        ray: &Ray,
+        lambda: &mut SampledWavelengths,
+        sampler: &mut SamplerType,
+        alloc: &mut Bump,
+    ) -> SampledSpectrum {
+        let scene = &self.borrow_state().scene;
+        let max_depth = self.borrow_state().max_depth;
+        let mut ray = *ray;
+        let mut medium = scene.camera.medium();
+        let mut depth = 0;
+        let mut radiance = SampledSpectrum::zero();
+        let mut throughput = SampledSpectrum::one();
+        // Rescaled path probabilities for unidirectional and light sampling
+        let mut r_u = SampledSpectrum::one();
+        let mut r_l = SampledSpectrum::one();
+
+        let mut specular_bounce = false;
+        let mut eta_scale = 1.;
+        let mut prev_interaction = SurfaceInteraction::default();
+        // Random numbers for delta tracking, their count varies between paths
+        let seed = (sampler.get_1d().to_bits() as u64) << 32 | sampler.get_1d().to_bits() as u64;
+        let mut rng = SmallRng::seed_from_u64(seed);
+
+        loop {
+            let hit = scene.cast_ray(&ray);
+
+            // Sample the participating medium, if any
+            if let Some(current) = medium.clone() {
+                let mut scattered_ray = None;
+                let mut terminated = false;
+                let t_max = hit.as_ref().map_or(f32::INFINITY, |x| x.hit.t);
+                let u = sampler.get_1d();
+                let t_maj = sample_majorant(
+                    &current,
+                    &ray,
+                    t_max,
+                    u,
+                    &mut rng,
+                    lambda,
+                    |point, props, sigma_maj, t_maj, rng| {
+                        // Add emission from the medium
+                        if depth < max_depth && !props.emission.is_zero() {
+                            let pdf = sigma_maj[0] * t_maj[0];
+                            let r_e = r_u * sigma_maj * t_maj / pdf;
+                            if !r_e.is_zero() {
+                                radiance += throughput * t_maj / pdf * props.sigma_a * props.emission / r_e.avg();
+                            }
+                        }
+
+                        // Choose between absorption, real and null scattering
+                        let prob_absorb = props.sigma_a[0] / sigma_maj[0];
+                        let prob_scatter = props.sigma_s[0] / sigma_maj[0];
+                        let rnd: f32 = rng.gen();
+                        if rnd < prob_absorb {
+                            terminated = true;
+                            false
+                        } else if rnd < prob_absorb + prob_scatter {
+                            if depth >= max_depth {
+                                terminated = true;
+                                return false;
+                            }
+                            depth += 1;
+
+                            let pdf = t_maj[0] * props.sigma_s[0];
+                            throughput *= t_maj * props.sigma_s / pdf;
+                            r_u *= t_maj * props.sigma_s / pdf;
+                            if throughput.is_zero() || r_u.is_zero() {
+                                terminated = true;
+                                return false;
+                            }
+
+                            let vertex = SurfaceInteraction::new(
+                                Interaction {
+                                    point,
+                                    outgoing: -ray.dir,
+                                    ..Default::default()
+                                },
+                                Default::default(),
+                                Default::default(),
+                                Default::default(),
+                                Default::default(),
+                            );
+                            if let Some(direct) = self.sample_direct_light(
+                                &vertex,
+                                Scattering::Medium(props.phase),
+                                Some(&current),
+                                throughput,
+                                r_u,
+                                lambda,
+                                sampler,
+                                rng,
+                            ) {
+                                radiance += direct;
+                            }
+
+                            // Continue the path in a direction sampled from the phase function
+                            match props.phase.sample(*vertex.hit.outgoing, sampler.get_2d()) {
+                                Some(sample) if sample.pdf != 0. => {
+                                    throughput *= sample.p / sample.pdf;
+                                    r_l = r_u / sample.pdf;
+                                    specular_bounce = false;
+                                    scattered_ray = Some(ray!(point, sample.incoming));
+                                    prev_interaction = vertex;
+                                }
+                                _ => terminated = true,
+                            }
+                            false
+                        } else {
+                            // Null scattering keeps the path going in the same direction
+                            let sigma_n = (sigma_maj - props.sigma_a - props.sigma_s).clamp(0., f32::INFINITY);
+                            let pdf = t_maj[0] * sigma_n[0];
+                            throughput *= t_maj * sigma_n / pdf;
+                            r_u *= t_maj * sigma_n / pdf;
+                            r_l *= t_maj * sigma_maj / pdf;
+                            !throughput.is_zero() && !r_u.is_zero()
+                        }
+                    },
+                );
+
+                if terminated || throughput.is_zero() || r_u.is_zero() {
+                    break;
+                }
+                if let Some(scattered_ray) = scattered_ray {
+                    ray = scattered_ray;
+                    continue;
+                }
+                throughput *= t_maj / t_maj[0];
+                r_u *= t_maj / t_maj[0];
+                r_l *= t_maj / t_maj[0];
+            }
+
+            let Some(mut interaction) = hit else {
+                // Incorporate emission from infinite lights for escaped ray
+                for light in scene.infinite_lights.iter() {
+                    let Some(emitted) = light.radiance_escaped(&ray, lambda) else {
+                        continue;
+                    };
+                    if depth == 0 || specular_bounce {
+                        radiance += throughput * emitted / r_u.avg();
+                    } else {
+                        let prob_light = self.borrow_light_sampler().pmf(&prev_interaction, light)
+                            * light.pdf_incoming(ray.dir, &prev_interaction);
+                        radiance += throughput * emitted / (r_u + r_l * prob_light).avg();
+                    }
+                }
+                break;
+            };
+
+            // Incorporate emission from surface hit by ray
+            if let Some(emitted) = interaction.emitted_light(lambda) {
+                if depth == 0 || specular_bounce {
+                    radiance += throughput * emitted / r_u.avg();
+                } else {
+                    let light_source = interaction.area_light.as_ref().unwrap().as_ref();
+                    let prob_light = self.borrow_light_sampler().pmf(&prev_interaction, light_source)
+                        * light_source.pdf_incoming(ray.dir, &prev_interaction);
+                    radiance += throughput * emitted / (r_u + r_l * prob_light).avg();
+                }
+            }
+
+            let Some(bsdf) = interaction.get_bsdf(&ray, lambda, &scene.camera, sampler, alloc) else {
+                // Interface between media, continue in the medium on the other side
+                medium = interaction.medium_towards(*ray.dir, medium.as_ref());
+                ray = interaction.skip_interaction(&ray);
+                continue;
+            };
+
+            if depth == max_depth {
+                break;
+            }
+            depth += 1;
+
+            // Sample direct illumination
+            if !bsdf.flags().contains(BxDFFlags::Specular)
+                && let Some(direct) = self.sample_direct_light(
+                    &interaction,
+                    Scattering::Surface(&bsdf),
+                    medium.as_ref(),
+                    throughput,
+                    r_u,
+                    lambda,
+                    sampler,
+                    &mut rng,
+                )
+            {
+                radiance += direct;
+            }
+
+            let Some(bsdf_sample) = bsdf.sample(*interaction.hit.outgoing, sampler.get_2d(), sampler.get_1d()) else {
+                break;
+            };
+
+            // Update path state variables after surface scattering
+            let cos = dot(&bsdf_sample.incoming, &interaction.shading.normal).abs();
+            throughput *= bsdf_sample.spectrum * cos / bsdf_sample.pdf;
+            r_l = r_u / bsdf_sample.pdf;
+            specular_bounce = bsdf_sample.flags.contains(BxDFFlags::Specular);
+            if bsdf_sample.flags.contains(BxDFFlags::Transmission) {
+                eta_scale *= bsdf_sample.eta.powi(2)
+            }
+            ray = interaction.spawn_ray(Unit::from_unchecked(bsdf_sample.incoming));
+            medium = interaction.medium_towards(*ray.dir, medium.as_ref());
+            prev_interaction = interaction;
+
+            // Possibly terminate the path with Russian roulette
+            let rr_depth = self.borrow_state().rr_depth;
+            let Some(survival_prob) = russian_roulette(&(throughput / r_u.avg()), eta_scale, depth, rr_depth, || {
+                sampler.get_1d()
+            }) else {
+                break;
+            };
+            throughput /= survival_prob;
+        }
+
+        radiance
+    }
+
+    fn get_ri_state(&self) -> &RIState { self.borrow_state() }
+}
@@ -20,13 +20,13 @@ use std::{ops::DerefMut, sync::atomic::AtomicU32};
 pub mod aggregates;
 pub mod bxdf;
 pub mod core;
+pub mod integrators;
 pub mod material;
 pub mod math;
-// pub mod mediums;
-pub mod integrators;
+pub mod mediums;
 // pub mod rendering;
 pub mod light;
-mod samplers;
+pub mod samplers;
 pub mod scene;
 pub mod shapes;
 pub mod spectra;
@@ -44,6 +44,7 @@ pub type Point2us = math::Point2<usize>;
 pub type Point3f = math::Point3<f32>;
 pub type Point3i = math::Point3<Int>;
 pub type Point3u = math::Point3<UInt>;
+pub type Vec2f = math::Vec2<f32>;
 pub type Vec3f = math::Vec3<f32>;
 pub type Normal3f = math::Normal3<f32>;
 pub type Bounds2f = math::Bounds2<f32>;
@@ -0,0 +1,379 @@
+use std::{collections::HashMap, f32::consts::PI, sync::Arc};
+
+use crate::{
+    aggregates::Aabb,
+    core::SurfaceInteraction,
+    light::{
+        light_sampler::{LightSampler, SampledLight},
+        Light, LightBounds, LightEnum, LightType,
+    },
+    math::axis::Axis3,
+};
+
+/// Number of candidate split positions per axis when building the BVH
+const BUCKETS: usize = 12;
+/// Depth after which nodes are split in halves instead of by cost. Bit trails hold 64 levels, halving keeps the
+/// remaining depth under 32 for any number of lights
+const MAX_COST_SPLIT_DEPTH: u32 = 32;
+
+/// Picks lights by traversing a BVH over their [LightBounds], choosing children according to how much they may
+/// contribute to the receiving point. Infinite and distant lights can't be bounded and are sampled separately
+pub struct BVHLightSampler<'a> {
+    lights: &'a Vec<Arc<LightEnum>>,
+    /// Lights without bounds: infinite and distant ones
+    infinite_lights: Vec<usize>,
+    /// Depth-first order, the first child of an interior node directly follows it
+    nodes: Vec<LightBVHNode>,
+    /// Light's address to the path from the root to its leaf, bit `i` tells which child was taken at depth `i`
+    bit_trails: HashMap<usize, u64>,
+}
+
+#[derive(Debug)]
+struct LightBVHNode {
+    bounds: LightBounds,
+    /// Index of the second child for interior nodes or index of the light in `lights` for leaves
+    child_or_light: usize,
+    is_leaf: bool,
+}
+
+impl<'a> BVHLightSampler<'a> {
+    pub fn new(lights: &'a Vec<Arc<LightEnum>>) -> Self {
+        let mut sampler = BVHLightSampler {
+            lights,
+            infinite_lights: vec![],
+            nodes: vec![],
+            bit_trails: HashMap::new(),
+        };
+
+        let mut bvh_lights = vec![];
+        for (i, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                None => sampler.infinite_lights.push(i),
+                Some(bounds) if bounds.phi > 0. => bvh_lights.push((i, bounds)),
+                // Lights that emit nothing are never sampled
+                Some(_) => {}
+            }
+        }
+        if !bvh_lights.is_empty() {
+            sampler.build(&mut bvh_lights, 0, 0);
+        }
+        sampler
+    }
+
+    fn build(&mut self, bvh_lights: &mut [(usize, LightBounds)], bit_trail: u64, depth: u32) -> (usize, LightBounds) {
+        if let [(light_index, bounds)] = bvh_lights {
+            let node_index = self.nodes.len();
+            self.nodes.push(LightBVHNode {
+                bounds: *bounds,
+                child_or_light: *light_index,
+                is_leaf: true,
+            });
+            self.bit_trails
+                .insert(Arc::as_ptr(&self.lights[*light_index]) as usize, bit_trail);
+            return (node_index, *bounds);
+        }
+
+        let (bounds, centroid_bounds) = bvh_lights.iter().fold(
+            (Aabb::default(), Aabb::default()),
+            |(bounds, centroid_bounds), (_, light_bounds)| {
+                (
+                    bounds + light_bounds.bounds,
+                    centroid_bounds.union(light_bounds.centroid()),
+                )
+            },
+        );
+
+        // Find the cheapest split among bucket boundaries along all axes
+        let mut min_cost = f32::INFINITY;
+        let mut split = None;
+        let axes = if depth < MAX_COST_SPLIT_DEPTH {
+            [Axis3::X, Axis3::Y, Axis3::Z].as_slice()
+        } else {
+            // Lopsided splits could keep going until bit trails overflow
+            let axis = centroid_bounds.max_dimension();
+            bvh_lights.sort_by(|(_, a), (_, b)| a.centroid()[axis].total_cmp(&b.centroid()[axis]));
+            [].as_slice()
+        };
+        for &axis in axes {
+            if centroid_bounds.max[axis] == centroid_bounds.min[axis] {
+                continue;
+            }
+            let mut buckets: [Option<LightBounds>; BUCKETS] = [None; BUCKETS];
+            for (_, light_bounds) in bvh_lights.iter() {
+                let bucket = Self::bucket(&centroid_bounds, light_bounds, axis);
+                buckets[bucket] = Some(buckets[bucket].map_or(*light_bounds, |x| x.union(light_bounds)));
+            }
+
+            for i in 0..BUCKETS - 1 {
+                let union =
+                    |buckets: &[Option<LightBounds>]| buckets.iter().flatten().copied().reduce(|a, b| a.union(&b));
+                let cost = [union(&buckets[..=i]), union(&buckets[i + 1..])]
+                    .iter()
+                    .flatten()
+                    .map(|x| Self::cost(x, &bounds, axis))
+                    .sum::<f32>();
+                if cost < min_cost {
+                    min_cost = cost;
+                    split = Some((axis, i));
+                }
+            }
+        }
+
+        let mut mid = match split {
+            None => bvh_lights.len() / 2,
+            Some((axis, split_bucket)) => {
+                bvh_lights.sort_by_key(|(_, x)| Self::bucket(&centroid_bounds, x, axis) > split_bucket);
+                bvh_lights
+                    .iter()
+                    .take_while(|(_, x)| Self::bucket(&centroid_bounds, x, axis) <= split_bucket)
+                    .count()
+            }
+        };
+        if mid == 0 || mid == bvh_lights.len() {
+            mid = bvh_lights.len() / 2;
+        }
+
+        // Placeholder, filled after the children are built
+        let node_index = self.nodes.len();
+        self.nodes.push(LightBVHNode {
+            bounds: bvh_lights[0].1,
+            child_or_light: 0,
+            is_leaf: false,
+        });
+        let (left, right) = bvh_lights.split_at_mut(mid);
+        let (_, left_bounds) = self.build(left, bit_trail, depth + 1);
+        let (right_index, right_bounds) = self.build(right, bit_trail | (1 << depth), depth + 1);
+
+        let bounds = left_bounds.union(&right_bounds);
+        self.nodes[node_index] = LightBVHNode {
+            bounds,
+            child_or_light: right_index,
+            is_leaf: false,
+        };
+        (node_index, bounds)
+    }
+
+    fn bucket(centroid_bounds: &Aabb<f32>, light_bounds: &LightBounds, axis: Axis3) -> usize {
+        let offset = centroid_bounds.offset(light_bounds.centroid())[axis];
+        ((offset * BUCKETS as f32) as usize).min(BUCKETS - 1)
+    }
+
+    /// Surface area orientation heuristic
+    fn cost(light_bounds: &LightBounds, bounds: &Aabb<f32>, axis: Axis3) -> f32 {
+        let theta_o = light_bounds.cos_theta_o.clamp(-1., 1.).acos();
+        let theta_e = light_bounds.cos_theta_e.clamp(-1., 1.).acos();
+        let theta_w = (theta_o + theta_e).min(PI);
+        let sin_theta_o = (1. - light_bounds.cos_theta_o.powi(2)).max(0.).sqrt();
+        let m_omega = 2. * PI * (1. - light_bounds.cos_theta_o)
+            + PI / 2.
+                * (2. * theta_w * sin_theta_o - (theta_o - 2. * theta_w).cos() - 2. * theta_o * sin_theta_o
+                    + light_bounds.cos_theta_o);
+
+        // Penalize splitting along short axes
+        let diagonal = bounds.max - bounds.min;
+        let k_r = diagonal.x.max(diagonal.y).max(diagonal.z) / diagonal[axis];
+        light_bounds.phi * m_omega * k_r * light_bounds.bounds.surface_area()
+    }
+
+    fn prob_infinite(&self) -> f32 {
+        let n_infinite = self.infinite_lights.len() as f32;
+        let n_bvh = if self.nodes.is_empty() { 0. } else { 1. };
+        if n_infinite == 0. {
+            0.
+        } else {
+            n_infinite / (n_infinite + n_bvh)
+        }
+    }
+
+    fn children_importance(&self, node_index: usize, surf_int: &SurfaceInteraction) -> [f32; 2] {
+        let children = [node_index + 1, self.nodes[node_index].child_or_light];
+        children.map(|x| {
+            self.nodes[x]
+                .bounds
+                .importance(surf_int.hit.point, surf_int.hit.normal.value)
+        })
+    }
+}
+
+impl LightSampler for BVHLightSampler<'_> {
+    fn sample(&self, surf_int: &SurfaceInteraction, rnd_c: f32) -> Option<SampledLight> {
+        let prob_infinite = self.prob_infinite();
+        if rnd_c < prob_infinite {
+            let n_infinite = self.infinite_lights.len();
+            let index = ((rnd_c / prob_infinite * n_infinite as f32) as usize).min(n_infinite - 1);
+            return Some(SampledLight {
+                light: self.lights[self.infinite_lights[index]].clone(),
+                prob: prob_infinite / n_infinite as f32,
+            });
+        }
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut rnd_c = ((rnd_c - prob_infinite) / (1. - prob_infinite)).min(1. - f32::EPSILON);
+        let mut prob = 1. - prob_infinite;
+        let mut node_index = 0;
+        loop {
+            let node = &self.nodes[node_index];
+            if node.is_leaf {
+                // The root leaf isn't checked on the way down
+                if node_index > 0 || node.bounds.importance(surf_int.hit.point, surf_int.hit.normal.value) > 0. {
+                    return Some(SampledLight {
+                        light: self.lights[node.child_or_light].clone(),
+                        prob,
+                    });
+                }
+                return None;
+            }
+
+            let importance = self.children_importance(node_index, surf_int);
+            let total = importance[0] + importance[1];
+            if total == 0. {
+                return None;
+            }
+            let prob_left = importance[0] / total;
+            if rnd_c < prob_left {
+                prob *= prob_left;
+                rnd_c = (rnd_c / prob_left).min(1. - f32::EPSILON);
+                node_index += 1;
+            } else {
+                prob *= 1. - prob_left;
+                rnd_c = ((rnd_c - prob_left) / (1. - prob_left)).min(1. - f32::EPSILON);
+                node_index = node.child_or_light;
+            }
+        }
+    }
+
+    fn pmf(&self, surf_int: &SurfaceInteraction, light: &LightEnum) -> f32 {
+        if light
+            .light_type()
+            .intersects(LightType::Infinite | LightType::DeltaDirection)
+        {
+            let is_known = self
+                .infinite_lights
+                .iter()
+                .any(|&i| Arc::as_ptr(&self.lights[i]) == light as *const LightEnum);
+            return if is_known {
+                self.prob_infinite() / self.infinite_lights.len() as f32
+            } else {
+                0.
+            };
+        }
+        let Some(&(mut bit_trail)) = self.bit_trails.get(&(light as *const LightEnum as usize)) else {
+            return 0.;
+        };
+
+        let root = &self.nodes[0];
+        if root.is_leaf && root.bounds.importance(surf_int.hit.point, surf_int.hit.normal.value) == 0. {
+            return 0.;
+        }
+        let mut prob = 1. - self.prob_infinite();
+        let mut node_index = 0;
+        while !self.nodes[node_index].is_leaf {
+            let importance = self.children_importance(node_index, surf_int);
+            let total = importance[0] + importance[1];
+            if total == 0. {
+                // Neither child can be sampled, see `sample`
+                return 0.;
+            }
+            let child = (bit_trail & 1) as usize;
+            prob *= importance[child] / total;
+            node_index = if child == 0 {
+                node_index + 1
+            } else {
+                self.nodes[node_index].child_or_light
+            };
+            bit_trail >>= 1;
+        }
+        prob
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{
+        light::{DistantLight, PointLight, UniformInfiniteLight},
+        math::Transform,
+        point3,
+        spectra::{ConstantSpectrum, SpectrumEnum},
+        unit_normal3, vec3,
+    };
+
+    #[test]
+    fn test_bvh_sampler() {
+        let spectrum = Arc::new(SpectrumEnum::Constant(ConstantSpectrum::new(1.)));
+        let mut lights: Vec<_> = (0..10)
+            .map(|i| {
+                let transform = Transform::translate(vec3!(i as f32, (i % 3) as f32, 1.));
+                Arc::new(LightEnum::Point(PointLight::new(spectrum.clone(), 1., transform)))
+            })
+            .collect();
+        lights.push(Arc::new(LightEnum::UniformInfinite(UniformInfiniteLight::new(
+            spectrum.clone(),
+            1.,
+        ))));
+        lights.push(Arc::new(LightEnum::Distant(DistantLight::new(
+            spectrum.clone(),
+            1.,
+            Transform::id(),
+        ))));
+        let sampler = BVHLightSampler::new(&lights);
+
+        let mut surf_int = SurfaceInteraction::default();
+        surf_int.hit.point = point3!(2., 0., 0.);
+        surf_int.hit.normal = unit_normal3!(0., 0., 1.);
+
+        // Sampled probabilities are consistent with the PMF and sum up to one
+        let total: f32 = lights.iter().map(|x| sampler.pmf(&surf_int, x)).sum();
+        assert_abs_diff_eq!(total, 1., epsilon = 1e-5);
+        // The BVH, the infinite and the distant light are equally likely
+        assert_abs_diff_eq!(sampler.pmf(&surf_int, &lights[10]), 1. / 3.);
+        assert_abs_diff_eq!(sampler.pmf(&surf_int, &lights[11]), 1. / 3.);
+        let n = 100;
+        for i in 0..n {
+            let sampled = sampler.sample(&surf_int, (i as f32 + 0.5) / n as f32).unwrap();
+            assert_abs_diff_eq!(sampled.prob, sampler.pmf(&surf_int, &sampled.light), epsilon = 1e-5);
+        }
+
+        // Closer lights are more likely to be sampled
+        assert!(sampler.pmf(&surf_int, &lights[2]) > sampler.pmf(&surf_int, &lights[9]));
+
+        // Infinite lights unknown to the sampler
+        let point_lights = lights[..10].to_vec();
+        let sampler = BVHLightSampler::new(&point_lights);
+        assert_eq!(sampler.pmf(&surf_int, &lights[10]), 0.);
+    }
+
+    #[test]
+    fn test_deep_bvh() {
+        // Point lights have no area, so every split costs nothing and the first bucket is split off each time,
+        // leaving 11/12 of the lights on one side
+        let spectrum = Arc::new(SpectrumEnum::Constant(ConstantSpectrum::new(1.)));
+        let lights: Vec<_> = (0..2000)
+            .map(|i| {
+                let transform = Transform::translate(vec3!(i as f32, 0., 0.));
+                Arc::new(LightEnum::Point(PointLight::new(spectrum.clone(), 1., transform)))
+            })
+            .collect();
+        let sampler = BVHLightSampler::new(&lights);
+        fn depth(sampler: &BVHLightSampler, node: usize) -> usize {
+            if sampler.nodes[node].is_leaf {
+                0
+            } else {
+                1 + depth(sampler, node + 1).max(depth(sampler, sampler.nodes[node].child_or_light))
+            }
+        }
+        assert!(depth(&sampler, 0) < 64);
+
+        let mut surf_int = SurfaceInteraction::default();
+        surf_int.hit.point = point3!(0., 1., 0.);
+        let total: f32 = lights.iter().map(|x| sampler.pmf(&surf_int, x)).sum();
+        assert_abs_diff_eq!(total, 1., epsilon = 1e-4);
+        for pmf in lights.iter().map(|x| sampler.pmf(&surf_int, x)) {
+            assert!(pmf.is_finite());
+        }
+    }
+}
@@ -1,33 +1,96 @@
 use std::{f32::consts::PI, sync::Arc};
 
-use image::{Pixel, Rgb};
+use image::Rgb32FImage;
 
 use crate::{
-    core::SurfaceInteraction,
-    light::{base::BaseLight, Light, LightSample, LightType},
-    math::{Normed, Transform, Unit},
+    core::{Interaction, SurfaceInteraction},
+    light::{base::BaseLight, Light, LightBounds, LightSample, LightType},
+    math::{dot, Normed, Transform, Unit},
     shapes::{BoundedIntersectable, Samplable},
-    spectra::{Spectrum, SpectrumEnum},
+    spectra::{
+        rgb::{sRGB, RGB},
+        RGBIlluminantSpectrum, Spectrum, SpectrumEnum,
+    },
     Point2f, SampledSpectrum, SampledWavelengths, Vec3f,
 };
 
-// TODO: emit on one side only? fix flux()
-//       emission from texture
-//       alpha
+/// Radiance emitted by an area light
+#[derive(Debug, Clone)]
+pub enum AreaEmission {
+    Spectrum(Arc<SpectrumEnum>),
+    /// Linear sRGB looked up with the surface UV, see [AreaEmission::image]
+    Image {
+        image: Arc<Rgb32FImage>,
+        average: RGB,
+    },
+}
+
+impl AreaEmission {
+    /// Keeps the average color, which stands in for the image when computing the flux
+    pub fn image(image: Rgb32FImage) -> Self {
+        assert!(image.width() > 0 && image.height() > 0);
+        let mut sum = [0.; 3];
+        for pixel in image.pixels() {
+            for (sum, x) in sum.iter_mut().zip(pixel.0) {
+                *sum += x.max(0.);
+            }
+        }
+        let [r, g, b] = sum.map(|x| x / image.pixels().len() as f32);
+        AreaEmission::Image {
+            image: Arc::new(image),
+            average: RGB::new(r, g, b),
+        }
+    }
 
+    fn sample(&self, uv: Point2f, lambda: &SampledWavelengths) -> SampledSpectrum {
+        match self {
+            AreaEmission::Spectrum(spectrum) => spectrum.sample(lambda),
+            AreaEmission::Image { image, .. } => {
+                // Image rows go down while v goes up, as with image textures
+                let (width, height) = image.dimensions();
+                let x = ((uv.x.clamp(0., 1.) * width as f32) as u32).min(width - 1);
+                let y = (((1. - uv.y).clamp(0., 1.) * height as f32) as u32).min(height - 1);
+                let [r, g, b] = image.get_pixel(x, y).0.map(|x| x.max(0.));
+                RGBIlluminantSpectrum::new(&sRGB, RGB::new(r, g, b)).sample(lambda)
+            }
+        }
+    }
+
+    fn average(&self, lambda: &SampledWavelengths) -> SampledSpectrum {
+        match self {
+            AreaEmission::Spectrum(spectrum) => spectrum.sample(lambda),
+            AreaEmission::Image { average, .. } => RGBIlluminantSpectrum::new(&sRGB, *average).sample(lambda),
+        }
+    }
+
+    fn max_value(&self) -> f32 {
+        match self {
+            AreaEmission::Spectrum(spectrum) => spectrum.max_value(),
+            AreaEmission::Image { average, .. } => RGBIlluminantSpectrum::new(&sRGB, *average).max_value(),
+        }
+    }
+}
+
+/// Emits uniformly in all directions from the surface of a shape. One-sided lights emit only on the side the shape is
+/// oriented towards
 #[derive(Debug)]
 pub struct DiffuseAreaLight {
     base: BaseLight,
-    spectrum: Arc<SpectrumEnum>,
+    emission: AreaEmission,
     scale: f32,
+    two_sided: bool,
     shape: Arc<dyn BoundedIntersectable>,
     area: f32,
 }
 
+unsafe impl Send for DiffuseAreaLight {}
+unsafe impl Sync for DiffuseAreaLight {}
+
 impl DiffuseAreaLight {
     pub fn new(
-        spectrum: Arc<SpectrumEnum>,
+        emission: AreaEmission,
         scale: f32,
+        two_sided: bool,
         light_to_render: Transform<f32>,
         shape: Arc<dyn BoundedIntersectable>,
     ) -> Self {
@@ -36,17 +99,30 @@ impl DiffuseAreaLight {
                 light_type: LightType::Area,
                 light_to_render,
             },
-            spectrum,
+            emission,
             scale,
+            two_sided,
             area: shape.area(),
             shape,
         }
     }
+
+    /// Whether light leaves `hit` towards its `outgoing` direction
+    fn emits_towards(&self, hit: &Interaction) -> bool {
+        let normal = if hit.flipped { -*hit.normal } else { *hit.normal };
+        self.two_sided || dot(&normal, &hit.outgoing) > 0.
+    }
+
+    fn emitted(&self, hit: &Interaction, lambda: &SampledWavelengths) -> Option<SampledSpectrum> {
+        self.emits_towards(hit)
+            .then(|| self.emission.sample(hit.uv, lambda) * self.scale)
+    }
 }
 
 impl Light for DiffuseAreaLight {
     fn flux(&self, lambda: &SampledWavelengths) -> SampledSpectrum {
-        2. * PI * self.scale * self.spectrum.sample(lambda)
+        let sides = if self.two_sided { 2. } else { 1. };
+        sides * PI * self.area * self.scale * self.emission.average(lambda)
     }
 
     fn light_type(&self) -> LightType { LightType::Area }
@@ -65,8 +141,12 @@ impl Light for DiffuseAreaLight {
         };
 
         let incoming = (shape_sample.hit.point - surf_int.hit.point).to_unit();
+        let hit = Interaction {
+            outgoing: -incoming,
+            ..shape_sample.hit
+        };
 
-        self.radiance(surf_int, lambda).map(|emitted| LightSample {
+        self.emitted(&hit, lambda).map(|emitted| LightSample {
             radiance: emitted,
             incoming,
             pdf: shape_sample.pdf,
@@ -75,10 +155,112 @@ impl Light for DiffuseAreaLight {
     }
 
     fn radiance(&self, surf_int: &SurfaceInteraction, lambda: &SampledWavelengths) -> Option<SampledSpectrum> {
-        Some(self.spectrum.sample(lambda) * self.scale)
+        self.emitted(&surf_int.hit, lambda)
     }
 
     fn pdf_incoming(&self, incoming: Unit<Vec3f>, surf_int: &SurfaceInteraction) -> f32 {
+        if !self.two_sided {
+            // Sampling never picks the back side, since it doesn't emit
+            let ray = surf_int.spawn_ray(incoming);
+            match self.shape.intersect(&ray, f32::INFINITY) {
+                Some(light_int) if self.emits_towards(&light_int.hit) => {}
+                _ => return 0.,
+            }
+        }
         self.shape.pdf_incoming(surf_int, incoming)
     }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let normals = self.shape.normal_bounds();
+        let sides = if self.two_sided { 2. } else { 1. };
+        Some(LightBounds {
+            bounds: self.shape.bound(),
+            w: normals.w,
+            phi: sides * self.area * self.scale * self.emission.max_value(),
+            cos_theta_o: normals.cos_theta,
+            cos_theta_e: 0.,
+            two_sided: self.two_sided,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{
+        core::Ray,
+        point2, point3, ray,
+        shapes::{quad::Quad, Intersectable},
+        spectra::ConstantSpectrum,
+        vec3,
+    };
+
+    #[test]
+    fn test_one_sided() {
+        // Faces +z
+        let quad = Arc::new(Quad::new(
+            point3!(-1., -1., 0.),
+            vec3!(2., 0., 0.),
+            vec3!(0., 2., 0.),
+            Transform::id(),
+        ));
+        let spectrum = AreaEmission::Spectrum(Arc::new(ConstantSpectrum::new(1.).into()));
+        let light = DiffuseAreaLight::new(spectrum.clone(), 2., false, Transform::id(), quad.clone());
+        let two_sided = DiffuseAreaLight::new(spectrum, 2., true, Transform::id(), quad.clone());
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        assert_abs_diff_eq!(light.flux(&lambda)[0], PI * 4. * 2., epsilon = 1e-4);
+        assert_abs_diff_eq!(two_sided.flux(&lambda)[0], 2. * light.flux(&lambda)[0]);
+
+        let mut front = SurfaceInteraction::default();
+        front.hit.point = point3!(0., 0., 1.);
+        let mut back = SurfaceInteraction::default();
+        back.hit.point = point3!(0., 0., -1.);
+        let rnd_p = point2!(0.5, 0.5);
+        assert_abs_diff_eq!(light.sample(&front, &lambda, rnd_p).unwrap().radiance[0], 2.);
+        assert!(light.sample(&back, &lambda, rnd_p).is_none());
+        assert!(two_sided.sample(&back, &lambda, rnd_p).is_some());
+
+        let down = vec3!(0., 0., -1.).to_unit();
+        let hit = quad.intersect(&ray!(front.hit.point, down), f32::INFINITY).unwrap();
+        assert!(light.radiance(&hit, &lambda).is_some());
+        let hit = quad.intersect(&ray!(back.hit.point, -down), f32::INFINITY).unwrap();
+        assert!(light.radiance(&hit, &lambda).is_none());
+        assert!(two_sided.radiance(&hit, &lambda).is_some());
+        assert_eq!(light.pdf_incoming(-down, &back), 0.);
+        assert!(light.pdf_incoming(down, &front) > 0.);
+    }
+
+    #[test]
+    fn test_image() {
+        let quad = Arc::new(Quad::new(
+            point3!(0., 0., 0.),
+            vec3!(1., 0., 0.),
+            vec3!(0., 1., 0.),
+            Transform::id(),
+        ));
+        // Red on the left, blue on the right
+        let mut image = Rgb32FImage::new(2, 1);
+        image.put_pixel(0, 0, image::Rgb([1., 0., 0.]));
+        image.put_pixel(1, 0, image::Rgb([0., 0., 1.]));
+        let light = DiffuseAreaLight::new(AreaEmission::image(image), 1., true, Transform::id(), quad.clone());
+
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        let down = vec3!(0., 0., -1.).to_unit();
+        let radiance = |x: f32| {
+            let hit = quad.intersect(&ray!(point3!(x, 0.5, 1.), down), f32::INFINITY).unwrap();
+            light.radiance(&hit, &lambda).unwrap()
+        };
+        let red = RGBIlluminantSpectrum::new(&sRGB, RGB::new(1., 0., 0.)).sample(&lambda);
+        let blue = RGBIlluminantSpectrum::new(&sRGB, RGB::new(0., 0., 1.)).sample(&lambda);
+        for (left, right) in [(radiance(0.25), red), (radiance(0.75), blue)] {
+            for (actual, expected) in left.iter().zip(right.iter()) {
+                assert_abs_diff_eq!(actual, expected, epsilon = 1e-5);
+            }
+        }
+        // The average color for the flux
+        let purple = RGBIlluminantSpectrum::new(&sRGB, RGB::new(0.5, 0., 0.5)).sample(&lambda);
+        assert_abs_diff_eq!(light.flux(&lambda)[0], 2. * PI * purple[0], epsilon = 1e-4);
+    }
 }
@@ -0,0 +1,96 @@
+use std::{f32::consts::PI, sync::Arc};
+
+use crate::{
+    aggregates::Aabb,
+    core::SurfaceInteraction,
+    light::{base::BaseLight, Light, LightBounds, LightSample, LightType},
+    math::{Normed, Transform, Transformable, Unit},
+    point3,
+    spectra::{Spectrum, SpectrumEnum},
+    vec3, Point2f, Point3f, SampledSpectrum, SampledWavelengths, Vec3f,
+};
+
+/// Infinitely far away light arriving from a single direction, e.g. the sun. Light comes from +z in light space.
+/// The scene bounds must be known before the flux can be computed, see [Light::preprocess]
+#[derive(Debug)]
+pub struct DistantLight {
+    base: BaseLight,
+    spectrum: Arc<SpectrumEnum>,
+    scale: f32,
+    scene_center: Point3f,
+    scene_radius: f32,
+}
+
+impl DistantLight {
+    pub fn new(spectrum: Arc<SpectrumEnum>, scale: f32, light_to_render: Transform<f32>) -> Self {
+        DistantLight {
+            base: BaseLight {
+                light_type: LightType::DeltaDirection,
+                light_to_render,
+            },
+            spectrum,
+            scale,
+            scene_center: point3!(0., 0., 0.),
+            scene_radius: 0.,
+        }
+    }
+}
+
+impl Light for DistantLight {
+    fn flux(&self, lambda: &SampledWavelengths) -> SampledSpectrum {
+        // Everything passing through a disk covering the scene
+        PI * self.scene_radius.powi(2) * self.scale * self.spectrum.sample(lambda)
+    }
+
+    fn light_type(&self) -> LightType { self.base.light_type }
+
+    fn sample(
+        &self,
+        surf_int: &SurfaceInteraction,
+        lambda: &SampledWavelengths,
+        rnd_p: Point2f,
+    ) -> Option<LightSample> {
+        let incoming = vec3!(0., 0., 1.).transform(&self.base.light_to_render).to_unit();
+        Some(LightSample {
+            radiance: self.spectrum.sample(lambda) * self.scale,
+            incoming,
+            pdf: 1.0,
+            point: surf_int.hit.point + incoming * (2. * self.scene_radius),
+        })
+    }
+
+    fn pdf_incoming(&self, incoming: Unit<Vec3f>, surf_int: &SurfaceInteraction) -> f32 { 0. }
+
+    fn preprocess(&mut self, scene_bounds: &Aabb<f32>) {
+        (self.scene_center, self.scene_radius) = scene_bounds.bounding_sphere();
+    }
+
+    fn bounds(&self) -> Option<LightBounds> { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{point2, spectra::ConstantSpectrum};
+
+    #[test]
+    fn test_distant() {
+        let spectrum = Arc::new(ConstantSpectrum::new(2.).into());
+        let light_to_render = Transform::rotate_from_to(&vec3!(0., 0., 1.), &vec3!(0., 1., 0.).to_unit());
+        let mut light = DistantLight::new(spectrum, 1.5, light_to_render);
+        light.preprocess(&Aabb::new(point3!(-1., -1., -1.), point3!(1., 1., 1.)));
+
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        assert_abs_diff_eq!(light.flux(&lambda)[0], PI * 3. * 3., epsilon = 1e-4);
+
+        let mut surf_int = SurfaceInteraction::default();
+        surf_int.hit.point = point3!(0.5, 0., 0.);
+        let sample = light.sample(&surf_int, &lambda, point2!(0.5, 0.5)).unwrap();
+        assert_abs_diff_eq!(*sample.incoming, vec3!(0., 1., 0.), epsilon = 1e-6);
+        assert_abs_diff_eq!(sample.radiance[0], 3.);
+        // Outside of the scene
+        assert!(sample.point.y > 3f32.sqrt());
+    }
+}
@@ -0,0 +1,144 @@
+use std::{f32::consts::PI, sync::Arc};
+
+use image::Rgb32FImage;
+
+use crate::{
+    aggregates::Aabb,
+    core::SurfaceInteraction,
+    light::{base::BaseLight, EnvironmentMapError, EnvironmentMapping, Light, LightBounds, LightSample, LightType},
+    math::{Normed, Transform, Transformable, Unit},
+    point3,
+    spectra::{Spectrum, SpectrumEnum},
+    unit3, Point2f, SampledSpectrum, SampledWavelengths, Vec3f,
+};
+
+/// Point light with the intensity varying by direction according to a square equal-area image, e.g. resampled from an
+/// IES profile by [load_ies](crate::light::ies::load_ies). +z is up in light space. RGB images are averaged
+#[derive(Debug)]
+pub struct GoniometricLight {
+    base: BaseLight,
+    spectrum: Arc<SpectrumEnum>,
+    scale: f32,
+    intensity: Vec<f32>,
+    resolution: u32,
+}
+
+impl GoniometricLight {
+    pub fn new(
+        spectrum: Arc<SpectrumEnum>,
+        scale: f32,
+        image: &Rgb32FImage,
+        light_to_render: Transform<f32>,
+    ) -> Result<Self, EnvironmentMapError> {
+        if image.width() == 0 {
+            return Err(EnvironmentMapError::Empty);
+        }
+        if image.width() != image.height() {
+            return Err(EnvironmentMapError::NotSquare);
+        }
+        let intensity = image.pixels().map(|pixel| pixel.0.iter().sum::<f32>() / 3.).collect();
+        Ok(GoniometricLight {
+            base: BaseLight {
+                light_type: LightType::DeltaPosition,
+                light_to_render,
+            },
+            spectrum,
+            scale,
+            intensity,
+            resolution: image.width(),
+        })
+    }
+
+    /// Average of the image over the sphere, the flux is `4 * PI` times that for unit `scale` and spectrum
+    pub fn average_intensity(&self) -> f32 { self.intensity.iter().sum::<f32>() / self.intensity.len() as f32 }
+
+    /// Relative intensity towards `dir` in light space
+    fn lookup(&self, dir: Unit<Vec3f>) -> f32 {
+        let uv = EnvironmentMapping::EqualArea.to_square(*dir);
+        let x = ((uv.x * self.resolution as f32) as u32).min(self.resolution - 1);
+        let y = ((uv.y * self.resolution as f32) as u32).min(self.resolution - 1);
+        self.intensity[(y * self.resolution + x) as usize].max(0.)
+    }
+}
+
+impl Light for GoniometricLight {
+    fn flux(&self, lambda: &SampledWavelengths) -> SampledSpectrum {
+        // Every pixel of the equal-area image covers the same solid angle
+        4. * PI * self.scale * self.average_intensity() * self.spectrum.sample(lambda)
+    }
+
+    fn light_type(&self) -> LightType { self.base.light_type }
+
+    fn sample(
+        &self,
+        surf_int: &SurfaceInteraction,
+        lambda: &SampledWavelengths,
+        rnd_p: Point2f,
+    ) -> Option<LightSample> {
+        let point = point3!(0., 0., 0.).transform(&self.base.light_to_render);
+        let vec = point - surf_int.hit.point;
+        let to_object = (-vec).inv_transform(&self.base.light_to_render).to_unit();
+        let intensity = self.lookup(to_object);
+        if intensity == 0. {
+            return None;
+        }
+        let radiance = self.spectrum.sample(lambda) * self.scale * intensity / vec.len_squared();
+        Some(LightSample {
+            radiance,
+            incoming: vec.to_unit(),
+            pdf: 1.0,
+            point,
+        })
+    }
+
+    fn pdf_incoming(&self, incoming: Unit<Vec3f>, surf_int: &SurfaceInteraction) -> f32 { 0. }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let point = point3!(0., 0., 0.).transform(&self.base.light_to_render);
+        Some(LightBounds {
+            bounds: Aabb::new(point, point),
+            w: unit3!(0., 0., 1.),
+            phi: 4. * PI * self.scale * self.spectrum.max_value() * self.average_intensity(),
+            cos_theta_o: -1.,
+            cos_theta_e: 0.,
+            two_sided: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{point2, spectra::ConstantSpectrum, vec3};
+
+    #[test]
+    fn test_goniometric() {
+        // Emits only into the lower hemisphere
+        let image = Rgb32FImage::from_fn(32, 32, |x, y| {
+            let uv = point2!((x as f32 + 0.5) / 32., (y as f32 + 0.5) / 32.);
+            let dir = EnvironmentMapping::EqualArea.to_sphere(uv);
+            image::Rgb([if dir.z < 0. { 2. } else { 0. }; 3])
+        });
+        let spectrum: Arc<SpectrumEnum> = Arc::new(ConstantSpectrum::new(1.).into());
+        let light =
+            GoniometricLight::new(spectrum.clone(), 3., &image, Transform::translate(vec3!(0., 0., 2.))).unwrap();
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        let uniform = Rgb32FImage::from_pixel(32, 32, image::Rgb([2., 2., 2.]));
+        let uniform = GoniometricLight::new(spectrum.clone(), 3., &uniform, Transform::id()).unwrap();
+        assert_abs_diff_eq!(uniform.flux(&lambda)[0], 4. * PI * 3. * 2., epsilon = 1e-3);
+
+        let mut surf_int = SurfaceInteraction::default();
+        let sample = light.sample(&surf_int, &lambda, point2!(0.5, 0.5)).unwrap();
+        assert_abs_diff_eq!(sample.radiance[0], 3. * 2. / 4.);
+        assert_abs_diff_eq!(*sample.incoming, vec3!(0., 0., 1.));
+
+        surf_int.hit.point = point3!(0., 0., 4.);
+        assert!(light.sample(&surf_int, &lambda, point2!(0.5, 0.5)).is_none());
+
+        let wide = Rgb32FImage::new(8, 4);
+        let err = GoniometricLight::new(spectrum, 1., &wide, Transform::id()).unwrap_err();
+        assert_eq!(err, EnvironmentMapError::NotSquare);
+    }
+}
@@ -0,0 +1,272 @@
+//! IESNA LM-63 photometric profiles for [GoniometricLight](crate::light::GoniometricLight). Only type C photometry,
+//! the one used by nearly all architectural luminaires, is supported. Tilt data is ignored.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use derive_more::{Display, Error};
+use image::Rgb32FImage;
+
+use crate::{light::EnvironmentMapping, point2};
+
+#[derive(Debug, Display, Error)]
+pub enum IesLoadError {
+    #[display("failed to read {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[display("{}: {message}", path.display())]
+    Invalid {
+        path: PathBuf,
+        #[error(not(source))]
+        message: String,
+    },
+}
+
+/// Side of the equal-area images profiles are resampled into. Profiles are smooth, this keeps lookups accurate to
+/// about half a degree
+const RESOLUTION: u32 = 256;
+
+/// Loads a profile resampled into a square equal-area image, with intensities in candela. +z is up in light space and
+/// the horizontal angle 0 points along +x
+pub fn load_ies(path: impl AsRef<Path>) -> Result<Rgb32FImage, IesLoadError> {
+    let path = path.as_ref();
+    let source = fs::read(path).map_err(|source| IesLoadError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    // Many files use Latin-1 in their keywords, only the numbers matter
+    let profile = IesProfile::parse(&String::from_utf8_lossy(&source)).map_err(|message| IesLoadError::Invalid {
+        path: path.to_path_buf(),
+        message,
+    })?;
+    Ok(profile.to_equal_area(RESOLUTION))
+}
+
+#[derive(Debug)]
+struct IesProfile {
+    /// Degrees from the nadir, increasing
+    vertical_angles: Vec<f32>,
+    /// Degrees counterclockwise from +x, increasing
+    horizontal_angles: Vec<f32>,
+    /// Candela for every horizontal angle, then every vertical angle
+    candela: Vec<f32>,
+}
+
+impl IesProfile {
+    /// Angle counts of real profiles are at most a few hundred, larger ones are treated as malformed files
+    const MAX_ANGLES: usize = 3601;
+    const MAX_CANDELA_VALUES: usize = 1 << 22;
+
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut lines = source.lines();
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if let Some(tilt) = line.strip_prefix("TILT=") {
+                if tilt != "NONE" && tilt != "INCLUDE" {
+                    return Err(format!("tilt file `{tilt}` is not supported"));
+                }
+                let mut numbers = lines
+                    .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.parse::<f32>().map_err(|_| format!("invalid number `{x}`")));
+                let mut next = || {
+                    numbers
+                        .next()
+                        .unwrap_or_else(|| Err("unexpected end of file".to_string()))
+                };
+                if tilt == "INCLUDE" {
+                    let _geometry = next()?;
+                    let count = next()? as usize;
+                    for _ in 0..2 * count {
+                        next()?;
+                    }
+                }
+                return Self::parse_data(next);
+            }
+        }
+        Err("missing `TILT=` line".to_string())
+    }
+
+    fn parse_data(mut next: impl FnMut() -> Result<f32, String>) -> Result<Self, String> {
+        let _lamps = next()?;
+        let _lumens = next()?;
+        let multiplier = next()?;
+        let vertical_count = Self::parse_count(next()?)?;
+        let horizontal_count = Self::parse_count(next()?)?;
+        if next()? != 1. {
+            return Err("only type C photometry is supported".to_string());
+        }
+        // Units and luminous opening dimensions
+        for _ in 0..4 {
+            next()?;
+        }
+        let ballast_factor = next()?;
+        let ballast_lamp_factor = next()?;
+        let _input_watts = next()?;
+        let candela_count = vertical_count
+            .checked_mul(horizontal_count)
+            .filter(|count| *count <= Self::MAX_CANDELA_VALUES)
+            .ok_or("too many candela values")?;
+
+        let mut read = |count| (0..count).map(|_| next()).collect::<Result<Vec<_>, _>>();
+        let vertical_angles = read(vertical_count)?;
+        let horizontal_angles = read(horizontal_count)?;
+        let scale = multiplier * ballast_factor * ballast_lamp_factor;
+        let candela = read(candela_count)?.into_iter().map(|x| x * scale).collect();
+        for angles in [&vertical_angles, &horizontal_angles] {
+            if !angles.windows(2).all(|x| x[0] < x[1]) {
+                return Err("angles must be increasing".to_string());
+            }
+        }
+        Ok(IesProfile {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+        })
+    }
+
+    fn parse_count(value: f32) -> Result<usize, String> {
+        if value < 1. || value.fract() != 0. {
+            return Err("the profile has no angles".to_string());
+        }
+        if value > Self::MAX_ANGLES as f32 {
+            return Err(format!("more than {} angles", Self::MAX_ANGLES));
+        }
+        Ok(value as usize)
+    }
+
+    /// Intensity towards a direction, `vertical` is in [0, 180] and `horizontal` in [0, 360) degrees
+    fn intensity(&self, vertical: f32, horizontal: f32) -> f32 {
+        let (first, last) = (self.horizontal_angles[0], *self.horizontal_angles.last().unwrap());
+        // Fold the direction into the range covered by the data according to its symmetry
+        let horizontal = if last == 0. {
+            0.
+        } else if last <= 90. {
+            let h = if horizontal > 180. {
+                360. - horizontal
+            } else {
+                horizontal
+            };
+            if h > 90. {
+                180. - h
+            } else {
+                h
+            }
+        } else if last <= 180. {
+            if horizontal > 180. {
+                360. - horizontal
+            } else {
+                horizontal
+            }
+        } else if first >= 90. && last <= 270. {
+            if horizontal < 90. {
+                180. - horizontal
+            } else if horizontal > 270. {
+                540. - horizontal
+            } else {
+                horizontal
+            }
+        } else {
+            horizontal
+        };
+
+        let Some((v, v_t)) = Self::segment(&self.vertical_angles, vertical) else {
+            return 0.;
+        };
+        let Some((h, h_t)) = Self::segment(&self.horizontal_angles, horizontal) else {
+            return 0.;
+        };
+        let stride = self.vertical_angles.len();
+        let at = |h: usize, v: usize| self.candela[h * stride + v];
+        let h1 = (h + 1).min(self.horizontal_angles.len() - 1);
+        let v1 = (v + 1).min(stride - 1);
+        let lower = at(h, v) * (1. - v_t) + at(h, v1) * v_t;
+        let upper = at(h1, v) * (1. - v_t) + at(h1, v1) * v_t;
+        lower * (1. - h_t) + upper * h_t
+    }
+
+    /// Index of the angle at or below `angle` and the interpolation weight towards the next one
+    fn segment(angles: &[f32], angle: f32) -> Option<(usize, f32)> {
+        if angles.len() == 1 {
+            return (angles[0] == angle).then_some((0, 0.));
+        }
+        if angle < angles[0] || angle > *angles.last().unwrap() {
+            return None;
+        }
+        let i = angles.partition_point(|x| *x <= angle).clamp(1, angles.len() - 1) - 1;
+        Some((i, (angle - angles[i]) / (angles[i + 1] - angles[i])))
+    }
+
+    fn to_equal_area(&self, resolution: u32) -> Rgb32FImage {
+        Rgb32FImage::from_fn(resolution, resolution, |x, y| {
+            let uv = point2!(
+                (x as f32 + 0.5) / resolution as f32,
+                (y as f32 + 0.5) / resolution as f32
+            );
+            let dir = EnvironmentMapping::EqualArea.to_sphere(uv);
+            // Type C vertical angles start at the nadir
+            let vertical = (-dir.z).clamp(-1., 1.).acos().to_degrees();
+            let horizontal = dir.y.atan2(dir.x).to_degrees().rem_euclid(360.);
+            image::Rgb([self.intensity(vertical, horizontal); 3])
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    // Downlight with a quadrant-symmetric distribution, brighter towards 90 degrees horizontally
+    const PROFILE: &str = "IESNA:LM-63-2002
+[TEST] test
+[MANUFAC] rusttracer
+TILT=NONE
+1 1000 2.0 3 2 1 2 0.1 0.1 0.0
+1.0 1.0 10
+0 45 90
+0, 90
+100 50 0
+200 100 0
+";
+
+    #[test]
+    fn test_parse() {
+        let profile = IesProfile::parse(PROFILE).unwrap();
+        assert_eq!(profile.vertical_angles, [0., 45., 90.]);
+        assert_abs_diff_eq!(profile.intensity(0., 0.), 200.);
+        assert_abs_diff_eq!(profile.intensity(0., 90.), 400.);
+        assert_abs_diff_eq!(profile.intensity(22.5, 45.), 225.);
+        // Mirrored into the first quadrant
+        assert_abs_diff_eq!(profile.intensity(0., 270.), 400.);
+        assert_abs_diff_eq!(profile.intensity(45., 180.), 100.);
+        assert_eq!(profile.intensity(135., 0.), 0.);
+
+        let image = profile.to_equal_area(16);
+        let down = EnvironmentMapping::EqualArea.to_square(crate::vec3!(0., 0., -1.));
+        let pixel = |uv: crate::Point2f| {
+            image
+                .get_pixel(((uv.x * 16.) as u32).min(15), ((uv.y * 16.) as u32).min(15))
+                .0[0]
+        };
+        assert!(pixel(down) > 150.);
+        assert_eq!(pixel(point2!(0.5, 0.5)), 0.);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(IesProfile::parse("IESNA:LM-63-2002\n").unwrap_err().contains("TILT"));
+        let truncated = PROFILE.replace("200 100 0\n", "");
+        assert!(IesProfile::parse(&truncated).unwrap_err().contains("end of file"));
+        let type_b = PROFILE.replace("3 2 1 2", "3 2 2 2");
+        assert!(IesProfile::parse(&type_b).unwrap_err().contains("type C"));
+        let huge = PROFILE.replace("3 2 1 2", "1e30 2 1 2");
+        assert!(IesProfile::parse(&huge).unwrap_err().contains("angles"));
+        let too_many = PROFILE.replace("3 2 1 2", "3000 3000 1 2");
+        assert!(IesProfile::parse(&too_many).unwrap_err().contains("too many"));
+        let empty = PROFILE.replace("3 2 1 2", "0 2 1 2");
+        assert!(IesProfile::parse(&empty).unwrap_err().contains("no angles"));
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    f32::consts::{FRAC_PI_4, PI},
+    sync::Arc,
+};
+
+use derive_more::{Display, Error};
+use image::Rgb32FImage;
+
+use crate::{
+    aggregates::Aabb,
+    core::{Ray, SurfaceInteraction},
+    light::{base::BaseLight, Light, LightBounds, LightSample, LightType},
+    math::{Normed, Transform, Transformable, Unit},
+    point2, point3,
+    samplers::piecewise::PiecewiseConstant2D,
+    spectra::{
+        rgb::{sRGB, RGBColorSpace, RGB},
+        RGBIlluminantSpectrum, Spectrum,
+    },
+    vec3, Point2f, Point3f, SampledSpectrum, SampledWavelengths, Vec3f,
+};
+
+/// How directions in light space map onto the environment image. `(0, 0)` is the top left corner of the image
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EnvironmentMapping {
+    /// Latitude-longitude map. +y is up, the center of the image is at +z and +x is to the right of it
+    Equirectangular,
+    /// Square image with the equal-area octahedral mapping used by pbrt-v4. +z is up
+    EqualArea,
+}
+
+impl EnvironmentMapping {
+    pub fn to_square(self, dir: Vec3f) -> Point2f {
+        match self {
+            EnvironmentMapping::Equirectangular => {
+                let phi = f32::atan2(dir.x, dir.z);
+                let theta = dir.y.clamp(-1., 1.).acos();
+                point2!((phi + PI) / (2. * PI), theta / PI)
+            }
+            EnvironmentMapping::EqualArea => {
+                let (x, y, z) = (dir.x.abs(), dir.y.abs(), dir.z.abs());
+                let r = (1. - z).max(0.).sqrt();
+                let (a, b) = (x.max(y), x.min(y));
+                let b = if a == 0. { 0. } else { b / a };
+                let mut phi = b.atan() / FRAC_PI_4 / 2.;
+                if x < y {
+                    phi = 1. - phi;
+                }
+                let mut v = phi * r;
+                let mut u = r - v;
+                if dir.z < 0. {
+                    (u, v) = (1. - v, 1. - u);
+                }
+                point2!(0.5 * (u.copysign(dir.x) + 1.), 0.5 * (v.copysign(dir.y) + 1.))
+            }
+        }
+    }
+
+    pub fn to_sphere(self, uv: Point2f) -> Vec3f {
+        match self {
+            EnvironmentMapping::Equirectangular => {
+                let phi = 2. * PI * uv.x - PI;
+                let theta = PI * uv.y;
+                vec3!(theta.sin() * phi.sin(), theta.cos(), theta.sin() * phi.cos())
+            }
+            EnvironmentMapping::EqualArea => {
+                let (u, v) = (2. * uv.x - 1., 2. * uv.y - 1.);
+                let (up, vp) = (u.abs(), v.abs());
+                // Signed distance from the diagonal
+                let signed_distance = 1. - (up + vp);
+                let r = 1. - signed_distance.abs();
+                let phi = if r == 0. { 1. } else { (vp - up) / r + 1. } * FRAC_PI_4;
+                let z = (1. - r * r).copysign(signed_distance);
+                let sin_theta = r * (2. - r * r).max(0.).sqrt();
+                vec3!(phi.cos().copysign(u) * sin_theta, phi.sin().copysign(v) * sin_theta, z)
+            }
+        }
+    }
+
+    /// Ratio of the solid angle to the image area around `uv`, converts PDFs over the image to solid angle
+    fn jacobian(self, uv: Point2f) -> f32 {
+        match self {
+            EnvironmentMapping::Equirectangular => 2. * PI * PI * (PI * uv.y).sin(),
+            EnvironmentMapping::EqualArea => 4. * PI,
+        }
+    }
+}
+
+#[derive(Debug, Display, Error, PartialEq)]
+pub enum EnvironmentMapError {
+    #[display("environment map is empty")]
+    Empty,
+    #[display("equal-area environment map must be square")]
+    NotSquare,
+}
+
+/// Infinitely far away sphere with emission defined by an environment map. Directions are importance sampled according
+/// to the brightness of the map
+#[derive(Debug)]
+pub struct ImageInfiniteLight {
+    base: BaseLight,
+    image: Rgb32FImage,
+    mapping: EnvironmentMapping,
+    color_space: Arc<RGBColorSpace>,
+    scale: f32,
+    distribution: PiecewiseConstant2D,
+    scene_center: Point3f,
+    scene_radius: f32,
+}
+
+impl ImageInfiniteLight {
+    /// `image` holds linear sRGB values
+    pub fn new(
+        image: Rgb32FImage,
+        mapping: EnvironmentMapping,
+        scale: f32,
+        light_to_render: Transform<f32>,
+    ) -> Result<Self, EnvironmentMapError> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err(EnvironmentMapError::Empty);
+        }
+        if mapping == EnvironmentMapping::EqualArea && image.width() != image.height() {
+            return Err(EnvironmentMapError::NotSquare);
+        }
+
+        // Brightness of each pixel, weighted by the solid angle it covers
+        let (width, height) = image.dimensions();
+        let func: Vec<f32> = image
+            .enumerate_pixels()
+            .map(|(x, y, pixel)| {
+                let uv = point2!((x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32);
+                pixel.0.iter().map(|x| x.max(0.)).sum::<f32>() / 3. * mapping.jacobian(uv)
+            })
+            .collect();
+
+        Ok(ImageInfiniteLight {
+            base: BaseLight {
+                light_type: LightType::Infinite,
+                light_to_render,
+            },
+            distribution: PiecewiseConstant2D::new(&func, width as usize),
+            image,
+            mapping,
+            color_space: sRGB.clone(),
+            scale,
+            scene_center: point3!(0., 0., 0.),
+            scene_radius: 0.,
+        })
+    }
+
+    fn lookup(&self, uv: Point2f) -> RGB {
+        let (width, height) = self.image.dimensions();
+        let x = ((uv.x * width as f32) as u32).min(width - 1);
+        let y = ((uv.y * height as f32) as u32).min(height - 1);
+        let [r, g, b] = self.image.get_pixel(x, y).0.map(|x| x.max(0.));
+        RGB::new(r, g, b)
+    }
+
+    fn radiance_at(&self, uv: Point2f, lambda: &SampledWavelengths) -> SampledSpectrum {
+        RGBIlluminantSpectrum::new(&self.color_space, self.lookup(uv)).sample(lambda) * self.scale
+    }
+
+    fn to_light_square(&self, dir: Unit<Vec3f>) -> Point2f {
+        let dir_light = (*dir).inv_transform(&self.base.light_to_render).to_unit();
+        self.mapping.to_square(*dir_light)
+    }
+}
+
+impl Light for ImageInfiniteLight {
+    fn flux(&self, lambda: &SampledWavelengths) -> SampledSpectrum {
+        // Integrate radiance over the sphere of directions
+        let (width, height) = self.image.dimensions();
+        let mut radiance = SampledSpectrum::default();
+        for y in 0..height {
+            for x in 0..width {
+                let uv = point2!((x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32);
+                radiance += self.radiance_at(uv, lambda) * self.mapping.jacobian(uv);
+            }
+        }
+        radiance * PI * self.scene_radius.powi(2) / (width * height) as f32
+    }
+
+    fn light_type(&self) -> LightType { self.base.light_type }
+
+    fn sample(
+        &self,
+        surf_int: &SurfaceInteraction,
+        lambda: &SampledWavelengths,
+        rnd_p: Point2f,
+    ) -> Option<LightSample> {
+        let (uv, map_pdf) = self.distribution.sample(rnd_p);
+        let jacobian = self.mapping.jacobian(uv);
+        if map_pdf == 0. || jacobian == 0. {
+            return None;
+        }
+        let incoming = self
+            .mapping
+            .to_sphere(uv)
+            .transform(&self.base.light_to_render)
+            .to_unit();
+        Some(LightSample {
+            radiance: self.radiance_at(uv, lambda),
+            incoming,
+            pdf: map_pdf / jacobian,
+            point: surf_int.hit.point + incoming * (2. * self.scene_radius),
+        })
+    }
+
+    fn pdf_incoming(&self, incoming: Unit<Vec3f>, surf_int: &SurfaceInteraction) -> f32 {
+        let uv = self.to_light_square(incoming);
+        let jacobian = self.mapping.jacobian(uv);
+        if jacobian == 0. {
+            0.
+        } else {
+            self.distribution.pdf(uv) / jacobian
+        }
+    }
+
+    fn radiance_escaped(&self, ray: &Ray, lambda: &SampledWavelengths) -> Option<SampledSpectrum> {
+        Some(self.radiance_at(self.to_light_square(ray.dir), lambda))
+    }
+
+    fn preprocess(&mut self, scene_bounds: &Aabb<f32>) {
+        (self.scene_center, self.scene_radius) = scene_bounds.bounding_sphere();
+    }
+
+    fn bounds(&self) -> Option<LightBounds> { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::samplers::utils::sample_uniform_sphere;
+
+    #[test]
+    fn test_mappings() {
+        for mapping in [EnvironmentMapping::Equirectangular, EnvironmentMapping::EqualArea] {
+            for i in 0..64 {
+                let u = point2!((i % 8) as f32 / 8. + 0.05, (i / 8) as f32 / 8. + 0.05);
+                let dir = sample_uniform_sphere(u);
+                let uv = mapping.to_square(*dir);
+                assert!((0. ..=1.).contains(&uv.x) && (0. ..=1.).contains(&uv.y));
+                assert_abs_diff_eq!(mapping.to_sphere(uv), *dir, epsilon = 1e-4);
+            }
+        }
+        let equirect = EnvironmentMapping::Equirectangular;
+        assert_abs_diff_eq!(equirect.to_square(vec3!(0., 0., 1.)), point2!(0.5, 0.5));
+        assert_abs_diff_eq!(equirect.to_square(vec3!(1., 0., 0.)), point2!(0.75, 0.5));
+        assert_abs_diff_eq!(equirect.to_square(vec3!(0., 1., 0.)).y, 0.);
+    }
+
+    #[test]
+    fn test_pdf() {
+        // Single bright pixel in the upper half
+        let mut image = Rgb32FImage::new(8, 4);
+        image.put_pixel(5, 1, image::Rgb([1., 2., 3.]));
+        let light = ImageInfiniteLight::new(image, EnvironmentMapping::Equirectangular, 1., Transform::id()).unwrap();
+
+        let surf_int = SurfaceInteraction::default();
+        for rnd_p in [point2!(0.1, 0.2), point2!(0.7, 0.9)] {
+            let (uv, map_pdf) = light.distribution.sample(rnd_p);
+            assert_eq!(((uv.x * 8.) as u32, (uv.y * 4.) as u32), (5, 1));
+
+            let incoming = light.mapping.to_sphere(uv).to_unit();
+            assert!(incoming.y > 0.);
+            let pdf = light.pdf_incoming(incoming, &surf_int);
+            assert_abs_diff_eq!(pdf, map_pdf / light.mapping.jacobian(uv), epsilon = 1e-3);
+        }
+        assert_eq!(light.pdf_incoming(vec3!(0., -1., 0.).to_unit(), &surf_int), 0.);
+    }
+
+    #[test]
+    fn test_invalid_image() {
+        let new = |width, height, mapping| {
+            ImageInfiniteLight::new(Rgb32FImage::new(width, height), mapping, 1., Transform::id()).err()
+        };
+        assert_eq!(
+            new(0, 0, EnvironmentMapping::Equirectangular),
+            Some(EnvironmentMapError::Empty)
+        );
+        assert_eq!(
+            new(8, 4, EnvironmentMapping::EqualArea),
+            Some(EnvironmentMapError::NotSquare)
+        );
+        assert_eq!(new(8, 4, EnvironmentMapping::Equirectangular), None);
+    }
+}
@@ -0,0 +1,105 @@
+use num_traits::Zero;
+
+use crate::{
+    aggregates::Aabb,
+    math::{DirectionCone, Dot, Normed, Unit},
+    Point3f, Vec3f,
+};
+
+/// Spatial and directional bounds of the emission of a light or a group of lights, used to estimate how much it
+/// contributes to a point
+#[derive(Copy, Clone, Debug)]
+pub struct LightBounds {
+    pub bounds: Aabb<f32>,
+    /// Principal direction of emission
+    pub w: Unit<Vec3f>,
+    /// Upper bound of the emitted power
+    pub phi: f32,
+    /// Cosine of the angle around `w` that bounds the normals of the emitters
+    pub cos_theta_o: f32,
+    /// Cosine of the angle beyond `theta_o` at which emission still happens
+    pub cos_theta_e: f32,
+    pub two_sided: bool,
+}
+
+fn cos_sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> f32 {
+    if cos_a > cos_b {
+        1.
+    } else {
+        cos_a * cos_b + sin_a * sin_b
+    }
+}
+
+fn sin_sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> f32 {
+    if cos_a > cos_b {
+        0.
+    } else {
+        sin_a * cos_b - cos_a * sin_b
+    }
+}
+
+fn sin_from_cos(cos: f32) -> f32 { (1. - cos * cos).max(0.).sqrt() }
+
+impl LightBounds {
+    pub fn centroid(&self) -> Point3f { self.bounds.center() }
+
+    /// Conservative estimate of the contribution to point `p` with normal `n`. `n` may be zero for points in media
+    pub fn importance(&self, p: Point3f, n: Vec3f) -> f32 {
+        let center = self.centroid();
+        let dist_squared = (p - center)
+            .len_squared()
+            .max((self.bounds.max - self.bounds.min).len() / 2.);
+
+        // Angle between the principal direction and the direction to `p`
+        let to_point = p - center;
+        let mut cos_theta_w = if to_point.len_squared() == 0. {
+            1.
+        } else {
+            self.w.dot(&to_point.to_unit())
+        };
+        if self.two_sided {
+            cos_theta_w = cos_theta_w.abs();
+        }
+        let sin_theta_w = sin_from_cos(cos_theta_w);
+
+        // Angular extent of the bounds as seen from `p`
+        let cos_theta_b = DirectionCone::bound_subtended_directions(&self.bounds, p).cos_theta;
+        let sin_theta_b = sin_from_cos(cos_theta_b);
+
+        // Minimal angle between the emitters' normals and the direction to `p`
+        let sin_theta_o = sin_from_cos(self.cos_theta_o);
+        let cos_theta_x = cos_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+        let sin_theta_x = sin_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+        let cos_theta_p = cos_sub_clamped(sin_theta_x, cos_theta_x, sin_theta_b, cos_theta_b);
+        if cos_theta_p <= self.cos_theta_e {
+            return 0.;
+        }
+
+        let mut importance = self.phi * cos_theta_p / dist_squared;
+        if !n.is_zero() && to_point.len_squared() != 0. {
+            // Minimal incident angle at the receiving surface
+            let cos_theta_i = to_point.to_unit().dot(&n).abs();
+            let sin_theta_i = sin_from_cos(cos_theta_i);
+            importance *= cos_sub_clamped(sin_theta_i, cos_theta_i, sin_theta_b, cos_theta_b);
+        }
+        importance.max(0.)
+    }
+
+    pub fn union(&self, other: &LightBounds) -> LightBounds {
+        if self.phi == 0. {
+            return *other;
+        }
+        if other.phi == 0. {
+            return *self;
+        }
+        let cone = DirectionCone::new(self.w, self.cos_theta_o).union(&DirectionCone::new(other.w, other.cos_theta_o));
+        LightBounds {
+            bounds: self.bounds + other.bounds,
+            w: cone.w,
+            phi: self.phi + other.phi,
+            cos_theta_o: cone.cos_theta,
+            cos_theta_e: self.cos_theta_e.min(other.cos_theta_e),
+            two_sided: self.two_sided || other.two_sided,
+        }
+    }
+}
@@ -1,12 +1,12 @@
 use std::sync::Arc;
 
+use strum_macros::{Display, EnumString};
+
 use crate::{
     core::SurfaceInteraction,
-    light::{Light, LightEnum, LightSample},
+    light::{BVHLightSampler, Light, LightEnum, LightSample, PowerLightSampler, UniformLightSampler},
 };
 
-// TODO: power and bvh light sampler
-
 pub struct SampledLight {
     pub light: Arc<LightEnum>,
     pub prob: f32,
@@ -17,3 +17,52 @@ pub trait LightSampler {
     fn pmf(&self, surf_int: &SurfaceInteraction, light: &LightEnum) -> f32;
     // todo: no-ctx variants
 }
+
+// enum_delegate doesn't support lifetimes, so the trait is delegated by hand
+pub enum LightSamplerType<'a> {
+    Uniform(UniformLightSampler<'a>),
+    Power(PowerLightSampler<'a>),
+    BVH(BVHLightSampler<'a>),
+}
+
+/// Strategy for choosing a light to sample, named as in pbrt's `lightsampler` parameter
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum LightSamplerKind {
+    /// All lights are equally likely
+    Uniform,
+    /// Proportionally to the emitted power
+    Power,
+    /// According to the estimated contribution to the shading point
+    #[default]
+    BVH,
+}
+
+impl<'a> LightSamplerType<'a> {
+    pub fn new(kind: LightSamplerKind, lights: &'a Vec<Arc<LightEnum>>) -> Self {
+        match kind {
+            LightSamplerKind::Uniform => LightSamplerType::Uniform(UniformLightSampler { lights }),
+            LightSamplerKind::Power => LightSamplerType::Power(PowerLightSampler::new(lights)),
+            LightSamplerKind::BVH => LightSamplerType::BVH(BVHLightSampler::new(lights)),
+        }
+    }
+}
+
+impl LightSampler for LightSamplerType<'_> {
+    fn sample(&self, surf_int: &SurfaceInteraction, rnd_c: f32) -> Option<SampledLight> {
+        match self {
+            LightSamplerType::Uniform(x) => x.sample(surf_int, rnd_c),
+            LightSamplerType::Power(x) => x.sample(surf_int, rnd_c),
+            LightSamplerType::BVH(x) => x.sample(surf_int, rnd_c),
+        }
+    }
+
+    fn pmf(&self, surf_int: &SurfaceInteraction, light: &LightEnum) -> f32 {
+        match self {
+            LightSamplerType::Uniform(x) => x.pmf(surf_int, light),
+            LightSamplerType::Power(x) => x.pmf(surf_int, light),
+            LightSamplerType::BVH(x) => x.pmf(surf_int, light),
+        }
+    }
+}
@@ -1,19 +1,41 @@
 use std::fmt::Debug;
 
 use bitflags::bitflags;
-pub use diffuse_area::DiffuseAreaLight;
-pub use light_sampler::LightSampler;
+pub use bvh_sampler::BVHLightSampler;
+pub use diffuse_area::{AreaEmission, DiffuseAreaLight};
+pub use distant::DistantLight;
+pub use goniometric::GoniometricLight;
+pub use image_infinite::{EnvironmentMapError, EnvironmentMapping, ImageInfiniteLight};
+pub use light_bounds::LightBounds;
+pub use light_sampler::{LightSampler, LightSamplerKind, LightSamplerType};
 pub use point::PointLight;
+pub use power_sampler::PowerLightSampler;
+pub use projection::ProjectionLight;
 pub use spotlight::Spotlight;
+pub use uniform_infinite::UniformInfiniteLight;
 pub use uniform_sampler::UniformLightSampler;
 
-use crate::{core::SurfaceInteraction, math::Unit, Point2f, Point3f, SampledSpectrum, SampledWavelengths, Vec3f};
+use crate::{
+    aggregates::Aabb,
+    core::{Ray, SurfaceInteraction},
+    math::Unit,
+    Point2f, Point3f, SampledSpectrum, SampledWavelengths, Vec3f,
+};
 
 mod base;
+mod bvh_sampler;
 mod diffuse_area;
+mod distant;
+mod goniometric;
+pub mod ies;
+mod image_infinite;
+mod light_bounds;
 mod light_sampler;
 mod point;
+mod power_sampler;
+mod projection;
 mod spotlight;
+mod uniform_infinite;
 mod uniform_sampler;
 
 // TODO: new from rgb?
@@ -39,8 +61,16 @@ pub trait Light {
     // Assume that a ray from `surf_int` in direction `incoming` has already been found to intersect the light source
     fn pdf_incoming(&self, incoming: Unit<Vec3f>, surf_int: &SurfaceInteraction) -> f32;
 
-    // todo: for infinite lights
-    //       fn Le(&self, ...) -> ... {}
+    /// Radiance arriving along a ray that escaped the scene. Only for infinite lights.
+    /// Le() in PBRT
+    fn radiance_escaped(&self, ray: &Ray, lambda: &SampledWavelengths) -> Option<SampledSpectrum> { None }
+
+    /// Called once the scene geometry is known, before rendering.
+    /// Infinite and distant lights use the scene bounds to place their samples outside the scene.
+    fn preprocess(&mut self, scene_bounds: &Aabb<f32>) {}
+
+    /// Bounds of the emission used by the light samplers. `None` for infinite lights
+    fn bounds(&self) -> Option<LightBounds>;
 }
 
 bitflags! {
@@ -74,5 +104,10 @@ pub struct LightSample {
 pub enum LightEnum {
     Point(PointLight),
     Spot(Spotlight),
+    Goniometric(GoniometricLight),
+    Projection(ProjectionLight),
+    Distant(DistantLight),
     DiffuseArea(DiffuseAreaLight),
+    UniformInfinite(UniformInfiniteLight),
+    ImageInfinite(ImageInfiniteLight),
 }
@@ -3,12 +3,13 @@ use std::{f32::consts::PI, sync::Arc};
 use image::{Pixel, Rgb};
 
 use crate::{
+    aggregates::Aabb,
     core::SurfaceInteraction,
-    light::{base::BaseLight, Light, LightSample, LightType},
+    light::{base::BaseLight, Light, LightBounds, LightSample, LightType},
     math::{Normed, Transform, Transformable, Unit},
     point3,
     spectra::{Spectrum, SpectrumEnum},
-    Point2f, SampledSpectrum, SampledWavelengths, Vec3f,
+    unit3, Point2f, SampledSpectrum, SampledWavelengths, Vec3f,
 };
 
 #[derive(Debug)]
@@ -58,4 +59,16 @@ impl Light for PointLight {
     }
 
     fn pdf_incoming(&self, incoming: Unit<Vec3f>, surf_int: &SurfaceInteraction) -> f32 { 0. }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let point = point3!(0., 0., 0.).transform(&self.base.light_to_render);
+        Some(LightBounds {
+            bounds: Aabb::new(point, point),
+            w: unit3!(0., 0., 1.),
+            phi: 4. * PI * self.scale * self.spectrum.max_value(),
+            cos_theta_o: -1.,
+            cos_theta_e: 0.,
+            two_sided: false,
+        })
+    }
 }
@@ -0,0 +1,98 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    core::SurfaceInteraction,
+    light::{
+        light_sampler::{LightSampler, SampledLight},
+        Light, LightEnum,
+    },
+    samplers::alias_table::AliasTable,
+    SampledWavelengths,
+};
+
+/// Picks lights with probability proportional to their emitted power, ignoring the receiving point
+pub struct PowerLightSampler<'a> {
+    lights: &'a Vec<Arc<LightEnum>>,
+    alias_table: AliasTable,
+    /// Light's address to its index in `lights`
+    light_to_index: HashMap<usize, usize>,
+}
+
+impl<'a> PowerLightSampler<'a> {
+    pub fn new(lights: &'a Vec<Arc<LightEnum>>) -> Self {
+        // Average power over the visible wavelengths
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        let mut weights: Vec<f32> = lights
+            .iter()
+            .map(|light| (light.flux(&lambda) / lambda.pdf()).avg())
+            .collect();
+        if weights.iter().all(|&x| x == 0.) {
+            weights.fill(1.);
+        }
+
+        PowerLightSampler {
+            lights,
+            alias_table: AliasTable::new(&weights),
+            light_to_index: lights
+                .iter()
+                .enumerate()
+                .map(|(i, light)| (Arc::as_ptr(light) as usize, i))
+                .collect(),
+        }
+    }
+}
+
+impl LightSampler for PowerLightSampler<'_> {
+    fn sample(&self, surf_int: &SurfaceInteraction, rnd_c: f32) -> Option<SampledLight> {
+        let (index, prob, _) = self.alias_table.sample(rnd_c)?;
+        Some(SampledLight {
+            light: self.lights[index].clone(),
+            prob,
+        })
+    }
+
+    fn pmf(&self, surf_int: &SurfaceInteraction, light: &LightEnum) -> f32 {
+        self.light_to_index
+            .get(&(light as *const LightEnum as usize))
+            .map_or(0., |&index| self.alias_table.pmf(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{
+        light::PointLight,
+        math::Transform,
+        spectra::{ConstantSpectrum, SpectrumEnum},
+    };
+
+    #[test]
+    fn test_power_sampler() {
+        let spectrum = Arc::new(SpectrumEnum::Constant(ConstantSpectrum::new(1.)));
+        let lights: Vec<_> = [1., 3.]
+            .map(|scale| {
+                Arc::new(LightEnum::Point(PointLight::new(
+                    spectrum.clone(),
+                    scale,
+                    Transform::id(),
+                )))
+            })
+            .into();
+        let sampler = PowerLightSampler::new(&lights);
+
+        let surf_int = SurfaceInteraction::default();
+        assert_abs_diff_eq!(sampler.pmf(&surf_int, &lights[0]), 0.25);
+        assert_abs_diff_eq!(sampler.pmf(&surf_int, &lights[1]), 0.75);
+
+        let sampled = sampler.sample(&surf_int, 0.9).unwrap();
+        assert!(Arc::ptr_eq(&sampled.light, &lights[1]));
+        assert_abs_diff_eq!(sampled.prob, 0.75);
+
+        // Lights that are not known to the sampler are never sampled
+        let other = LightEnum::Point(PointLight::new(spectrum, 1., Transform::id()));
+        assert_eq!(sampler.pmf(&surf_int, &other), 0.);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{f32::consts::PI, sync::Arc};
+
+use image::Rgb32FImage;
+
+use crate::{
+    aggregates::Aabb,
+    core::SurfaceInteraction,
+    light::{base::BaseLight, Light, LightBounds, LightSample, LightType},
+    math::{Normed, Transform, Transformable, Unit},
+    point2, point3,
+    spectra::{
+        rgb::{sRGB, RGBColorSpace, RGB},
+        RGBIlluminantSpectrum, Spectrum,
+    },
+    vec3, Point2f, SampledSpectrum, SampledWavelengths, Vec3f,
+};
+
+/// Point light projecting an image through a frustum along +z in light space, like a slide projector. As with the
+/// cameras, +x is to the right of the image and +y is up
+#[derive(Debug)]
+pub struct ProjectionLight {
+    base: BaseLight,
+    /// Linear sRGB
+    image: Rgb32FImage,
+    color_space: Arc<RGBColorSpace>,
+    scale: f32,
+    /// Half extents of the image on the `z = 1` plane
+    half_width: f32,
+    half_height: f32,
+}
+
+impl ProjectionLight {
+    /// `fov` in degrees spans the shorter side of the image
+    pub fn new(image: Rgb32FImage, scale: f32, fov: f32, light_to_render: Transform<f32>) -> Self {
+        assert!(image.width() > 0 && image.height() > 0);
+        let aspect = image.width() as f32 / image.height() as f32;
+        let tan = (fov.to_radians() / 2.).tan();
+        let (half_width, half_height) = if aspect > 1. {
+            (tan * aspect, tan)
+        } else {
+            (tan, tan / aspect)
+        };
+        ProjectionLight {
+            base: BaseLight {
+                light_type: LightType::DeltaPosition,
+                light_to_render,
+            },
+            image,
+            color_space: sRGB.clone(),
+            scale,
+            half_width,
+            half_height,
+        }
+    }
+
+    /// Integral of the average channel over the solid angle of the frustum, the flux for unit `scale` of a gray image
+    pub fn solid_angle_integral(&self) -> f32 {
+        let mut integral = 0.;
+        self.for_each_pixel(|rgb, solid_angle| integral += rgb.iter().sum::<f32>() / 3. * solid_angle);
+        integral
+    }
+
+    /// Calls `f` with the color of every pixel and the solid angle it covers
+    fn for_each_pixel(&self, mut f: impl FnMut([f32; 3], f32)) {
+        let (width, height) = self.image.dimensions();
+        let area = 4. * self.half_width * self.half_height / (width * height) as f32;
+        for (x, y, pixel) in self.image.enumerate_pixels() {
+            let px = ((x as f32 + 0.5) / width as f32 * 2. - 1.) * self.half_width;
+            let py = (1. - (y as f32 + 0.5) / height as f32 * 2.) * self.half_height;
+            let cos = (1. + px * px + py * py).sqrt().recip();
+            f(pixel.0.map(|x| x.max(0.)), area * cos.powi(3));
+        }
+    }
+
+    /// Pixel the direction `dir` in light space passes through
+    fn lookup(&self, dir: Vec3f) -> Option<RGB> {
+        if dir.z <= 0. {
+            return None;
+        }
+        let uv = point2!(
+            (dir.x / dir.z / self.half_width + 1.) / 2.,
+            (1. - dir.y / dir.z / self.half_height) / 2.
+        );
+        if !(0. ..=1.).contains(&uv.x) || !(0. ..=1.).contains(&uv.y) {
+            return None;
+        }
+        let (width, height) = self.image.dimensions();
+        let x = ((uv.x * width as f32) as u32).min(width - 1);
+        let y = ((uv.y * height as f32) as u32).min(height - 1);
+        let [r, g, b] = self.image.get_pixel(x, y).0.map(|x| x.max(0.));
+        Some(RGB::new(r, g, b))
+    }
+}
+
+impl Light for ProjectionLight {
+    fn flux(&self, lambda: &SampledWavelengths) -> SampledSpectrum {
+        let mut flux = SampledSpectrum::default();
+        self.for_each_pixel(|[r, g, b], solid_angle| {
+            flux += RGBIlluminantSpectrum::new(&self.color_space, RGB::new(r, g, b)).sample(lambda) * solid_angle
+        });
+        flux * self.scale
+    }
+
+    fn light_type(&self) -> LightType { self.base.light_type }
+
+    fn sample(
+        &self,
+        surf_int: &SurfaceInteraction,
+        lambda: &SampledWavelengths,
+        rnd_p: Point2f,
+    ) -> Option<LightSample> {
+        let point = point3!(0., 0., 0.).transform(&self.base.light_to_render);
+        let vec = point - surf_int.hit.point;
+        let to_object = (-vec).inv_transform(&self.base.light_to_render);
+        let rgb = self.lookup(to_object)?;
+        let radiance =
+            RGBIlluminantSpectrum::new(&self.color_space, rgb).sample(lambda) * self.scale / vec.len_squared();
+        Some(LightSample {
+            radiance,
+            incoming: vec.to_unit(),
+            pdf: 1.0,
+            point,
+        })
+    }
+
+    fn pdf_incoming(&self, incoming: Unit<Vec3f>, surf_int: &SurfaceInteraction) -> f32 { 0. }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let point = point3!(0., 0., 0.).transform(&self.base.light_to_render);
+        let w = vec3!(0., 0., 1.).transform(&self.base.light_to_render).to_unit();
+        let brightest = self
+            .image
+            .pixels()
+            .max_by(|a, b| a.0.iter().sum::<f32>().total_cmp(&b.0.iter().sum::<f32>()))
+            .unwrap();
+        let [r, g, b] = brightest.0.map(|x| x.max(0.));
+        let max_value = RGBIlluminantSpectrum::new(&self.color_space, RGB::new(r, g, b)).max_value();
+        Some(LightBounds {
+            bounds: Aabb::new(point, point),
+            w,
+            phi: 4. * PI * self.scale * max_value,
+            // Angle to the corners of the image
+            cos_theta_o: (1. + self.half_width.powi(2) + self.half_height.powi(2)).sqrt().recip(),
+            cos_theta_e: 0.,
+            two_sided: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_projection() {
+        let mut image = Rgb32FImage::from_pixel(64, 64, image::Rgb([1., 1., 1.]));
+        image.put_pixel(63, 0, image::Rgb([0., 0., 4.]));
+        let light = ProjectionLight::new(image, 2., 90., Transform::id());
+        // A 90 degree square frustum covers 2/3 PI steradians
+        let solid_angle = 2. * PI / 3.;
+        assert_abs_diff_eq!(light.solid_angle_integral(), solid_angle, epsilon = 0.02);
+
+        // The top right corner is blue
+        let mut surf_int = SurfaceInteraction::default();
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        let radiance = |surf_int: &SurfaceInteraction| {
+            light
+                .sample(surf_int, &lambda, point2!(0.5, 0.5))
+                .map(|sample| sample.radiance)
+        };
+        let check = |surf_int: &SurfaceInteraction, rgb: RGB, distance_sqr: f32| {
+            let expected = RGBIlluminantSpectrum::new(&sRGB, rgb).sample(&lambda) * 2. / distance_sqr;
+            for (actual, expected) in radiance(surf_int).unwrap().iter().zip(expected.iter()) {
+                assert_abs_diff_eq!(actual, expected, epsilon = 1e-4);
+            }
+        };
+        surf_int.hit.point = point3!(0.99, 0.99, 1.);
+        check(&surf_int, RGB::new(0., 0., 4.), 2.9602);
+        surf_int.hit.point = point3!(-0.5, 0.5, 1.);
+        check(&surf_int, RGB::new(1., 1., 1.), 1.5);
+
+        // Outside of the frustum and behind the light
+        surf_int.hit.point = point3!(1.5, 0., 1.);
+        assert!(radiance(&surf_int).is_none());
+        surf_int.hit.point = point3!(0., 0., -1.);
+        assert!(radiance(&surf_int).is_none());
+    }
+}
@@ -1,12 +1,13 @@
 use std::{f32::consts::PI, sync::Arc};
 
 use crate::{
+    aggregates::Aabb,
     core::SurfaceInteraction,
-    light::{base::BaseLight, Light, LightSample, LightType},
+    light::{base::BaseLight, Light, LightBounds, LightSample, LightType},
     math::{utils::lerp, Normed, Transform, Transformable, Unit},
     point3,
     spectra::{Spectrum, SpectrumEnum},
-    Point2f, SampledSpectrum, SampledWavelengths, Vec3f,
+    vec3, Point2f, SampledSpectrum, SampledWavelengths, Vec3f,
 };
 
 #[derive(Debug)]
@@ -86,4 +87,19 @@ impl Light for Spotlight {
     }
 
     fn pdf_incoming(&self, incoming: Unit<Vec3f>, surf_int: &SurfaceInteraction) -> f32 { 0. }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let point = point3!(0., 0., 0.).transform(&self.base.light_to_render);
+        let w = vec3!(0., 0., 1.).transform(&self.base.light_to_render).to_unit();
+        // Emission beyond the falloff start is covered by the extra angle `theta_e`
+        let theta_e = self.cos_falloff_end.acos() - self.cos_falloff_start.acos();
+        Some(LightBounds {
+            bounds: Aabb::new(point, point),
+            w,
+            phi: 4. * PI * self.scale * self.spectrum.max_value(),
+            cos_theta_o: self.cos_falloff_start,
+            cos_theta_e: theta_e.cos(),
+            two_sided: false,
+        })
+    }
 }
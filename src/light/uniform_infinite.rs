@@ -0,0 +1,72 @@
+use std::{f32::consts::PI, sync::Arc};
+
+use crate::{
+    aggregates::Aabb,
+    core::{Ray, SurfaceInteraction},
+    light::{base::BaseLight, Light, LightBounds, LightSample, LightType},
+    math::{Transform, Unit},
+    point3,
+    samplers::utils::{sample_uniform_sphere, uniform_sphere_pdf},
+    spectra::{Spectrum, SpectrumEnum},
+    Point2f, Point3f, SampledSpectrum, SampledWavelengths, Vec3f,
+};
+
+/// Infinitely far away sphere emitting the same radiance in all directions, e.g. an overcast sky
+#[derive(Debug)]
+pub struct UniformInfiniteLight {
+    base: BaseLight,
+    spectrum: Arc<SpectrumEnum>,
+    scale: f32,
+    scene_center: Point3f,
+    scene_radius: f32,
+}
+
+impl UniformInfiniteLight {
+    pub fn new(spectrum: Arc<SpectrumEnum>, scale: f32) -> Self {
+        UniformInfiniteLight {
+            base: BaseLight {
+                light_type: LightType::Infinite,
+                light_to_render: Transform::id(),
+            },
+            spectrum,
+            scale,
+            scene_center: point3!(0., 0., 0.),
+            scene_radius: 0.,
+        }
+    }
+}
+
+impl Light for UniformInfiniteLight {
+    fn flux(&self, lambda: &SampledWavelengths) -> SampledSpectrum {
+        4. * PI * PI * self.scene_radius.powi(2) * self.scale * self.spectrum.sample(lambda)
+    }
+
+    fn light_type(&self) -> LightType { self.base.light_type }
+
+    fn sample(
+        &self,
+        surf_int: &SurfaceInteraction,
+        lambda: &SampledWavelengths,
+        rnd_p: Point2f,
+    ) -> Option<LightSample> {
+        let incoming = sample_uniform_sphere(rnd_p);
+        Some(LightSample {
+            radiance: self.spectrum.sample(lambda) * self.scale,
+            incoming,
+            pdf: uniform_sphere_pdf(),
+            point: surf_int.hit.point + incoming * (2. * self.scene_radius),
+        })
+    }
+
+    fn pdf_incoming(&self, incoming: Unit<Vec3f>, surf_int: &SurfaceInteraction) -> f32 { uniform_sphere_pdf() }
+
+    fn radiance_escaped(&self, ray: &Ray, lambda: &SampledWavelengths) -> Option<SampledSpectrum> {
+        Some(self.spectrum.sample(lambda) * self.scale)
+    }
+
+    fn preprocess(&mut self, scene_bounds: &Aabb<f32>) {
+        (self.scene_center, self.scene_radius) = scene_bounds.bounding_sphere();
+    }
+
+    fn bounds(&self) -> Option<LightBounds> { None }
+}
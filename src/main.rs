@@ -1,38 +1,260 @@
+#![feature(let_chains, isqrt)]
 #![allow(unused)]
 
-use std::sync::Arc;
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use image::buffer::ConvertBuffer;
-use itertools::Itertools;
-use num_traits::Pow;
+use clap::{Parser, ValueEnum};
+use log::warn;
 use rusttracer::{
-    aggregates::BVH,
-    integrators::{DebugNormalIntegrator, Integrator, PathIntegrator, RandomWalkIntegrator, SimplePathIntegrator},
-    light::{DiffuseAreaLight, Light, PointLight},
-    material::{matte::Matte, MaterialsEnum},
-    math::Transform,
-    point2, point3,
+    integrators::{
+        DebugNormalIntegrator, Integrator, IntegratorConfig, PathIntegrator, RandomWalkIntegrator,
+        SimplePathIntegrator, VolPathIntegrator,
+    },
+    light::LightSamplerKind,
+    point2,
+    samplers::{IndependentSampler, SamplerType, StratifiedSampler},
     scene::{
-        cameras::{BaseCameraConfig, CameraType, OrthographicCamera, OrthographicCameraConfig},
-        film::RGBFilm,
-        primitives::{geometric::GeometricPrimitive, simple::SimplePrimitive, PrimitiveEnum},
+        film::{ImageOutput, OutputFormat},
+        loader::SceneFile,
+        pbrt::load_pbrt,
         Scene,
     },
-    shapes::{mesh::Triangle, sphere::Sphere},
-    test_scenes::cornell_box,
-    textures::constant::ConstantSpectrumTexture,
-    vec3, Bounds2f, Point3f,
+    test_scenes::{cornell_box, cubes, teapot},
+    Point2us,
 };
 
-fn main() {
+const BUILTIN_SCENES: &[&str] = &["cornell_box", "teapot", "cubes"];
+
+/// Spectral path tracer
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// Built-in scene name (`cornell_box`, `teapot`, `cubes`) or path to a `.toml` or `.pbrt` scene file
+    #[arg(default_value = "cornell_box")]
+    scene: String,
+
+    #[arg(short, long, value_enum, default_value_t = IntegratorKind::Path)]
+    integrator: IntegratorKind,
+
+    /// Maximum path length [default: 6, or the value from a pbrt file]
+    #[arg(short = 'd', long)]
+    max_depth: Option<u32>,
+
+    /// Path depth after which paths with low throughput are randomly terminated (Russian roulette)
+    #[arg(long, default_value_t = 1)]
+    rr_depth: u32,
+
+    /// How lights are chosen for direct lighting: `uniform`, `power` or `bvh` [default: bvh, or the value from a
+    /// pbrt file]
+    #[arg(long)]
+    light_sampler: Option<LightSamplerKind>,
+
+    /// Samples per pixel, rounded down to a perfect square for the stratified sampler [default: 16, or the value
+    /// from a pbrt file]
+    #[arg(short, long)]
+    spp: Option<u32>,
+
+    #[arg(long, value_enum, default_value_t = SamplerKind::Stratified)]
+    sampler: SamplerKind,
+
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    /// Image resolution overriding the one from the scene, e.g. `800x600`
+    #[arg(short, long, value_parser = parse_resolution)]
+    resolution: Option<Point2us>,
+
+    /// Number of rendering threads [default: number of logical cores]
+    #[arg(short, long)]
+    threads: Option<usize>,
+
+    /// Output image, format is deduced from the extension. `.exr` and `.pfm` keep linear HDR values, other formats
+    /// are 8-bit [default: ./images/_image.png, or the file name from a pbrt file]
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Write OpenEXR channels as 16-bit half floats
+    #[arg(long)]
+    half: bool,
+
+    /// Add a `weight` layer with the sum of filter weights in each pixel to OpenEXR output
+    #[arg(long)]
+    weight_layer: bool,
+
+    /// Save the image after each wave of samples
+    #[arg(long)]
+    save_intermediate: bool,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+#[value(rename_all = "snake_case")]
+enum IntegratorKind {
+    Path,
+    /// Path tracing through participating media
+    VolPath,
+    SimplePath,
+    RandomWalk,
+    DebugNormal,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum SamplerKind {
+    Independent,
+    Stratified,
+}
+
+/// Scene along with the options it specifies
+struct LoadedScene {
+    scene: Scene,
+    max_depth: Option<u32>,
+    light_sampler: Option<LightSamplerKind>,
+    samples_per_pixel: Option<u32>,
+    output: Option<PathBuf>,
+}
+
+fn parse_resolution(value: &str) -> Result<Point2us, String> {
+    let parse = |x: &str| x.trim().parse::<usize>().ok().filter(|x| *x > 0);
+    match value.split_once(['x', 'X']) {
+        Some((width, height)) => match (parse(width), parse(height)) {
+            (Some(width), Some(height)) => Ok(point2!(width, height)),
+            _ => Err("width and height must be positive integers".to_string()),
+        },
+        None => Err("expected `<width>x<height>`".to_string()),
+    }
+}
+
+fn load_scene(name: &str, resolution: Option<Point2us>) -> Result<LoadedScene, Box<dyn Error>> {
+    let builtin = |scene| LoadedScene {
+        scene,
+        max_depth: None,
+        light_sampler: None,
+        samples_per_pixel: None,
+        output: None,
+    };
+    match name {
+        "cornell_box" => return Ok(builtin(cornell_box(resolution.unwrap_or(point2!(400, 400)))?)),
+        "teapot" => return Ok(builtin(teapot(resolution.unwrap_or(point2!(640, 360)))?)),
+        "cubes" => return Ok(builtin(cubes(resolution.unwrap_or(point2!(640, 360)))?)),
+        _ => {}
+    }
+
+    let path = Path::new(name);
+    match path.extension().and_then(|x| x.to_str()) {
+        Some("toml") => {
+            let mut file = SceneFile::load(path)?;
+            if let Some(resolution) = resolution {
+                *file.description.film.width.get_mut() = resolution.x;
+                *file.description.film.height.get_mut() = resolution.y;
+            }
+            Ok(builtin(file.build()?))
+        }
+        Some("pbrt") => {
+            let mut imported = load_pbrt(path)?;
+            if let Some(resolution) = resolution {
+                imported.resolution = resolution;
+            }
+            let (max_depth, light_sampler, samples_per_pixel) =
+                (imported.max_depth, imported.light_sampler, imported.samples_per_pixel);
+            let output = imported
+                .output
+                .take()
+                .map(|x| path.parent().unwrap_or(Path::new("")).join(x));
+            Ok(LoadedScene {
+                scene: imported.build()?,
+                max_depth: Some(max_depth),
+                light_sampler: Some(light_sampler),
+                samples_per_pixel: Some(samples_per_pixel),
+                output,
+            })
+        }
+        _ if path.exists() => Err(format!("`{name}`: unsupported scene format, expected `.toml` or `.pbrt`").into()),
+        _ => Err(format!(
+            "`{name}` is neither a scene file nor a built-in scene ({})",
+            BUILTIN_SCENES.join(", ")
+        )
+        .into()),
+    }
+}
+
+fn create_sampler(kind: SamplerKind, samples_per_pixel: u32, seed: u64) -> SamplerType {
+    match kind {
+        SamplerKind::Independent => IndependentSampler::new(samples_per_pixel, seed).into(),
+        SamplerKind::Stratified => {
+            let sqrt_spp = samples_per_pixel.isqrt().max(1);
+            if sqrt_spp * sqrt_spp != samples_per_pixel {
+                warn!(
+                    "Stratified sampler needs a perfect square number of samples, using {}",
+                    sqrt_spp * sqrt_spp
+                );
+            }
+            StratifiedSampler::new(sqrt_spp, sqrt_spp, true, seed).into()
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new().num_threads(threads).build_global()?;
+    }
+
+    let loaded = load_scene(&args.scene, args.resolution)?;
+    let output = ImageOutput {
+        path: args
+            .output
+            .or(loaded.output)
+            .unwrap_or_else(|| PathBuf::from("./images/_image.png")),
+        half: args.half,
+        weight_layer: args.weight_layer,
+    };
+    // Check the format before rendering rather than after
+    if output.format()? != OutputFormat::Exr && (output.half || output.weight_layer) {
+        warn!("`--half` and `--weight-layer` only apply to OpenEXR output, ignoring");
+    }
+    if let Some(parent) = output.path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let samples_per_pixel = args.spp.or(loaded.samples_per_pixel).unwrap_or(16);
+    if samples_per_pixel == 0 {
+        return Err("number of samples per pixel must be positive".into());
+    }
+    let config = IntegratorConfig {
+        max_depth: args.max_depth.or(loaded.max_depth).unwrap_or(6),
+        rr_depth: args.rr_depth,
+        light_sampler: args.light_sampler.or(loaded.light_sampler).unwrap_or_default(),
+        sampler: create_sampler(args.sampler, samples_per_pixel, args.seed),
+        output,
+        save_intermediate: args.save_intermediate,
+    };
+
+    let scene = loaded.scene;
+    match args.integrator {
+        IntegratorKind::Path => PathIntegrator::create(scene, config).render()?,
+        IntegratorKind::VolPath => VolPathIntegrator::create(scene, config).render()?,
+        IntegratorKind::SimplePath => SimplePathIntegrator::create(scene, config).render()?,
+        IntegratorKind::RandomWalk => RandomWalkIntegrator::new(scene, config).render()?,
+        IntegratorKind::DebugNormal => DebugNormalIntegrator::new(scene, config).render()?,
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
     env_logger::init();
 
-    let scene = cornell_box();
-    // let mut integrator = DebugNormalIntegrator::new(scene);
-    // let mut integrator = RandomWalkIntegrator::new(scene, 5, 2u32.pow(4));
-    // let mut integrator = SimplePathIntegrator::create(scene, 6, 2u32.pow(4));
-    let mut integrator = PathIntegrator::create(scene, 6, 2u32.pow(4));
-    integrator.render();
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }
 
 // #[cfg(test)]
@@ -1,22 +1,26 @@
 use std::{cmp::PartialEq, marker::PhantomData, sync::Arc};
 
 use bumpalo::Bump;
+use either::Either;
 use image::Rgb;
 
 use crate::{
     bxdf::{BxDFEnum, ConductorBxDF, DielectricBxDF, DiffuseBxDF, BSDF},
     core::SurfaceInteraction,
-    material::Material,
+    material::{BumpMap, Material, Roughness},
     spectra::{Spectrum, SpectrumEnum},
-    textures::{SpectrumTexture, SpectrumTextureEnum},
+    textures::{FloatTexture, FloatTextureEnum, SpectrumTexture, SpectrumTextureEnum},
     SampledWavelengths,
 };
 
 #[derive(Debug)]
 pub struct Glass {
-    pub ior: SpectrumEnum,
-    // pub roughness: f32,
+    /// Either a spectrally varying index of refraction, which causes dispersion, or a texture varying over the
+    /// surface
+    pub ior: Either<SpectrumEnum, Arc<FloatTextureEnum>>,
+    pub roughness: Roughness,
     pub spectrum: Arc<SpectrumTextureEnum>,
+    pub bump: Option<BumpMap>,
 }
 
 impl Material for Glass {
@@ -28,14 +32,23 @@ impl Material for Glass {
         lambda: &mut SampledWavelengths,
         alloc: &'a mut Bump,
     ) -> BSDF<'a> {
-        // If IOR depend on wavelength, trace only the first one
-        let first_wavelength_ior = self.ior.value(lambda[0]);
-        match self.ior {
-            SpectrumEnum::Constant(_) => {}
-            _ => lambda.terminate_secondary(),
-        }
+        let first_wavelength_ior = match &self.ior {
+            Either::Left(ior) => {
+                // If IOR depend on wavelength, trace only the first one
+                if !matches!(ior, SpectrumEnum::Constant(_)) {
+                    lambda.terminate_secondary();
+                }
+                ior.value(lambda[0])
+            }
+            Either::Right(ior) => ior.evaluate(surf_int),
+        };
 
-        let bxdf = alloc.alloc(BxDFEnum::Dielectric(DielectricBxDF::new(first_wavelength_ior)));
-        BSDF::new(**surf_int.hit.normal, surf_int.dp_du, bxdf)
+        let bxdf = alloc.alloc(BxDFEnum::Dielectric(DielectricBxDF::new(
+            first_wavelength_ior,
+            self.roughness.distribution(surf_int),
+        )));
+        BSDF::new(**surf_int.shading.normal, surf_int.shading.dp_du, bxdf)
     }
+
+    fn bump(&self) -> Option<&BumpMap> { self.bump.as_ref() }
 }
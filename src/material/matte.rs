@@ -6,7 +6,7 @@ use image::{Pixel, Rgb};
 use crate::{
     bxdf::{BxDFEnum, DiffuseBxDF, BSDF},
     core::SurfaceInteraction,
-    material::Material,
+    material::{BumpMap, Material},
     textures::{SpectrumTexture, SpectrumTextureEnum},
     SampledSpectrum, SampledWavelengths,
 };
@@ -14,6 +14,7 @@ use crate::{
 #[derive(Debug)]
 pub struct Matte {
     pub reflectance: Arc<SpectrumTextureEnum>,
+    pub bump: Option<BumpMap>,
 }
 
 impl Material for Matte {
@@ -27,6 +28,8 @@ impl Material for Matte {
     ) -> BSDF<'a> {
         let bxdf: &mut BxDFEnum =
             alloc.alloc(DiffuseBxDF::new(self.reflectance.evaluate(surf_int, lambda).clamp(0., 1.)).into());
-        BSDF::new(**surf_int.hit.normal, surf_int.dp_du, bxdf)
+        BSDF::new(**surf_int.shading.normal, surf_int.shading.dp_du, bxdf)
     }
+
+    fn bump(&self) -> Option<&BumpMap> { self.bump.as_ref() }
 }
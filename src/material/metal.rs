@@ -6,14 +6,19 @@ use either::Either;
 use crate::{
     bxdf::{BxDFEnum, ConductorBxDF, BSDF},
     core::SurfaceInteraction,
-    material::Material,
+    material::{BumpMap, Material, Roughness},
     textures::{SpectrumTexture, SpectrumTextureEnum},
     Pair, SampledSpectrum, SampledWavelengths,
 };
 
 #[derive(Debug)]
 pub struct Metal {
+    /// Either the reflectance at normal incidence or the complex index of refraction `(eta, k)`, e.g.
+    /// [NamedSpectra::AuEta](crate::spectra::named::NamedSpectra::AuEta) and
+    /// [NamedSpectra::AuK](crate::spectra::named::NamedSpectra::AuK)
     pub reflectance: Either<Arc<SpectrumTextureEnum>, Pair<Arc<SpectrumTextureEnum>>>,
+    pub roughness: Roughness,
+    pub bump: Option<BumpMap>,
 }
 
 impl Material for Metal {
@@ -32,12 +37,16 @@ impl Material for Metal {
                 let k = 2. * reflectance.sqrt() / (SampledSpectrum::from(1.) - reflectance).sqrt();
                 (eta, k)
             }
-            Either::Right((eta, k)) => {
-                unimplemented!()
-            }
+            Either::Right((eta, k)) => (eta.evaluate(surf_int, lambda), k.evaluate(surf_int, lambda)),
         };
 
-        let bxdf = alloc.alloc(BxDFEnum::Conductor(ConductorBxDF::new(eta, k)));
-        BSDF::new(**surf_int.hit.normal, surf_int.dp_du, bxdf)
+        let bxdf = alloc.alloc(BxDFEnum::Conductor(ConductorBxDF::new(
+            self.roughness.distribution(surf_int),
+            eta,
+            k,
+        )));
+        BSDF::new(**surf_int.shading.normal, surf_int.shading.dp_du, bxdf)
     }
+
+    fn bump(&self) -> Option<&BumpMap> { self.bump.as_ref() }
 }
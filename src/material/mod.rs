@@ -1,14 +1,17 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 use bumpalo::Bump;
 use image::Rgb;
 
 use crate::{
     bxdf,
-    bxdf::{BxDF, BSDF},
+    bxdf::{BxDF, TrowbridgeReitzDistribution, BSDF},
     core::{Ray, SurfaceInteraction},
     material::{glass::Glass, matte::Matte, metal::Metal},
-    SampledSpectrum, SampledWavelengths,
+    math::{cross, dot, Normed},
+    point2,
+    textures::{constant::ConstantFloatTexture, mipmap::MIPMap, FloatTexture, FloatTextureEnum},
+    vec2, vec3, SampledSpectrum, SampledWavelengths, Vec3f,
 };
 
 pub mod glass;
@@ -25,6 +28,9 @@ pub trait Material {
         lambda: &mut SampledWavelengths,
         alloc: &'a mut Bump,
     ) -> BSDF<'a>;
+
+    /// Applied to the shading geometry before [Material::get_bsdf]
+    fn bump(&self) -> Option<&BumpMap>;
 }
 
 #[derive(Debug)]
@@ -34,3 +40,172 @@ pub enum MaterialsEnum {
     Metal(Metal),
     Glass(Glass),
 }
+
+/// Microfacet roughness of [Metal] and [Glass]. Anisotropic when `u` and `v` differ
+#[derive(Debug)]
+pub struct Roughness {
+    /// Along dp/du
+    pub u: Arc<FloatTextureEnum>,
+    /// Along dp/dv
+    pub v: Arc<FloatTextureEnum>,
+    /// Values are perceptually linear roughness in [0; 1] rather than microfacet alpha
+    pub remap: bool,
+}
+
+impl Roughness {
+    /// Perfectly specular surface
+    pub fn smooth() -> Self { Self::isotropic(Arc::new(ConstantFloatTexture { value: 0. }.into())) }
+
+    pub fn isotropic(roughness: Arc<FloatTextureEnum>) -> Self {
+        Roughness {
+            u: roughness.clone(),
+            v: roughness,
+            remap: true,
+        }
+    }
+
+    pub(crate) fn distribution(&self, surf_int: &SurfaceInteraction) -> TrowbridgeReitzDistribution {
+        let mut u = self.u.evaluate(surf_int).max(0.);
+        let mut v = self.v.evaluate(surf_int).max(0.);
+        if self.remap {
+            u = TrowbridgeReitzDistribution::roughness_to_alpha(u);
+            v = TrowbridgeReitzDistribution::roughness_to_alpha(v);
+        }
+        TrowbridgeReitzDistribution::new(u, v)
+    }
+}
+
+/// Surface detail added by perturbing the shading frame without changing the geometry
+#[derive(Debug, Clone)]
+pub enum BumpMap {
+    /// Offset along the shading normal, in render space units
+    Displacement(Arc<FloatTextureEnum>),
+    /// Tangent space normals encoded as linear RGB in [0; 1], looked up by uv. Green points along dp/dv
+    Normal(Arc<MIPMap>),
+}
+
+impl BumpMap {
+    /// Perturbed shading dp/du and dp/dv, the shading normal is their cross product
+    pub(crate) fn shading_derivatives(&self, surf_int: &SurfaceInteraction) -> (Vec3f, Vec3f) {
+        let shading = &surf_int.shading;
+        let normal = **shading.normal;
+        match self {
+            BumpMap::Displacement(displacement) => {
+                // Forward differences with steps of about half the pixel footprint
+                let step = |x: f32| if x == 0. { 5e-4 } else { x };
+                let du = step(0.5 * (surf_int.du_dx.abs() + surf_int.du_dy.abs()));
+                let dv = step(0.5 * (surf_int.dv_dx.abs() + surf_int.dv_dy.abs()));
+
+                let mut shifted = surf_int.clone();
+                shifted.hit.point = surf_int.hit.point + shading.dp_du * du;
+                shifted.hit.uv = surf_int.hit.uv + vec2!(du, 0.);
+                let u_displace = displacement.evaluate(&shifted);
+                shifted.hit.point = surf_int.hit.point + shading.dp_dv * dv;
+                shifted.hit.uv = surf_int.hit.uv + vec2!(0., dv);
+                let v_displace = displacement.evaluate(&shifted);
+                let displace = displacement.evaluate(surf_int);
+
+                let dp_du = shading.dp_du + normal * ((u_displace - displace) / du) + *shading.dn_du * displace;
+                let dp_dv = shading.dp_dv + normal * ((v_displace - displace) / dv) + *shading.dn_dv * displace;
+                (dp_du, dp_dv)
+            }
+            BumpMap::Normal(map) => {
+                let uv = surf_int.hit.uv;
+                let [r, g, b] = map.bilerp(0, point2!(uv.x, 1. - uv.y)).into();
+                let local = vec3!(2. * r - 1., 2. * g - 1., 2. * b - 1.);
+
+                let tangent = *shading.dp_du.to_unit();
+                let mut bitangent = cross(&normal, &tangent);
+                if dot(&bitangent, &shading.dp_dv) < 0. {
+                    bitangent = -bitangent;
+                }
+                let ns = *(tangent * local.x + bitangent * local.y + normal * local.z).to_unit();
+
+                // Tangents orthogonal to the new normal, lengths are preserved
+                let dp_du = shading.dp_du - ns * dot(&shading.dp_du, &ns);
+                let dp_du = *dp_du.to_unit() * shading.dp_du.len();
+                let dp_dv = *cross(&ns, &dp_du).to_unit() * shading.dp_dv.len();
+                (dp_du, dp_dv)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use image::{Rgb, Rgb32FImage};
+
+    use super::*;
+    use crate::{
+        core::Interaction,
+        point3,
+        textures::{
+            image::{FloatImageTexture, ImageChannel},
+            mappings::UVMapping,
+            mipmap::{FilterFunction, WrapMode},
+        },
+        unit_normal3, Point2f,
+    };
+
+    /// Unit square in the z = 0 plane
+    fn interaction(uv: Point2f) -> SurfaceInteraction {
+        SurfaceInteraction::new(
+            Interaction {
+                point: point3!(uv.x, uv.y, 0.),
+                normal: unit_normal3!(0., 0., 1.),
+                uv,
+                ..Default::default()
+            },
+            vec3!(1., 0., 0.),
+            vec3!(0., 1., 0.),
+            Default::default(),
+            Default::default(),
+        )
+    }
+
+    fn bumped_normal(bump: &BumpMap, surf_int: &SurfaceInteraction) -> Vec3f {
+        let (dp_du, dp_dv) = bump.shading_derivatives(surf_int);
+        *cross(&dp_du, &dp_dv).to_unit()
+    }
+
+    #[test]
+    fn test_displacement() {
+        let surf_int = interaction(point2!(0.5, 0.5));
+        let flat = BumpMap::Displacement(Arc::new(ConstantFloatTexture { value: 0.3 }.into()));
+        assert_abs_diff_eq!(bumped_normal(&flat, &surf_int), vec3!(0., 0., 1.), epsilon = 1e-6);
+
+        // Texel centers at u = 0.25 and 0.75, so the height grows as 2u between them
+        let ramp = Rgb32FImage::from_fn(2, 1, |x, _| Rgb([x as f32; 3]));
+        let ramp = BumpMap::Displacement(Arc::new(
+            FloatImageTexture {
+                mapping: UVMapping::default().into(),
+                mipmap: Arc::new(MIPMap::new(ramp, WrapMode::Clamp, FilterFunction::Bilinear)),
+                channel: ImageChannel::Red,
+                scale: 1.,
+                invert: false,
+            }
+            .into(),
+        ));
+        let expected = *vec3!(-2., 0., 1.).to_unit();
+        assert_abs_diff_eq!(bumped_normal(&ramp, &surf_int), expected, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_normal_map() {
+        let normal_map = |rgb: [f32; 3]| {
+            let image = Rgb32FImage::from_pixel(1, 1, Rgb(rgb));
+            BumpMap::Normal(Arc::new(MIPMap::new(image, WrapMode::Repeat, FilterFunction::Bilinear)))
+        };
+        let surf_int = interaction(point2!(0.3, 0.6));
+        let flat = normal_map([0.5, 0.5, 1.]);
+        assert_abs_diff_eq!(bumped_normal(&flat, &surf_int), vec3!(0., 0., 1.), epsilon = 1e-6);
+        // Red tilts towards dp/du and green towards dp/dv
+        let tilted = normal_map([1., 0.5, 1.]);
+        let expected = *vec3!(1., 0., 1.).to_unit();
+        assert_abs_diff_eq!(bumped_normal(&tilted, &surf_int), expected, epsilon = 1e-6);
+        let tilted = normal_map([0.5, 1., 1.]);
+        let expected = *vec3!(0., 1., 1.).to_unit();
+        assert_abs_diff_eq!(bumped_normal(&tilted, &surf_int), expected, epsilon = 1e-6);
+    }
+}
@@ -0,0 +1,110 @@
+use std::f32::consts::PI;
+
+use crate::{
+    aggregates::Aabb,
+    math::{Cross, Dot, Normed, Transform, Transformable, Unit},
+    unit3, Point3f, Vec3f,
+};
+
+/// Set of directions within `acos(cos_theta)` of the central direction `w`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DirectionCone {
+    pub w: Unit<Vec3f>,
+    pub cos_theta: f32,
+}
+
+impl DirectionCone {
+    pub fn new(w: Unit<Vec3f>, cos_theta: f32) -> Self { DirectionCone { w, cos_theta } }
+
+    pub fn from_direction(w: Unit<Vec3f>) -> Self { DirectionCone::new(w, 1.) }
+
+    pub fn entire_sphere() -> Self { DirectionCone::new(unit3!(0., 0., 1.), -1.) }
+
+    /// Cone that contains no directions at all
+    pub fn empty() -> Self { DirectionCone::new(unit3!(0., 0., 1.), f32::INFINITY) }
+
+    pub fn is_empty(&self) -> bool { self.cos_theta == f32::INFINITY }
+
+    /// Cone of directions from `p` towards every point of `bounds`
+    pub fn bound_subtended_directions(bounds: &Aabb<f32>, p: Point3f) -> Self {
+        let (center, radius) = bounds.bounding_sphere();
+        let dist_squared = (center - p).len_squared();
+        if dist_squared < radius * radius {
+            return DirectionCone::entire_sphere();
+        }
+        let sin_theta_max_squared = radius * radius / dist_squared;
+        let cos_theta_max = (1. - sin_theta_max_squared).max(0.).sqrt();
+        DirectionCone::new((center - p).to_unit(), cos_theta_max)
+    }
+
+    /// Smallest cone containing both cones
+    pub fn union(&self, other: &DirectionCone) -> DirectionCone {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+
+        // One of the cones contains the other
+        let theta_a = self.cos_theta.clamp(-1., 1.).acos();
+        let theta_b = other.cos_theta.clamp(-1., 1.).acos();
+        let theta_d = self.w.dot(&other.w).clamp(-1., 1.).acos();
+        if (theta_d + theta_b).min(PI) <= theta_a {
+            return *self;
+        }
+        if (theta_d + theta_a).min(PI) <= theta_b {
+            return *other;
+        }
+
+        let theta_o = (theta_a + theta_d + theta_b) / 2.;
+        if theta_o >= PI {
+            return DirectionCone::entire_sphere();
+        }
+
+        // Rotate `self.w` towards `other.w` so that the new cone's edges touch both cones
+        let theta_r = theta_o - theta_a;
+        let axis = self.w.cross(&other.w);
+        if axis.len_squared() == 0. {
+            return DirectionCone::entire_sphere();
+        }
+        let w = (*self.w).transform(&Transform::rotate_arbitrary_axis(axis, theta_r));
+        DirectionCone::new(w.to_unit(), theta_o.cos())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{point3, vec3};
+
+    #[test]
+    fn test_union() {
+        let x = DirectionCone::from_direction(unit3!(1., 0., 0.));
+        let y = DirectionCone::from_direction(unit3!(0., 1., 0.));
+        let union = x.union(&y);
+        assert_abs_diff_eq!(*union.w, vec3!(1., 1., 0.) / 2_f32.sqrt(), epsilon = 1e-5);
+        assert_abs_diff_eq!(union.cos_theta, FRAC_PI_4.cos(), epsilon = 1e-5);
+
+        assert_eq!(union.union(&x), union);
+        assert_eq!(DirectionCone::empty().union(&x), x);
+        let opposite = DirectionCone::from_direction(unit3!(-1., 0., 0.));
+        assert_eq!(x.union(&opposite).cos_theta, -1.);
+    }
+
+    #[test]
+    fn test_subtended_directions() {
+        let bounds = Aabb::new(point3!(-1., -1., -1.), point3!(1., 1., 1.));
+        assert_eq!(
+            DirectionCone::bound_subtended_directions(&bounds, point3!(0.5, 0., 0.)).cos_theta,
+            -1.
+        );
+        let cone = DirectionCone::bound_subtended_directions(&bounds, point3!(0., 0., -6.));
+        assert_abs_diff_eq!(*cone.w, vec3!(0., 0., 1.));
+        assert_abs_diff_eq!(cone.cos_theta, (1. - 3. / 36_f32).sqrt());
+    }
+}
@@ -38,7 +38,7 @@ impl<T: Number> Frame<T> {
         let b = v1.x * v1.y * a;
         let v2 = vec3!(T::one() + sign * v1.x.powi(2) * a, sign * b, -sign * v1.x);
         let v3 = vec3!(b, sign + v1.y.powi(2) * a, -v1.y);
-        Frame::new(v1, v2, v3)
+        Frame::new(v2, v3, v1)
     }
 
     pub fn to_local(&self, vec: Vec3<T>) -> Vec3<T> {
@@ -72,6 +72,9 @@ mod tests {
             y: unit3!(0., 1., 0.),
             z: unit3!(0., 0., 1.),
         };
-        assert_eq!(Frame::from_x_y(x, y), expected)
+        assert_eq!(Frame::from_x_y(x, y), expected);
+
+        let z = vec3!(0., 0.6, 0.8);
+        assert_abs_diff_eq!(Frame::from_z(z).from_local(vec3!(0., 0., 1.)), z, epsilon = 1e-6);
     }
 }
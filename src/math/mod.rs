@@ -9,8 +9,10 @@ use std::{
 
 use approx::AbsDiffEq;
 pub use bounds::Bounds2;
+pub use direction_cone::DirectionCone;
 pub use frame::Frame;
 pub use matrix3::*;
+pub use matrix4::Matrix4;
 pub use normal::Normal3;
 use num_traits::{Float, Num, NumAssignOps, One, Pow, Signed};
 pub use point2::Point2;
@@ -34,6 +36,7 @@ use crate::{normal3, unit3, unit_normal3};
 
 pub mod axis;
 mod bounds;
+mod direction_cone;
 mod frame;
 mod matrix3;
 mod matrix4;
@@ -7,11 +7,11 @@ use strum::IntoEnumIterator;
 use crate::{
     math::{
         axis::{Axis3, Axis4},
-        dot,
+        cross, dot,
         matrix4::Matrix4,
         Dot, Normed, Number, Vec3,
     },
-    vec3, Vec3f,
+    vec3, Point3f, Vec3f,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -153,8 +153,6 @@ impl<T: Number> Transform<T> {
         self
     }
 
-    pub fn look_at() -> Self { todo!() }
-
     pub fn orthographic(z_near: T, z_far: T) -> Self {
         Transform::compose(
             Transform::translate(vec3!(T::zero(), T::zero(), -z_near)),
@@ -172,9 +170,7 @@ impl<T: Number> Transform<T> {
             _1, _0, _0, _0,
             _0, _1, _0, _0,
             _0, _0, z_far / (z_far - z_near), -z_far * z_near / (z_far - z_near),
-            _0, _0, -_1, _0,
-            // _0, _0, z_far / (z_far - z_near), -_1,
-            // _0, _0, -z_far * z_near / (z_far - z_near), _0,
+            _0, _0, _1, _0,
         );
         let scale = (fov.to_radians() / _2).tan().recip();
         Transform::compose(Transform::from_matrix(perspective), Transform::scale(scale, scale, _1))
@@ -200,6 +196,23 @@ impl<T: Number> Transform<T> {
 }
 
 impl Transform<f32> {
+    /// World-to-camera transform for a camera at `pos` looking at `look`. Camera space is left-handed: +z points
+    /// towards `look`, +y is as close to `up` as possible.
+    pub fn look_at(pos: Point3f, look: Point3f, up: Vec3f) -> Self {
+        let dir = (look - pos).to_unit();
+        let right = cross(&*up.to_unit(), &*dir).to_unit();
+        let new_up = cross(&*dir, &*right);
+
+        #[rustfmt::skip]
+        let camera_to_world = Matrix4::from_elements(
+            right.x, new_up.x, dir.x, pos.x,
+            right.y, new_up.y, dir.y, pos.y,
+            right.z, new_up.z, dir.z, pos.z,
+            0., 0., 0., 1.,
+        );
+        Transform::from_matrix(camera_to_world).invert()
+    }
+
     pub fn rotate_from_to(from: &Vec3f, to: &Vec3f) -> Self {
         // Compute intermediate vector for vector reflection
         let reflection_vec = if (from.x.abs() < 0.72 && to.x.abs() < 0.72) {
@@ -301,6 +314,17 @@ mod tests {
         assert_abs_diff_eq!(vec.transform(&rot_x), exp_rot_x, epsilon = 1e-5);
     }
 
+    #[test]
+    fn test_look_at() {
+        let t = Transform::look_at(point3!(1., 0., 0.), point3!(1., 0., 5.), vec3!(0., 1., 0.));
+        assert_abs_diff_eq!(point3!(1., 0., 5.).transform(&t), point3!(0., 0., 5.), epsilon = 1e-5);
+
+        let t = Transform::look_at(point3!(0., 0., 0.), point3!(-2., 0., 0.), vec3!(0., 1., 0.));
+        assert_abs_diff_eq!(vec3!(0., 0., 1.).inv_transform(&t), vec3!(-1., 0., 0.), epsilon = 1e-5);
+        assert_abs_diff_eq!(vec3!(1., 0., 0.).inv_transform(&t), vec3!(0., 0., 1.), epsilon = 1e-5);
+        assert_abs_diff_eq!(vec3!(0., 1., 0.).inv_transform(&t), vec3!(0., 1., 0.), epsilon = 1e-5);
+    }
+
     #[test]
     fn test_compose() {
         let p = point3!(0., 0., 0.);
@@ -17,6 +17,15 @@ where T: Copy + One + Add<Output = T> + Sub<Output = T> + Mul<Output = T> {
     (T::one() - t) * a + t * b
 }
 
+/// Smooth Hermite interpolation from 0 at `a` to 1 at `b`
+pub fn smooth_step(x: f32, a: f32, b: f32) -> f32 {
+    if a == b {
+        return if x < a { 0. } else { 1. };
+    }
+    let t = ((x - a) / (b - a)).clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
 pub fn power_heuristic(n_f: usize, f_pdf: f32, n_g: usize, g_pdf: f32) -> f32 {
     let f = n_f as f32 * f_pdf;
     let g = n_g as f32 * g_pdf;
@@ -62,7 +71,7 @@ pub mod spherical_coordinates {
 
     use crate::{math::Vec3, vec3, Vec3f};
 
-    pub fn spherical_theta(vec: Vec3<f32>) -> f32 { vec.z.acos() * FRAC_1_PI }
+    pub fn spherical_theta(vec: Vec3<f32>) -> f32 { vec.z.clamp(-1., 1.).acos() }
 
     pub fn spherical_phi(vec: Vec3<f32>) -> f32 {
         let p = f32::atan2(vec.y, vec.x);
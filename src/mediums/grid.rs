@@ -0,0 +1,289 @@
+use std::sync::Arc;
+
+use crate::{
+    aggregates::Aabb,
+    core::Ray,
+    math::{Transform, Transformable},
+    mediums::{
+        HGPhaseFunction, MajorantGrid, MajorantIterator, Medium, MediumProperties, RayMajorantSegment, SampledGrid,
+    },
+    point3,
+    spectra::{BlackbodySpectrum, Spectrum, SpectrumEnum},
+    Point3f, SampledSpectrum, SampledWavelengths, Vec3f,
+};
+
+/// Resolution of the majorant grid along each axis
+const MAJORANT_RESOLUTION: usize = 16;
+
+/// Heterogeneous medium with the density given on a voxel grid filling `bounds`, e.g. smoke. Both coefficients
+/// are scaled by the density
+#[derive(Debug)]
+pub struct GridMedium {
+    bounds: Aabb<f32>,
+    medium_to_render: Transform<f32>,
+    sigma_a: Arc<SpectrumEnum>,
+    sigma_s: Arc<SpectrumEnum>,
+    scale: f32,
+    phase: HGPhaseFunction,
+    density: SampledGrid,
+    emission: Option<(GridEmission, f32)>,
+    majorants: Arc<MajorantGrid>,
+}
+
+/// Radiance emitted by a [GridMedium]
+#[derive(Debug)]
+pub enum GridEmission {
+    /// Spectrum multiplied by the grid values
+    Spectrum(Arc<SpectrumEnum>, SampledGrid),
+    /// Blackbody emission at the temperatures in Kelvin given by the grid, normalized to a peak of one. Points
+    /// colder than 100K don't emit
+    Temperature(SampledGrid),
+}
+
+impl GridEmission {
+    fn sample(&self, point: Point3f, lambda: &SampledWavelengths) -> SampledSpectrum {
+        match self {
+            GridEmission::Spectrum(spectrum, grid) => spectrum.sample(lambda) * grid.lookup(point),
+            GridEmission::Temperature(grid) => {
+                let temperature = grid.lookup(point);
+                if temperature > 100. {
+                    BlackbodySpectrum::new(temperature).sample(lambda)
+                } else {
+                    SampledSpectrum::from(0.)
+                }
+            }
+        }
+    }
+}
+
+pub struct GridMediumConfig {
+    /// Box in medium space covered by the grids
+    pub bounds: Aabb<f32>,
+    pub medium_to_render: Transform<f32>,
+    pub sigma_a: Arc<SpectrumEnum>,
+    pub sigma_s: Arc<SpectrumEnum>,
+    pub scale: f32,
+    pub g: f32,
+    pub density: SampledGrid,
+    /// Emitted radiance and its multiplier
+    pub emission: Option<(GridEmission, f32)>,
+}
+
+impl GridMedium {
+    pub fn new(config: GridMediumConfig) -> Self { GridMedium::from(config) }
+
+    /// Position of a medium space point within the bounds, in [0; 1]^3 inside of them
+    fn offset(&self, point: Point3f) -> Point3f {
+        let diag = self.bounds.max - self.bounds.min;
+        let offset = point - self.bounds.min;
+        point3!(offset.x / diag.x, offset.y / diag.y, offset.z / diag.z)
+    }
+}
+
+impl From<GridMediumConfig> for GridMedium {
+    fn from(config: GridMediumConfig) -> Self {
+        let majorants = Arc::new(MajorantGrid::new(&config.density, [MAJORANT_RESOLUTION; 3]));
+        GridMedium {
+            bounds: config.bounds,
+            medium_to_render: config.medium_to_render,
+            sigma_a: config.sigma_a,
+            sigma_s: config.sigma_s,
+            scale: config.scale,
+            phase: HGPhaseFunction::new(config.g),
+            density: config.density,
+            emission: config.emission,
+            majorants,
+        }
+    }
+}
+
+impl Medium for GridMedium {
+    fn is_emissive(&self) -> bool { self.emission.as_ref().is_some_and(|(_, scale)| *scale > 0.) }
+
+    fn sample_point(&self, point: Point3f, lambda: &SampledWavelengths) -> MediumProperties {
+        let point = self.offset(point.inv_transform(&self.medium_to_render));
+        let density = self.density.lookup(point);
+        MediumProperties {
+            sigma_a: self.sigma_a.sample(lambda) * (self.scale * density),
+            sigma_s: self.sigma_s.sample(lambda) * (self.scale * density),
+            phase: self.phase,
+            emission: self
+                .emission
+                .as_ref()
+                .map_or(SampledSpectrum::from(0.), |(emission, scale)| {
+                    emission.sample(point, lambda) * *scale
+                }),
+        }
+    }
+
+    fn sample_ray(&self, ray: &Ray, t_max: f32, lambda: &SampledWavelengths) -> MajorantIterator {
+        // Direction is not normalized after the transformation, so `t` still measures render space distances
+        let origin = ray.origin.inv_transform(&self.medium_to_render);
+        let dir = (*ray.dir).inv_transform(&self.medium_to_render);
+        let Some((t_min, t_max)) = self.bounds.intersect_range(origin, dir, t_max) else {
+            return MajorantIterator::Homogeneous(None);
+        };
+        let sigma_t = (self.sigma_a.sample(lambda) + self.sigma_s.sample(lambda)) * self.scale;
+        let diag = self.bounds.max - self.bounds.min;
+        let grid_dir = [dir.x / diag.x, dir.y / diag.y, dir.z / diag.z];
+        MajorantIterator::DDA(DDAMajorantIterator::new(
+            self.offset(origin),
+            grid_dir,
+            t_min,
+            t_max,
+            self.majorants.clone(),
+            sigma_t,
+        ))
+    }
+}
+
+/// Walks through the voxels of a [MajorantGrid] pierced by a ray, yielding a segment per voxel
+pub struct DDAMajorantIterator {
+    t_min: f32,
+    t_max: f32,
+    sigma_t: SampledSpectrum,
+    /// Shared with the medium, so the iterator doesn't borrow it
+    majorants: Arc<MajorantGrid>,
+    voxel: [i64; 3],
+    step: [i64; 3],
+    voxel_limit: [i64; 3],
+    next_crossing_t: [f32; 3],
+    delta_t: [f32; 3],
+}
+
+impl DDAMajorantIterator {
+    /// `origin` and `dir` are in grid space, i.e. [0; 1]^3 covers the grid
+    fn new(
+        origin: Point3f,
+        dir: [f32; 3],
+        t_min: f32,
+        t_max: f32,
+        majorants: Arc<MajorantGrid>,
+        sigma_t: SampledSpectrum,
+    ) -> Self {
+        let resolution = majorants.resolution();
+        let origin = [origin.x, origin.y, origin.z];
+        let mut iter = DDAMajorantIterator {
+            t_min,
+            t_max,
+            sigma_t,
+            majorants,
+            voxel: [0; 3],
+            step: [0; 3],
+            voxel_limit: [0; 3],
+            next_crossing_t: [0.; 3],
+            delta_t: [0.; 3],
+        };
+        for axis in 0..3 {
+            let n = resolution[axis] as i64;
+            let entry = origin[axis] + dir[axis] * t_min;
+            // Negative zero would step in the wrong direction
+            let dir = if dir[axis] == 0. { 0. } else { dir[axis] };
+            iter.voxel[axis] = ((entry * n as f32) as i64).clamp(0, n - 1);
+            iter.delta_t[axis] = 1. / (dir.abs() * n as f32);
+            if dir >= 0. {
+                let next_voxel_pos = (iter.voxel[axis] + 1) as f32 / n as f32;
+                iter.next_crossing_t[axis] = t_min + (next_voxel_pos - entry) / dir;
+                iter.step[axis] = 1;
+                iter.voxel_limit[axis] = n;
+            } else {
+                let next_voxel_pos = iter.voxel[axis] as f32 / n as f32;
+                iter.next_crossing_t[axis] = t_min + (next_voxel_pos - entry) / dir;
+                iter.step[axis] = -1;
+                iter.voxel_limit[axis] = -1;
+            }
+        }
+        iter
+    }
+}
+
+impl Iterator for DDAMajorantIterator {
+    type Item = RayMajorantSegment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.t_min >= self.t_max {
+            return None;
+        }
+        // Axis whose voxel boundary is crossed first
+        let t = self.next_crossing_t;
+        let step_axis = if t[0] < t[1] && t[0] < t[2] {
+            0
+        } else if t[1] < t[2] {
+            1
+        } else {
+            2
+        };
+        let t_voxel_exit = self.t_max.min(t[step_axis]);
+        let segment = RayMajorantSegment {
+            t_min: self.t_min,
+            t_max: t_voxel_exit,
+            sigma_maj: self.sigma_t * self.majorants.lookup(self.voxel.map(|x| x as usize)),
+        };
+
+        self.t_min = t_voxel_exit;
+        if t[step_axis] > self.t_max {
+            self.t_min = self.t_max;
+        }
+        self.voxel[step_axis] += self.step[step_axis];
+        if self.voxel[step_axis] == self.voxel_limit[step_axis] {
+            self.t_min = self.t_max;
+        }
+        self.next_crossing_t[step_axis] += self.delta_t[step_axis];
+        Some(segment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{
+        math::Normed,
+        ray,
+        spectra::{ConstantSpectrum, SpectrumEnum},
+        vec3,
+    };
+
+    #[test]
+    fn test_majorant_segments() {
+        // Dense in the upper half along x only
+        let resolution: usize = 32;
+        let values = (0..resolution.pow(3))
+            .map(|i| if i % resolution >= resolution / 2 { 1. } else { 0. })
+            .collect();
+        let medium = GridMedium::new(GridMediumConfig {
+            bounds: Aabb::new(point3!(0., 0., 0.), point3!(1., 1., 1.)),
+            medium_to_render: Transform::scale_uniform(2.),
+            sigma_a: Arc::new(SpectrumEnum::Constant(ConstantSpectrum::new(1.))),
+            sigma_s: Arc::new(SpectrumEnum::Constant(ConstantSpectrum::new(1.))),
+            scale: 1.,
+            g: 0.,
+            density: SampledGrid::new(values, [resolution; 3]).unwrap(),
+            emission: None,
+        });
+        let lambda = SampledWavelengths::sample_visible(0.5);
+
+        // Segments are contiguous and cover the medium in render space units
+        let ray = ray!(point3!(-1., 1., 1.), vec3!(1., 0., 0.).to_unit());
+        let segments: Vec<_> = medium.sample_ray(&ray, 10., &lambda).collect();
+        assert_eq!(segments.len(), MAJORANT_RESOLUTION);
+        assert_abs_diff_eq!(segments[0].t_min, 1.);
+        assert_abs_diff_eq!(segments.last().unwrap().t_max, 3., epsilon = 1e-5);
+        for pair in segments.windows(2) {
+            assert_abs_diff_eq!(pair[0].t_max, pair[1].t_min);
+        }
+        assert_eq!(segments[0].sigma_maj[0], 0.);
+        assert_eq!(segments.last().unwrap().sigma_maj[0], 2.);
+
+        // Majorants bound the medium along the ray
+        for segment in &segments {
+            let point = ray.at((segment.t_min + segment.t_max) / 2.);
+            let properties = medium.sample_point(point, &lambda);
+            assert!(properties.sigma_a[0] + properties.sigma_s[0] <= segment.sigma_maj[0]);
+        }
+
+        let ray = ray!(point3!(-1., 1., 1.), vec3!(-1., 0., 0.).to_unit());
+        assert_eq!(medium.sample_ray(&ray, 10., &lambda).count(), 0);
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use crate::{
+    core::Ray,
+    mediums::{HGPhaseFunction, MajorantIterator, Medium, MediumProperties, RayMajorantSegment},
+    spectra::{Spectrum, SpectrumEnum},
+    Point3f, SampledSpectrum, SampledWavelengths,
+};
+
+/// Medium with the same properties everywhere, e.g. fog filling a whole room
+#[derive(Debug)]
+pub struct HomogeneousMedium {
+    pub sigma_a: Arc<SpectrumEnum>,
+    pub sigma_s: Arc<SpectrumEnum>,
+    /// Multiplier of both coefficients, lets them be given as colors
+    pub scale: f32,
+    pub phase: HGPhaseFunction,
+    /// Emitted radiance and its multiplier
+    pub emission: Option<(Arc<SpectrumEnum>, f32)>,
+}
+
+impl Medium for HomogeneousMedium {
+    fn is_emissive(&self) -> bool { self.emission.as_ref().is_some_and(|(_, scale)| *scale > 0.) }
+
+    fn sample_point(&self, point: Point3f, lambda: &SampledWavelengths) -> MediumProperties {
+        MediumProperties {
+            sigma_a: self.sigma_a.sample(lambda) * self.scale,
+            sigma_s: self.sigma_s.sample(lambda) * self.scale,
+            phase: self.phase,
+            emission: self
+                .emission
+                .as_ref()
+                .map_or(SampledSpectrum::from(0.), |(emission, scale)| {
+                    emission.sample(lambda) * *scale
+                }),
+        }
+    }
+
+    fn sample_ray(&self, ray: &Ray, t_max: f32, lambda: &SampledWavelengths) -> MajorantIterator {
+        let sigma_t = (self.sigma_a.sample(lambda) + self.sigma_s.sample(lambda)) * self.scale;
+        MajorantIterator::Homogeneous(Some(RayMajorantSegment {
+            t_min: 0.,
+            t_max,
+            sigma_maj: sigma_t,
+        }))
+    }
+}
@@ -1,53 +1,206 @@
-use rand::random;
+//! Participating media filling the space between surfaces. Media are sampled with delta tracking against a majorant,
+//! an upper bound of the extinction coefficient along the ray, see [sample_majorant].
 
-use crate::{
-    aggregates::Aabb,
-    core::{Hit, Ray},
-    math::Normed,
-    shapes::{Bounded, BoundedIntersectable, Intersectable},
-    unit3_unchecked,
-};
+use std::sync::Arc;
+
+pub use grid::{GridEmission, GridMedium, GridMediumConfig};
+pub use homogeneous::HomogeneousMedium;
+pub use phase::{HGPhaseFunction, PhaseFunctionSample};
+use rand::Rng;
+pub use sampled_grid::{MajorantGrid, SampledGrid};
+
+use crate::{core::Ray, mediums::grid::DDAMajorantIterator, Point3f, SampledSpectrum, SampledWavelengths};
+
+mod grid;
+mod homogeneous;
+pub mod nrrd;
+mod phase;
+mod sampled_grid;
+
+#[enum_delegate::register]
+pub trait Medium {
+    fn is_emissive(&self) -> bool;
+
+    /// Coefficients at a point of the medium
+    fn sample_point(&self, point: Point3f, lambda: &SampledWavelengths) -> MediumProperties;
+
+    /// Segments of `ray` up to `t_max` along with their majorants. Parts of the ray outside of the medium are
+    /// skipped
+    fn sample_ray(&self, ray: &Ray, t_max: f32, lambda: &SampledWavelengths) -> MajorantIterator;
+}
 
 #[derive(Debug)]
-pub struct Medium {
-    shape: Box<dyn BoundedIntersectable>,
-    inv_density: f32,
+#[enum_delegate::implement(Medium)]
+pub enum MediumEnum {
+    Homogeneous(HomogeneousMedium),
+    Grid(GridMedium),
+}
+
+#[derive(Debug, Clone)]
+pub struct MediumProperties {
+    /// Absorption coefficient
+    pub sigma_a: SampledSpectrum,
+    /// Scattering coefficient
+    pub sigma_s: SampledSpectrum,
+    pub phase: HGPhaseFunction,
+    /// Emitted radiance, scaled by `sigma_a` when added to a path
+    pub emission: SampledSpectrum,
+}
+
+/// Part of a ray with a constant majorant
+#[derive(Debug, Clone)]
+pub struct RayMajorantSegment {
+    pub t_min: f32,
+    pub t_max: f32,
+    pub sigma_maj: SampledSpectrum,
+}
+
+pub enum MajorantIterator {
+    Homogeneous(Option<RayMajorantSegment>),
+    DDA(DDAMajorantIterator),
 }
 
-impl Medium {
-    pub fn new(shape: Box<dyn BoundedIntersectable>, density: f32) -> Self {
-        Medium {
-            shape,
-            inv_density: -1. / density,
+impl Iterator for MajorantIterator {
+    type Item = RayMajorantSegment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            MajorantIterator::Homogeneous(segment) => segment.take(),
+            MajorantIterator::DDA(iter) => iter.next(),
         }
     }
 }
 
-impl Intersectable for Medium {
-    fn hit(&self, ray: &Ray) -> Option<Hit> {
-        // TO DO: assuming convex shape and ray starting outside a medium
-        match self.shape.hit(ray) {
-            None => None,
-            Some(first_hit) => {
-                let inside_ray = Ray::new(ray.at(first_hit.t + 0.001), ray.dir, None);
-                if let Some(second_hit) = self.shape.hit(&inside_ray) {
-                    let dist_inside = (second_hit.point - first_hit.point).len();
-                    let hit_distance = self.inv_density * f32::ln(random());
-                    if hit_distance < dist_inside {
-                        let hit_t = first_hit.t + hit_distance;
-                        return Some(Hit {
-                            t: hit_t,
-                            point: ray.at(hit_t),
-                            normal: unit3_unchecked!(1., 0., 0.).cast(),
-                        });
-                    }
-                }
-                None
+/// Media on both sides of a surface. The inside is the side opposite to the surface orientation, i.e. inside of
+/// closed shapes whose normals point outwards. `None` is vacuum
+#[derive(Debug, Clone, Default)]
+pub struct MediumInterface {
+    pub inside: Option<Arc<MediumEnum>>,
+    pub outside: Option<Arc<MediumEnum>>,
+}
+
+impl MediumInterface {
+    /// Same medium on both sides, e.g. for the camera or objects inside of a medium
+    pub fn uniform(medium: Option<Arc<MediumEnum>>) -> Self {
+        MediumInterface {
+            inside: medium.clone(),
+            outside: medium,
+        }
+    }
+}
+
+/// Delta tracking along `ray` up to `t_max`. Points are sampled proportionally to the majorant transmittance and
+/// passed to `callback` along with the majorant and the majorant transmittance since the previous point, tracking
+/// stops once it returns `false`. Returns the majorant transmittance from the last point to `t_max`, or one if
+/// tracking was stopped. `u` is the first random number, the following ones are taken from `rng`
+pub fn sample_majorant<R, F>(
+    medium: &MediumEnum,
+    ray: &Ray,
+    t_max: f32,
+    mut u: f32,
+    rng: &mut R,
+    lambda: &SampledWavelengths,
+    mut callback: F,
+) -> SampledSpectrum
+where
+    R: Rng,
+    F: FnMut(Point3f, MediumProperties, SampledSpectrum, SampledSpectrum, &mut R) -> bool,
+{
+    let mut t_maj = SampledSpectrum::from(1.);
+    for segment in medium.sample_ray(ray, t_max, lambda) {
+        // Majorant transmittance over a segment of length `dt`, infinite segments have zero transmittance
+        let transmittance = |dt: f32| (segment.sigma_maj * -dt.min(f32::MAX)).exp();
+        if segment.sigma_maj[0] == 0. {
+            t_maj *= transmittance(segment.t_max - segment.t_min);
+            continue;
+        }
+
+        // Generate samples along the current majorant segment
+        let mut t_min = segment.t_min;
+        loop {
+            let t = t_min - (1. - u).ln() / segment.sigma_maj[0];
+            u = rng.gen();
+            if t >= segment.t_max {
+                t_maj *= transmittance(segment.t_max - t_min);
+                break;
             }
+            t_maj *= transmittance(t - t_min);
+            let point = ray.at(t);
+            if !callback(point, medium.sample_point(point, lambda), segment.sigma_maj, t_maj, rng) {
+                return SampledSpectrum::from(1.);
+            }
+            t_maj = SampledSpectrum::from(1.);
+            t_min = t;
         }
     }
+    t_maj
 }
 
-impl Bounded<f32> for Medium {
-    fn bound(&self) -> Aabb<f32> { self.shape.bound() }
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        math::Normed,
+        point3, ray,
+        spectra::{ConstantSpectrum, SpectrumEnum},
+        vec3,
+    };
+
+    #[test]
+    fn test_sample_majorant() {
+        let sigma_t = 0.5;
+        let medium = MediumEnum::Homogeneous(HomogeneousMedium {
+            sigma_a: Arc::new(SpectrumEnum::Constant(ConstantSpectrum::new(0.2))),
+            sigma_s: Arc::new(SpectrumEnum::Constant(ConstantSpectrum::new(0.3))),
+            scale: 1.,
+            phase: HGPhaseFunction::new(0.),
+            emission: None,
+        });
+        let ray = ray!(point3!(0., 0., 0.), vec3!(1., 0., 0.).to_unit());
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        let mut rng = SmallRng::seed_from_u64(7);
+
+        // Points come in order along the ray with the majorant transmittance since the previous one
+        let distance = 2.;
+        let n = 20000;
+        for _ in 0..n {
+            let mut t_prev = 0.;
+            let u = rng.gen();
+            sample_majorant(
+                &medium,
+                &ray,
+                distance,
+                u,
+                &mut rng,
+                &lambda,
+                |p, mp, sigma_maj, t_maj, _| {
+                    let t = p.x;
+                    assert!(t > t_prev && t < distance);
+                    assert_abs_diff_eq!(sigma_maj[0], mp.sigma_a[0] + mp.sigma_s[0]);
+                    assert_abs_diff_eq!(t_maj[0], (-sigma_t * (t - t_prev)).exp(), epsilon = 1e-5);
+                    t_prev = t;
+                    true
+                },
+            );
+        }
+
+        // Collisions follow the exponential distribution
+        let mut first_collisions = 0;
+        for _ in 0..n {
+            let u = rng.gen();
+            let t_maj = sample_majorant(&medium, &ray, distance, u, &mut rng, &lambda, |_, _, _, _, _| {
+                first_collisions += 1;
+                false
+            });
+            // Escaped rays get the transmittance to the end of the segment
+            if t_maj[0] != 1. {
+                assert_abs_diff_eq!(t_maj[0], (-sigma_t * distance).exp(), epsilon = 1e-5);
+            }
+        }
+        let expected = 1. - (-sigma_t * distance).exp();
+        assert_abs_diff_eq!(first_collisions as f32 / n as f32, expected, epsilon = 0.02);
+    }
 }
@@ -0,0 +1,371 @@
+//! NRRD volume loader for density and temperature grids. Scalar 3D volumes of any integer or floating point type are
+//! supported with `raw`, `ascii` and `gzip` encodings, the data may follow the header or be in a detached file.
+//! Spatial fields such as `spacing` are ignored, the grid is stretched over the bounds of the medium.
+
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use derive_more::{Display, Error};
+use flate2::read::GzDecoder;
+
+use crate::mediums::SampledGrid;
+
+#[derive(Debug, Display, Error)]
+pub enum NrrdLoadError {
+    #[display("failed to read {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[display("{}: {message}", path.display())]
+    Invalid {
+        path: PathBuf,
+        #[error(not(source))]
+        message: String,
+    },
+}
+
+/// Loads a scalar volume, values are ordered by x first, then y, then z as in [SampledGrid]
+pub fn load_nrrd(path: impl AsRef<Path>) -> Result<SampledGrid, NrrdLoadError> {
+    let path = path.as_ref();
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| NrrdLoadError::Io { path, source }
+    };
+    let invalid = |message| NrrdLoadError::Invalid {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let bytes = fs::read(path).map_err(io_error(path))?;
+    let (header, body_start) = parse_header(&bytes).map_err(invalid)?;
+    let values = match &header.data_file {
+        Some(data_file) => {
+            let data_path = path.parent().unwrap_or(Path::new("")).join(data_file);
+            let data = fs::read(&data_path).map_err(io_error(&data_path))?;
+            decode(&header, &data)
+        }
+        None => decode(&header, &bytes[body_start..]),
+    }
+    .map_err(invalid)?;
+    SampledGrid::new(values, header.sizes).ok_or_else(|| invalid("volume is empty".to_string()))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        let scalar = match name {
+            "signed char" | "int8" | "int8_t" => Scalar::I8,
+            "uchar" | "unsigned char" | "uint8" | "uint8_t" => Scalar::U8,
+            "short" | "short int" | "signed short" | "signed short int" | "int16" | "int16_t" => Scalar::I16,
+            "ushort" | "unsigned short" | "unsigned short int" | "uint16" | "uint16_t" => Scalar::U16,
+            "int" | "signed int" | "int32" | "int32_t" => Scalar::I32,
+            "uint" | "unsigned int" | "uint32" | "uint32_t" => Scalar::U32,
+            "longlong"
+            | "long long"
+            | "long long int"
+            | "signed long long"
+            | "signed long long int"
+            | "int64"
+            | "int64_t" => Scalar::I64,
+            "ulonglong" | "unsigned long long" | "unsigned long long int" | "uint64" | "uint64_t" => Scalar::U64,
+            "float" => Scalar::F32,
+            "double" => Scalar::F64,
+            _ => return None,
+        };
+        Some(scalar)
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::I64 | Scalar::U64 | Scalar::F64 => 8,
+        }
+    }
+
+    /// Converts a single value stored in little endian order
+    fn read_le(self, bytes: &[u8]) -> f32 {
+        let array = |bytes: &[u8]| -> [u8; 8] {
+            let mut array = [0; 8];
+            array[..bytes.len()].copy_from_slice(bytes);
+            array
+        };
+        let bytes = array(bytes);
+        match self {
+            Scalar::I8 => bytes[0] as i8 as f32,
+            Scalar::U8 => bytes[0] as f32,
+            Scalar::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+            Scalar::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+            Scalar::I32 => i32::from_le_bytes(bytes[..4].try_into().unwrap()) as f32,
+            Scalar::U32 => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as f32,
+            Scalar::I64 => i64::from_le_bytes(bytes) as f32,
+            Scalar::U64 => u64::from_le_bytes(bytes) as f32,
+            Scalar::F32 => f32::from_le_bytes(bytes[..4].try_into().unwrap()),
+            Scalar::F64 => f64::from_le_bytes(bytes) as f32,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Encoding {
+    Raw,
+    Ascii,
+    Gzip,
+}
+
+#[derive(Debug)]
+struct Header {
+    scalar: Scalar,
+    sizes: [usize; 3],
+    encoding: Encoding,
+    big_endian: bool,
+    /// Bytes skipped before the data after decompression, `None` if the data is at the end of a raw file
+    byte_skip: Option<usize>,
+    line_skip: usize,
+    data_file: Option<String>,
+}
+
+/// Splits the file into the header and the offset of the attached data
+fn parse_header(bytes: &[u8]) -> Result<(Header, usize), String> {
+    if !bytes.starts_with(b"NRRD000") {
+        return Err("not a NRRD file".to_string());
+    }
+    // The header ends with an empty line, or with the file for detached data
+    let mut end = 0;
+    let body_start = loop {
+        if end >= bytes.len() {
+            end = bytes.len();
+            break end;
+        }
+        let line_end = bytes[end..]
+            .iter()
+            .position(|&x| x == b'\n')
+            .map_or(bytes.len(), |x| end + x);
+        if bytes[end..line_end].iter().all(|x| x.is_ascii_whitespace()) {
+            break (line_end + 1).min(bytes.len());
+        }
+        end = line_end + 1;
+    };
+    let header = std::str::from_utf8(&bytes[..end]).map_err(|_| "header is not valid UTF-8")?;
+
+    let (mut scalar, mut dimension, mut sizes, mut encoding, mut endian) = (None, None, None, None, None);
+    let mut byte_skip = Some(0);
+    let mut line_skip = 0;
+    let mut data_file = None;
+    for line in header.lines().skip(1).map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        // Key-value pairs (`key:=value`) hold arbitrary metadata
+        let Some((field, value)) = line.split_once(": ") else {
+            continue;
+        };
+        let value = value.trim();
+        let parse_usize = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("invalid `{field}`: `{value}`"))
+        };
+        match field {
+            "type" => scalar = Some(Scalar::parse(value).ok_or_else(|| format!("unsupported type `{value}`"))?),
+            "dimension" => dimension = Some(parse_usize(value)?),
+            "sizes" => {
+                sizes = Some(
+                    value
+                        .split_ascii_whitespace()
+                        .map(parse_usize)
+                        .collect::<Result<Vec<_>, _>>()?,
+                )
+            }
+            "encoding" => {
+                encoding = Some(match value {
+                    "raw" => Encoding::Raw,
+                    "ascii" | "text" | "txt" => Encoding::Ascii,
+                    "gzip" | "gz" => Encoding::Gzip,
+                    _ => return Err(format!("unsupported encoding `{value}`")),
+                })
+            }
+            "endian" => endian = Some(value == "big"),
+            "byte skip" | "byteskip" => byte_skip = if value == "-1" { None } else { Some(parse_usize(value)?) },
+            "line skip" | "lineskip" => line_skip = parse_usize(value)?,
+            "data file" | "datafile" => {
+                if value.starts_with("LIST") || value.split_ascii_whitespace().count() > 1 {
+                    return Err("data split over multiple files is not supported".to_string());
+                }
+                data_file = Some(value.to_string())
+            }
+            _ => {}
+        }
+    }
+
+    let missing = |field| format!("missing `{field}`");
+    let scalar = scalar.ok_or_else(|| missing("type"))?;
+    let encoding = encoding.ok_or_else(|| missing("encoding"))?;
+    let sizes = sizes.ok_or_else(|| missing("sizes"))?;
+    if dimension.is_some_and(|x| x != sizes.len()) {
+        return Err("`sizes` don't match `dimension`".to_string());
+    }
+    // A leading axis of size one is a scalar volume stored as a vector one
+    let sizes = match sizes.as_slice() {
+        [x, y, z] | [1, x, y, z] => [*x, *y, *z],
+        _ => return Err("only scalar 3D volumes are supported".to_string()),
+    };
+    if encoding != Encoding::Ascii && scalar.size() > 1 && endian.is_none() {
+        return Err(missing("endian"));
+    }
+    if byte_skip.is_none() && encoding != Encoding::Raw {
+        return Err("`byte skip: -1` is only supported with raw encoding".to_string());
+    }
+    let header = Header {
+        scalar,
+        sizes,
+        encoding,
+        big_endian: endian.unwrap_or(false),
+        byte_skip,
+        line_skip,
+        data_file,
+    };
+    Ok((header, body_start))
+}
+
+/// Decodes the data following the header or in the detached file
+fn decode(header: &Header, data: &[u8]) -> Result<Vec<f32>, String> {
+    let count: usize = header.sizes.iter().product();
+    let mut data = data;
+    for _ in 0..header.line_skip {
+        let line_end = data
+            .iter()
+            .position(|&x| x == b'\n')
+            .ok_or("`line skip` past the end of data")?;
+        data = &data[line_end + 1..];
+    }
+
+    if header.encoding == Encoding::Ascii {
+        let text = std::str::from_utf8(data).map_err(|_| "data is not valid UTF-8")?;
+        let values = text
+            .split(|c: char| c.is_ascii_whitespace() || c == ',')
+            .filter(|x| !x.is_empty())
+            .take(count)
+            .map(|x| x.parse::<f32>().map_err(|_| format!("invalid value `{x}`")))
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() < count {
+            return Err(format!("expected {count} values, found {}", values.len()));
+        }
+        return Ok(values);
+    }
+
+    let decompressed;
+    if header.encoding == Encoding::Gzip {
+        let mut buffer = vec![];
+        GzDecoder::new(data)
+            .read_to_end(&mut buffer)
+            .map_err(|err| format!("invalid gzip data: {err}"))?;
+        decompressed = buffer;
+        data = &decompressed;
+    }
+    let size = header.scalar.size();
+    let length = count * size;
+    let start = match header.byte_skip {
+        Some(skip) => skip,
+        None => data.len().saturating_sub(length),
+    };
+    let Some(data) = data.get(start..start + length) else {
+        return Err(format!(
+            "expected {length} bytes of data, found {}",
+            data.len().saturating_sub(start)
+        ));
+    };
+    Ok(data
+        .chunks_exact(size)
+        .map(|bytes| {
+            if header.big_endian {
+                let mut bytes = bytes.to_vec();
+                bytes.reverse();
+                header.scalar.read_le(&bytes)
+            } else {
+                header.scalar.read_le(bytes)
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Result<Vec<f32>, String> {
+        let (header, body_start) = parse_header(bytes)?;
+        decode(&header, &bytes[body_start..])
+    }
+
+    #[test]
+    fn test_encodings() {
+        let values: Vec<f32> = (0..12).map(|x| x as f32 * 0.5).collect();
+        let header = |ty: &str, encoding: &str, endian: &str| {
+            format!("NRRD0004\n# comment\ntype: {ty}\ndimension: 3\nsizes: 3 2 2\nencoding: {encoding}\n{endian}\n")
+        };
+
+        let mut raw = header("float", "raw", "endian: little\n").into_bytes();
+        raw.extend(values.iter().flat_map(|x| x.to_le_bytes()));
+        assert_eq!(parse(&raw).unwrap(), values);
+
+        let mut big = header("ushort", "raw", "endian: big\n").into_bytes();
+        big.extend((0..12_u16).flat_map(|x| x.to_be_bytes()));
+        assert_eq!(parse(&big).unwrap(), (0..12).map(|x| x as f32).collect::<Vec<_>>());
+
+        let text = values.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" ");
+        let ascii = header("double", "ascii", "") + &text;
+        assert_eq!(parse(ascii.as_bytes()).unwrap(), values);
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&(0..12_u8).collect::<Vec<_>>()).unwrap();
+        let mut gzip = header("uchar", "gzip", "").into_bytes();
+        gzip.extend(encoder.finish().unwrap());
+        assert_eq!(parse(&gzip).unwrap(), (0..12).map(|x| x as f32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_detached() {
+        let dir = std::env::temp_dir();
+        let header =
+            "NRRD0004\ntype: uint16\nsizes: 2 1 1\nendian: little\nencoding: raw\ndata file: rusttracer_test.raw\n";
+        fs::write(dir.join("rusttracer_test.nhdr"), header).unwrap();
+        fs::write(dir.join("rusttracer_test.raw"), [1, 0, 0, 1]).unwrap();
+        let grid = load_nrrd(dir.join("rusttracer_test.nhdr")).unwrap();
+        assert_eq!(grid.resolution(), [2, 1, 1]);
+        assert_eq!(grid.sample([1, 0, 0]), 256.);
+
+        let err = load_nrrd(dir.join("rusttracer_missing.nrrd")).unwrap_err();
+        assert!(matches!(err, NrrdLoadError::Io { .. }));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse(b"P6\n").is_err());
+        let source = "NRRD0004\ntype: float\nsizes: 2 2\nencoding: raw\nendian: little\n\n";
+        assert!(parse(source.as_bytes()).unwrap_err().contains("3D"));
+        let source = "NRRD0004\ntype: float\nsizes: 1 1 2\nencoding: raw\n\n";
+        assert!(parse(source.as_bytes()).unwrap_err().contains("endian"));
+        let source = "NRRD0004\ntype: uchar\nsizes: 1 1 2\nencoding: raw\n\n\x01";
+        assert!(parse(source.as_bytes()).unwrap_err().contains("expected 2 bytes"));
+    }
+}
@@ -0,0 +1,89 @@
+use std::f32::consts::PI;
+
+use crate::{
+    math::{dot, utils::spherical_coordinates::spherical_direction, Frame, Normed, Unit},
+    Point2f, Vec3f,
+};
+
+#[derive(Debug)]
+pub struct PhaseFunctionSample {
+    /// Value of the phase function, equal to `pdf` for [HGPhaseFunction]
+    pub p: f32,
+    pub incoming: Unit<Vec3f>,
+    pub pdf: f32,
+}
+
+/// Henyey-Greenstein phase function. Both directions point away from the scattering point, as with BSDFs
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct HGPhaseFunction {
+    /// Asymmetry in (-1; 1), positive values scatter forward, 0 is isotropic
+    pub g: f32,
+}
+
+impl HGPhaseFunction {
+    pub fn new(g: f32) -> Self { HGPhaseFunction { g } }
+
+    pub fn p(&self, outgoing: Vec3f, incoming: Vec3f) -> f32 { henyey_greenstein(dot(&outgoing, &incoming), self.g) }
+
+    pub fn sample(&self, outgoing: Vec3f, rnd_p: Point2f) -> Option<PhaseFunctionSample> {
+        let g = self.g;
+        // Cosine of the angle between `outgoing` and `incoming`, forward scattering makes them opposite
+        let cos_theta = if g.abs() < 1e-3 {
+            1. - 2. * rnd_p.x
+        } else {
+            -1. / (2. * g) * (1. + g.powi(2) - ((1. - g.powi(2)) / (1. + g - 2. * g * rnd_p.x)).powi(2))
+        };
+        let sin_theta = (1. - cos_theta.powi(2)).max(0.).sqrt();
+        let phi = 2. * PI * rnd_p.y;
+        let incoming = Frame::from_z(outgoing).from_local(spherical_direction(sin_theta, cos_theta, phi));
+        let pdf = henyey_greenstein(cos_theta, g);
+        Some(PhaseFunctionSample {
+            p: pdf,
+            incoming: incoming.to_unit(),
+            pdf,
+        })
+    }
+
+    pub fn pdf(&self, outgoing: Vec3f, incoming: Vec3f) -> f32 { self.p(outgoing, incoming) }
+}
+
+fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let denom = 1. + g.powi(2) + 2. * g * cos_theta;
+    (1. - g.powi(2)) / (4. * PI * denom * denom.max(0.).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{point2, samplers::utils::sample_uniform_sphere, vec3};
+
+    #[test]
+    fn test_henyey_greenstein() {
+        let outgoing = vec3!(0., 0.6, 0.8);
+        for g in [-0.7, 0., 0.3, 0.9] {
+            let phase = HGPhaseFunction::new(g);
+            // Integrates to one over the sphere
+            let n = 64;
+            let mut integral = 0.;
+            for i in 0..n {
+                for j in 0..n {
+                    let u = point2!((i as f32 + 0.5) / n as f32, (j as f32 + 0.5) / n as f32);
+                    integral += phase.p(outgoing, *sample_uniform_sphere(u)) * 4. * PI;
+                }
+            }
+            assert_abs_diff_eq!(integral / (n * n) as f32, 1., epsilon = 0.05);
+
+            // Sampled directions are distributed according to the function itself
+            let sample = phase.sample(outgoing, point2!(0.3, 0.7)).unwrap();
+            assert_abs_diff_eq!(sample.pdf, phase.pdf(outgoing, *sample.incoming), epsilon = 1e-3);
+        }
+
+        // Forward scattering continues the ray, i.e. `incoming` is opposite to `outgoing`
+        let forward = HGPhaseFunction::new(0.9);
+        assert!(forward.p(outgoing, -outgoing) > forward.p(outgoing, outgoing));
+        let sample = forward.sample(outgoing, point2!(0.5, 0.5)).unwrap();
+        assert!(dot(&sample.incoming, &outgoing) < 0.);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{aggregates::Aabb, math::utils::lerp, point3, Point3f};
+
+/// Values on a regular grid over [0; 1]^3. Samples are at voxel centers and interpolated trilinearly, lookups fade to
+/// zero within half a voxel of the boundary
+#[derive(Debug, Clone)]
+pub struct SampledGrid {
+    resolution: [usize; 3],
+    values: Vec<f32>,
+}
+
+impl SampledGrid {
+    /// `values` are ordered by x first, then y, then z. `None` if their number doesn't match the resolution
+    pub fn new(values: Vec<f32>, resolution: [usize; 3]) -> Option<Self> {
+        (resolution.iter().all(|x| *x > 0) && values.len() == resolution.iter().product())
+            .then_some(SampledGrid { resolution, values })
+    }
+
+    pub fn resolution(&self) -> [usize; 3] { self.resolution }
+
+    /// Value of a single sample, zero outside of the grid
+    pub fn sample(&self, [x, y, z]: [i64; 3]) -> f32 {
+        let [nx, ny, nz] = self.resolution.map(|x| x as i64);
+        if !(0..nx).contains(&x) || !(0..ny).contains(&y) || !(0..nz).contains(&z) {
+            return 0.;
+        }
+        self.values[((z * ny + y) * nx + x) as usize]
+    }
+
+    pub fn lookup(&self, p: Point3f) -> f32 {
+        let p = [p.x, p.y, p.z];
+        let p_samples: [f32; 3] = std::array::from_fn(|i| p[i] * self.resolution[i] as f32 - 0.5);
+        let [x, y, z] = p_samples.map(|x| x.floor() as i64);
+        let [dx, dy, dz]: [f32; 3] = std::array::from_fn(|i| p_samples[i] - p_samples[i].floor());
+        let along_x = |y, z| lerp(self.sample([x, y, z]), self.sample([x + 1, y, z]), dx);
+        let along_y = |z| lerp(along_x(y, z), along_x(y + 1, z), dy);
+        lerp(along_y(z), along_y(z + 1), dz)
+    }
+
+    /// Upper bound of the lookups inside `bounds`, given in [0; 1]^3
+    pub fn max_value(&self, bounds: &Aabb<f32>) -> f32 {
+        let min = [bounds.min.x, bounds.min.y, bounds.min.z];
+        let max = [bounds.max.x, bounds.max.y, bounds.max.z];
+        let [x0, y0, z0]: [i64; 3] =
+            std::array::from_fn(|i| ((min[i] * self.resolution[i] as f32 - 0.5).floor() as i64).max(0));
+        let [x1, y1, z1]: [i64; 3] = std::array::from_fn(|i| {
+            ((max[i] * self.resolution[i] as f32 - 0.5).floor() as i64 + 1).min(self.resolution[i] as i64 - 1)
+        });
+        let mut max_value = 0_f32;
+        for z in z0..=z1 {
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    max_value = max_value.max(self.sample([x, y, z]));
+                }
+            }
+        }
+        max_value
+    }
+}
+
+/// Coarse grid of maximum densities over [0; 1]^3, lets tracking take long steps through thin parts of a medium
+#[derive(Debug, Clone)]
+pub struct MajorantGrid {
+    resolution: [usize; 3],
+    values: Vec<f32>,
+}
+
+impl MajorantGrid {
+    pub fn new(grid: &SampledGrid, resolution: [usize; 3]) -> Self {
+        let [nx, ny, nz] = resolution;
+        let mut values = Vec::with_capacity(nx * ny * nz);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let voxel = |i: usize, n: usize| [i as f32 / n as f32, (i + 1) as f32 / n as f32];
+                    let ([x0, x1], [y0, y1], [z0, z1]) = (voxel(x, nx), voxel(y, ny), voxel(z, nz));
+                    values.push(grid.max_value(&Aabb::new(point3!(x0, y0, z0), point3!(x1, y1, z1))));
+                }
+            }
+        }
+        MajorantGrid { resolution, values }
+    }
+
+    pub fn resolution(&self) -> [usize; 3] { self.resolution }
+
+    pub fn lookup(&self, [x, y, z]: [usize; 3]) -> f32 {
+        let [nx, ny, _] = self.resolution;
+        self.values[(z * ny + y) * nx + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let grid = SampledGrid::new((0..8).map(|x| x as f32).collect(), [2, 2, 2]).unwrap();
+        // Voxel centers hold the samples
+        assert_abs_diff_eq!(grid.lookup(point3!(0.25, 0.25, 0.25)), 0.);
+        assert_abs_diff_eq!(grid.lookup(point3!(0.75, 0.25, 0.25)), 1.);
+        assert_abs_diff_eq!(grid.lookup(point3!(0.75, 0.75, 0.75)), 7.);
+        assert_abs_diff_eq!(grid.lookup(point3!(0.5, 0.5, 0.5)), 3.5);
+        // Fades out towards the boundary
+        assert_abs_diff_eq!(grid.lookup(point3!(0.75, 0.75, 1.)), 3.5);
+        assert!(SampledGrid::new(vec![0.; 7], [2, 2, 2]).is_none());
+    }
+
+    #[test]
+    fn test_majorants() {
+        let mut values = vec![0.; 64];
+        values[(3 * 4 + 3) * 4 + 3] = 2.;
+        let grid = SampledGrid::new(values, [4, 4, 4]).unwrap();
+        let majorants = MajorantGrid::new(&grid, [2, 2, 2]);
+        assert_eq!(majorants.lookup([1, 1, 1]), 2.);
+        assert_eq!(majorants.lookup([0, 0, 0]), 0.);
+        // Bounds every lookup within the voxel
+        for i in 0..=20 {
+            let t = 0.5 + i as f32 / 40.;
+            assert!(grid.lookup(point3!(t, t, t)) <= majorants.lookup([1, 1, 1]));
+        }
+    }
+}
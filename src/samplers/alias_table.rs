@@ -0,0 +1,119 @@
+/// Discrete distribution over indices, sampled in constant time with Walker's alias method
+#[derive(Debug, Clone)]
+pub struct AliasTable {
+    bins: Vec<Bin>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Bin {
+    /// Probability of keeping this bin instead of jumping to its alias
+    q: f32,
+    pmf: f32,
+    alias: usize,
+}
+
+impl AliasTable {
+    /// Negative weights are treated as their absolute values. If all weights are zero, nothing can be sampled
+    /// and the table is empty
+    pub fn new(weights: &[f32]) -> Self {
+        let n = weights.len();
+        let sum: f64 = weights.iter().map(|x| x.abs() as f64).sum();
+        if sum == 0. {
+            return AliasTable { bins: vec![] };
+        }
+        let mut bins: Vec<Bin> = weights
+            .iter()
+            .map(|x| Bin {
+                pmf: (x.abs() as f64 / sum) as f32,
+                ..Default::default()
+            })
+            .collect();
+
+        // Split bins into those under and over the average probability, then pair them up
+        let mut under = vec![];
+        let mut over = vec![];
+        for (i, bin) in bins.iter().enumerate() {
+            let p = bin.pmf as f64 * n as f64;
+            if p < 1. {
+                under.push((i, p));
+            } else {
+                over.push((i, p));
+            }
+        }
+        while let (Some(&(small, p_small)), Some(&(large, p_large))) = (under.last(), over.last()) {
+            under.pop();
+            over.pop();
+            bins[small].q = p_small as f32;
+            bins[small].alias = large;
+
+            let excess = p_small + p_large - 1.;
+            if excess < 1. {
+                under.push((large, excess));
+            } else {
+                over.push((large, excess));
+            }
+        }
+        // Leftovers are due to rounding and must have probability of one
+        for (i, _) in under.into_iter().chain(over) {
+            bins[i].q = 1.;
+            bins[i].alias = i;
+        }
+
+        AliasTable { bins }
+    }
+
+    pub fn size(&self) -> usize { self.bins.len() }
+
+    /// Returns sampled index, its probability and `rnd_c` remapped to `[0, 1)` for reuse
+    pub fn sample(&self, rnd_c: f32) -> Option<(usize, f32, f32)> {
+        if self.bins.is_empty() {
+            return None;
+        }
+        let scaled = rnd_c * self.size() as f32;
+        let offset = (scaled as usize).min(self.size() - 1);
+        let up = (scaled - offset as f32).min(1. - f32::EPSILON);
+
+        let bin = &self.bins[offset];
+        if up < bin.q {
+            Some((offset, bin.pmf, (up / bin.q).min(1. - f32::EPSILON)))
+        } else {
+            let alias = bin.alias;
+            let remapped = ((up - bin.q) / (1. - bin.q)).min(1. - f32::EPSILON);
+            Some((alias, self.bins[alias].pmf, remapped))
+        }
+    }
+
+    pub fn pmf(&self, index: usize) -> f32 { self.bins.get(index).map_or(0., |x| x.pmf) }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_alias_table() {
+        let table = AliasTable::new(&[1., 0., 3., 4.]);
+        assert_abs_diff_eq!(table.pmf(0), 0.125);
+        assert_eq!(table.pmf(1), 0.);
+        assert_abs_diff_eq!(table.pmf(3), 0.5);
+
+        // Sampled frequencies match the PMF
+        let n = 1000;
+        let mut counts = [0; 4];
+        for i in 0..n {
+            let (index, pmf, remapped) = table.sample((i as f32 + 0.5) / n as f32).unwrap();
+            assert_eq!(pmf, table.pmf(index));
+            assert!((0. ..1.).contains(&remapped));
+            counts[index] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        for (i, count) in counts.into_iter().enumerate() {
+            assert_abs_diff_eq!(count as f32 / n as f32, table.pmf(i), epsilon = 1e-2);
+        }
+
+        assert!(AliasTable::new(&[0., 0.]).sample(0.5).is_none());
+        assert!(AliasTable::new(&[]).sample(0.5).is_none());
+    }
+}
@@ -13,7 +13,7 @@ pub struct IndependentSampler {
 }
 
 impl IndependentSampler {
-    pub(crate) fn new(samples_per_pixel: u32, seed: u64) -> Self {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
         let rng = SmallRng::seed_from_u64(seed);
         IndependentSampler {
             samples_per_pixel,
@@ -5,7 +5,9 @@ use num_traits::One;
 pub use crate::samplers::stratified::StratifiedSampler;
 use crate::{Point2f, Point2us};
 
+pub mod alias_table;
 mod independent;
+pub mod piecewise;
 mod stratified;
 pub mod utils;
 
@@ -0,0 +1,152 @@
+use crate::{point2, Point2f};
+
+/// Distribution over `[0, 1)` proportional to a step function with equally sized steps
+#[derive(Debug, Clone)]
+pub struct PiecewiseConstant1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    func_int: f32,
+}
+
+impl PiecewiseConstant1D {
+    /// Negative values are treated as their absolute values
+    pub fn new(func: &[f32]) -> Self {
+        assert!(!func.is_empty(), "function must have at least one value");
+        let func: Vec<f32> = func.iter().map(|x| x.abs()).collect();
+        let n = func.len();
+
+        let mut cdf = Vec::with_capacity(n + 1);
+        cdf.push(0.);
+        for i in 0..n {
+            cdf.push(cdf[i] + func[i] / n as f32);
+        }
+        let func_int = cdf[n];
+        if func_int == 0. {
+            // Fall back to the uniform distribution
+            cdf.iter_mut().enumerate().for_each(|(i, x)| *x = i as f32 / n as f32);
+        } else {
+            cdf.iter_mut().for_each(|x| *x /= func_int);
+        }
+
+        PiecewiseConstant1D { func, cdf, func_int }
+    }
+
+    pub fn size(&self) -> usize { self.func.len() }
+
+    /// Integral of the function over `[0, 1)`
+    pub fn integral(&self) -> f32 { self.func_int }
+
+    /// Returns sampled value, its PDF and index of the step it falls into
+    pub fn sample(&self, rnd_c: f32) -> (f32, f32, usize) {
+        // Last cdf entry not greater than `rnd_c`
+        let offset = self.cdf.partition_point(|&x| x <= rnd_c).clamp(1, self.size()) - 1;
+
+        let mut du = rnd_c - self.cdf[offset];
+        let step = self.cdf[offset + 1] - self.cdf[offset];
+        if step > 0. {
+            du /= step;
+        }
+        let pdf = if self.func_int > 0. {
+            self.func[offset] / self.func_int
+        } else {
+            0.
+        };
+        let x = ((offset as f32 + du) / self.size() as f32).min(1. - f32::EPSILON);
+        (x, pdf, offset)
+    }
+
+    pub fn pdf(&self, x: f32) -> f32 {
+        if self.func_int == 0. {
+            return 0.;
+        }
+        let offset = ((x * self.size() as f32) as usize).min(self.size() - 1);
+        self.func[offset] / self.func_int
+    }
+}
+
+/// Distribution over `[0, 1)^2` proportional to a function defined on a grid. Sampled by choosing a row from the
+/// marginal distribution, then a column from the conditional distribution of that row
+#[derive(Debug, Clone)]
+pub struct PiecewiseConstant2D {
+    conditional: Vec<PiecewiseConstant1D>,
+    marginal: PiecewiseConstant1D,
+}
+
+impl PiecewiseConstant2D {
+    /// `func` is stored row by row, `nu` values per row
+    pub fn new(func: &[f32], nu: usize) -> Self {
+        assert!(nu > 0 && func.len() % nu == 0, "function must have `nu` values per row");
+        let conditional: Vec<_> = func.chunks(nu).map(PiecewiseConstant1D::new).collect();
+        let marginal = PiecewiseConstant1D::new(&conditional.iter().map(|x| x.integral()).collect::<Vec<_>>());
+        PiecewiseConstant2D { conditional, marginal }
+    }
+
+    pub fn integral(&self) -> f32 { self.marginal.integral() }
+
+    /// Returns sampled point and its PDF
+    pub fn sample(&self, rnd_p: Point2f) -> (Point2f, f32) {
+        let (v, pdf_v, row) = self.marginal.sample(rnd_p.y);
+        let (u, pdf_u, _) = self.conditional[row].sample(rnd_p.x);
+        (point2!(u, v), pdf_u * pdf_v)
+    }
+
+    pub fn pdf(&self, p: Point2f) -> f32 {
+        if self.integral() == 0. {
+            return 0.;
+        }
+        let row = &self.conditional[((p.y * self.marginal.size() as f32) as usize).min(self.marginal.size() - 1)];
+        let col = ((p.x * row.size() as f32) as usize).min(row.size() - 1);
+        row.func[col] / self.integral()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_1d() {
+        let dist = PiecewiseConstant1D::new(&[1., 0., 3.]);
+        assert_abs_diff_eq!(dist.integral(), 4. / 3.);
+
+        let (x, pdf, offset) = dist.sample(0.1);
+        assert_eq!(offset, 0);
+        assert_abs_diff_eq!(x, 0.4 / 3.);
+        assert_abs_diff_eq!(pdf, 0.75);
+
+        // Zero-valued steps are never sampled
+        let (x, pdf, offset) = dist.sample(0.25);
+        assert_eq!(offset, 2);
+        assert_abs_diff_eq!(x, 2. / 3.);
+        assert_abs_diff_eq!(pdf, 2.25);
+        assert_abs_diff_eq!(dist.pdf(x), pdf);
+        assert_eq!(dist.pdf(0.5), 0.);
+
+        let uniform = PiecewiseConstant1D::new(&[0., 0.]);
+        let (x, pdf, _) = uniform.sample(0.75);
+        assert_abs_diff_eq!(x, 0.75);
+        assert_eq!(pdf, 0.);
+    }
+
+    #[test]
+    fn test_2d() {
+        #[rustfmt::skip]
+        let dist = PiecewiseConstant2D::new(&[
+            1., 1.,
+            0., 2.,
+        ], 2);
+        assert_abs_diff_eq!(dist.integral(), 1.);
+
+        let (p, pdf) = dist.sample(point2!(0.5, 0.75));
+        assert_abs_diff_eq!(p, point2!(0.75, 0.75));
+        assert_abs_diff_eq!(pdf, 2.);
+        assert_abs_diff_eq!(dist.pdf(p), pdf);
+
+        let (p, pdf) = dist.sample(point2!(0.25, 0.25));
+        assert_abs_diff_eq!(p, point2!(0.25, 0.25));
+        assert_abs_diff_eq!(pdf, 1.);
+        assert_eq!(dist.pdf(point2!(0.25, 0.75)), 0.);
+    }
+}
@@ -19,7 +19,7 @@ pub struct StratifiedSampler {
 }
 
 impl StratifiedSampler {
-    pub(crate) fn new(x_samples: u32, y_samples: u32, jitter: bool, seed: u64) -> Self {
+    pub fn new(x_samples: u32, y_samples: u32, jitter: bool, seed: u64) -> Self {
         let rng = SmallRng::seed_from_u64(seed);
         let samples_per_pixel = x_samples * y_samples;
         StratifiedSampler {
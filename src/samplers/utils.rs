@@ -3,10 +3,10 @@ use std::f32::consts::{FRAC_2_PI, FRAC_PI_4, PI};
 use num_traits::Zero;
 
 use crate::{
-    math::{utils::spherical_coordinates::spherical_direction, Unit, Vec3},
+    math::{cross, dot, utils::spherical_coordinates::spherical_direction, Normed, Unit, Vec3},
     point2,
     spectra::{VISIBLE_MAX, VISIBLE_MIN},
-    unit3_unchecked, vec2, Point2f, Vec3f,
+    unit3_unchecked, vec2, Point2f, Point3f, Vec3f,
 };
 
 // TODO: should check all math-y things and do them properly. Finding NaNs in random places isn't funny
@@ -66,6 +66,106 @@ pub fn sample_uniform_disk_concentric(u: Point2f) -> Point2f {
     point2!(theta.cos(), theta.sin()) * r
 }
 
+pub fn sample_uniform_disk_polar(u: Point2f) -> Point2f {
+    let r = u.x.sqrt();
+    let theta = 2. * PI * u.y;
+    point2!(r * theta.cos(), r * theta.sin())
+}
+
+/// Barycentric coordinates of a uniformly distributed point on a triangle
+pub fn sample_uniform_triangle(u: Point2f) -> [f32; 3] {
+    let (b0, b1) = if u.x < u.y {
+        let b0 = u.x / 2.;
+        (b0, u.y - b0)
+    } else {
+        let b1 = u.y / 2.;
+        (u.x - b1, b1)
+    };
+    [b0, b1, 1. - b0 - b1]
+}
+
+/// Samples the tent function of radius `r` centered at zero
+pub fn sample_tent(u: f32, r: f32) -> f32 {
+    // Pick a side, then sample the linear ramp on it
+    if u < 0.5 {
+        -r + r * (2. * u).sqrt()
+    } else {
+        r - r * (2. - 2. * u).sqrt()
+    }
+}
+
+/// Solid angle of the spherical triangle with the given vertices on the unit sphere
+pub fn spherical_triangle_area(a: Vec3f, b: Vec3f, c: Vec3f) -> f32 {
+    (2. * f32::atan2(dot(&a, &cross(&b, &c)), 1. + dot(&a, &b) + dot(&a, &c) + dot(&b, &c))).abs()
+}
+
+/// Uniformly samples the solid angle the triangle `vertices` subtends at `point` (Arvo 1995). Returns barycentric
+/// coordinates of the point seen in the sampled direction and the solid angle density
+pub fn sample_spherical_triangle(vertices: [Point3f; 3], point: Point3f, u: Point2f) -> Option<([f32; 3], f32)> {
+    let [a, b, c] = vertices.map(|v| *(v - point).to_unit());
+    let [n_ab, n_bc, n_ca] = [cross(&a, &b), cross(&b, &c), cross(&c, &a)];
+    if [n_ab, n_bc, n_ca].iter().any(|n| n.len_squared() == 0.) {
+        return None;
+    }
+    let [n_ab, n_bc, n_ca] = [n_ab, n_bc, n_ca].map(|n| *n.to_unit());
+
+    // Angles at the vertices of the spherical triangle
+    let alpha = angle_between(n_ab, -n_ca);
+    let beta = angle_between(n_bc, -n_ab);
+    let gamma = angle_between(n_ca, -n_bc);
+    let area = alpha + beta + gamma - PI;
+    if area <= 0. {
+        return None;
+    }
+
+    // Area of the sub-triangle with the sampled vertex `c_p` on the arc from `a` to `c`
+    let area_pi = PI + u.x * area;
+    let (sin_alpha, cos_alpha) = alpha.sin_cos();
+    let sin_phi = area_pi.sin() * cos_alpha - area_pi.cos() * sin_alpha;
+    let cos_phi = area_pi.cos() * cos_alpha + area_pi.sin() * sin_alpha;
+    let k1 = cos_phi + cos_alpha;
+    let k2 = sin_phi - sin_alpha * dot(&a, &b);
+    let cos_b_p =
+        ((k2 + (k2 * cos_phi - k1 * sin_phi) * cos_alpha) / ((k2 * sin_phi + k1 * cos_phi) * sin_alpha)).clamp(-1., 1.);
+    let sin_b_p = (1. - cos_b_p.powi(2)).max(0.).sqrt();
+    let c_p = cos_b_p * a + sin_b_p * *gram_schmidt(c, a).to_unit();
+
+    // Direction on the arc from `b` to `c_p`
+    let cos_theta = 1. - u.y * (1. - dot(&c_p, &b));
+    let sin_theta = (1. - cos_theta.powi(2)).max(0.).sqrt();
+    let dir = cos_theta * b + sin_theta * *gram_schmidt(c_p, b).to_unit();
+
+    // Intersect the triangle in that direction
+    let [v0, v1, v2] = vertices;
+    let (e1, e2) = (v1 - v0, v2 - v0);
+    let s1 = cross(&dir, &e2);
+    let divisor = dot(&s1, &e1);
+    if divisor == 0. {
+        return Some(([1. / 3.; 3], area.recip()));
+    }
+    let s = point - v0;
+    let b1 = (dot(&s, &s1) / divisor).clamp(0., 1.);
+    let b2 = (dot(&dir, &cross(&s, &e1)) / divisor).clamp(0., 1.);
+    let (b1, b2) = if b1 + b2 > 1. {
+        (b1 / (b1 + b2), b2 / (b1 + b2))
+    } else {
+        (b1, b2)
+    };
+    Some(([1. - b1 - b2, b1, b2], area.recip()))
+}
+
+/// Angle between unit vectors, accurate for nearly parallel ones
+fn angle_between(a: Vec3f, b: Vec3f) -> f32 {
+    if dot(&a, &b) < 0. {
+        PI - 2. * ((a + b).len() / 2.).clamp(-1., 1.).asin()
+    } else {
+        2. * ((b - a).len() / 2.).clamp(-1., 1.).asin()
+    }
+}
+
+/// Component of `v` orthogonal to the unit vector `w`
+fn gram_schmidt(v: Vec3f, w: Vec3f) -> Vec3f { v - dot(&v, &w) * w }
+
 /// Samples visible wavelength according to it visual importance
 pub fn sample_visible_wavelengths(rnd_c: f32) -> f32 { 538.0 - 138.888_89 * (0.85691062 - 1.827_502 * rnd_c).atanh() }
 
@@ -2,8 +2,9 @@ use std::{cmp::max, ops::Deref, sync::Arc};
 
 use crate::{
     core::{ray::RayDifferential, Ray},
-    math::{dot, Normed, Transform, Transformable},
-    point3,
+    math::{dot, Frame, Normed, Transform, Transformable},
+    mediums::MediumEnum,
+    point2, point3,
     scene::{
         cameras::{Camera, CameraSample},
         film::RGBFilm,
@@ -15,16 +16,66 @@ use crate::{
 pub(super) struct BaseCamera {
     pub(super) camera_to_world: Transform<f32>,
     pub(super) film: Arc<RGBFilm>,
-    // pub(super) medium: ???
-    min_pos_differential_x: Vec3f,
-    min_pos_differential_y: Vec3f,
-    min_dir_differential_x: Vec3f,
-    min_dir_differential_y: Vec3f,
+    pub(super) medium: Option<Arc<MediumEnum>>,
+    pub(super) min_differentials: MinimumDifferentials,
+}
+
+/// Smallest camera space differentials of camera rays across the film. Used to approximate dp/dx and dp/dy where
+/// no ray differentials are available
+#[derive(Debug, Default, Copy, Clone)]
+pub(super) struct MinimumDifferentials {
+    pos_x: Vec3f,
+    pos_y: Vec3f,
+    dir_x: Vec3f,
+    dir_y: Vec3f,
+}
+
+impl MinimumDifferentials {
+    pub(super) fn find(camera: &impl Camera, camera_to_world: &Transform<f32>) -> Self {
+        const N: usize = 512;
+        let resolution = camera.get_film().resolution.map(|x| x as f32);
+        let infinity = vec3!(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut min = MinimumDifferentials {
+            pos_x: infinity,
+            pos_y: infinity,
+            dir_x: infinity,
+            dir_y: infinity,
+        };
+        let keep_shortest = |min: &mut Vec3f, v: Vec3f| {
+            if v.len() < min.len() {
+                *min = v
+            }
+        };
+
+        // Sample the film diagonal
+        for i in 0..N {
+            let t = i as f32 / (N - 1) as f32;
+            let sample = CameraSample {
+                p_film: point2!(t * resolution.x, t * resolution.y),
+                p_lens: point2!(0.5, 0.5),
+                filter_weight: 1.,
+            };
+            let ray = camera.generate_differential_ray(sample).inv_transform(camera_to_world);
+            let Some(diff) = ray.diff else { continue };
+
+            keep_shortest(&mut min.pos_x, diff.rx_origin - ray.origin);
+            keep_shortest(&mut min.pos_y, diff.ry_origin - ray.origin);
+
+            // Direction differentials in the frame of the main ray
+            let frame = Frame::from_z(*ray.dir);
+            let dir = frame.to_local(*ray.dir);
+            keep_shortest(&mut min.dir_x, frame.to_local(*diff.rx_direction) - dir);
+            keep_shortest(&mut min.dir_y, frame.to_local(*diff.ry_direction) - dir);
+        }
+        min
+    }
 }
 
 pub struct BaseCameraConfig {
     pub transform: Transform<f32>,
-    pub film: RGBFilm, // pub medium: ???
+    pub film: RGBFilm,
+    /// Medium the camera is in, `None` for vacuum
+    pub medium: Option<Arc<MediumEnum>>,
 }
 
 impl BaseCamera {
@@ -63,18 +114,18 @@ impl BaseCamera {
 
         // Find intersection points for approximated camera differential rays
         let x_ray = Ray::new(
-            point3!() + self.min_pos_differential_x,
-            (z + self.min_dir_differential_x).to_unit(),
+            point3!() + self.min_differentials.pos_x,
+            (z + self.min_differentials.dir_x).to_unit(),
             None,
         );
         let tx = -(dot(&n_down_z, &x_ray.origin) - d) / dot(&n_down_z, &x_ray.dir);
         let y_ray = Ray::new(
-            point3!() + self.min_pos_differential_y,
-            (z + self.min_dir_differential_y).to_unit(),
+            point3!() + self.min_differentials.pos_y,
+            (z + self.min_differentials.dir_y).to_unit(),
             None,
         );
 
-        let ty = -(dot(&n_down_z, &x_ray.origin) - d) / dot(&n_down_z, &y_ray.dir);
+        let ty = -(dot(&n_down_z, &y_ray.origin) - d) / dot(&n_down_z, &y_ray.dir);
         let px = x_ray.at(tx);
         let py = y_ray.at(ty);
 
@@ -102,10 +153,8 @@ impl From<BaseCameraConfig> for BaseCamera {
         Self {
             camera_to_world: config.transform,
             film: Arc::new(config.film),
-            min_pos_differential_x: Default::default(),
-            min_pos_differential_y: Default::default(),
-            min_dir_differential_x: Default::default(),
-            min_dir_differential_y: Default::default(),
+            medium: config.medium,
+            min_differentials: Default::default(),
         }
     }
 }
@@ -6,9 +6,14 @@ pub use perspective::{PerspectiveCamera, PerspectiveCameraConfig};
 
 use crate::{
     core::Ray,
+    mediums::MediumEnum,
+    point2,
     samplers::{Sampler, SamplerType},
-    scene::film::RGBFilm,
-    Normal3f, Point2f, Point2us, Point3f, Vec3f,
+    scene::{
+        film::RGBFilm,
+        filters::{Filter, FilterEnum},
+    },
+    Bounds2f, Normal3f, Point2f, Point2us, Point3f, Vec2f, Vec3f,
 };
 
 mod base;
@@ -28,6 +33,9 @@ pub trait Camera {
     fn approximate_dp_dxy(&self, point: Point3f, normal: Normal3f, samples_per_pixel: u32) -> (Vec3f, Vec3f);
 
     fn get_film(&self) -> Arc<RGBFilm>;
+
+    /// Medium camera rays start in
+    fn medium(&self) -> Option<Arc<MediumEnum>>;
 }
 
 #[enum_delegate::implement(Camera)]
@@ -40,15 +48,41 @@ pub enum CameraType {
 pub struct CameraSample {
     pub p_film: Point2f,
     pub p_lens: Point2f,
+    /// Weight of the sample's radiance on the film
+    pub filter_weight: f32,
 }
 
 impl CameraSample {
-    pub fn new(pixel: Point2us, sampler: &mut SamplerType) -> Self {
+    /// Samples a film position around the center of `pixel` according to `filter`
+    pub fn new(pixel: Point2us, sampler: &mut SamplerType, filter: &FilterEnum) -> Self {
         // Offset from discrete pixels to continuous one
         // Disc. |---0---|---1---|---2---|
         // Cont. 0-------1-------2-------3
-        let p_film = pixel.map(|x| x as f32) + *sampler.get_2d();
+        let filter_sample = filter.sample(sampler.get_2d());
+        let p_film = pixel.map(|x| x as f32 + 0.5) + *filter_sample.p;
         let p_lens = sampler.get_2d();
-        CameraSample { p_film, p_lens }
+        CameraSample {
+            p_film,
+            p_lens,
+            filter_weight: filter_sample.weight,
+        }
+    }
+
+    /// Same lens sample on a film position moved by `offset` pixels
+    pub fn offset(self, offset: Vec2f) -> Self {
+        CameraSample {
+            p_film: self.p_film + offset,
+            ..self
+        }
+    }
+}
+
+/// Screen window spanning [-1; 1] along the shorter image axis, as in PBRT
+pub fn default_screen_window(resolution: Point2us) -> Bounds2f {
+    let aspect_ratio = resolution.x as f32 / resolution.y as f32;
+    if aspect_ratio > 1. {
+        Bounds2f::from_points(point2!(-aspect_ratio, -1.), point2!(aspect_ratio, 1.))
+    } else {
+        Bounds2f::from_points(point2!(-1., -1. / aspect_ratio), point2!(1., 1. / aspect_ratio))
     }
 }
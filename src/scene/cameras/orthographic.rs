@@ -3,17 +3,18 @@ use std::sync::Arc;
 use crate::{
     core::{ray::RayDifferential, Ray},
     math::{Normed, Transform, Transformable},
+    mediums::MediumEnum,
     ray,
     samplers::utils::sample_uniform_disk_concentric,
     scene::{
         cameras::{
-            base::BaseCameraConfig,
+            base::{BaseCameraConfig, MinimumDifferentials},
             projective::{ProjectiveCamera, ProjectiveCameraConfig},
             Camera, CameraSample, CameraType,
         },
         film::RGBFilm,
     },
-    unit_vec3, vec3, Bounds2f, Normal3f, Point2f, Point3f, Vec3f,
+    unit_vec3, vec2, vec3, Bounds2f, Normal3f, Point2f, Point3f, Vec3f,
 };
 
 pub struct OrthographicCamera {
@@ -53,7 +54,15 @@ impl Camera for OrthographicCamera {
         let mut ray = self.generate_camera_space_ray(sample);
 
         if self.projective.lens_radius > 0. {
-            // TODO
+            // Rays through the neighboring pixels and the same point on the lens
+            let rx = self.generate_camera_space_ray(sample.offset(vec2!(1., 0.)));
+            let ry = self.generate_camera_space_ray(sample.offset(vec2!(0., 1.)));
+            ray.diff = Some(RayDifferential {
+                rx_origin: rx.origin,
+                ry_origin: ry.origin,
+                rx_direction: rx.dir,
+                ry_direction: ry.dir,
+            })
         } else {
             let diff = RayDifferential {
                 rx_origin: ray.origin + self.dx_camera,
@@ -73,16 +82,21 @@ impl Camera for OrthographicCamera {
     }
 
     fn get_film(&self) -> Arc<RGBFilm> { self.projective.base.film.clone() }
+
+    fn medium(&self) -> Option<Arc<MediumEnum>> { self.projective.base.medium.clone() }
 }
 
 impl From<OrthographicCameraConfig> for OrthographicCamera {
     fn from(config: OrthographicCameraConfig) -> Self {
         let projective = ProjectiveCamera::from(config);
-        OrthographicCamera {
+        let mut camera = OrthographicCamera {
             dx_camera: vec3!(1., 0., 0.).transform(&projective.raster_to_camera),
             dy_camera: vec3!(0., 1., 0.).transform(&projective.raster_to_camera),
             projective,
-        }
+        };
+        camera.projective.base.min_differentials =
+            MinimumDifferentials::find(&camera, &camera.projective.base.camera_to_world);
+        camera
     }
 }
 
@@ -4,16 +4,18 @@ use crate::{
     breakpoint,
     core::{ray::RayDifferential, Ray},
     math::{Normed, Transform, Transformable},
+    mediums::MediumEnum,
     point3, ray,
     samplers::utils::sample_uniform_disk_concentric,
     scene::{
         cameras::{
+            base::MinimumDifferentials,
             projective::{ProjectiveCamera, ProjectiveCameraConfig},
             BaseCameraConfig, Camera, CameraSample, CameraType, OrthographicCamera, OrthographicCameraConfig,
         },
         film::RGBFilm,
     },
-    vec3, Bounds2f, Normal3f, Point2f, Point3f, Vec3f,
+    vec2, vec3, Bounds2f, Normal3f, Point2f, Point3f, Vec3f,
 };
 
 pub struct PerspectiveCamera {
@@ -56,7 +58,15 @@ impl Camera for PerspectiveCamera {
         let point_camera = point_raster.transform(&self.projective.raster_to_camera);
 
         if self.projective.lens_radius > 0. {
-            // TODO
+            // Rays through the neighboring pixels and the same point on the lens
+            let rx = self.generate_camera_space_ray(sample.offset(vec2!(1., 0.)));
+            let ry = self.generate_camera_space_ray(sample.offset(vec2!(0., 1.)));
+            ray.diff = Some(RayDifferential {
+                rx_origin: rx.origin,
+                ry_origin: ry.origin,
+                rx_direction: rx.dir,
+                ry_direction: ry.dir,
+            })
         } else {
             let diff = RayDifferential {
                 rx_origin: ray.origin,
@@ -76,6 +86,8 @@ impl Camera for PerspectiveCamera {
     }
 
     fn get_film(&self) -> Arc<RGBFilm> { self.projective.base.film.clone() }
+
+    fn medium(&self) -> Option<Arc<MediumEnum>> { self.projective.base.medium.clone() }
 }
 
 impl From<PerspectiveCameraConfig> for PerspectiveCamera {
@@ -85,11 +97,14 @@ impl From<PerspectiveCameraConfig> for PerspectiveCamera {
             - vec3!(0., 0., 0.).transform(&projective.raster_to_camera);
         let dy_camera = vec3!(0., 1., 0.).transform(&projective.raster_to_camera)
             - vec3!(0., 0., 0.).transform(&projective.raster_to_camera);
-        PerspectiveCamera {
+        let mut camera = PerspectiveCamera {
             projective,
             dx_camera,
             dy_camera,
-        }
+        };
+        camera.projective.base.min_differentials =
+            MinimumDifferentials::find(&camera, &camera.projective.base.camera_to_world);
+        camera
     }
 }
 
@@ -104,3 +119,37 @@ impl From<PerspectiveCameraConfig> for ProjectiveCameraConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point2, scene::filters::FilterEnum, spectra::rgb::sRGB};
+
+    #[test]
+    fn test_orientation() {
+        for lens_radius in [0., 1.] {
+            let camera = PerspectiveCamera::new(PerspectiveCameraConfig {
+                base_config: BaseCameraConfig {
+                    transform: Transform::id(),
+                    film: RGBFilm::new(100, 100, FilterEnum::default(), sRGB.clone()),
+                    medium: None,
+                },
+                fov: 90.,
+                screen_window: Bounds2f::from_points(point2!(-1., -1.), point2!(1., 1.)),
+                lens_radius,
+                focal_distance: 10.,
+            });
+            let sample = |x, y| CameraSample {
+                p_film: point2!(x, y),
+                p_lens: point2!(0.5, 0.5),
+                filter_weight: 1.,
+            };
+
+            // Camera looks along +z, upper-left corner of the image is at -x, +y
+            let center = camera.generate_ray(sample(50., 50.));
+            assert!(center.dir.z > 0.999);
+            let corner = camera.generate_ray(sample(0., 0.));
+            assert!(corner.dir.x < 0. && corner.dir.y > 0. && corner.dir.z > 0.);
+        }
+    }
+}
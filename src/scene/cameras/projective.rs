@@ -26,7 +26,7 @@ impl ProjectiveCamera {
     pub(super) fn adjust_for_dof(&self, ray: &mut Ray, p_lens: Point2f) {
         if self.lens_radius > 0. {
             let point_on_lens = sample_uniform_disk_concentric(p_lens) * self.lens_radius;
-            let t_focal_intersect = self.focal_distance / -ray.dir.z;
+            let t_focal_intersect = self.focal_distance / ray.dir.z;
             let focus_point = ray.at(t_focal_intersect);
             ray.origin = Point3f::from(point_on_lens);
             ray.dir = (focus_point - ray.origin).to_unit();
@@ -42,7 +42,7 @@ where T: Into<ProjectiveCameraConfig>
         // Screen space - regular cartesian 2D parallel to the film plane / XY-camera
         // NDC space - image coordinates from (0,0) - upper-left to (1,1) - bottom-right
         // Raster space - same as NDC, but with actual pixel coordinates
-        // Camera space - left-handed, X - left, Y - up, Z - forward
+        // Camera space - left-handed, X - right, Y - up, Z - forward
         let config = config.into();
 
         let screen_to_raster = Transform::compose_iter([
@@ -1,7 +1,20 @@
-use std::{cmp::min, sync::Arc};
+use std::{
+    cmp::min,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+use exr::prelude::{
+    f16, AnyChannel, AnyChannels, Encoding, FlatSamples as ExrSamples, Image as ExrImage, ImageAttributes,
+    IntegerBounds, Layer, LayerAttributes, SmallVec, WritableImage,
+};
 use image::{
-    buffer::ConvertBuffer, codecs::avif::ColorSpace, FlatSamples, ImageBuffer, ImageResult, Pixel, Rgb, RgbImage,
+    buffer::ConvertBuffer,
+    codecs::avif::ColorSpace,
+    error::{EncodingError, ImageFormatHint, UnsupportedError},
+    FlatSamples, ImageBuffer, ImageError, ImageFormat, ImageResult, Pixel, Rgb, RgbImage,
 };
 use itertools::{any, Itertools};
 use log::{debug, warn};
@@ -12,6 +25,7 @@ use rand::random;
 use crate::{
     math::{Bounds2, Point2},
     point2,
+    scene::filters::FilterEnum,
     spectra::rgb::{RGBColorSpace, RGB},
     utils::linear_to_gamma,
     Point2u, Point2us, SampledSpectrum, SampledWavelengths,
@@ -22,10 +36,62 @@ pub trait Film {
     // fn sample_bounds(&self);
     // fn resolution(&self);
     fn sample_wavelengths(&self, rnd_c: f32) -> SampledWavelengths;
-    fn write_image(&self, path: &str) -> ImageResult<()>;
+    fn write_image(&self, output: &ImageOutput) -> ImageResult<()>;
     fn tiles(&self, height: usize, width: usize) -> Vec<Bounds2<usize>>;
 }
 
+/// Where and how the film is saved
+#[derive(Debug, Clone)]
+pub struct ImageOutput {
+    /// Format is deduced from the extension, see [OutputFormat]
+    pub path: PathBuf,
+    /// Store OpenEXR channels as 16-bit halves instead of 32-bit floats
+    pub half: bool,
+    /// Add an OpenEXR layer named `weight` with the sum of filter weights in each pixel
+    pub weight_layer: bool,
+}
+
+impl ImageOutput {
+    pub fn new(path: PathBuf) -> Self {
+        ImageOutput {
+            path,
+            half: false,
+            weight_layer: false,
+        }
+    }
+
+    /// Fails on extensions that can't be written, so it can be checked before rendering
+    pub fn format(&self) -> ImageResult<OutputFormat> {
+        let extension = self.path.extension().and_then(|x| x.to_str()).map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("exr") => return Ok(OutputFormat::Exr),
+            Some("pfm") => return Ok(OutputFormat::Pfm),
+            _ => {}
+        }
+        match ImageFormat::from_path(&self.path)? {
+            format @ (ImageFormat::Png
+            | ImageFormat::Jpeg
+            | ImageFormat::Bmp
+            | ImageFormat::Tga
+            | ImageFormat::Tiff
+            | ImageFormat::Pnm) => Ok(OutputFormat::Ldr(format)),
+            format => Err(ImageError::Unsupported(UnsupportedError::from(ImageFormatHint::Exact(
+                format,
+            )))),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OutputFormat {
+    /// 8 bits per channel, values are clamped to `[0, 1]`
+    Ldr(ImageFormat),
+    /// Linear half or float OpenEXR, optionally with more layers
+    Exr,
+    /// Linear float Portable FloatMap
+    Pfm,
+}
+
 #[derive(Copy, Clone, Debug, Default)]
 pub struct RGBPixel {
     // TODO: f64?
@@ -33,21 +99,91 @@ pub struct RGBPixel {
     weight: f32,
 }
 
+impl RGBPixel {
+    fn value(&self) -> RGB {
+        // Weights may cancel out with filters that have negative lobes
+        if self.weight != 0. {
+            self.rgb / self.weight
+        } else {
+            RGB::BLACK
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RGBFilm {
     pub resolution: Point2us,
     pixels: Array2<RGBPixel>,
+    filter: FilterEnum,
     color_space: Arc<RGBColorSpace>,
 }
 
 impl RGBFilm {
-    pub fn new(width: usize, height: usize, color_space: Arc<RGBColorSpace>) -> Self {
+    pub fn new(width: usize, height: usize, filter: FilterEnum, color_space: Arc<RGBColorSpace>) -> Self {
         RGBFilm {
             resolution: point2!(width, height),
             pixels: Array2::from_elem((height, width), RGBPixel::default()),
+            filter,
             color_space,
         }
     }
+
+    /// Filter used to place camera samples around pixel centers
+    pub fn filter(&self) -> &FilterEnum { &self.filter }
+
+    fn write_exr(&self, raw_pixels: Vec<f32>, output: &ImageOutput) -> ImageResult<()> {
+        let samples = |values: Vec<f32>| {
+            if output.half {
+                ExrSamples::F16(values.into_iter().map(f16::from_f32).collect())
+            } else {
+                ExrSamples::F32(values)
+            }
+        };
+        let channel = |index: usize| samples(raw_pixels.iter().skip(index).step_by(3).copied().collect());
+        let size = (self.resolution.x, self.resolution.y);
+        let rgb = AnyChannels::sort(SmallVec::from_vec(vec![
+            AnyChannel::new("R", channel(0)),
+            AnyChannel::new("G", channel(1)),
+            AnyChannel::new("B", channel(2)),
+        ]));
+        let mut layers = vec![Layer::new(
+            size,
+            LayerAttributes::named("rgb"),
+            Encoding::FAST_LOSSLESS,
+            rgb,
+        )];
+        if output.weight_layer {
+            let weight = samples(self.pixels.iter().map(|pix| pix.weight).collect());
+            let weight = AnyChannels::sort(SmallVec::from_vec(vec![AnyChannel::new("Y", weight)]));
+            layers.push(Layer::new(
+                size,
+                LayerAttributes::named("weight"),
+                Encoding::FAST_LOSSLESS,
+                weight,
+            ));
+        }
+        let image = ExrImage::from_layers(
+            ImageAttributes::new(IntegerBounds::from_dimensions(size)),
+            SmallVec::from_vec(layers),
+        );
+        image
+            .write()
+            .to_file(&output.path)
+            .map_err(|err| ImageError::Encoding(EncodingError::new(ImageFormatHint::Exact(ImageFormat::OpenExr), err)))
+    }
+
+    fn write_pfm(&self, raw_pixels: &[f32], path: &Path) -> ImageResult<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        // Negative scale marks little-endian data, rows go from bottom to top
+        write!(file, "PF\n{} {}\n-1.0\n", self.resolution.x, self.resolution.y)?;
+        for row in raw_pixels.chunks(3 * self.resolution.x).rev() {
+            for value in row {
+                file.write_all(&value.to_le_bytes())?;
+            }
+        }
+        file.flush()?;
+        Ok(())
+    }
 }
 
 impl Film for RGBFilm {
@@ -60,7 +196,7 @@ impl Film for RGBFilm {
         let width = coord.x;
         let height = coord.y;
         if let Some(pixel) = self.pixels.get_mut((height, width)) {
-            pixel.rgb += rgb;
+            pixel.rgb += rgb * weight;
             pixel.weight += weight;
         } else {
             warn!(
@@ -72,19 +208,28 @@ impl Film for RGBFilm {
 
     fn sample_wavelengths(&self, rnd_c: f32) -> SampledWavelengths { SampledWavelengths::sample_visible(rnd_c) }
 
-    fn write_image(&self, path: &str) -> ImageResult<()> {
+    fn write_image(&self, output: &ImageOutput) -> ImageResult<()> {
         // TODO: white balance / linear_to_gamma
         let raw_pixels: Vec<f32> = self
             .pixels
             .iter()
             // .flat_map(|pix| linear_to_gamma(pix.rgb.map(|x| x / pix.weight)).0)
-            .flat_map(|pix| <[f32; 3]>::from(pix.rgb / pix.weight))
+            .flat_map(|pix| <[f32; 3]>::from(pix.value()))
             .collect();
-        let image =
-            ImageBuffer::<Rgb<f32>, Vec<f32>>::from_vec(self.resolution.x as u32, self.resolution.y as u32, raw_pixels)
+        match output.format()? {
+            OutputFormat::Ldr(format) => {
+                let image = ImageBuffer::<Rgb<f32>, Vec<f32>>::from_vec(
+                    self.resolution.x as u32,
+                    self.resolution.y as u32,
+                    raw_pixels,
+                )
                 .unwrap();
-        let image: RgbImage = image.convert();
-        image.save(path)
+                let image: RgbImage = image.convert();
+                image.save_with_format(&output.path, format)
+            }
+            OutputFormat::Exr => self.write_exr(raw_pixels, output),
+            OutputFormat::Pfm => self.write_pfm(&raw_pixels, &output.path),
+        }
     }
 
     fn tiles(&self, width: usize, height: usize) -> Vec<Bounds2<usize>> {
@@ -103,3 +248,52 @@ impl Film for RGBFilm {
         chunks
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use exr::prelude::read_all_flat_layers_from_file;
+
+    use super::*;
+    use crate::spectra::rgb::sRGB;
+
+    #[test]
+    fn test_hdr_output() {
+        let mut film = RGBFilm::new(2, 1, FilterEnum::default(), sRGB.clone());
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        film.add_sample(point2!(1, 0), SampledSpectrum::from(4.), lambda, 1.);
+        let [_, expected, _] = <[f32; 3]>::from(film.pixels[(0, 1)].value());
+        assert!(expected > 1.);
+
+        let dir = std::env::temp_dir();
+        let output = ImageOutput::new(dir.join("rusttracer_test_film.pfm"));
+        film.write_image(&output).unwrap();
+        let data = std::fs::read(&output.path).unwrap();
+        let header = b"PF\n2 1\n-1.0\n";
+        assert_eq!(&data[..header.len()], header);
+        let green = f32::from_le_bytes(data[header.len() + 16..header.len() + 20].try_into().unwrap());
+        assert_eq!(green, expected);
+
+        let output = ImageOutput {
+            path: dir.join("rusttracer_test_film.exr"),
+            half: true,
+            weight_layer: true,
+        };
+        film.write_image(&output).unwrap();
+        let image = read_all_flat_layers_from_file(&output.path).unwrap();
+        assert_eq!(image.layer_data.len(), 2);
+        let rgb = &image.layer_data[0].channel_data.list;
+        let green = rgb.iter().find(|channel| channel.name == *"G").unwrap();
+        assert_abs_diff_eq!(
+            green.sample_data.value_by_flat_index(1).to_f32(),
+            expected,
+            epsilon = 1e-2
+        );
+        let weight = &image.layer_data[1].channel_data.list[0];
+        assert_eq!(weight.sample_data.value_by_flat_index(0).to_f32(), 0.);
+        assert_eq!(weight.sample_data.value_by_flat_index(1).to_f32(), 1.);
+
+        let output = ImageOutput::new(dir.join("rusttracer_test_film.gif"));
+        assert!(output.format().is_err());
+    }
+}
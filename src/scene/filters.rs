@@ -0,0 +1,335 @@
+//! Pixel reconstruction filters. Camera samples are placed around the pixel center by importance sampling the filter,
+//! so every sample contributes to a single pixel with the weight returned by [Filter::sample]
+
+use std::f32::consts::PI;
+
+use crate::{
+    math::utils::lerp,
+    point2,
+    samplers::{piecewise::PiecewiseConstant2D, utils::sample_tent},
+    vec2, Point2f, Vec2f,
+};
+
+#[enum_delegate::register]
+pub trait Filter {
+    /// Half extents of the support around the pixel center, in pixels
+    fn radius(&self) -> Vec2f;
+
+    fn evaluate(&self, p: Point2f) -> f32;
+
+    /// Samples an offset from the pixel center proportionally to the absolute value of the filter
+    fn sample(&self, rnd_p: Point2f) -> FilterSample;
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct FilterSample {
+    pub p: Point2f,
+    /// Filter value divided by the sample density, negative in negative lobes
+    pub weight: f32,
+}
+
+#[derive(Debug, Clone)]
+#[enum_delegate::implement(Filter)]
+pub enum FilterEnum {
+    Box(BoxFilter),
+    Triangle(TriangleFilter),
+    Gaussian(GaussianFilter),
+    Mitchell(MitchellFilter),
+    LanczosSinc(LanczosSincFilter),
+}
+
+impl Default for FilterEnum {
+    /// Every sample only counts for the pixel it lands in
+    fn default() -> Self { FilterEnum::Box(BoxFilter::new(vec2!(0.5))) }
+}
+
+#[derive(Debug, Clone)]
+pub struct BoxFilter {
+    radius: Vec2f,
+}
+
+impl BoxFilter {
+    pub fn new(radius: Vec2f) -> Self { BoxFilter { radius } }
+}
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> Vec2f { self.radius }
+
+    fn evaluate(&self, p: Point2f) -> f32 {
+        if p.x.abs() <= self.radius.x && p.y.abs() <= self.radius.y {
+            1.
+        } else {
+            0.
+        }
+    }
+
+    fn sample(&self, rnd_p: Point2f) -> FilterSample {
+        let p = point2!(
+            lerp(-self.radius.x, self.radius.x, rnd_p.x),
+            lerp(-self.radius.y, self.radius.y, rnd_p.y)
+        );
+        FilterSample { p, weight: 1. }
+    }
+}
+
+/// Tent falling off linearly to zero at the radius
+#[derive(Debug, Clone)]
+pub struct TriangleFilter {
+    radius: Vec2f,
+}
+
+impl TriangleFilter {
+    pub fn new(radius: Vec2f) -> Self { TriangleFilter { radius } }
+}
+
+impl Filter for TriangleFilter {
+    fn radius(&self) -> Vec2f { self.radius }
+
+    fn evaluate(&self, p: Point2f) -> f32 { (self.radius.x - p.x.abs()).max(0.) * (self.radius.y - p.y.abs()).max(0.) }
+
+    fn sample(&self, rnd_p: Point2f) -> FilterSample {
+        let p = point2!(sample_tent(rnd_p.x, self.radius.x), sample_tent(rnd_p.y, self.radius.y));
+        FilterSample { p, weight: 1. }
+    }
+}
+
+/// Gaussian shifted down to reach zero at the radius
+#[derive(Debug, Clone)]
+pub struct GaussianFilter {
+    radius: Vec2f,
+    sigma: f32,
+    sampler: FilterSampler,
+}
+
+impl GaussianFilter {
+    pub fn new(radius: Vec2f, sigma: f32) -> Self {
+        GaussianFilter {
+            radius,
+            sigma,
+            sampler: FilterSampler::new(radius, |p| gaussian_2d(p, radius, sigma)),
+        }
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> Vec2f { self.radius }
+
+    fn evaluate(&self, p: Point2f) -> f32 { gaussian_2d(p, self.radius, self.sigma) }
+
+    fn sample(&self, rnd_p: Point2f) -> FilterSample { self.sampler.sample(rnd_p) }
+}
+
+/// Mitchell-Netravali cubic with the `b` and `c` parameters, `b = c = 1/3` is their recommendation. Has negative lobes
+#[derive(Debug, Clone)]
+pub struct MitchellFilter {
+    radius: Vec2f,
+    b: f32,
+    c: f32,
+    sampler: FilterSampler,
+}
+
+impl MitchellFilter {
+    pub fn new(radius: Vec2f, b: f32, c: f32) -> Self {
+        let evaluate = |p: Point2f| mitchell_1d(2. * p.x / radius.x, b, c) * mitchell_1d(2. * p.y / radius.y, b, c);
+        MitchellFilter {
+            radius,
+            b,
+            c,
+            sampler: FilterSampler::new(radius, evaluate),
+        }
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn radius(&self) -> Vec2f { self.radius }
+
+    fn evaluate(&self, p: Point2f) -> f32 {
+        mitchell_1d(2. * p.x / self.radius.x, self.b, self.c) * mitchell_1d(2. * p.y / self.radius.y, self.b, self.c)
+    }
+
+    fn sample(&self, rnd_p: Point2f) -> FilterSample { self.sampler.sample(rnd_p) }
+}
+
+/// Sinc windowed by a wider sinc with `tau` periods. Has negative lobes
+#[derive(Debug, Clone)]
+pub struct LanczosSincFilter {
+    radius: Vec2f,
+    tau: f32,
+    sampler: FilterSampler,
+}
+
+impl LanczosSincFilter {
+    pub fn new(radius: Vec2f, tau: f32) -> Self {
+        let evaluate = |p: Point2f| windowed_sinc(p.x, radius.x, tau) * windowed_sinc(p.y, radius.y, tau);
+        LanczosSincFilter {
+            radius,
+            tau,
+            sampler: FilterSampler::new(radius, evaluate),
+        }
+    }
+}
+
+impl Filter for LanczosSincFilter {
+    fn radius(&self) -> Vec2f { self.radius }
+
+    fn evaluate(&self, p: Point2f) -> f32 {
+        windowed_sinc(p.x, self.radius.x, self.tau) * windowed_sinc(p.y, self.radius.y, self.tau)
+    }
+
+    fn sample(&self, rnd_p: Point2f) -> FilterSample { self.sampler.sample(rnd_p) }
+}
+
+/// Samples filters without an analytic inverse from a tabulation of their absolute value. The weights are the
+/// tabulated values over the density, so they only differ in sign
+#[derive(Debug, Clone)]
+struct FilterSampler {
+    radius: Vec2f,
+    values: Vec<f32>,
+    width: usize,
+    distribution: PiecewiseConstant2D,
+}
+
+impl FilterSampler {
+    /// Values per pixel along each axis
+    const DENSITY: f32 = 32.;
+
+    fn new(radius: Vec2f, evaluate: impl Fn(Point2f) -> f32) -> Self {
+        let width = ((2. * radius.x * Self::DENSITY) as usize).max(1);
+        let height = ((2. * radius.y * Self::DENSITY) as usize).max(1);
+        let values: Vec<f32> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                evaluate(point2!(
+                    lerp(-radius.x, radius.x, (x as f32 + 0.5) / width as f32),
+                    lerp(-radius.y, radius.y, (y as f32 + 0.5) / height as f32)
+                ))
+            })
+            .collect();
+        FilterSampler {
+            radius,
+            distribution: PiecewiseConstant2D::new(&values, width),
+            values,
+            width,
+        }
+    }
+
+    fn sample(&self, rnd_p: Point2f) -> FilterSample {
+        let (u, pdf) = self.distribution.sample(rnd_p);
+        let height = self.values.len() / self.width;
+        let x = ((u.x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((u.y * height as f32) as usize).min(height - 1);
+        let p = point2!(
+            lerp(-self.radius.x, self.radius.x, u.x),
+            lerp(-self.radius.y, self.radius.y, u.y)
+        );
+        // Density over the filter's support instead of the unit square
+        let pdf = pdf / (4. * self.radius.x * self.radius.y);
+        FilterSample {
+            p,
+            weight: self.values[y * self.width + x] / pdf,
+        }
+    }
+}
+
+fn gaussian(x: f32, sigma: f32) -> f32 { (-x * x / (2. * sigma * sigma)).exp() / (2. * PI * sigma * sigma).sqrt() }
+
+fn gaussian_2d(p: Point2f, radius: Vec2f, sigma: f32) -> f32 {
+    (gaussian(p.x, sigma) - gaussian(radius.x, sigma)).max(0.)
+        * (gaussian(p.y, sigma) - gaussian(radius.y, sigma)).max(0.)
+}
+
+fn mitchell_1d(x: f32, b: f32, c: f32) -> f32 {
+    let x = x.abs();
+    if x <= 1. {
+        ((12. - 9. * b - 6. * c) * x.powi(3) + (-18. + 12. * b + 6. * c) * x.powi(2) + (6. - 2. * b)) / 6.
+    } else if x <= 2. {
+        ((-b - 6. * c) * x.powi(3) + (6. * b + 30. * c) * x.powi(2) + (-12. * b - 48. * c) * x + (8. * b + 24. * c))
+            / 6.
+    } else {
+        0.
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        1.
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn windowed_sinc(x: f32, radius: f32, tau: f32) -> f32 {
+    if x.abs() > radius {
+        0.
+    } else {
+        sinc(x) * sinc(x / tau)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    /// Monte Carlo estimate of the filter's integral from stratified samples, exact for the analytic samplers
+    fn integral(filter: &FilterEnum) -> f32 {
+        let n = 256;
+        let mut sum = 0.;
+        for i in 0..n {
+            for j in 0..n {
+                let sample = filter.sample(point2!((i as f32 + 0.5) / n as f32, (j as f32 + 0.5) / n as f32));
+                let radius = filter.radius();
+                assert!(sample.p.x.abs() <= radius.x && sample.p.y.abs() <= radius.y);
+                sum += sample.weight;
+            }
+        }
+        sum / (n * n) as f32
+    }
+
+    #[test]
+    fn test_analytic() {
+        let filter = FilterEnum::default();
+        assert_eq!(filter.evaluate(point2!(0.4, -0.4)), 1.);
+        assert_eq!(filter.evaluate(point2!(0.6, 0.)), 0.);
+        assert_abs_diff_eq!(integral(&filter), 1.);
+
+        let triangle = FilterEnum::Triangle(TriangleFilter::new(vec2!(2., 1.)));
+        assert_abs_diff_eq!(triangle.evaluate(point2!(1., 0.5)), 0.5);
+        // Samples are distributed like the filter, so they concentrate at the center
+        let mean_distance: f32 = (0..16)
+            .map(|i| triangle.sample(point2!((i as f32 + 0.5) / 16., 0.5)).p.x.abs())
+            .sum::<f32>()
+            / 16.;
+        assert_abs_diff_eq!(mean_distance, 2. / 3., epsilon = 0.02);
+    }
+
+    #[test]
+    fn test_tabulated() {
+        let gaussian = FilterEnum::Gaussian(GaussianFilter::new(vec2!(1.5), 0.5));
+        assert_eq!(gaussian.evaluate(point2!(1.5, 0.)), 0.);
+        let mitchell = FilterEnum::Mitchell(MitchellFilter::new(vec2!(2.), 1. / 3., 1. / 3.));
+        let lanczos = FilterEnum::LanczosSinc(LanczosSincFilter::new(vec2!(4.), 3.));
+        // The negative lobes get negative weights
+        assert!(mitchell.evaluate(point2!(1.5, 0.)) < 0.);
+        assert!(lanczos.evaluate(point2!(1.5, 0.)) < 0.);
+
+        for filter in [gaussian, mitchell, lanczos] {
+            // Midpoint rule over a fine grid
+            let radius = filter.radius();
+            let n = 256;
+            let mut expected = 0.;
+            for i in 0..n {
+                for j in 0..n {
+                    let p = point2!(
+                        lerp(-radius.x, radius.x, (i as f32 + 0.5) / n as f32),
+                        lerp(-radius.y, radius.y, (j as f32 + 0.5) / n as f32)
+                    );
+                    expected += filter.evaluate(p);
+                }
+            }
+            expected *= 4. * radius.x * radius.y / (n * n) as f32;
+            assert_abs_diff_eq!(integral(&filter), expected, epsilon = 0.02 * expected);
+        }
+    }
+}
@@ -0,0 +1,1098 @@
+use std::{collections::HashMap, fmt::Display, ops::Range, path::Path, sync::Arc};
+
+use either::Either;
+use image::{ImageResult, Rgb32FImage};
+use toml::Spanned;
+
+use crate::{
+    aggregates::{Aabb, BVH},
+    light::{
+        ies::load_ies, AreaEmission, DiffuseAreaLight, DistantLight, EnvironmentMapping, GoniometricLight,
+        ImageInfiniteLight, LightEnum, PointLight, ProjectionLight, Spotlight, UniformInfiniteLight,
+    },
+    material::{glass::Glass, matte::Matte, metal::Metal, BumpMap, MaterialsEnum, Roughness},
+    math::{axis::Axis3, Normed, Transform},
+    mediums::{
+        nrrd::load_nrrd, GridEmission, GridMedium, GridMediumConfig, HGPhaseFunction, HomogeneousMedium, MediumEnum,
+        MediumInterface, SampledGrid,
+    },
+    point2, point3,
+    scene::{
+        cameras::{
+            default_screen_window, BaseCameraConfig, CameraType, OrthographicCamera, OrthographicCameraConfig,
+            PerspectiveCamera, PerspectiveCameraConfig,
+        },
+        film::RGBFilm,
+        filters::{BoxFilter, FilterEnum, GaussianFilter, LanczosSincFilter, MitchellFilter, TriangleFilter},
+        loader::{
+            error::SceneLoadError,
+            schema::{
+                CameraDesc, ChannelDesc, EmissionDesc, FilmDesc, FilterDesc, FloatParam, FloatTextureDesc, GridDesc,
+                LightDesc, MappingDesc, MaterialDesc, MediumDesc, MediumEmissionDesc, ObjectDesc, PixelFilterDesc,
+                RoughnessDesc, ScaleDesc, ShapeDesc, SpectrumDesc, TextureDesc, TextureMappingDesc, TextureParam,
+                TransformOp, WrapDesc,
+            },
+            SceneFile,
+        },
+        primitives::{geometric::GeometricPrimitive, simple::SimplePrimitive, PrimitiveEnum},
+        Scene,
+    },
+    shapes::{
+        mesh::{
+            obj::{load_obj, MeshMaterial},
+            ply::load_ply,
+        },
+        quad::Quad,
+        sphere::Sphere,
+        BoundedIntersectable,
+    },
+    spectra::{
+        named::NamedSpectra,
+        piecewise_linear::PiecewiseLinearSpectrum,
+        rgb::{sRGB, RGB},
+        spectrum_to_photometric, BlackbodySpectrum, ConstantSpectrum, RGBAlbedoSpectrum, RGBIlluminantSpectrum,
+        RGBUnboundedSpectrum, SpectrumEnum,
+    },
+    textures::{
+        checkerboard::{CheckerboardTexture, FloatCheckerboardTexture},
+        constant::{ConstantFloatTexture, ConstantSpectrumTexture},
+        dots::DotsTexture,
+        fbm::{FBmTexture, WrinkledTexture},
+        image::{FloatImageTexture, ImageChannel, RGBSpectrumKind, SpectrumImageTexture},
+        mappings::{
+            CylindricalMapping, PlanarMapping, PointTransformMapping, SphericalMapping, TextureMapping2DEnum, UVMapping,
+        },
+        marble::MarbleTexture,
+        mipmap::{FilterFunction, MIPMap, WrapMode},
+        mix::{FloatMixTexture, FloatScaledTexture},
+        wood::WoodTexture,
+        FloatTextureEnum, SpectrumTextureEnum,
+    },
+    utils::{read_alpha, read_linear_rgb, read_raw_rgb},
+    vec2, vec3, Bounds2f, Point3f, Vec3f,
+};
+
+/// How RGB values are turned into spectra
+#[derive(Copy, Clone)]
+enum SpectrumKind {
+    /// Reflectance in [0; 1]
+    Albedo,
+    Illuminant,
+    /// Index of refraction, RGB is not allowed
+    Unbounded,
+    /// Medium coefficient, non-negative but unbounded
+    Coefficient,
+}
+
+type BuildResult<T> = Result<T, SceneLoadError>;
+
+/// Shapes sharing a material, meshes consist of one group per material
+struct ShapeGroup {
+    shapes: Vec<Arc<dyn BoundedIntersectable>>,
+    /// Material and emission from the mesh file
+    material: Option<MeshMaterial>,
+}
+
+pub(super) struct SceneBuilder<'a> {
+    file: &'a SceneFile,
+    textures: HashMap<&'a str, Arc<SpectrumTextureEnum>>,
+    float_textures: HashMap<&'a str, Arc<FloatTextureEnum>>,
+    materials: HashMap<&'a str, Arc<MaterialsEnum>>,
+    media: HashMap<&'a str, Arc<MediumEnum>>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    pub(super) fn new(file: &'a SceneFile) -> Self {
+        SceneBuilder {
+            file,
+            textures: HashMap::new(),
+            float_textures: HashMap::new(),
+            materials: HashMap::new(),
+            media: HashMap::new(),
+        }
+    }
+
+    pub(super) fn build(mut self) -> BuildResult<Scene> {
+        let description = &self.file.description;
+        for (name, texture) in description.float_textures.iter() {
+            self.float_texture(name, texture.span(), &mut vec![])?;
+        }
+        for (name, texture) in description.textures.iter() {
+            let built = self.build_texture(texture)?;
+            self.textures.insert(name, built);
+        }
+        for (name, material) in description.materials.iter() {
+            let built = self.build_material(material)?;
+            self.materials.insert(name, built);
+        }
+        for (name, medium) in description.media.iter() {
+            let built = self.build_medium(medium)?;
+            self.media.insert(name, built);
+        }
+
+        let mut primitives: Vec<Arc<PrimitiveEnum>> = vec![];
+        let mut lights: Vec<Arc<LightEnum>> = vec![];
+        for object in description.objects.iter() {
+            self.build_object(object, &mut primitives, &mut lights)?;
+        }
+        for light in description.lights.iter() {
+            lights.push(Arc::new(self.build_light(light)?));
+        }
+
+        let camera = self.build_camera(&description.camera, &description.film)?;
+        let objects = PrimitiveEnum::BVH(BVH::new(primitives, 8));
+        Scene::new(camera, objects, lights).map_err(SceneLoadError::Scene)
+    }
+
+    fn error(&self, span: Range<usize>, message: impl Into<String>) -> SceneLoadError {
+        SceneLoadError::invalid(self.file.path.clone(), &self.file.source, Some(span), message.into())
+    }
+
+    fn build_pixel_filter(&self, filter: &Spanned<PixelFilterDesc>) -> BuildResult<FilterEnum> {
+        let radius = match filter.get_ref() {
+            PixelFilterDesc::Box { radius }
+            | PixelFilterDesc::Triangle { radius }
+            | PixelFilterDesc::Gaussian { radius, .. }
+            | PixelFilterDesc::Mitchell { radius, .. }
+            | PixelFilterDesc::Lanczos { radius, .. } => vec2!(radius[0], radius[1]),
+        };
+        if radius.x <= 0. || radius.y <= 0. {
+            return Err(self.error(filter.span(), "filter radius must be positive"));
+        }
+        Ok(match *filter.get_ref() {
+            PixelFilterDesc::Box { .. } => FilterEnum::Box(BoxFilter::new(radius)),
+            PixelFilterDesc::Triangle { .. } => FilterEnum::Triangle(TriangleFilter::new(radius)),
+            PixelFilterDesc::Gaussian { sigma, .. } => FilterEnum::Gaussian(GaussianFilter::new(radius, sigma)),
+            PixelFilterDesc::Mitchell { b, c, .. } => FilterEnum::Mitchell(MitchellFilter::new(radius, b, c)),
+            PixelFilterDesc::Lanczos { tau, .. } => FilterEnum::LanczosSinc(LanczosSincFilter::new(radius, tau)),
+        })
+    }
+
+    fn build_camera(&self, camera: &Spanned<CameraDesc>, film: &FilmDesc) -> BuildResult<CameraType> {
+        for size in [&film.width, &film.height] {
+            if *size.get_ref() == 0 {
+                return Err(self.error(size.span(), "film resolution must be non-zero"));
+            }
+        }
+        let filter = match &film.filter {
+            Some(filter) => self.build_pixel_filter(filter)?,
+            None => FilterEnum::default(),
+        };
+        let film = RGBFilm::new(*film.width.get_ref(), *film.height.get_ref(), filter, sRGB.clone());
+        let resolution = film.resolution;
+        let screen_window = |window: &Option<[f32; 4]>| match window {
+            Some([x_min, y_min, x_max, y_max]) => {
+                Bounds2f::from_points(point2!(*x_min, *y_min), point2!(*x_max, *y_max))
+            }
+            None => default_screen_window(resolution),
+        };
+
+        let span = camera.span();
+        let camera = match camera.get_ref() {
+            CameraDesc::Perspective {
+                fov,
+                lens_radius,
+                focal_distance,
+                screen_window: window,
+                transform,
+                medium,
+            } => PerspectiveCamera::new(PerspectiveCameraConfig {
+                base_config: BaseCameraConfig {
+                    transform: build_transform(transform),
+                    film,
+                    medium: medium
+                        .as_ref()
+                        .map(|name| self.medium(name, span.clone()))
+                        .transpose()?,
+                },
+                fov: *fov,
+                screen_window: screen_window(window),
+                lens_radius: *lens_radius,
+                focal_distance: *focal_distance,
+            })
+            .into(),
+            CameraDesc::Orthographic {
+                lens_radius,
+                focal_distance,
+                screen_window: window,
+                transform,
+                medium,
+            } => OrthographicCamera::new(OrthographicCameraConfig {
+                base_config: BaseCameraConfig {
+                    transform: build_transform(transform),
+                    film,
+                    medium: medium
+                        .as_ref()
+                        .map(|name| self.medium(name, span.clone()))
+                        .transpose()?,
+                },
+                screen_window: screen_window(window),
+                lens_radius: *lens_radius,
+                focal_distance: *focal_distance,
+            })
+            .into(),
+        };
+        Ok(camera)
+    }
+
+    fn build_texture(&self, texture: &Spanned<TextureDesc>) -> BuildResult<Arc<SpectrumTextureEnum>> {
+        let texture = match texture.get_ref() {
+            TextureDesc::Constant { value } => ConstantSpectrumTexture {
+                value: self.build_spectrum(value, SpectrumKind::Albedo, texture.span())?,
+            }
+            .into(),
+            TextureDesc::Image {
+                file,
+                scale,
+                invert,
+                filter,
+                wrap,
+                mapping,
+            } => SpectrumImageTexture {
+                mapping: self.surface_mapping(mapping, texture.span())?,
+                mipmap: self.mipmap(file, read_linear_rgb, *filter, *wrap, texture.span())?,
+                scale: *scale,
+                invert: *invert,
+                kind: RGBSpectrumKind::Albedo,
+            }
+            .into(),
+            TextureDesc::Checkerboard { colors, mapping } => CheckerboardTexture {
+                mapping: build_texture_mapping(mapping),
+                tex: [
+                    self.constant_texture(&colors[0], texture.span())?,
+                    self.constant_texture(&colors[1], texture.span())?,
+                ],
+            }
+            .into(),
+            TextureDesc::Dots {
+                inside,
+                outside,
+                mapping,
+            } => DotsTexture {
+                mapping: self.surface_mapping(mapping, texture.span())?,
+                inside: self.constant_texture(inside, texture.span())?,
+                outside: self.constant_texture(outside, texture.span())?,
+            }
+            .into(),
+            TextureDesc::Marble {
+                scale,
+                variation,
+                omega,
+                octaves,
+                transform,
+            } => MarbleTexture {
+                mapping: PointTransformMapping::new(build_transform(transform)),
+                scale: *scale,
+                variation: *variation,
+                omega: *omega,
+                octaves: *octaves,
+            }
+            .into(),
+            TextureDesc::Wood {
+                light,
+                dark,
+                rings,
+                variation,
+                omega,
+                octaves,
+                transform,
+            } => WoodTexture {
+                mapping: PointTransformMapping::new(build_transform(transform)),
+                light: self.constant_texture(light, texture.span())?,
+                dark: self.constant_texture(dark, texture.span())?,
+                rings: *rings,
+                variation: *variation,
+                omega: *omega,
+                octaves: *octaves,
+            }
+            .into(),
+        };
+        Ok(Arc::new(texture))
+    }
+
+    fn mipmap(
+        &self,
+        file: &str,
+        read: fn(&Path) -> ImageResult<Rgb32FImage>,
+        filter: FilterDesc,
+        wrap: WrapDesc,
+        span: Range<usize>,
+    ) -> BuildResult<Arc<MIPMap>> {
+        let path = self.file.path.parent().unwrap_or(Path::new(".")).join(file);
+        let image = read(&path).map_err(|err| self.error(span, format!("failed to load {}: {err}", path.display())))?;
+        let filter = match filter {
+            FilterDesc::Point => FilterFunction::Point,
+            FilterDesc::Bilinear => FilterFunction::Bilinear,
+            FilterDesc::Trilinear => FilterFunction::Trilinear,
+        };
+        let wrap = match wrap {
+            WrapDesc::Repeat => WrapMode::Repeat,
+            WrapDesc::Clamp => WrapMode::Clamp,
+            WrapDesc::Black => WrapMode::Black,
+        };
+        Ok(Arc::new(MIPMap::new(image, wrap, filter)))
+    }
+
+    /// Float textures may refer to each other, so they are built on demand in dependency order. `stack` holds the
+    /// ones being built to detect cycles
+    fn float_texture(
+        &mut self,
+        name: &'a str,
+        span: Range<usize>,
+        stack: &mut Vec<&'a str>,
+    ) -> BuildResult<Arc<FloatTextureEnum>> {
+        if let Some(texture) = self.float_textures.get(name) {
+            return Ok(texture.clone());
+        }
+        let file = self.file;
+        let Some(texture) = file.description.float_textures.get(name) else {
+            return Err(self.error(span, format!("unknown float texture `{name}`")));
+        };
+        if stack.contains(&name) {
+            return Err(self.error(texture.span(), format!("float texture `{name}` depends on itself")));
+        }
+        stack.push(name);
+        let built = self.build_float_texture(texture, stack)?;
+        stack.pop();
+        self.float_textures.insert(name, built.clone());
+        Ok(built)
+    }
+
+    fn build_float_texture(
+        &mut self,
+        texture: &'a Spanned<FloatTextureDesc>,
+        stack: &mut Vec<&'a str>,
+    ) -> BuildResult<Arc<FloatTextureEnum>> {
+        let span = texture.span();
+        let texture = match texture.get_ref() {
+            FloatTextureDesc::Constant { value } => ConstantFloatTexture { value: *value }.into(),
+            FloatTextureDesc::Image {
+                file,
+                channel,
+                scale,
+                invert,
+                filter,
+                wrap,
+                mapping,
+            } => FloatImageTexture {
+                mapping: self.surface_mapping(mapping, span.clone())?,
+                mipmap: match channel {
+                    ChannelDesc::Alpha => self.mipmap(file, read_alpha, *filter, *wrap, span)?,
+                    _ => self.mipmap(file, read_linear_rgb, *filter, *wrap, span)?,
+                },
+                channel: match channel {
+                    ChannelDesc::Red => ImageChannel::Red,
+                    ChannelDesc::Green => ImageChannel::Green,
+                    ChannelDesc::Blue => ImageChannel::Blue,
+                    // The alpha image has it in all channels
+                    ChannelDesc::Average | ChannelDesc::Alpha => ImageChannel::Average,
+                },
+                scale: *scale,
+                invert: *invert,
+            }
+            .into(),
+            FloatTextureDesc::Checkerboard { values, mapping } => FloatCheckerboardTexture {
+                mapping: build_texture_mapping(mapping),
+                tex: [
+                    self.float_dependency(&values[0], span.clone(), stack)?,
+                    self.float_dependency(&values[1], span, stack)?,
+                ],
+            }
+            .into(),
+            FloatTextureDesc::Fbm {
+                omega,
+                octaves,
+                transform,
+            } => FBmTexture {
+                mapping: PointTransformMapping::new(build_transform(transform)),
+                omega: *omega,
+                octaves: *octaves,
+            }
+            .into(),
+            FloatTextureDesc::Wrinkled {
+                omega,
+                octaves,
+                transform,
+            } => WrinkledTexture {
+                mapping: PointTransformMapping::new(build_transform(transform)),
+                omega: *omega,
+                octaves: *octaves,
+            }
+            .into(),
+            FloatTextureDesc::Scale { texture, scale } => FloatScaledTexture {
+                tex: self.float_dependency(texture, span.clone(), stack)?,
+                scale: self.float_dependency(scale, span, stack)?,
+            }
+            .into(),
+            FloatTextureDesc::Mix { textures, amount } => FloatMixTexture {
+                tex: [
+                    self.float_dependency(&textures[0], span.clone(), stack)?,
+                    self.float_dependency(&textures[1], span.clone(), stack)?,
+                ],
+                amount: self.float_dependency(amount, span, stack)?,
+            }
+            .into(),
+        };
+        Ok(Arc::new(texture))
+    }
+
+    /// Float parameter of another float texture, building the referenced texture if needed
+    fn float_dependency(
+        &mut self,
+        param: &'a FloatParam,
+        span: Range<usize>,
+        stack: &mut Vec<&'a str>,
+    ) -> BuildResult<Arc<FloatTextureEnum>> {
+        match param {
+            FloatParam::Value(value) => Ok(Arc::new(ConstantFloatTexture { value: *value }.into())),
+            FloatParam::Texture(name) => self.float_texture(name, span, stack),
+        }
+    }
+
+    fn float_param(&self, param: &FloatParam, span: Range<usize>) -> BuildResult<Arc<FloatTextureEnum>> {
+        match param {
+            FloatParam::Value(value) => Ok(Arc::new(ConstantFloatTexture { value: *value }.into())),
+            FloatParam::Texture(name) => self
+                .float_textures
+                .get(name.as_str())
+                .cloned()
+                .ok_or_else(|| self.error(span, format!("unknown float texture `{name}`"))),
+        }
+    }
+
+    /// Textures are built in no particular order, so the ones nested in other textures can't be references
+    fn constant_texture(&self, spectrum: &SpectrumDesc, span: Range<usize>) -> BuildResult<Arc<SpectrumTextureEnum>> {
+        let value = self.build_spectrum(spectrum, SpectrumKind::Albedo, span)?;
+        Ok(Arc::new(ConstantSpectrumTexture { value }.into()))
+    }
+
+    fn surface_mapping(&self, mapping: &TextureMappingDesc, span: Range<usize>) -> BuildResult<TextureMapping2DEnum> {
+        build_texture_mapping(mapping)
+            .left()
+            .ok_or_else(|| self.error(span, "`solid` mapping is only supported by checkerboards"))
+    }
+
+    /// Strings are looked up in the `[textures]` table first, everything else becomes a constant texture
+    fn texture_param(&self, param: &TextureParam, span: Range<usize>) -> BuildResult<Arc<SpectrumTextureEnum>> {
+        if let SpectrumDesc::Named(name) = param
+            && let Some(texture) = self.textures.get(name.as_str())
+        {
+            return Ok(texture.clone());
+        }
+        let value = self.build_spectrum(param, SpectrumKind::Albedo, span)?;
+        Ok(Arc::new(ConstantSpectrumTexture { value }.into()))
+    }
+
+    fn build_material(&self, material: &Spanned<MaterialDesc>) -> BuildResult<Arc<MaterialsEnum>> {
+        let span = material.span();
+        let material = match material.get_ref() {
+            MaterialDesc::Matte {
+                reflectance,
+                displacement,
+                normal_map,
+            } => MaterialsEnum::Matte(Matte {
+                reflectance: self.texture_param(reflectance, span.clone())?,
+                bump: self.build_bump(displacement, normal_map, span)?,
+            }),
+            MaterialDesc::Metal {
+                reflectance,
+                eta,
+                k,
+                roughness,
+                displacement,
+                normal_map,
+            } => {
+                let reflectance = match (reflectance, eta, k) {
+                    (Some(reflectance), None, None) => Either::Left(self.texture_param(reflectance, span.clone())?),
+                    (None, Some(eta), Some(k)) => {
+                        let constant = |spectrum| -> BuildResult<Arc<SpectrumTextureEnum>> {
+                            let value = self.build_spectrum(spectrum, SpectrumKind::Unbounded, span.clone())?;
+                            Ok(Arc::new(ConstantSpectrumTexture { value }.into()))
+                        };
+                        Either::Right((constant(eta)?, constant(k)?))
+                    }
+                    _ => return Err(self.error(span, "metal needs either `reflectance` or both `eta` and `k`")),
+                };
+                MaterialsEnum::Metal(Metal {
+                    reflectance,
+                    roughness: self.build_roughness(roughness, span.clone())?,
+                    bump: self.build_bump(displacement, normal_map, span)?,
+                })
+            }
+            MaterialDesc::Glass {
+                ior,
+                color,
+                roughness,
+                displacement,
+                normal_map,
+            } => MaterialsEnum::Glass(Glass {
+                ior: match ior {
+                    SpectrumDesc::Named(name) if self.float_textures.contains_key(name.as_str()) => {
+                        Either::Right(self.float_textures[name.as_str()].clone())
+                    }
+                    ior => Either::Left((*self.build_spectrum(ior, SpectrumKind::Unbounded, span.clone())?).clone()),
+                },
+                roughness: self.build_roughness(roughness, span.clone())?,
+                spectrum: match color {
+                    Some(color) => self.texture_param(color, span.clone())?,
+                    None => Arc::new(
+                        ConstantSpectrumTexture {
+                            value: Arc::new(ConstantSpectrum::new(1.).into()),
+                        }
+                        .into(),
+                    ),
+                },
+                bump: self.build_bump(displacement, normal_map, span)?,
+            }),
+        };
+        Ok(Arc::new(material))
+    }
+
+    fn build_bump(
+        &self,
+        displacement: &Option<FloatParam>,
+        normal_map: &Option<String>,
+        span: Range<usize>,
+    ) -> BuildResult<Option<BumpMap>> {
+        match (displacement, normal_map) {
+            (None, None) => Ok(None),
+            (Some(displacement), None) => Ok(Some(BumpMap::Displacement(self.float_param(displacement, span)?))),
+            (None, Some(file)) => {
+                let path = self.file.path.parent().unwrap_or(Path::new(".")).join(file);
+                let image = read_raw_rgb(&path)
+                    .map_err(|err| self.error(span, format!("failed to load {}: {err}", path.display())))?;
+                let map = MIPMap::new(image, WrapMode::Repeat, FilterFunction::Bilinear);
+                Ok(Some(BumpMap::Normal(Arc::new(map))))
+            }
+            (Some(_), Some(_)) => Err(self.error(span, "`displacement` and `normal_map` are mutually exclusive")),
+        }
+    }
+
+    fn build_roughness(&self, roughness: &RoughnessDesc, span: Range<usize>) -> BuildResult<Roughness> {
+        let [u, v] = match roughness {
+            RoughnessDesc::Isotropic(roughness) => [roughness; 2],
+            RoughnessDesc::Anisotropic([u, v]) => [u, v],
+        };
+        let in_range = |x: &FloatParam| !matches!(x, FloatParam::Value(x) if !(0. ..=1.).contains(x));
+        if !in_range(u) || !in_range(v) {
+            return Err(self.error(span, "roughness must be in [0; 1]"));
+        }
+        Ok(Roughness {
+            u: self.float_param(u, span.clone())?,
+            v: self.float_param(v, span)?,
+            remap: true,
+        })
+    }
+
+    fn build_spectrum(
+        &self,
+        spectrum: &SpectrumDesc,
+        kind: SpectrumKind,
+        span: Range<usize>,
+    ) -> BuildResult<Arc<SpectrumEnum>> {
+        let spectrum = match spectrum {
+            SpectrumDesc::Constant(value) => ConstantSpectrum::new(*value).into(),
+            SpectrumDesc::Rgb([r, g, b]) => {
+                let rgb = RGB::new(*r, *g, *b);
+                match kind {
+                    SpectrumKind::Albedo => {
+                        if [r, g, b].iter().any(|x| !(0. ..=1.).contains(*x)) {
+                            return Err(self.error(span, "reflectance RGB components must be in [0; 1]"));
+                        }
+                        RGBAlbedoSpectrum::new(&sRGB, rgb).into()
+                    }
+                    SpectrumKind::Illuminant => RGBIlluminantSpectrum::new(&sRGB, rgb).into(),
+                    SpectrumKind::Unbounded => {
+                        return Err(self.error(span, "RGB is not allowed here, use a constant or a spectrum"))
+                    }
+                    SpectrumKind::Coefficient => {
+                        if [r, g, b].iter().any(|x| **x < 0.) {
+                            return Err(self.error(span, "coefficient RGB components must be non-negative"));
+                        }
+                        RGBUnboundedSpectrum::new(&sRGB, rgb).into()
+                    }
+                }
+            }
+            SpectrumDesc::Named(name) => {
+                return NamedSpectra::from_name(name)
+                    .map(|named| named.get())
+                    .ok_or_else(|| self.error(span, format!("unknown texture or spectrum `{name}`")))
+            }
+            SpectrumDesc::Blackbody { blackbody } => {
+                if !matches!(kind, SpectrumKind::Illuminant) {
+                    return Err(self.error(span, "blackbody spectra are only allowed for emission"));
+                }
+                if *blackbody <= 0. {
+                    return Err(self.error(span, "blackbody temperature must be positive"));
+                }
+                BlackbodySpectrum::new(*blackbody).into()
+            }
+            SpectrumDesc::PiecewiseLinear { lambdas, values } => {
+                if lambdas.is_empty() || lambdas.len() != values.len() {
+                    return Err(self.error(span, "`lambdas` and `values` must be non-empty and of the same length"));
+                }
+                if !lambdas.windows(2).all(|x| x[0] < x[1]) {
+                    return Err(self.error(span, "`lambdas` must be strictly increasing"));
+                }
+                PiecewiseLinearSpectrum::new(lambdas, values).into()
+            }
+        };
+        Ok(Arc::new(spectrum))
+    }
+
+    /// Emission spectrum and its scale. Like PBRT, blackbody spectra are normalized so that `scale` sets their
+    /// luminance relative to the named illuminants rather than their peak value.
+    fn build_illuminant(
+        &self,
+        spectrum: &SpectrumDesc,
+        scale: f32,
+        span: Range<usize>,
+    ) -> BuildResult<(Arc<SpectrumEnum>, f32)> {
+        let normalize = matches!(spectrum, SpectrumDesc::Blackbody { .. });
+        let spectrum = self.build_spectrum(spectrum, SpectrumKind::Illuminant, span)?;
+        let scale = if normalize {
+            scale / spectrum_to_photometric(&spectrum)
+        } else {
+            scale
+        };
+        Ok((spectrum, scale))
+    }
+
+    /// Image of a goniometric or projection light. IES profiles are resampled into an equal-area image
+    fn light_image(&self, file: &str, span: Range<usize>) -> BuildResult<Rgb32FImage> {
+        let path = self.file.path.parent().unwrap_or(Path::new(".")).join(file);
+        if path.extension().is_some_and(|x| x.eq_ignore_ascii_case("ies")) {
+            load_ies(&path).map_err(|err| self.error(span, err.to_string()))
+        } else {
+            read_linear_rgb(&path).map_err(|err| self.error(span, format!("failed to load {}: {err}", path.display())))
+        }
+    }
+
+    fn build_emission(&self, emission: &Spanned<EmissionDesc>) -> BuildResult<(AreaEmission, f32, bool)> {
+        let desc = emission.get_ref();
+        let (emission, scale) = match (&desc.spectrum, &desc.image) {
+            (Some(spectrum), None) => {
+                let (spectrum, scale) = self.build_illuminant(spectrum.get_ref(), desc.scale, spectrum.span())?;
+                (AreaEmission::Spectrum(spectrum), scale)
+            }
+            (None, Some(image)) => {
+                let path = self.file.path.parent().unwrap_or(Path::new(".")).join(image.get_ref());
+                let image = read_linear_rgb(&path)
+                    .map_err(|err| self.error(image.span(), format!("failed to load {}: {err}", path.display())))?;
+                (AreaEmission::image(image), desc.scale)
+            }
+            (Some(_), Some(image)) => return Err(self.error(image.span(), "can't have both `spectrum` and `image`")),
+            (None, None) => return Err(self.error(emission.span(), "emission needs a `spectrum` or an `image`")),
+        };
+        Ok((emission, scale, desc.two_sided))
+    }
+
+    fn build_object(
+        &self,
+        object: &ObjectDesc,
+        primitives: &mut Vec<Arc<PrimitiveEnum>>,
+        lights: &mut Vec<Arc<LightEnum>>,
+    ) -> BuildResult<()> {
+        let material = object
+            .material
+            .as_ref()
+            .map(|name| {
+                self.materials
+                    .get(name.get_ref().as_str())
+                    .cloned()
+                    .ok_or_else(|| self.error(name.span(), format!("unknown material `{}`", name.get_ref())))
+            })
+            .transpose()?;
+        let emission = object
+            .emission
+            .as_ref()
+            .map(|emission| self.build_emission(emission))
+            .transpose()?;
+        let alpha = object
+            .alpha
+            .as_ref()
+            .map(|alpha| self.float_param(alpha, object.shape.span()))
+            .transpose()?;
+        let medium_interface = object
+            .medium
+            .as_ref()
+            .map(|interface| {
+                let medium = |name: &Option<Spanned<String>>| {
+                    name.as_ref()
+                        .map(|name| self.medium(name.get_ref(), name.span()))
+                        .transpose()
+                };
+                Ok(MediumInterface {
+                    inside: medium(&interface.inside)?,
+                    outside: medium(&interface.outside)?,
+                })
+            })
+            .transpose()?;
+        let transform = build_transform(&object.transform);
+
+        for group in self.build_shape(&object.shape, transform)? {
+            // Materials from the object description take precedence over the ones from mesh files
+            let material = material.clone().or(group.material.as_ref().map(|x| x.material.clone()));
+            if material.is_none() && medium_interface.is_none() {
+                return Err(self.error(object.shape.span(), "object has no `material`"));
+            }
+            let emission = emission.clone().or(group
+                .material
+                .as_ref()
+                .and_then(|x| x.emission.clone())
+                .map(|spectrum| (AreaEmission::Spectrum(spectrum), 1., true)));
+
+            let alpha = alpha.clone().or(group.material.as_ref().and_then(|x| x.alpha.clone()));
+
+            let Some((emission, scale, two_sided)) = emission else {
+                primitives.extend(group.shapes.into_iter().map(|shape| {
+                    let primitive = match (&material, &alpha, &medium_interface) {
+                        (Some(material), None, None) => PrimitiveEnum::Simple(SimplePrimitive {
+                            shape,
+                            material: material.clone(),
+                        }),
+                        _ => PrimitiveEnum::Geometric(GeometricPrimitive {
+                            shape,
+                            material: material.clone(),
+                            light: None,
+                            medium_interface: medium_interface.clone(),
+                            alpha: alpha.clone(),
+                        }),
+                    };
+                    Arc::new(primitive)
+                }));
+                continue;
+            };
+            for shape in group.shapes {
+                let light = Arc::new(LightEnum::DiffuseArea(DiffuseAreaLight::new(
+                    emission.clone(),
+                    scale,
+                    two_sided,
+                    Transform::id(),
+                    shape.clone(),
+                )));
+                primitives.push(Arc::new(PrimitiveEnum::Geometric(GeometricPrimitive {
+                    shape,
+                    material: material.clone(),
+                    light: Some(light.clone()),
+                    medium_interface: medium_interface.clone(),
+                    alpha: alpha.clone(),
+                })));
+                lights.push(light);
+            }
+        }
+        Ok(())
+    }
+
+    fn build_shape(&self, shape: &Spanned<ShapeDesc>, transform: Transform<f32>) -> BuildResult<Vec<ShapeGroup>> {
+        let shapes: Vec<Arc<dyn BoundedIntersectable>> = match shape.get_ref() {
+            ShapeDesc::Sphere { radius } => vec![Arc::new(Sphere::new(*radius, transform))],
+            ShapeDesc::Quad { corner, edges } => vec![Arc::new(Quad::new(
+                point3(corner),
+                vec3(&edges[0]),
+                vec3(&edges[1]),
+                transform,
+            ))],
+            ShapeDesc::Box { size: [x, y, z] } => Quad::quad_box(*x, *y, *z, transform)
+                .into_iter()
+                .map(|quad| Arc::new(quad) as _)
+                .collect(),
+            ShapeDesc::Mesh { file } => {
+                let path = self.file.path.parent().unwrap_or(Path::new(".")).join(file);
+                let error = |err: &dyn Display| self.error(shape.span(), err.to_string());
+                return match path.extension().and_then(|x| x.to_str()) {
+                    Some("obj") => Ok(load_obj(path, transform)
+                        .map_err(|err| error(&err))?
+                        .groups
+                        .into_iter()
+                        .map(|group| ShapeGroup {
+                            shapes: group
+                                .triangles
+                                .into_iter()
+                                .map(|triangle| Arc::new(triangle) as _)
+                                .collect(),
+                            material: group.material,
+                        })
+                        .collect()),
+                    Some("ply") => Ok(vec![ShapeGroup {
+                        shapes: load_ply(path, transform)
+                            .map_err(|err| error(&err))?
+                            .into_triangles()
+                            .into_iter()
+                            .map(|triangle| Arc::new(triangle) as _)
+                            .collect(),
+                        material: None,
+                    }]),
+                    _ => Err(error(&"unsupported mesh format, expected `.obj` or `.ply`")),
+                };
+            }
+        };
+        Ok(vec![ShapeGroup { shapes, material: None }])
+    }
+
+    fn medium(&self, name: &str, span: Range<usize>) -> BuildResult<Arc<MediumEnum>> {
+        self.media
+            .get(name)
+            .cloned()
+            .ok_or_else(|| self.error(span, format!("unknown medium `{name}`")))
+    }
+
+    /// Grid given by values or loaded from a file
+    fn build_grid(
+        &self,
+        grid: &GridDesc,
+        resolution: Option<[usize; 3]>,
+        name: &str,
+        span: Range<usize>,
+    ) -> BuildResult<SampledGrid> {
+        match grid {
+            GridDesc::Values(values) => {
+                let Some(resolution) = resolution else {
+                    return Err(self.error(span, format!("`resolution` is required for `{name}` values")));
+                };
+                SampledGrid::new(values.clone(), resolution).ok_or_else(|| {
+                    let [x, y, z] = resolution;
+                    self.error(span, format!("`{name}` must have {x} * {y} * {z} values"))
+                })
+            }
+            GridDesc::File(file) => {
+                let path = self.file.path.parent().unwrap_or(Path::new(".")).join(file);
+                match path.extension().and_then(|x| x.to_str()) {
+                    Some("nrrd" | "nhdr") => load_nrrd(path).map_err(|err| self.error(span, err.to_string())),
+                    _ => Err(self.error(
+                        span,
+                        format!("`{name}`: unsupported volume format, expected `.nrrd` or `.nhdr`"),
+                    )),
+                }
+            }
+        }
+    }
+
+    fn build_medium(&self, medium: &Spanned<MediumDesc>) -> BuildResult<Arc<MediumEnum>> {
+        let span = medium.span();
+        let emission = |emission: &Option<MediumEmissionDesc>| {
+            emission
+                .as_ref()
+                .map(|emission| self.build_illuminant(&emission.spectrum, emission.scale, span.clone()))
+                .transpose()
+        };
+        let check_g = |g: f32| {
+            if g.abs() >= 1. {
+                return Err(self.error(span.clone(), "`g` must be in (-1; 1)"));
+            }
+            Ok(HGPhaseFunction::new(g))
+        };
+        let medium = match medium.get_ref() {
+            MediumDesc::Homogeneous {
+                sigma_a,
+                sigma_s,
+                scale,
+                g,
+                emission: emission_desc,
+            } => HomogeneousMedium {
+                sigma_a: self.build_spectrum(sigma_a, SpectrumKind::Coefficient, span.clone())?,
+                sigma_s: self.build_spectrum(sigma_s, SpectrumKind::Coefficient, span.clone())?,
+                scale: *scale,
+                phase: check_g(*g)?,
+                emission: emission(emission_desc)?,
+            }
+            .into(),
+            MediumDesc::Grid {
+                sigma_a,
+                sigma_s,
+                scale,
+                g,
+                bounds: [min, max],
+                resolution,
+                density,
+                emission: emission_desc,
+                emission_scale,
+                temperature,
+                temperature_scale,
+                transform,
+            } => {
+                if (0..3).any(|i| min[i] >= max[i]) {
+                    return Err(self.error(span, "`bounds` must be `[min, max]` with `min` below `max`"));
+                }
+                let grid = |grid: &GridDesc, name: &str| self.build_grid(grid, *resolution, name, span.clone());
+                let density = grid(density, "density")?;
+                let emission = match (emission(emission_desc)?, temperature) {
+                    (Some(_), Some(_)) => {
+                        return Err(self.error(span, "`emission` and `temperature` are mutually exclusive"));
+                    }
+                    (Some((spectrum, scale)), None) => {
+                        let grid = match emission_scale {
+                            Some(values) => grid(values, "emission_scale")?,
+                            None => density.clone(),
+                        };
+                        Some((GridEmission::Spectrum(spectrum, grid), scale))
+                    }
+                    (None, Some(temperature)) => Some((
+                        GridEmission::Temperature(grid(temperature, "temperature")?),
+                        *temperature_scale,
+                    )),
+                    (None, None) => None,
+                };
+                GridMedium::new(GridMediumConfig {
+                    bounds: Aabb::new(point3(min), point3(max)),
+                    medium_to_render: build_transform(transform),
+                    sigma_a: self.build_spectrum(sigma_a, SpectrumKind::Coefficient, span.clone())?,
+                    sigma_s: self.build_spectrum(sigma_s, SpectrumKind::Coefficient, span.clone())?,
+                    scale: *scale,
+                    g: check_g(*g)?.g,
+                    density,
+                    emission,
+                })
+                .into()
+            }
+        };
+        Ok(Arc::new(medium))
+    }
+
+    fn build_light(&self, light: &Spanned<LightDesc>) -> BuildResult<LightEnum> {
+        let span = light.span();
+        let light = match light.get_ref() {
+            LightDesc::Point {
+                spectrum,
+                scale,
+                position,
+            } => {
+                let (spectrum, scale) = self.build_illuminant(spectrum, *scale, span)?;
+                LightEnum::Point(PointLight::new(spectrum, scale, Transform::translate(vec3(position))))
+            }
+            LightDesc::Spot {
+                spectrum,
+                scale,
+                from,
+                to,
+                falloff_start,
+                falloff_end,
+            } => {
+                if falloff_start > falloff_end {
+                    return Err(self.error(span, "`falloff_start` must not exceed `falloff_end`"));
+                }
+                let dir = vec3(to) - vec3(from);
+                if dir.len_squared() == 0. {
+                    return Err(self.error(span, "`from` and `to` must differ"));
+                }
+                // Spotlights shine along +z in light space
+                let light_to_world =
+                    Transform::rotate_from_to(&vec3!(0., 0., 1.), &dir.to_unit()).then_translate(vec3(from));
+                let (spectrum, scale) = self.build_illuminant(spectrum, *scale, span)?;
+                LightEnum::Spot(Spotlight::new(
+                    spectrum,
+                    scale,
+                    light_to_world,
+                    *falloff_start,
+                    *falloff_end,
+                ))
+            }
+            LightDesc::Goniometric {
+                spectrum,
+                scale,
+                file,
+                transform,
+            } => {
+                let (spectrum, scale) = self.build_illuminant(spectrum, *scale, span.clone())?;
+                let image = self.light_image(file, span.clone())?;
+                LightEnum::Goniometric(
+                    GoniometricLight::new(spectrum, scale, &image, build_transform(transform))
+                        .map_err(|err| self.error(span, format!("goniometric image: {err}")))?,
+                )
+            }
+            LightDesc::Projection {
+                file,
+                scale,
+                from,
+                to,
+                fov,
+            } => {
+                if !(*fov > 0. && *fov < 180.) {
+                    return Err(self.error(span, "`fov` must be in (0; 180)"));
+                }
+                let dir = vec3(to) - vec3(from);
+                if dir.len_squared() == 0. {
+                    return Err(self.error(span, "`from` and `to` must differ"));
+                }
+                let light_to_world =
+                    Transform::rotate_from_to(&vec3!(0., 0., 1.), &dir.to_unit()).then_translate(vec3(from));
+                let image = self.light_image(file, span)?;
+                LightEnum::Projection(ProjectionLight::new(image, *scale, *fov, light_to_world))
+            }
+            LightDesc::Distant {
+                spectrum,
+                scale,
+                direction,
+            } => {
+                let direction = vec3(direction);
+                if direction.len_squared() == 0. {
+                    return Err(self.error(span, "`direction` must not be zero"));
+                }
+                let (spectrum, scale) = self.build_illuminant(spectrum, *scale, span)?;
+                // Light comes from +z in light space
+                let light_to_world = Transform::rotate_from_to(&vec3!(0., 0., 1.), &(-direction).to_unit());
+                LightEnum::Distant(DistantLight::new(spectrum, scale, light_to_world))
+            }
+            LightDesc::Infinite { spectrum, scale } => {
+                let (spectrum, scale) = self.build_illuminant(spectrum, *scale, span)?;
+                LightEnum::UniformInfinite(UniformInfiniteLight::new(spectrum, scale))
+            }
+            LightDesc::Environment {
+                file,
+                scale,
+                mapping,
+                transform,
+            } => {
+                let path = self.file.path.parent().unwrap_or(Path::new(".")).join(file);
+                let image = read_linear_rgb(&path)
+                    .map_err(|err| self.error(span.clone(), format!("failed to load {}: {err}", path.display())))?;
+                let mapping = match mapping {
+                    MappingDesc::Equirectangular => EnvironmentMapping::Equirectangular,
+                    MappingDesc::EqualArea => EnvironmentMapping::EqualArea,
+                };
+                LightEnum::ImageInfinite(
+                    ImageInfiniteLight::new(image, mapping, *scale, build_transform(transform))
+                        .map_err(|err| self.error(span, err.to_string()))?,
+                )
+            }
+        };
+        Ok(light)
+    }
+}
+
+fn build_texture_mapping(mapping: &TextureMappingDesc) -> Either<TextureMapping2DEnum, PointTransformMapping> {
+    let mapping = match mapping {
+        &TextureMappingDesc::Uv {
+            scale: [su, sv],
+            offset: [du, dv],
+        } => UVMapping { su, sv, du, dv }.into(),
+        TextureMappingDesc::Spherical { transform } => SphericalMapping::new(build_transform(transform)).into(),
+        TextureMappingDesc::Cylindrical { transform } => CylindricalMapping::new(build_transform(transform)).into(),
+        TextureMappingDesc::Planar {
+            s,
+            t,
+            offset: [ds, dt],
+            transform,
+        } => PlanarMapping::new(build_transform(transform), vec3(s), vec3(t), *ds, *dt).into(),
+        TextureMappingDesc::Solid { transform } => {
+            return Either::Right(PointTransformMapping::new(build_transform(transform)))
+        }
+    };
+    Either::Left(mapping)
+}
+
+fn build_transform(ops: &[TransformOp]) -> Transform<f32> {
+    ops.iter().fold(Transform::id(), |transform, op| match op {
+        TransformOp::Translate(offset) => transform.then_translate(vec3(offset)),
+        TransformOp::Scale(ScaleDesc::Uniform(factor)) => transform.then_scale_uniform(*factor),
+        TransformOp::Scale(ScaleDesc::PerAxis([x, y, z])) => transform.then_scale(*x, *y, *z),
+        TransformOp::RotateX(degrees) => transform.then_rotate_degrees(Axis3::X, *degrees),
+        TransformOp::RotateY(degrees) => transform.then_rotate_degrees(Axis3::Y, *degrees),
+        TransformOp::RotateZ(degrees) => transform.then_rotate_degrees(Axis3::Z, *degrees),
+        TransformOp::Rotate { axis, degrees } => transform.then_rotate_arbitrary_axis(vec3(axis), degrees.to_radians()),
+    })
+}
+
+fn point3(x: &[f32; 3]) -> Point3f { point3!(x[0], x[1], x[2]) }
+
+fn vec3(x: &[f32; 3]) -> Vec3f { vec3!(x[0], x[1], x[2]) }
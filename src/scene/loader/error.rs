@@ -0,0 +1,43 @@
+use std::{io, ops::Range, path::PathBuf};
+
+use derive_more::{Display, Error};
+
+use crate::scene::SceneError;
+
+#[derive(Debug, Display, Error)]
+pub enum SceneLoadError {
+    #[display("failed to read {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    /// Either a syntax error or a semantically invalid value (unknown material, malformed spectrum, etc.)
+    #[display("{}:{line}:{column}: {message}", path.display())]
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        #[error(not(source))]
+        message: String,
+    },
+    #[display("{_0}")]
+    Scene(SceneError),
+}
+
+impl SceneLoadError {
+    /// Builds [SceneLoadError::Invalid] pointing at the start of `span` within `source`
+    pub(crate) fn invalid(path: PathBuf, source: &str, span: Option<Range<usize>>, message: String) -> Self {
+        let (line, column) = span.map_or((1, 1), |span| line_column(source, span.start));
+        SceneLoadError::Invalid {
+            path,
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+/// 1-based line and column of a byte offset
+pub(crate) fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    (line, column)
+}
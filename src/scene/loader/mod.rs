@@ -0,0 +1,513 @@
+//! Declarative scene description in TOML. See `scenes/cornell_box.toml` for an example.
+//!
+//! Loading is split into two steps: [SceneFile::load] parses the file into a [SceneDescription] which can be
+//! tweaked (e.g. to override film resolution), then [SceneFile::build] produces a [Scene].
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub(crate) use error::line_column;
+pub use error::SceneLoadError;
+pub use schema::*;
+
+use crate::scene::{loader::builder::SceneBuilder, Scene};
+
+mod builder;
+mod error;
+mod schema;
+
+pub struct SceneFile {
+    pub description: SceneDescription,
+    path: PathBuf,
+    source: String,
+}
+
+impl SceneFile {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneLoadError> {
+        let path = path.as_ref().to_path_buf();
+        let source = fs::read_to_string(&path).map_err(|source| SceneLoadError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        Self::parse(source, path)
+    }
+
+    /// Parses scene description from a string. `path` is used for error messages and to resolve relative paths.
+    pub fn parse(source: String, path: PathBuf) -> Result<Self, SceneLoadError> {
+        match toml::from_str(&source) {
+            Ok(description) => Ok(SceneFile {
+                description,
+                path,
+                source,
+            }),
+            Err(err) => Err(SceneLoadError::invalid(
+                path,
+                &source,
+                err.span(),
+                err.message().to_string(),
+            )),
+        }
+    }
+
+    pub fn build(&self) -> Result<Scene, SceneLoadError> { SceneBuilder::new(self).build() }
+}
+
+pub fn load_scene(path: impl AsRef<Path>) -> Result<Scene, SceneLoadError> { SceneFile::load(path)?.build() }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{
+        light::{Light, LightEnum},
+        mediums::MediumEnum,
+        point2,
+        scene::{
+            cameras::{Camera, CameraSample},
+            filters::{Filter, FilterEnum},
+        },
+        spectra::sampled_wavelengths::SampledWavelengths,
+        vec2,
+    };
+
+    const SCENE: &str = r#"
+[film]
+width = 32
+height = 16
+
+[camera]
+type = "perspective"
+fov = 45.0
+transform = [{ translate = [0.0, 0.0, -5.0] }]
+
+[materials]
+white = { type = "matte", reflectance = 0.8 }
+
+[[objects]]
+shape = { type = "sphere", radius = 1.0 }
+material = "white"
+
+[[objects]]
+shape = { type = "quad", corner = [-1.0, 3.0, -1.0], edges = [[2.0, 0.0, 0.0], [0.0, 0.0, 2.0]] }
+material = "white"
+emission = { spectrum = "stdillum-D65" }
+
+[[lights]]
+type = "point"
+spectrum = { lambdas = [360.0, 830.0], values = [1.0, 1.0] }
+position = [0.0, 5.0, 0.0]
+
+[[lights]]
+type = "infinite"
+spectrum = 0.1
+"#;
+
+    fn parse(source: &str) -> Result<SceneFile, SceneLoadError> {
+        SceneFile::parse(source.to_string(), PathBuf::from("test.toml"))
+    }
+
+    fn location(err: SceneLoadError) -> (usize, usize) {
+        match err {
+            SceneLoadError::Invalid { line, column, .. } => (line, column),
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let scene = parse(SCENE).unwrap().build().unwrap();
+        assert_eq!(scene.lights.len(), 3);
+        assert_eq!(scene.infinite_lights.len(), 1);
+        assert_eq!(scene.camera.get_film().resolution, point2!(32, 16));
+    }
+
+    #[test]
+    fn test_unknown_material() {
+        let source = SCENE.replacen(r#"material = "white""#, r#"material = "black""#, 1);
+        let err = parse(&source).unwrap().build().err().unwrap();
+        assert!(err.to_string().contains("unknown material `black`"));
+        assert_eq!(location(err), (16, 12));
+    }
+
+    #[test]
+    fn test_zero_resolution() {
+        let source = SCENE.replace("height = 16", "height = 0");
+        let err = parse(&source).unwrap().build().err().unwrap();
+        assert_eq!(location(err), (4, 10));
+    }
+
+    #[test]
+    fn test_pixel_filter() {
+        let film = |filter: &str| {
+            let source = SCENE.replace("height = 16", &format!("height = 16\nfilter = {filter}"));
+            parse(&source).unwrap().build()
+        };
+        let scene = film(r#"{ type = "mitchell", radius = [1.0, 2.0] }"#).unwrap();
+        let FilterEnum::Mitchell(filter) = scene.camera.get_film().filter().clone() else {
+            panic!("expected a Mitchell filter");
+        };
+        assert_eq!(filter.radius(), vec2!(1., 2.));
+        let scene = film(r#"{ type = "gaussian" }"#).unwrap();
+        assert_eq!(scene.camera.get_film().filter().radius(), vec2!(1.5));
+
+        let err = film(r#"{ type = "lanczos", radius = [0.0, 1.0] }"#).err().unwrap();
+        assert!(err.to_string().contains("filter radius must be positive"));
+        assert_eq!(location(err), (5, 10));
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let source = SCENE.replace("radius = 1.0", "radius = \"big\"");
+        assert_eq!(location(parse(&source).err().unwrap()).0, 15);
+    }
+
+    #[test]
+    fn test_metal() {
+        let material = |desc: &str| {
+            let source = SCENE.replace(r#"{ type = "matte", reflectance = 0.8 }"#, desc);
+            parse(&source).unwrap().build()
+        };
+        material(r#"{ type = "metal", eta = "metal-Au-eta", k = "metal-Au-k", roughness = 0.3 }"#).unwrap();
+        material(r#"{ type = "metal", reflectance = 0.9, roughness = [0.1, 0.5] }"#).unwrap();
+        let err = material(r#"{ type = "metal", eta = "metal-Au-eta" }"#).err().unwrap();
+        assert!(err.to_string().contains("either `reflectance` or both `eta` and `k`"));
+        let err = material(r#"{ type = "glass", ior = 1.5, roughness = 2.0 }"#)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("roughness must be in [0; 1]"));
+    }
+
+    #[test]
+    fn test_image_texture() {
+        let path = std::env::temp_dir().join("rusttracer_test_texture.png");
+        image::RgbImage::from_pixel(4, 4, image::Rgb([200, 100, 50]))
+            .save(&path)
+            .unwrap();
+        let with_texture = |texture: &str| {
+            let source = SCENE
+                .replace("[materials]", &format!("[textures]\nwood = {texture}\n\n[materials]"))
+                .replace(r#"reflectance = 0.8"#, r#"reflectance = "wood""#);
+            parse(&source).unwrap().build()
+        };
+        let file = path.display();
+        with_texture(&format!(r#"{{ type = "image", file = "{file}", wrap = "clamp" }}"#)).unwrap();
+        with_texture(&format!(
+            r#"{{ type = "image", file = "{file}", mapping = {{ type = "uv", scale = [4.0, 4.0] }} }}"#
+        ))
+        .unwrap();
+        let err = with_texture(r#"{ type = "image", file = "missing.png" }"#)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("failed to load"));
+    }
+
+    #[test]
+    fn test_procedural_textures() {
+        let with_texture = |texture: &str| {
+            let source = SCENE
+                .replace("[materials]", &format!("[textures]\nwood = {texture}\n\n[materials]"))
+                .replace(r#"reflectance = 0.8"#, r#"reflectance = "wood""#);
+            parse(&source).unwrap().build()
+        };
+        for texture in [
+            r#"{ type = "checkerboard", colors = [0.1, 0.9], mapping = { type = "uv", scale = [8.0, 8.0] } }"#,
+            r#"{ type = "checkerboard", colors = [0.1, [0.9, 0.2, 0.2]], mapping = { type = "solid" } }"#,
+            r#"{ type = "dots", inside = 0.1, outside = 0.9, mapping = { type = "spherical" } }"#,
+            r#"{ type = "dots", inside = 0.1, outside = 0.9, mapping = { type = "cylindrical" } }"#,
+            r#"{ type = "dots", inside = 0.1, outside = 0.9, mapping = { type = "planar", s = [1.0, 0.0, 0.0], t = [0.0, 1.0, 0.0] } }"#,
+            r#"{ type = "marble", scale = 4.0, transform = [{ rotate_x = 30.0 }] }"#,
+            r#"{ type = "wood", light = [0.6, 0.4, 0.2], dark = [0.3, 0.2, 0.1], rings = 20.0 }"#,
+        ] {
+            with_texture(texture).unwrap();
+        }
+        let err = with_texture(r#"{ type = "dots", inside = 0.1, outside = 0.9, mapping = { type = "solid" } }"#)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("only supported by checkerboards"));
+    }
+
+    #[test]
+    fn test_float_textures() {
+        let with_textures = |textures: &str, material: &str| {
+            let source = SCENE
+                .replace("[materials]", &format!("[float_textures]\n{textures}\n\n[materials]"))
+                .replace(r#"{ type = "matte", reflectance = 0.8 }"#, material);
+            parse(&source).unwrap().build()
+        };
+        let textures = r#"
+noise = { type = "fbm", omega = 0.6, transform = [{ scale = 4.0 }] }
+wrinkles = { type = "wrinkled" }
+rough = { type = "mix", textures = ["checks", 0.5], amount = "noise" }
+checks = { type = "checkerboard", values = [0.1, "wrinkles"], mapping = { type = "solid" } }
+ior = { type = "scale", texture = "noise", scale = 0.1 }
+"#;
+        with_textures(
+            textures,
+            r#"{ type = "metal", reflectance = 0.9, roughness = "rough" }"#,
+        )
+        .unwrap();
+        with_textures(
+            textures,
+            r#"{ type = "glass", ior = "ior", roughness = ["checks", 0.2] }"#,
+        )
+        .unwrap();
+
+        let err = with_textures(
+            textures,
+            r#"{ type = "metal", reflectance = 0.9, roughness = "smooth" }"#,
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("unknown float texture `smooth`"));
+        let err = with_textures(
+            r#"loop = { type = "scale", texture = "loop", scale = 2.0 }"#,
+            r#"{ type = "matte", reflectance = 0.8 }"#,
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("depends on itself"));
+    }
+
+    #[test]
+    fn test_bump() {
+        let path = std::env::temp_dir().join("rusttracer_test_normal_map.png");
+        image::RgbImage::from_pixel(4, 4, image::Rgb([128, 128, 255]))
+            .save(&path)
+            .unwrap();
+        let material = |desc: &str| {
+            let source = SCENE
+                .replace(
+                    "[materials]",
+                    "[float_textures]\nnoise = { type = \"fbm\" }\n\n[materials]",
+                )
+                .replace(r#"{ type = "matte", reflectance = 0.8 }"#, desc);
+            parse(&source).unwrap().build()
+        };
+        let file = path.display();
+        material(r#"{ type = "matte", reflectance = 0.8, displacement = "noise" }"#).unwrap();
+        material(&format!(
+            r#"{{ type = "metal", reflectance = 0.8, normal_map = "{file}" }}"#
+        ))
+        .unwrap();
+        let err = material(&format!(
+            r#"{{ type = "glass", ior = 1.5, displacement = 0.1, normal_map = "{file}" }}"#
+        ))
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("mutually exclusive"));
+    }
+
+    #[test]
+    fn test_blackbody() {
+        let flux = |spectrum: &str| {
+            let source = SCENE.replace("{ lambdas = [360.0, 830.0], values = [1.0, 1.0] }", spectrum);
+            let scene = parse(&source).unwrap().build()?;
+            let lambda = SampledWavelengths::sample_visible(0.5);
+            let light = scene
+                .lights
+                .iter()
+                .find(|x| matches!(***x, LightEnum::Point(_)))
+                .unwrap();
+            Ok::<_, SceneLoadError>(light.flux(&lambda)[0])
+        };
+        // Normalized to the same luminance as the standard illuminants
+        let ratio = flux("{ blackbody = 6500.0 }").unwrap() / flux(r#""stdillum-D65""#).unwrap();
+        assert!((0.8..1.25).contains(&ratio), "{ratio}");
+        let ratio = flux("{ blackbody = 2700.0 }").unwrap() / flux(r#""stdillum-A""#).unwrap();
+        assert!((0.8..1.25).contains(&ratio), "{ratio}");
+
+        let source = SCENE.replace("reflectance = 0.8", "reflectance = { blackbody = 2700.0 }");
+        let err = parse(&source).unwrap().build().err().unwrap();
+        assert!(err.to_string().contains("only allowed for emission"));
+    }
+
+    #[test]
+    fn test_goniometric_and_projection() {
+        let path = std::env::temp_dir().join("rusttracer_test_projection.png");
+        image::RgbImage::from_pixel(4, 2, image::Rgb([255, 128, 0]))
+            .save(&path)
+            .unwrap();
+        let light = |desc: String| {
+            let source = SCENE.replace("type = \"infinite\"\nspectrum = 0.1", &desc);
+            parse(&source).unwrap().build()
+        };
+        let file = path.display();
+        let scene = light(format!(
+            r#"type = "projection"
+file = "{file}"
+from = [0.0, 5.0, 0.0]
+to = [0.0, 0.0, 0.0]
+fov = 40.0"#
+        ))
+        .unwrap();
+        assert!(matches!(*scene.lights[2], LightEnum::Projection(_)));
+        let err = light(format!("type = \"goniometric\"\nspectrum = 1.0\nfile = \"{file}\""))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("must be square"));
+    }
+
+    #[test]
+    fn test_area_emission() {
+        let emission = |desc: &str| {
+            let source = SCENE.replace(r#"emission = { spectrum = "stdillum-D65" }"#, desc);
+            parse(&source).unwrap().build()
+        };
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        let flux = |desc: &str| {
+            let scene = emission(desc).unwrap();
+            let LightEnum::DiffuseArea(light) = &*scene.lights[0] else {
+                panic!("expected an area light");
+            };
+            light.flux(&lambda)[0]
+        };
+        let two_sided = flux(r#"emission = { spectrum = 1.0 }"#);
+        assert_abs_diff_eq!(two_sided, 2. * PI * 4., epsilon = 1e-4);
+        assert_abs_diff_eq!(
+            flux(r#"emission = { spectrum = 1.0, two_sided = false }"#),
+            two_sided / 2.,
+            epsilon = 1e-4
+        );
+
+        let path = std::env::temp_dir().join("rusttracer_test_emission.png");
+        image::RgbImage::from_pixel(2, 2, image::Rgb([255, 255, 255]))
+            .save(&path)
+            .unwrap();
+        let image = format!(r#"emission = {{ image = "{}", scale = 2.0 }}"#, path.display());
+        assert!(flux(&image) > 0.);
+
+        let both = format!(r#"emission = {{ spectrum = 1.0, image = "{}" }}"#, path.display());
+        let err = emission(&both).err().unwrap();
+        assert!(err.to_string().contains("can't have both"));
+        let err = emission("emission = { scale = 2.0 }").err().unwrap();
+        assert!(err.to_string().contains("needs a `spectrum` or an `image`"));
+    }
+
+    #[test]
+    fn test_distant_light() {
+        let source = SCENE.replace(
+            "type = \"infinite\"\nspectrum = 0.1",
+            "type = \"distant\"\nspectrum = \"stdillum-D65\"\nscale = 2.0\ndirection = [0.0, -1.0, 0.0]",
+        );
+        let scene = parse(&source).unwrap().build().unwrap();
+        assert!(scene.infinite_lights.is_empty());
+        let LightEnum::Distant(light) = &*scene.lights[2] else {
+            panic!("expected a distant light");
+        };
+        // Preprocessed with the scene bounds
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        assert!(light.flux(&lambda)[0] > 0.);
+
+        let source = source.replace("direction = [0.0, -1.0, 0.0]", "direction = [0.0, 0.0, 0.0]");
+        let err = parse(&source).unwrap().build().err().unwrap();
+        assert!(err.to_string().contains("`direction` must not be zero"));
+    }
+
+    #[test]
+    fn test_alpha() {
+        let source = SCENE.replace(
+            "shape = { type = \"sphere\", radius = 1.0 }",
+            "shape = { type = \"sphere\", radius = 1.0 }\nalpha = 0.0",
+        );
+        let scene = parse(&source).unwrap().build().unwrap();
+        let ray = scene.camera.generate_ray(CameraSample {
+            p_film: point2!(16., 8.),
+            p_lens: point2!(0.5, 0.5),
+            filter_weight: 1.,
+        });
+        assert!(scene.cast_ray(&ray).is_none());
+
+        let source = SCENE.replace(
+            "shape = { type = \"sphere\", radius = 1.0 }",
+            "shape = { type = \"sphere\", radius = 1.0 }\nalpha = \"holes\"",
+        );
+        let err = parse(&source).unwrap().build().err().unwrap();
+        assert!(err.to_string().contains("unknown float texture `holes`"));
+    }
+
+    #[test]
+    fn test_media() {
+        let media = r#"
+[media]
+fog = { type = "homogeneous", sigma_a = 0.1, sigma_s = [0.2, 0.3, 0.4], g = 0.5 }
+smoke = { type = "grid", sigma_a = 1.0, sigma_s = 1.0, bounds = [[-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]], resolution = [2, 1, 1], density = [0.0, 1.0] }
+"#;
+        let source = format!("{SCENE}{media}")
+            .replacen(
+                "material = \"white\"",
+                "medium = { inside = \"smoke\", outside = \"fog\" }",
+                1,
+            )
+            .replace(
+                "transform = [{ translate = [0.0, 0.0, -5.0] }]",
+                "transform = [{ translate = [0.0, 0.0, -5.0] }]\nmedium = \"fog\"",
+            );
+        let scene = parse(&source).unwrap().build().unwrap();
+        assert!(scene.camera.medium().is_some());
+        let ray = scene.camera.generate_ray(CameraSample {
+            p_film: point2!(16., 8.),
+            p_lens: point2!(0.5, 0.5),
+            filter_weight: 1.,
+        });
+        // The sphere only bounds the smoke
+        let hit = scene.cast_ray(&ray).unwrap();
+        assert!(hit.material.is_none());
+        let inside = hit.medium_towards(*ray.dir, None).unwrap();
+        assert!(matches!(inside.as_ref(), MediumEnum::Grid(_)));
+        let outside = hit.medium_towards(-*ray.dir, None).unwrap();
+        assert!(matches!(outside.as_ref(), MediumEnum::Homogeneous(_)));
+
+        let err = parse(&source.replace("inside = \"smoke\"", "inside = \"steam\""))
+            .unwrap()
+            .build()
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("unknown medium `steam`"));
+        let err = parse(&source.replace("density = [0.0, 1.0]", "density = [0.0]"))
+            .unwrap()
+            .build()
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("`density` must have"));
+        let err = parse(&source.replace("g = 0.5", "g = 1.0"))
+            .unwrap()
+            .build()
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("`g` must be in"));
+
+        // Grids from files, with blackbody emission
+        let path = std::env::temp_dir().join("rusttracer_test_density.nrrd");
+        let mut file = b"NRRD0004\ntype: uchar\nsizes: 2 2 2\nencoding: raw\n\n".to_vec();
+        file.extend([0, 1, 2, 3, 4, 5, 6, 7]);
+        std::fs::write(&path, file).unwrap();
+        let with_grid = |grid: &str| {
+            let file = path.display();
+            let source = source.replace("density = [0.0, 1.0]", &format!(r#"density = "{file}", {grid}"#));
+            parse(&source).unwrap().build()
+        };
+        with_grid(r#"temperature = [1500.0, 2000.0], temperature_scale = 2.0"#).unwrap();
+        let err = with_grid(r#"temperature = "missing.nrrd""#).err().unwrap();
+        assert!(err.to_string().contains("failed to read"));
+        let err = with_grid(r#"temperature = "fire.vdb""#).err().unwrap();
+        assert!(err.to_string().contains("unsupported volume format"));
+        let err = with_grid(r#"temperature = [1.0, 2.0], emission = { spectrum = 1.0 }"#)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("mutually exclusive"));
+    }
+
+    #[test]
+    fn test_example_scene() {
+        SceneFile::load("./scenes/cornell_box.toml").unwrap();
+        SceneFile::load("./scenes/cornell_foggy_box.toml")
+            .unwrap()
+            .build()
+            .unwrap();
+    }
+}
@@ -0,0 +1,638 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+/// Root of a scene description file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    pub film: FilmDesc,
+    pub camera: Spanned<CameraDesc>,
+    #[serde(default)]
+    pub textures: HashMap<String, Spanned<TextureDesc>>,
+    /// Scalar textures for material parameters such as roughness
+    #[serde(default)]
+    pub float_textures: HashMap<String, Spanned<FloatTextureDesc>>,
+    #[serde(default)]
+    pub materials: HashMap<String, Spanned<MaterialDesc>>,
+    /// Participating media, used by the camera and by object medium interfaces
+    #[serde(default)]
+    pub media: HashMap<String, Spanned<MediumDesc>>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDesc>,
+    #[serde(default)]
+    pub lights: Vec<Spanned<LightDesc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilmDesc {
+    pub width: Spanned<usize>,
+    pub height: Spanned<usize>,
+    /// Pixel reconstruction filter, a box covering a single pixel if omitted
+    pub filter: Option<Spanned<PixelFilterDesc>>,
+}
+
+/// See [filters](crate::scene::filters). Radii are `[x, y]` in pixels
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PixelFilterDesc {
+    Box {
+        #[serde(default = "default_box_radius")]
+        radius: [f32; 2],
+    },
+    Triangle {
+        #[serde(default = "default_triangle_radius")]
+        radius: [f32; 2],
+    },
+    Gaussian {
+        #[serde(default = "default_gaussian_radius")]
+        radius: [f32; 2],
+        #[serde(default = "default_sigma")]
+        sigma: f32,
+    },
+    Mitchell {
+        #[serde(default = "default_triangle_radius")]
+        radius: [f32; 2],
+        #[serde(default = "default_mitchell_param")]
+        b: f32,
+        #[serde(default = "default_mitchell_param")]
+        c: f32,
+    },
+    Lanczos {
+        #[serde(default = "default_lanczos_radius")]
+        radius: [f32; 2],
+        #[serde(default = "default_tau")]
+        tau: f32,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CameraDesc {
+    Perspective {
+        fov: f32,
+        #[serde(default)]
+        lens_radius: f32,
+        #[serde(default = "default_focal_distance")]
+        focal_distance: f32,
+        /// `[x_min, y_min, x_max, y_max]`, derived from the film aspect ratio if omitted
+        screen_window: Option<[f32; 4]>,
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+        /// Name of a medium from the `[media]` table the camera is in
+        medium: Option<String>,
+    },
+    Orthographic {
+        #[serde(default)]
+        lens_radius: f32,
+        #[serde(default = "default_focal_distance")]
+        focal_distance: f32,
+        screen_window: Option<[f32; 4]>,
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+        medium: Option<String>,
+    },
+}
+
+/// Single step of a transform. Steps are applied in the order they are listed.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformOp {
+    Translate([f32; 3]),
+    Scale(ScaleDesc),
+    /// Degrees
+    RotateX(f32),
+    /// Degrees
+    RotateY(f32),
+    /// Degrees
+    RotateZ(f32),
+    Rotate {
+        axis: [f32; 3],
+        degrees: f32,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ScaleDesc {
+    Uniform(f32),
+    PerAxis([f32; 3]),
+}
+
+/// Spectral value. Interpretation of RGB triplets depends on the context: reflectances become
+/// [RGBAlbedoSpectrum](crate::spectra::RGBAlbedoSpectrum), emission becomes
+/// [RGBIlluminantSpectrum](crate::spectra::RGBIlluminantSpectrum), etc.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SpectrumDesc {
+    Constant(f32),
+    Rgb([f32; 3]),
+    /// Name of one of [NamedSpectra](crate::spectra::named::NamedSpectra)
+    Named(String),
+    /// Blackbody emitter at the given temperature in Kelvin, normalized to the luminance of the standard illuminants.
+    /// Only allowed for emission.
+    Blackbody {
+        blackbody: f32,
+    },
+    PiecewiseLinear {
+        lambdas: Vec<f32>,
+        values: Vec<f32>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TextureDesc {
+    Constant {
+        value: SpectrumDesc,
+    },
+    /// sRGB image file, relative to the scene file
+    Image {
+        file: String,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        #[serde(default)]
+        invert: bool,
+        #[serde(default)]
+        filter: FilterDesc,
+        #[serde(default)]
+        wrap: WrapDesc,
+        #[serde(default)]
+        mapping: TextureMappingDesc,
+    },
+    /// Unit checkers in texture space, the `solid` mapping makes them 3D
+    Checkerboard {
+        colors: [SpectrumDesc; 2],
+        #[serde(default)]
+        mapping: TextureMappingDesc,
+    },
+    /// Randomly placed dots, at most one per unit cell of texture space
+    Dots {
+        inside: SpectrumDesc,
+        outside: SpectrumDesc,
+        #[serde(default)]
+        mapping: TextureMappingDesc,
+    },
+    /// Solid texture with noisy bands along y
+    Marble {
+        #[serde(default = "default_scale")]
+        scale: f32,
+        #[serde(default = "default_scale")]
+        variation: f32,
+        #[serde(default = "default_omega")]
+        omega: f32,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+    /// Solid texture with rings around z
+    Wood {
+        light: SpectrumDesc,
+        dark: SpectrumDesc,
+        /// Rings per unit of distance from the axis
+        #[serde(default = "default_rings")]
+        rings: f32,
+        /// Distortion of the rings, in rings
+        #[serde(default = "default_wood_variation")]
+        variation: f32,
+        #[serde(default = "default_omega")]
+        omega: f32,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum FloatTextureDesc {
+    Constant {
+        value: f32,
+    },
+    /// Single channel of an image file, relative to the scene file. Integer images are assumed to be sRGB-encoded
+    Image {
+        file: String,
+        #[serde(default)]
+        channel: ChannelDesc,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        #[serde(default)]
+        invert: bool,
+        #[serde(default)]
+        filter: FilterDesc,
+        #[serde(default)]
+        wrap: WrapDesc,
+        #[serde(default)]
+        mapping: TextureMappingDesc,
+    },
+    Checkerboard {
+        values: [FloatParam; 2],
+        #[serde(default)]
+        mapping: TextureMappingDesc,
+    },
+    /// Solid fractional Brownian motion noise in about [-1; 1]
+    Fbm {
+        #[serde(default = "default_omega")]
+        omega: f32,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+    /// Solid turbulence, like `fbm` with absolute values of the noise
+    Wrinkled {
+        #[serde(default = "default_omega")]
+        omega: f32,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+    /// `texture * scale`
+    Scale {
+        texture: FloatParam,
+        scale: FloatParam,
+    },
+    /// `(1 - amount) * textures[0] + amount * textures[1]`
+    Mix {
+        textures: [FloatParam; 2],
+        amount: FloatParam,
+    },
+}
+
+/// See [ImageChannel](crate::textures::image::ImageChannel)
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelDesc {
+    Red,
+    Green,
+    Blue,
+    #[default]
+    Average,
+    /// Alpha channel, or the average of RGB without sRGB decoding for images without one
+    Alpha,
+}
+
+/// Either a number or a name of a texture from the `[float_textures]` table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FloatParam {
+    Value(f32),
+    Texture(String),
+}
+
+/// See [FilterFunction](crate::textures::mipmap::FilterFunction)
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterDesc {
+    Point,
+    Bilinear,
+    #[default]
+    Trilinear,
+}
+
+/// See [WrapMode](crate::textures::mipmap::WrapMode)
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapDesc {
+    #[default]
+    Repeat,
+    Clamp,
+    Black,
+}
+
+/// Mappings other than `uv` are defined in texture space placed by `transform`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TextureMappingDesc {
+    /// `st = scale * uv + offset`
+    Uv {
+        #[serde(default = "default_uv_scale")]
+        scale: [f32; 2],
+        #[serde(default)]
+        offset: [f32; 2],
+    },
+    /// Azimuth and polar angle around z, both in [0, 1]
+    Spherical {
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+    /// Azimuth around z in [0, 1] and height along z
+    Cylindrical {
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+    /// `st = [dot(s, p), dot(t, p)] + offset`
+    Planar {
+        s: [f32; 3],
+        t: [f32; 3],
+        #[serde(default)]
+        offset: [f32; 2],
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+    /// 3D texture space point, only for checkerboards
+    Solid {
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+}
+
+impl Default for TextureMappingDesc {
+    fn default() -> Self {
+        TextureMappingDesc::Uv {
+            scale: default_uv_scale(),
+            offset: [0.; 2],
+        }
+    }
+}
+
+/// Either an inline spectrum or a name of a texture from the `[textures]` table
+pub type TextureParam = SpectrumDesc;
+
+/// Every material accepts either a `displacement` along the normal in scene units or a tangent space `normal_map` image
+/// relative to the scene file. Normal maps are read as is, without sRGB decoding
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MaterialDesc {
+    Matte {
+        reflectance: TextureParam,
+        displacement: Option<FloatParam>,
+        normal_map: Option<String>,
+    },
+    /// Specified either by `reflectance` or by the complex index of refraction `eta` and `k`, e.g. `"metal-Au-eta"`
+    /// and `"metal-Au-k"`
+    Metal {
+        reflectance: Option<TextureParam>,
+        eta: Option<SpectrumDesc>,
+        k: Option<SpectrumDesc>,
+        #[serde(default)]
+        roughness: RoughnessDesc,
+        displacement: Option<FloatParam>,
+        normal_map: Option<String>,
+    },
+    Glass {
+        /// A spectrum, or a name from the `[float_textures]` table for an index of refraction varying over the
+        /// surface
+        ior: SpectrumDesc,
+        color: Option<TextureParam>,
+        #[serde(default)]
+        roughness: RoughnessDesc,
+        displacement: Option<FloatParam>,
+        normal_map: Option<String>,
+    },
+}
+
+/// Perceptually linear microfacet roughness in [0; 1], 0 is a perfect mirror
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum RoughnessDesc {
+    Isotropic(FloatParam),
+    /// Along the surface u and v directions
+    Anisotropic([FloatParam; 2]),
+}
+
+impl Default for RoughnessDesc {
+    fn default() -> Self { RoughnessDesc::Isotropic(FloatParam::Value(0.)) }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectDesc {
+    pub shape: Spanned<ShapeDesc>,
+    /// Name of a material from the `[materials]` table. May be omitted for meshes with MTL materials and for
+    /// objects with a `medium` that only bound a medium
+    pub material: Option<Spanned<String>>,
+    #[serde(default)]
+    pub transform: Vec<TransformOp>,
+    /// Turns the object into a diffuse area light. Overrides `Ke` of MTL materials
+    pub emission: Option<Spanned<EmissionDesc>>,
+    /// Opacity in [0; 1], rays pass through where it is below 1. Overrides `map_d` of MTL materials
+    pub alpha: Option<FloatParam>,
+    /// Media inside and outside of the object, rays crossing objects without one keep their current medium
+    pub medium: Option<MediumInterfaceDesc>,
+}
+
+/// Names of media from the `[media]` table, omitted ones are vacuum. Inside is opposite to the surface normals:
+/// inside of spheres and boxes, behind quads when looking along `cross(edges[0], edges[1])`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MediumInterfaceDesc {
+    pub inside: Option<Spanned<String>>,
+    pub outside: Option<Spanned<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ShapeDesc {
+    Sphere {
+        radius: f32,
+    },
+    /// Parallelogram spanned by two edges starting at `corner`
+    Quad {
+        corner: [f32; 3],
+        edges: [[f32; 3]; 2],
+    },
+    /// Axis-aligned box centered at the origin
+    Box {
+        size: [f32; 3],
+    },
+    /// Wavefront OBJ or PLY file, relative to the scene file
+    Mesh {
+        file: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmissionDesc {
+    pub spectrum: Option<Spanned<SpectrumDesc>>,
+    /// Linear sRGB image mapped with the UV of the shape instead of `spectrum`, relative to the scene file
+    pub image: Option<Spanned<String>>,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    /// One-sided lights only emit on the side the surface normals point to, see [MediumInterfaceDesc]
+    #[serde(default = "default_two_sided")]
+    pub two_sided: bool,
+}
+
+/// Coefficients are per unit of scene distance and may be RGB. Emission is absorbed radiance re-emitted by the
+/// medium, e.g. for fire
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MediumDesc {
+    Homogeneous {
+        /// Absorption coefficient
+        sigma_a: SpectrumDesc,
+        /// Scattering coefficient
+        sigma_s: SpectrumDesc,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        /// Henyey-Greenstein asymmetry in (-1; 1), positive values scatter forward
+        #[serde(default)]
+        g: f32,
+        emission: Option<MediumEmissionDesc>,
+    },
+    /// Coefficients scaled by a density grid filling a box, zero outside of it
+    Grid {
+        sigma_a: SpectrumDesc,
+        sigma_s: SpectrumDesc,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        #[serde(default)]
+        g: f32,
+        /// `[min, max]` corners of the box before `transform`
+        bounds: [[f32; 3]; 2],
+        /// Number of voxels of the grids given as values
+        resolution: Option<[usize; 3]>,
+        density: GridDesc,
+        emission: Option<MediumEmissionDesc>,
+        /// Multiplier of `emission` varying over the medium, `density` if omitted
+        emission_scale: Option<GridDesc>,
+        /// Temperatures in Kelvin for blackbody emission, e.g. for fire. Can't be combined with `emission`
+        temperature: Option<GridDesc>,
+        /// Multiplier of the blackbody emission, whose spectrum peaks at one
+        #[serde(default = "default_scale")]
+        temperature_scale: f32,
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+}
+
+/// Same as [EmissionDesc], spans aren't available within tagged enums
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MediumEmissionDesc {
+    pub spectrum: SpectrumDesc,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+/// Values of a [MediumDesc::Grid] grid
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum GridDesc {
+    /// Values at voxel centers, x varies fastest, then y, then z
+    Values(Vec<f32>),
+    /// NRRD volume relative to the scene file, its resolution may differ from `resolution`
+    File(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum LightDesc {
+    Point {
+        spectrum: SpectrumDesc,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        position: [f32; 3],
+    },
+    Spot {
+        spectrum: SpectrumDesc,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        from: [f32; 3],
+        to: [f32; 3],
+        /// Degrees
+        falloff_start: f32,
+        /// Degrees
+        falloff_end: f32,
+    },
+    /// Point light with directional intensity from an IES profile or a square equal-area image. +z is up in light
+    /// space, the horizontal angle 0 of IES profiles points along +x
+    Goniometric {
+        spectrum: SpectrumDesc,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        /// `.ies` profile or image, relative to the scene file
+        file: String,
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+    /// Point light projecting an image, e.g. a slide projector
+    Projection {
+        /// Relative to the scene file
+        file: String,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        from: [f32; 3],
+        to: [f32; 3],
+        /// Degrees, spans the shorter side of the image
+        #[serde(default = "default_projection_fov")]
+        fov: f32,
+    },
+    /// Parallel light from an infinitely far away source, e.g. the sun. With the normalized named illuminants,
+    /// `scale` is the illuminance at a surface facing the light
+    Distant {
+        spectrum: SpectrumDesc,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        /// Direction the light travels in
+        direction: [f32; 3],
+    },
+    /// Infinitely far away sphere emitting uniformly in all directions
+    Infinite {
+        spectrum: SpectrumDesc,
+        #[serde(default = "default_scale")]
+        scale: f32,
+    },
+    /// Infinitely far away sphere textured with an image. Integer images are assumed to be sRGB-encoded, floating
+    /// point ones (EXR, HDR) linear
+    Environment {
+        /// Relative to the scene file
+        file: String,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        #[serde(default)]
+        mapping: MappingDesc,
+        #[serde(default)]
+        transform: Vec<TransformOp>,
+    },
+}
+
+/// See [EnvironmentMapping](crate::light::EnvironmentMapping)
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingDesc {
+    /// Latitude-longitude image, +y is up
+    #[default]
+    Equirectangular,
+    /// Square image in the pbrt-v4 format, +z is up
+    EqualArea,
+}
+
+fn default_scale() -> f32 { 1. }
+
+fn default_projection_fov() -> f32 { 90. }
+
+fn default_two_sided() -> bool { true }
+
+fn default_uv_scale() -> [f32; 2] { [1.; 2] }
+
+fn default_omega() -> f32 { 0.5 }
+
+fn default_octaves() -> u32 { 8 }
+
+fn default_rings() -> f32 { 10. }
+
+fn default_wood_variation() -> f32 { 0.5 }
+
+fn default_focal_distance() -> f32 { 1e6 }
+
+fn default_box_radius() -> [f32; 2] { [0.5; 2] }
+
+fn default_triangle_radius() -> [f32; 2] { [2.; 2] }
+
+fn default_gaussian_radius() -> [f32; 2] { [1.5; 2] }
+
+fn default_sigma() -> f32 { 0.5 }
+
+fn default_mitchell_param() -> f32 { 1. / 3. }
+
+fn default_lanczos_radius() -> [f32; 2] { [4.; 2] }
+
+fn default_tau() -> f32 { 3. }
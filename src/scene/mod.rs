@@ -1,5 +1,8 @@
 pub use scene::*;
 pub mod cameras;
 pub mod film;
+pub mod filters;
+pub mod loader;
+pub mod pbrt;
 pub mod primitives;
 mod scene;
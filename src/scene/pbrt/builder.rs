@@ -0,0 +1,1324 @@
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::PI,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use either::Either;
+use image::Rgb32FImage;
+use log::warn;
+
+use crate::{
+    aggregates::Aabb,
+    light::{
+        ies::load_ies, AreaEmission, DiffuseAreaLight, DistantLight, EnvironmentMapping, GoniometricLight,
+        ImageInfiniteLight, LightEnum, LightSamplerKind, PointLight, ProjectionLight, Spotlight, UniformInfiniteLight,
+    },
+    material::{glass::Glass, matte::Matte, metal::Metal, BumpMap, MaterialsEnum, Roughness},
+    math::{cross, Matrix4, Normal3, Normed, Transform, Transformable},
+    mediums::{
+        GridEmission, GridMedium, GridMediumConfig, HGPhaseFunction, HomogeneousMedium, MediumEnum, MediumInterface,
+        SampledGrid,
+    },
+    point2, point3,
+    scene::{
+        filters::{BoxFilter, FilterEnum, GaussianFilter, LanczosSincFilter, MitchellFilter, TriangleFilter},
+        loader::SceneLoadError,
+        pbrt::{
+            params::{ParamSet, SpectrumType},
+            tokenizer::{ParseError, ParseResult, Token, TokenKind, Tokens},
+            CameraDesc, PbrtScene, Projection,
+        },
+        primitives::{geometric::GeometricPrimitive, simple::SimplePrimitive, PrimitiveEnum},
+    },
+    shapes::{
+        mesh::{ply::load_ply, Triangle, TriangleMesh},
+        quad::Quad,
+        sphere::Sphere,
+        BoundedIntersectable,
+    },
+    spectra::{
+        named::NamedSpectra,
+        rgb::{sRGB, RGB},
+        spectrum_to_photometric, ConstantSpectrum, RGBUnboundedSpectrum, Spectrum, SpectrumEnum,
+    },
+    textures::{
+        checkerboard::{CheckerboardTexture, FloatCheckerboardTexture},
+        constant::{ConstantFloatTexture, ConstantSpectrumTexture},
+        dots::DotsTexture,
+        fbm::{FBmTexture, WrinkledTexture},
+        image::{FloatImageTexture, ImageChannel, RGBSpectrumKind, SpectrumImageTexture},
+        mappings::{
+            CylindricalMapping, PlanarMapping, PointTransformMapping, SphericalMapping, TextureMapping2DEnum, UVMapping,
+        },
+        marble::MarbleTexture,
+        mipmap::{FilterFunction, MIPMap, WrapMode},
+        mix::{FloatMixTexture, FloatScaledTexture},
+        FloatTextureEnum, SpectrumTextureEnum,
+    },
+    utils::{read_linear_rgb, read_raw_rgb},
+    vec2, vec3, Bounds2f, Point3f,
+};
+
+#[derive(Clone)]
+struct AreaLight {
+    emission: AreaEmission,
+    scale: f32,
+    two_sided: bool,
+    /// Total emitted power of each shape, or mesh, overrides `scale`
+    power: Option<f32>,
+}
+
+/// Attributes saved and restored by `AttributeBegin`/`AttributeEnd`
+#[derive(Clone)]
+struct GraphicsState {
+    /// Current transformation matrix, object-to-world
+    ctm: Transform<f32>,
+    /// `None` for the `interface` material of shapes that only separate media
+    material: Option<Arc<MaterialsEnum>>,
+    area_light: Option<AreaLight>,
+    /// Names of the media set by `MediumInterface`, `None` is vacuum
+    inside_medium: Option<String>,
+    outside_medium: Option<String>,
+    reverse_orientation: bool,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Block {
+    Attribute,
+    Transform,
+    Object,
+}
+
+pub(super) struct Importer {
+    state: GraphicsState,
+    stack: Vec<(Block, GraphicsState)>,
+    named_coordinate_systems: HashMap<String, Transform<f32>>,
+    named_materials: HashMap<String, Option<Arc<MaterialsEnum>>>,
+    media: HashMap<String, Arc<MediumEnum>>,
+    /// Outside medium at the `Camera` directive, media may be defined after it
+    camera_medium: Option<String>,
+    textures: HashMap<String, Arc<SpectrumTextureEnum>>,
+    float_textures: HashMap<String, Arc<FloatTextureEnum>>,
+    /// Textures that were declared but are not supported, references to them fall back to default values
+    skipped_textures: HashSet<String>,
+    /// Image textures sharing a file and lookup settings share the pyramid
+    mipmaps: HashMap<(PathBuf, WrapMode, FilterFunction), Arc<MIPMap>>,
+    scene: PbrtScene,
+}
+
+impl Importer {
+    pub fn new() -> Self {
+        Importer {
+            state: GraphicsState {
+                ctm: Transform::id(),
+                material: Some(default_material()),
+                area_light: None,
+                inside_medium: None,
+                outside_medium: None,
+                reverse_orientation: false,
+            },
+            stack: vec![],
+            named_coordinate_systems: HashMap::new(),
+            named_materials: HashMap::new(),
+            media: HashMap::new(),
+            camera_medium: None,
+            textures: HashMap::new(),
+            float_textures: HashMap::new(),
+            skipped_textures: HashSet::new(),
+            mipmaps: HashMap::new(),
+            scene: PbrtScene {
+                resolution: point2!(1280, 720),
+                samples_per_pixel: 16,
+                max_depth: 5,
+                sampler: "zsobol".to_string(),
+                integrator: "volpath".to_string(),
+                light_sampler: LightSamplerKind::default(),
+                output: None,
+                filter: FilterEnum::Gaussian(GaussianFilter::new(vec2!(1.5), 0.5)),
+                camera: CameraDesc {
+                    projection: Projection::Perspective { fov: 90. },
+                    camera_to_world: Transform::id(),
+                    screen_window: None,
+                    lens_radius: 0.,
+                    focal_distance: 1e6,
+                    medium: None,
+                },
+                primitives: vec![],
+                lights: vec![],
+            },
+        }
+    }
+
+    pub fn finish(mut self) -> PbrtScene {
+        if !self.stack.is_empty() {
+            warn!(
+                "{} unclosed attribute block(s) at the end of the scene",
+                self.stack.len()
+            );
+        }
+        if let Some(name) = &self.camera_medium {
+            self.scene.camera.medium = self.media.get(name).cloned();
+            if self.scene.camera.medium.is_none() {
+                warn!("Camera: unknown medium `{name}`, the camera is in vacuum");
+            }
+        }
+        self.scene
+    }
+
+    pub fn import_file(&mut self, path: &Path) -> Result<(), SceneLoadError> { self.import(Tokens::load(path)?) }
+
+    pub fn import_source(&mut self, source: String, path: PathBuf) -> Result<(), SceneLoadError> {
+        self.import(Tokens::new(source, path)?)
+    }
+
+    fn import(&mut self, mut tokens: Tokens) -> Result<(), SceneLoadError> {
+        while let Some(token) = tokens.next() {
+            let TokenKind::Identifier(directive) = token.kind else {
+                return Err(tokens.error(ParseError::new(token.offset, "expected a directive")));
+            };
+            if let "Include" | "Import" = directive.as_str() {
+                let file = tokens.expect_string().map_err(|err| tokens.error(err))?;
+                let path = tokens.path.parent().unwrap_or(Path::new(".")).join(file);
+                self.import_file(&path)?;
+                continue;
+            }
+            self.directive(&directive, token.offset, &mut tokens)
+                .map_err(|err| tokens.error(err))?;
+        }
+        Ok(())
+    }
+
+    fn directive(&mut self, directive: &str, offset: usize, tokens: &mut Tokens) -> ParseResult<()> {
+        match directive {
+            "Identity" => self.state.ctm = Transform::id(),
+            "Translate" => {
+                let [x, y, z] = tokens.expect_numbers()?;
+                self.apply(Transform::translate(vec3!(x, y, z)));
+            }
+            "Scale" => {
+                let [x, y, z] = tokens.expect_numbers()?;
+                self.apply(Transform::scale(x, y, z));
+            }
+            "Rotate" => {
+                let [angle, x, y, z] = tokens.expect_numbers()?;
+                let axis = vec3!(x, y, z);
+                if axis.len_squared() == 0. {
+                    return Err(ParseError::new(offset, "rotation axis must be non-zero"));
+                }
+                self.apply(Transform::rotate_arbitrary_axis(axis, angle.to_radians()));
+            }
+            "LookAt" => {
+                let [ex, ey, ez, lx, ly, lz, ux, uy, uz] = tokens.expect_numbers()?;
+                let (eye, look, up) = (point3!(ex, ey, ez), point3!(lx, ly, lz), vec3!(ux, uy, uz));
+                if cross(&up, &(look - eye)).len_squared() == 0. {
+                    return Err(ParseError::new(
+                        offset,
+                        "LookAt: `up` must not be parallel to the view direction",
+                    ));
+                }
+                self.apply(Transform::look_at(eye, look, up));
+            }
+            "Transform" | "ConcatTransform" => {
+                let values = tokens.expect_number_list()?;
+                let transform = matrix_transform(&values).ok_or(ParseError::new(
+                    offset,
+                    format!("{directive}: expected 16 values of an invertible matrix"),
+                ))?;
+                if directive == "Transform" {
+                    self.state.ctm = transform;
+                } else {
+                    self.apply(transform);
+                }
+            }
+            "CoordinateSystem" => {
+                let name = tokens.expect_string()?;
+                self.named_coordinate_systems.insert(name, self.state.ctm);
+            }
+            "CoordSysTransform" => {
+                let name = tokens.expect_string()?;
+                match self.named_coordinate_systems.get(&name) {
+                    Some(transform) => self.state.ctm = *transform,
+                    None => warn!("{}: unknown coordinate system `{name}`", tokens.location(offset)),
+                }
+            }
+            "AttributeBegin" => self.stack.push((Block::Attribute, self.state.clone())),
+            "TransformBegin" => self.stack.push((Block::Transform, self.state.clone())),
+            "ObjectBegin" => {
+                tokens.expect_string()?;
+                warn!(
+                    "{}: object instancing is not supported, shapes of the object are skipped",
+                    tokens.location(offset)
+                );
+                self.stack.push((Block::Object, self.state.clone()));
+            }
+            "AttributeEnd" | "TransformEnd" | "ObjectEnd" => {
+                let expected = match directive {
+                    "AttributeEnd" => Block::Attribute,
+                    "TransformEnd" => Block::Transform,
+                    _ => Block::Object,
+                };
+                match self.stack.pop() {
+                    Some((block, state)) if block == expected => match block {
+                        Block::Transform => self.state.ctm = state.ctm,
+                        _ => self.state = state,
+                    },
+                    _ => return Err(ParseError::new(offset, format!("unmatched {directive}"))),
+                }
+            }
+            "WorldBegin" => {
+                self.state.ctm = Transform::id();
+                self.named_coordinate_systems
+                    .insert("world".to_string(), Transform::id());
+            }
+            "ActiveTransform" => {
+                // Takes a bare word argument
+                tokens.next();
+                warn!("{}: {directive} is not supported, ignoring", tokens.location(offset));
+            }
+            // Normals are always flipped towards the incoming ray, the orientation only tells the inside from the
+            // outside for media
+            "ReverseOrientation" => self.state.reverse_orientation = !self.state.reverse_orientation,
+            "MediumInterface" => {
+                // Either both names or a single one for both sides, empty names are vacuum
+                let inside = tokens.expect_string()?;
+                let outside = match tokens.peek() {
+                    Some(Token {
+                        kind: TokenKind::Str(_),
+                        ..
+                    }) => tokens.expect_string()?,
+                    _ => inside.clone(),
+                };
+                let medium = |name: String| (!name.is_empty()).then_some(name);
+                self.state.inside_medium = medium(inside);
+                self.state.outside_medium = medium(outside);
+            }
+            "MakeNamedMedium" => {
+                let name = tokens.expect_string()?;
+                let params = ParamSet::parse(tokens)?;
+                let ty = params
+                    .string("type")?
+                    .ok_or(ParseError::new(offset, "MakeNamedMedium: missing `string type`"))?
+                    .to_string();
+                let context = format!("MakeNamedMedium \"{name}\"");
+                match self.medium(&ty, &params, offset)? {
+                    Some(medium) => {
+                        params.warn_unused(&context, tokens);
+                        self.media.insert(name, medium);
+                    }
+                    None => warn!(
+                        "{}: {context}: medium type \"{ty}\" is not supported, ignoring",
+                        tokens.location(offset)
+                    ),
+                }
+            }
+            "Camera" | "Film" | "PixelFilter" | "Sampler" | "Integrator" | "Shape" | "Material" | "LightSource"
+            | "AreaLightSource" => {
+                let ty = tokens.expect_string()?;
+                let params = ParamSet::parse(tokens)?;
+                let context = format!("{directive} \"{ty}\"");
+                let supported = match directive {
+                    "Camera" => self.camera(&ty, &params)?,
+                    "Film" => self.film(&ty, &params, tokens, offset)?,
+                    "PixelFilter" => self.pixel_filter(&ty, &params)?,
+                    "Sampler" => self.sampler(&ty, &params)?,
+                    "Integrator" => self.integrator(&ty, &params)?,
+                    "Shape" => self.shape(&ty, &params, offset, tokens)?,
+                    "Material" => {
+                        self.state.material = self.material(&ty, &params, offset, tokens)?;
+                        true
+                    }
+                    "LightSource" => self.light(&ty, &params, offset, tokens)?,
+                    _ => self.area_light(&ty, &params, offset, tokens)?,
+                };
+                if supported {
+                    params.warn_unused(&context, tokens);
+                } else {
+                    warn!("{}: {context} is not supported, ignoring", tokens.location(offset));
+                }
+            }
+            "MakeNamedMaterial" => {
+                let name = tokens.expect_string()?;
+                let params = ParamSet::parse(tokens)?;
+                let ty = params
+                    .string("type")?
+                    .ok_or(ParseError::new(offset, "MakeNamedMaterial: missing `string type`"))?
+                    .to_string();
+                let material = self.material(&ty, &params, offset, tokens)?;
+                params.warn_unused(&format!("MakeNamedMaterial \"{name}\""), tokens);
+                self.named_materials.insert(name, material);
+            }
+            "NamedMaterial" => {
+                let name = tokens.expect_string()?;
+                let material = self
+                    .named_materials
+                    .get(&name)
+                    .ok_or(ParseError::new(offset, format!("unknown material `{name}`")))?;
+                self.state.material.clone_from(material);
+            }
+            "Texture" => {
+                let name = tokens.expect_string()?;
+                let ty = tokens.expect_string()?;
+                let class = tokens.expect_string()?;
+                let params = ParamSet::parse(tokens)?;
+                let supported = match ty.as_str() {
+                    "spectrum" => self
+                        .spectrum_texture_definition(&class, &params, offset, tokens)?
+                        .map(|texture| self.textures.insert(name.clone(), texture))
+                        .is_some(),
+                    "float" => self
+                        .float_texture_definition(&class, &params, offset, tokens)?
+                        .map(|texture| self.float_textures.insert(name.clone(), texture))
+                        .is_some(),
+                    _ => false,
+                };
+                if supported {
+                    params.warn_unused(&format!("Texture \"{name}\""), tokens);
+                } else {
+                    warn!(
+                        "{}: {ty} texture \"{class}\" is not supported, `{name}` will be replaced with a default value",
+                        tokens.location(offset)
+                    );
+                    self.skipped_textures.insert(name);
+                }
+            }
+            _ => {
+                tokens.skip_arguments();
+                warn!("{}: {directive} is not supported, ignoring", tokens.location(offset));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a transform in object space, i.e. before the current one
+    fn apply(&mut self, transform: Transform<f32>) { self.state.ctm = Transform::compose(transform, self.state.ctm); }
+
+    fn camera(&mut self, ty: &str, params: &ParamSet) -> ParseResult<bool> {
+        let projection = match ty {
+            "perspective" => Projection::Perspective {
+                fov: params.float("fov", 90.)?,
+            },
+            "orthographic" => Projection::Orthographic,
+            _ => return Ok(false),
+        };
+        let screen_window = match params.floats("screenwindow")?.as_deref() {
+            Some(&[x_min, x_max, y_min, y_max]) => {
+                Some(Bounds2f::from_points(point2!(x_min, y_min), point2!(x_max, y_max)))
+            }
+            Some(_) => {
+                let offset = params.get("screenwindow").unwrap().offset;
+                return Err(ParseError::new(offset, "`screenwindow` must have 4 values"));
+            }
+            None => None,
+        };
+        let camera_to_world = self.state.ctm.invert();
+        self.named_coordinate_systems
+            .insert("camera".to_string(), camera_to_world);
+        self.scene.camera = CameraDesc {
+            projection,
+            camera_to_world,
+            screen_window,
+            lens_radius: params.float("lensradius", 0.)?,
+            focal_distance: params.float("focaldistance", 1e6)?,
+            medium: None,
+        };
+        self.camera_medium.clone_from(&self.state.outside_medium);
+        Ok(true)
+    }
+
+    fn film(&mut self, ty: &str, params: &ParamSet, tokens: &Tokens, offset: usize) -> ParseResult<bool> {
+        if ty != "rgb" {
+            warn!(
+                "{}: Film \"{ty}\" is not supported, rendering to an RGB film instead",
+                tokens.location(offset)
+            );
+        }
+        let x = params.int("xresolution", 1280)?;
+        let y = params.int("yresolution", 720)?;
+        if x <= 0 || y <= 0 {
+            let offset = params.get("xresolution").or(params.get("yresolution")).unwrap().offset;
+            return Err(ParseError::new(offset, "film resolution must be positive"));
+        }
+        self.scene.resolution = point2!(x as usize, y as usize);
+        self.scene.output = params.string("filename")?.map(PathBuf::from);
+        Ok(true)
+    }
+
+    fn pixel_filter(&mut self, ty: &str, params: &ParamSet) -> ParseResult<bool> {
+        let default_radius = match ty {
+            "box" => 0.5,
+            "gaussian" => 1.5,
+            "mitchell" | "triangle" => 2.,
+            "sinc" => 4.,
+            _ => return Ok(false),
+        };
+        let radius = vec2!(
+            params.float("xradius", default_radius)?,
+            params.float("yradius", default_radius)?
+        );
+        if radius.x <= 0. || radius.y <= 0. {
+            let offset = params.get("xradius").or(params.get("yradius")).unwrap().offset;
+            return Err(ParseError::new(offset, "filter radius must be positive"));
+        }
+        self.scene.filter = match ty {
+            "box" => FilterEnum::Box(BoxFilter::new(radius)),
+            "gaussian" => FilterEnum::Gaussian(GaussianFilter::new(radius, params.float("sigma", 0.5)?)),
+            "mitchell" => FilterEnum::Mitchell(MitchellFilter::new(
+                radius,
+                params.float("B", 1. / 3.)?,
+                params.float("C", 1. / 3.)?,
+            )),
+            "triangle" => FilterEnum::Triangle(TriangleFilter::new(radius)),
+            _ => FilterEnum::LanczosSinc(LanczosSincFilter::new(radius, params.float("tau", 3.)?)),
+        };
+        Ok(true)
+    }
+
+    fn sampler(&mut self, ty: &str, params: &ParamSet) -> ParseResult<bool> {
+        self.scene.sampler = ty.to_string();
+        self.scene.samples_per_pixel = params.int("pixelsamples", 16)?.max(1) as u32;
+        Ok(true)
+    }
+
+    fn integrator(&mut self, ty: &str, params: &ParamSet) -> ParseResult<bool> {
+        self.scene.integrator = ty.to_string();
+        self.scene.max_depth = params.int("maxdepth", 5)?.max(0) as u32;
+        if let Some(light_sampler) = params.string("lightsampler")? {
+            self.scene.light_sampler = light_sampler.parse().map_err(|_| {
+                let offset = params.get("lightsampler").unwrap().offset;
+                ParseError::new(offset, format!("unknown light sampler `{light_sampler}`"))
+            })?;
+        }
+        Ok(true)
+    }
+
+    /// `None` for the `interface` material
+    fn material(
+        &self,
+        ty: &str,
+        params: &ParamSet,
+        offset: usize,
+        tokens: &Tokens,
+    ) -> ParseResult<Option<Arc<MaterialsEnum>>> {
+        let material = match ty {
+            "interface" => return Ok(None),
+            "diffuse" => MaterialsEnum::Matte(Matte {
+                reflectance: self.spectrum_texture(params, "reflectance", 0.5, tokens)?,
+                bump: self.bump(params, tokens)?,
+            }),
+            "coateddiffuse" => {
+                warn!(
+                    "{}: coateddiffuse is approximated with diffuse",
+                    tokens.location(offset)
+                );
+                MaterialsEnum::Matte(Matte {
+                    reflectance: self.spectrum_texture(params, "reflectance", 0.5, tokens)?,
+                    bump: self.bump(params, tokens)?,
+                })
+            }
+            "conductor" => {
+                let reflectance = if params.get("reflectance").is_some() {
+                    Either::Left(self.spectrum_texture(params, "reflectance", 1., tokens)?)
+                } else {
+                    // Copper by default, like PBRT
+                    let eta = params
+                        .spectrum("eta", SpectrumType::Unbounded)?
+                        .unwrap_or(NamedSpectra::CuEta.get());
+                    let k = params
+                        .spectrum("k", SpectrumType::Unbounded)?
+                        .unwrap_or(NamedSpectra::CuK.get());
+                    let constant = |value| Arc::new(ConstantSpectrumTexture { value }.into());
+                    Either::Right((constant(eta), constant(k)))
+                };
+                MaterialsEnum::Metal(Metal {
+                    reflectance,
+                    roughness: self.roughness(params, tokens)?,
+                    bump: self.bump(params, tokens)?,
+                })
+            }
+            "dielectric" => {
+                let ior = if params.texture("eta")?.is_some() {
+                    Either::Right(self.float_texture(params, "eta", 1.5, tokens)?)
+                } else {
+                    let ior = params
+                        .spectrum("eta", SpectrumType::Unbounded)?
+                        .unwrap_or(Arc::new(ConstantSpectrum::new(1.5).into()));
+                    Either::Left((*ior).clone())
+                };
+                MaterialsEnum::Glass(Glass {
+                    ior,
+                    roughness: self.roughness(params, tokens)?,
+                    spectrum: constant_texture(1.),
+                    bump: self.bump(params, tokens)?,
+                })
+            }
+            _ => {
+                warn!(
+                    "{}: material \"{ty}\" is not supported, using default diffuse",
+                    tokens.location(offset)
+                );
+                return Ok(Some(default_material()));
+            }
+        };
+        Ok(Some(Arc::new(material)))
+    }
+
+    /// `None` if the texture class is not supported
+    fn spectrum_texture_definition(
+        &mut self,
+        class: &str,
+        params: &ParamSet,
+        offset: usize,
+        tokens: &Tokens,
+    ) -> ParseResult<Option<Arc<SpectrumTextureEnum>>> {
+        let texture = match class {
+            "constant" => {
+                let value = params
+                    .spectrum("value", SpectrumType::Albedo)?
+                    .unwrap_or(Arc::new(ConstantSpectrum::new(1.).into()));
+                ConstantSpectrumTexture { value }.into()
+            }
+            "imagemap" => SpectrumImageTexture {
+                mapping: texture_mapping(params, self.state.ctm)?,
+                mipmap: self.mipmap(params, offset, tokens)?,
+                scale: params.float("scale", 1.)?,
+                invert: params.bool("invert", false)?,
+                kind: RGBSpectrumKind::Albedo,
+            }
+            .into(),
+            "checkerboard" => CheckerboardTexture {
+                mapping: self.checkerboard_mapping(params)?,
+                tex: [
+                    self.spectrum_texture(params, "tex1", 1., tokens)?,
+                    self.spectrum_texture(params, "tex2", 0., tokens)?,
+                ],
+            }
+            .into(),
+            "dots" => DotsTexture {
+                mapping: texture_mapping(params, self.state.ctm)?,
+                inside: self.spectrum_texture(params, "inside", 1., tokens)?,
+                outside: self.spectrum_texture(params, "outside", 0., tokens)?,
+            }
+            .into(),
+            "marble" => MarbleTexture {
+                mapping: PointTransformMapping::new(self.state.ctm),
+                scale: params.float("scale", 1.)?,
+                variation: params.float("variation", 0.2)?,
+                omega: params.float("roughness", 0.5)?,
+                octaves: params.int("octaves", 8)?.max(0) as u32,
+            }
+            .into(),
+            _ => return Ok(None),
+        };
+        Ok(Some(Arc::new(texture)))
+    }
+
+    /// `None` if the texture class is not supported
+    fn float_texture_definition(
+        &mut self,
+        class: &str,
+        params: &ParamSet,
+        offset: usize,
+        tokens: &Tokens,
+    ) -> ParseResult<Option<Arc<FloatTextureEnum>>> {
+        let texture = match class {
+            "constant" => ConstantFloatTexture {
+                value: params.float("value", 1.)?,
+            }
+            .into(),
+            "imagemap" => FloatImageTexture {
+                mapping: texture_mapping(params, self.state.ctm)?,
+                mipmap: self.mipmap(params, offset, tokens)?,
+                channel: ImageChannel::Average,
+                scale: params.float("scale", 1.)?,
+                invert: params.bool("invert", false)?,
+            }
+            .into(),
+            "checkerboard" => FloatCheckerboardTexture {
+                mapping: self.checkerboard_mapping(params)?,
+                tex: [
+                    self.float_texture(params, "tex1", 1., tokens)?,
+                    self.float_texture(params, "tex2", 0., tokens)?,
+                ],
+            }
+            .into(),
+            "fbm" => FBmTexture {
+                mapping: PointTransformMapping::new(self.state.ctm),
+                omega: params.float("roughness", 0.5)?,
+                octaves: params.int("octaves", 8)?.max(0) as u32,
+            }
+            .into(),
+            "wrinkled" => WrinkledTexture {
+                mapping: PointTransformMapping::new(self.state.ctm),
+                omega: params.float("roughness", 0.5)?,
+                octaves: params.int("octaves", 8)?.max(0) as u32,
+            }
+            .into(),
+            "scale" => FloatScaledTexture {
+                tex: self.float_texture(params, "tex", 1., tokens)?,
+                scale: self.float_texture(params, "scale", 1., tokens)?,
+            }
+            .into(),
+            "mix" => FloatMixTexture {
+                tex: [
+                    self.float_texture(params, "tex1", 0., tokens)?,
+                    self.float_texture(params, "tex2", 1., tokens)?,
+                ],
+                amount: self.float_texture(params, "amount", 0.5, tokens)?,
+            }
+            .into(),
+            _ => return Ok(None),
+        };
+        Ok(Some(Arc::new(texture)))
+    }
+
+    fn checkerboard_mapping(
+        &self,
+        params: &ParamSet,
+    ) -> ParseResult<Either<TextureMapping2DEnum, PointTransformMapping>> {
+        match params.int("dimension", 2)? {
+            2 => Ok(Either::Left(texture_mapping(params, self.state.ctm)?)),
+            3 => Ok(Either::Right(PointTransformMapping::new(self.state.ctm))),
+            _ => {
+                let offset = params.get("dimension").unwrap().offset;
+                Err(ParseError::new(offset, "checkerboard dimension must be 2 or 3"))
+            }
+        }
+    }
+
+    fn mipmap(&mut self, params: &ParamSet, offset: usize, tokens: &Tokens) -> ParseResult<Arc<MIPMap>> {
+        let filter = match params.string("filter")?.unwrap_or("bilinear") {
+            "point" => FilterFunction::Point,
+            "bilinear" => FilterFunction::Bilinear,
+            // EWA is approximated by the closest supported filter
+            "trilinear" | "ewa" | "EWA" => FilterFunction::Trilinear,
+            filter => {
+                let offset = params.get("filter").unwrap().offset;
+                return Err(ParseError::new(offset, format!("unknown filter `{filter}`")));
+            }
+        };
+        let wrap = match params.string("wrap")?.unwrap_or("repeat") {
+            "repeat" => WrapMode::Repeat,
+            "clamp" => WrapMode::Clamp,
+            "black" => WrapMode::Black,
+            wrap => {
+                let offset = params.get("wrap").unwrap().offset;
+                return Err(ParseError::new(offset, format!("unknown wrap mode `{wrap}`")));
+            }
+        };
+        let offset = params.get("filename").map_or(offset, |x| x.offset);
+        let filename = params
+            .string("filename")?
+            .ok_or(ParseError::new(offset, "imagemap: missing `string filename`"))?;
+        let path = tokens.path.parent().unwrap_or(Path::new(".")).join(filename);
+        if let Some(mipmap) = self.mipmaps.get(&(path.clone(), wrap, filter)) {
+            return Ok(mipmap.clone());
+        }
+        let image = read_linear_rgb(&path)
+            .map_err(|err| ParseError::new(offset, format!("failed to load {}: {err}", path.display())))?;
+        let mipmap = Arc::new(MIPMap::new(image, wrap, filter));
+        self.mipmaps.insert((path, wrap, filter), mipmap.clone());
+        Ok(mipmap)
+    }
+
+    /// Parameter that is either a `texture` reference or a spectrum
+    fn spectrum_texture(
+        &self,
+        params: &ParamSet,
+        name: &str,
+        default: f32,
+        tokens: &Tokens,
+    ) -> ParseResult<Arc<SpectrumTextureEnum>> {
+        if let Some(texture) = params.texture(name)? {
+            let offset = params.get(name).unwrap().offset;
+            if let Some(texture) = self.textures.get(texture) {
+                return Ok(texture.clone());
+            }
+            if !self.skipped_textures.contains(texture) {
+                return Err(ParseError::new(offset, format!("unknown texture `{texture}`")));
+            }
+            return Ok(constant_texture(default));
+        }
+        match params.spectrum(name, SpectrumType::Albedo)? {
+            Some(value) => Ok(Arc::new(ConstantSpectrumTexture { value }.into())),
+            None => Ok(constant_texture(default)),
+        }
+    }
+
+    /// Parameter that is either a `texture` reference or a float
+    fn float_texture(
+        &self,
+        params: &ParamSet,
+        name: &str,
+        default: f32,
+        tokens: &Tokens,
+    ) -> ParseResult<Arc<FloatTextureEnum>> {
+        let constant = |value| Arc::new(ConstantFloatTexture { value }.into());
+        if let Some(texture) = params.texture(name)? {
+            let offset = params.get(name).unwrap().offset;
+            if let Some(texture) = self.float_textures.get(texture) {
+                return Ok(texture.clone());
+            }
+            if !self.skipped_textures.contains(texture) {
+                return Err(ParseError::new(offset, format!("unknown float texture `{texture}`")));
+            }
+            return Ok(constant(default));
+        }
+        Ok(constant(params.float(name, default)?))
+    }
+
+    /// `normalmap` takes precedence over `displacement`, like in PBRT
+    fn bump(&self, params: &ParamSet, tokens: &Tokens) -> ParseResult<Option<BumpMap>> {
+        if let Some(filename) = params.string("normalmap")? {
+            let offset = params.get("normalmap").unwrap().offset;
+            let path = tokens.path.parent().unwrap_or(Path::new(".")).join(filename);
+            let image = read_raw_rgb(&path)
+                .map_err(|err| ParseError::new(offset, format!("failed to load {}: {err}", path.display())))?;
+            let map = MIPMap::new(image, WrapMode::Repeat, FilterFunction::Bilinear);
+            return Ok(Some(BumpMap::Normal(Arc::new(map))));
+        }
+        if params.get("displacement").is_some() {
+            let displacement = self.float_texture(params, "displacement", 0., tokens)?;
+            return Ok(Some(BumpMap::Displacement(displacement)));
+        }
+        Ok(None)
+    }
+
+    /// `roughness` sets both directions, `uroughness` and `vroughness` override it
+    fn roughness(&self, params: &ParamSet, tokens: &Tokens) -> ParseResult<Roughness> {
+        let roughness = self.float_texture(params, "roughness", 0., tokens)?;
+        let directional = |name| match params.get(name) {
+            Some(_) => self.float_texture(params, name, 0., tokens),
+            None => Ok(roughness.clone()),
+        };
+        Ok(Roughness {
+            u: directional("uroughness")?,
+            v: directional("vroughness")?,
+            remap: params.bool("remaproughness", true)?,
+        })
+    }
+
+    fn light(&mut self, ty: &str, params: &ParamSet, offset: usize, tokens: &Tokens) -> ParseResult<bool> {
+        if ty == "infinite" {
+            let light = self.infinite_light(params, tokens)?;
+            self.scene.lights.push(Arc::new(light));
+            return Ok(true);
+        }
+        if ty == "distant" {
+            let light = self.distant_light(params)?;
+            self.scene.lights.push(Arc::new(light));
+            return Ok(true);
+        }
+        let spectrum = params
+            .spectrum("I", SpectrumType::Illuminant)?
+            .unwrap_or(NamedSpectra::IlluminantD65.get());
+        // Like PBRT, spectra are normalized so that `scale` sets the luminance regardless of their shape
+        let mut scale = params.float("scale", 1.)? / spectrum_to_photometric(&spectrum);
+        let power = params.float("power", -1.)?;
+        let from = params.point3("from", point3!(0., 0., 0.))?;
+        let light = match ty {
+            "point" => {
+                if power > 0. {
+                    scale *= power / (4. * PI);
+                }
+                LightEnum::Point(PointLight::new(
+                    spectrum,
+                    scale,
+                    Transform::compose(Transform::translate(*from), self.state.ctm),
+                ))
+            }
+            "spot" => {
+                let to = params.point3("to", point3!(0., 0., 1.))?;
+                let cone_angle = params.float("coneangle", 30.)?;
+                let cone_delta = params.float("conedelta", 5.)?;
+                let dir = to - from;
+                if dir.len_squared() == 0. {
+                    let offset = params.get("to").unwrap().offset;
+                    return Err(ParseError::new(offset, "`from` and `to` must differ"));
+                }
+                // Spotlights shine along +z in light space
+                let light_to_world = Transform::compose(
+                    Transform::rotate_from_to(&vec3!(0., 0., 1.), &dir.to_unit()).then_translate(*from),
+                    self.state.ctm,
+                );
+                let falloff_start = (cone_angle - cone_delta).max(0.);
+                if power > 0. {
+                    let cos_start = falloff_start.to_radians().cos();
+                    let cos_end = cone_angle.to_radians().cos();
+                    scale *= power / (2. * PI * ((1. - cos_start) + (cos_start - cos_end) / 2.));
+                }
+                LightEnum::Spot(Spotlight::new(
+                    spectrum,
+                    scale,
+                    light_to_world,
+                    falloff_start,
+                    cone_angle,
+                ))
+            }
+            "goniometric" => {
+                let image = self.light_image(params, offset, tokens)?;
+                // Like PBRT, +y is up in light space while the equal-area images have +z up
+                let swap_yz =
+                    matrix_transform(&[1., 0., 0., 0., 0., 0., 1., 0., 0., 1., 0., 0., 0., 0., 0., 1.]).unwrap();
+                let light_to_world = Transform::compose(swap_yz, self.state.ctm);
+                let new = |scale| {
+                    GoniometricLight::new(spectrum.clone(), scale, &image, light_to_world).map_err(|err| {
+                        let offset = params.get("filename").unwrap().offset;
+                        ParseError::new(offset, format!("goniometric image: {err}"))
+                    })
+                };
+                if power > 0. {
+                    scale *= power / (4. * PI * new(1.)?.average_intensity());
+                }
+                LightEnum::Goniometric(new(scale)?)
+            }
+            "projection" => {
+                let image = self.light_image(params, offset, tokens)?;
+                let fov = params.float("fov", 90.)?;
+                if power > 0. {
+                    let light = ProjectionLight::new(image.clone(), 1., fov, self.state.ctm);
+                    scale *= power / light.solid_angle_integral();
+                }
+                LightEnum::Projection(ProjectionLight::new(image, scale, fov, self.state.ctm))
+            }
+            _ => return Ok(false),
+        };
+        self.scene.lights.push(Arc::new(light));
+        Ok(true)
+    }
+
+    fn distant_light(&self, params: &ParamSet) -> ParseResult<LightEnum> {
+        let spectrum = params
+            .spectrum("L", SpectrumType::Illuminant)?
+            .unwrap_or(NamedSpectra::IlluminantD65.get());
+        let mut scale = params.float("scale", 1.)? / spectrum_to_photometric(&spectrum);
+        // Illuminance at a surface facing the light
+        let illuminance = params.float("illuminance", -1.)?;
+        if illuminance > 0. {
+            scale *= illuminance;
+        }
+        let from = params.point3("from", point3!(0., 0., 0.))?;
+        let to = params.point3("to", point3!(0., 0., 1.))?;
+        let dir = from - to;
+        if dir.len_squared() == 0. {
+            let offset = params.get("to").or(params.get("from")).unwrap().offset;
+            return Err(ParseError::new(offset, "`from` and `to` must differ"));
+        }
+        // Light comes from +z in light space
+        let light_to_world = Transform::compose(
+            Transform::rotate_from_to(&vec3!(0., 0., 1.), &dir.to_unit()),
+            self.state.ctm,
+        );
+        Ok(LightEnum::Distant(DistantLight::new(spectrum, scale, light_to_world)))
+    }
+
+    /// Image of a goniometric or projection light. IES profiles are resampled into an equal-area image
+    fn light_image(&self, params: &ParamSet, offset: usize, tokens: &Tokens) -> ParseResult<Rgb32FImage> {
+        let Some(filename) = params.string("filename")? else {
+            return Err(ParseError::new(offset, "missing `string filename`"));
+        };
+        let offset = params.get("filename").unwrap().offset;
+        let path = tokens.path.parent().unwrap_or(Path::new(".")).join(filename);
+        if path.extension().is_some_and(|x| x.eq_ignore_ascii_case("ies")) {
+            load_ies(&path).map_err(|err| ParseError::new(offset, err.to_string()))
+        } else {
+            read_linear_rgb(&path)
+                .map_err(|err| ParseError::new(offset, format!("failed to load {}: {err}", path.display())))
+        }
+    }
+
+    fn infinite_light(&self, params: &ParamSet, tokens: &Tokens) -> ParseResult<LightEnum> {
+        let scale = params.float("scale", 1.)?;
+        let spectrum = params.spectrum("L", SpectrumType::Illuminant)?;
+        let Some(filename) = params.string("filename")? else {
+            let spectrum = spectrum.unwrap_or(NamedSpectra::IlluminantD65.get());
+            let mut scale = scale / spectrum_to_photometric(&spectrum);
+            // Illuminance at an unoccluded surface
+            let illuminance = params.float("illuminance", -1.)?;
+            if illuminance > 0. {
+                scale *= illuminance / PI;
+            }
+            return Ok(LightEnum::UniformInfinite(UniformInfiniteLight::new(spectrum, scale)));
+        };
+        let offset = params.get("filename").unwrap().offset;
+        if spectrum.is_some() {
+            return Err(ParseError::new(offset, "can't have both `L` and `filename`"));
+        }
+        let path = tokens.path.parent().unwrap_or(Path::new(".")).join(filename);
+        let image = read_linear_rgb(&path)
+            .map_err(|err| ParseError::new(offset, format!("failed to load {}: {err}", path.display())))?;
+        let light = ImageInfiniteLight::new(image, EnvironmentMapping::EqualArea, scale, self.state.ctm)
+            .map_err(|err| ParseError::new(offset, err.to_string()))?;
+        Ok(LightEnum::ImageInfinite(light))
+    }
+
+    fn area_light(&mut self, ty: &str, params: &ParamSet, offset: usize, tokens: &Tokens) -> ParseResult<bool> {
+        if ty != "diffuse" {
+            return Ok(false);
+        }
+        let spectrum = params.spectrum("L", SpectrumType::Illuminant)?;
+        let two_sided = params.bool("twosided", false)?;
+        if !two_sided && self.state.reverse_orientation {
+            warn!(
+                "{}: `ReverseOrientation` is ignored, one-sided area lights emit on the side their shapes face",
+                tokens.location(offset)
+            );
+        }
+        // Images are in the sRGB color space, whose illuminant is D65
+        let illuminant = spectrum.clone().unwrap_or(NamedSpectra::IlluminantD65.get());
+        let scale = params.float("scale", 1.)? / spectrum_to_photometric(&illuminant);
+        let emission = match params.get("filename") {
+            Some(filename) => {
+                if spectrum.is_some() {
+                    return Err(ParseError::new(filename.offset, "can't have both `L` and `filename`"));
+                }
+                AreaEmission::image(self.light_image(params, offset, tokens)?)
+            }
+            None => AreaEmission::Spectrum(illuminant),
+        };
+        let power = params.float("power", -1.)?;
+        self.state.area_light = Some(AreaLight {
+            emission,
+            scale,
+            two_sided,
+            power: (power > 0.).then_some(power),
+        });
+        Ok(true)
+    }
+
+    fn shape(&mut self, ty: &str, params: &ParamSet, offset: usize, tokens: &Tokens) -> ParseResult<bool> {
+        if self.stack.iter().any(|(block, _)| *block == Block::Object) {
+            return Ok(true);
+        }
+        let ctm = self.state.ctm;
+        let shapes: Vec<Arc<dyn BoundedIntersectable>> = match ty {
+            "sphere" => vec![Arc::new(Sphere::new(params.float("radius", 1.)?, ctm))],
+            "trianglemesh" => triangle_mesh(params, offset, ctm)?
+                .into_iter()
+                .map(|x| Arc::new(x) as _)
+                .collect(),
+            "plymesh" => {
+                let filename = params
+                    .string("filename")?
+                    .ok_or(ParseError::new(offset, "plymesh: missing `string filename`"))?;
+                let path = tokens.path.parent().unwrap_or(Path::new(".")).join(filename);
+                load_ply(path, ctm)
+                    .map_err(|err| ParseError::new(offset, err.to_string()))?
+                    .into_triangles()
+                    .into_iter()
+                    .map(|x| Arc::new(x) as _)
+                    .collect()
+            }
+            "bilinearmesh" => match bilinear_quad(params, offset, ctm)? {
+                Some(quad) => vec![Arc::new(quad)],
+                None => {
+                    warn!(
+                        "{}: only single planar parallelogram bilinear patches are supported, skipping",
+                        tokens.location(offset)
+                    );
+                    return Ok(true);
+                }
+            },
+            _ => return Ok(false),
+        };
+
+        let material = self.state.material.clone();
+        let medium_interface = self.medium_interface(offset, tokens);
+        let alpha = match params.get("alpha") {
+            Some(_) => Some(self.float_texture(params, "alpha", 1., tokens)?),
+            None => None,
+        };
+        let area_light = self.state.area_light.clone();
+        // Meshes emit the power in total
+        let total_area: f32 = shapes.iter().map(|shape| shape.area()).sum();
+        for shape in shapes {
+            let primitive = match &area_light {
+                Some(area_light) => {
+                    // Emission over the whole shape, on one or both sides. Every triangle of a mesh is a light
+                    let scale = match area_light.power {
+                        Some(power) => {
+                            let sides = if area_light.two_sided { 2. } else { 1. };
+                            // Images emit their average color on average
+                            let average = match &area_light.emission {
+                                AreaEmission::Spectrum(_) => 1.,
+                                AreaEmission::Image { average, .. } => average.average(),
+                            };
+                            area_light.scale * power / (sides * PI * total_area * average)
+                        }
+                        None => area_light.scale,
+                    };
+                    let light = Arc::new(LightEnum::DiffuseArea(DiffuseAreaLight::new(
+                        area_light.emission.clone(),
+                        scale,
+                        area_light.two_sided,
+                        Transform::id(),
+                        shape.clone(),
+                    )));
+                    self.scene.lights.push(light.clone());
+                    PrimitiveEnum::Geometric(GeometricPrimitive {
+                        shape,
+                        material: material.clone(),
+                        light: Some(light),
+                        medium_interface: medium_interface.clone(),
+                        alpha: alpha.clone(),
+                    })
+                }
+                None => match (&material, &alpha, &medium_interface) {
+                    (Some(material), None, None) => PrimitiveEnum::Simple(SimplePrimitive {
+                        shape,
+                        material: material.clone(),
+                    }),
+                    _ => PrimitiveEnum::Geometric(GeometricPrimitive {
+                        shape,
+                        material: material.clone(),
+                        light: None,
+                        medium_interface: medium_interface.clone(),
+                        alpha: alpha.clone(),
+                    }),
+                },
+            };
+            self.scene.primitives.push(Arc::new(primitive));
+        }
+        Ok(true)
+    }
+}
+
+impl Importer {
+    /// `None` if the medium type is not supported
+    fn medium(&self, ty: &str, params: &ParamSet, offset: usize) -> ParseResult<Option<Arc<MediumEnum>>> {
+        // Defaults are the ones of PBRT, coefficients of skin in mm^-1
+        let coefficient = |name, [r, g, b]: [f32; 3]| -> ParseResult<Arc<SpectrumEnum>> {
+            Ok(params
+                .spectrum(name, SpectrumType::Unbounded)?
+                .unwrap_or(Arc::new(RGBUnboundedSpectrum::new(&sRGB, RGB::new(r, g, b)).into())))
+        };
+        let sigma_a = coefficient("sigma_a", [0.0011, 0.0024, 0.014])?;
+        let sigma_s = coefficient("sigma_s", [2.55, 3.21, 3.77])?;
+        let scale = params.float("scale", 1.)?;
+        let g = params.float("g", 0.)?;
+        if g.abs() >= 1. {
+            let offset = params.get("g").unwrap().offset;
+            return Err(ParseError::new(offset, "`g` must be in (-1; 1)"));
+        }
+        let emission = match params.spectrum("Le", SpectrumType::Illuminant)? {
+            Some(spectrum) if spectrum.max_value() > 0. => {
+                let scale = params.float("Lescale", 1.)? / spectrum_to_photometric(&spectrum);
+                Some((spectrum, scale))
+            }
+            _ => None,
+        };
+
+        let medium = match ty {
+            "homogeneous" => HomogeneousMedium {
+                sigma_a,
+                sigma_s,
+                scale,
+                phase: HGPhaseFunction::new(g),
+                emission,
+            }
+            .into(),
+            "uniformgrid" => {
+                let resolution =
+                    [params.int("nx", 1)?, params.int("ny", 1)?, params.int("nz", 1)?].map(|x| x.max(1) as usize);
+                let grid = |name: &str, values: Option<Vec<f32>>| {
+                    values
+                        .and_then(|values| SampledGrid::new(values, resolution))
+                        .ok_or_else(|| {
+                            let [x, y, z] = resolution;
+                            let offset = params.get(name).map_or(offset, |x| x.offset);
+                            ParseError::new(offset, format!("uniformgrid: expected {x} * {y} * {z} `{name}` values"))
+                        })
+                };
+                let density = grid("density", params.floats("density")?)?;
+                let bounds = Aabb::new(
+                    params.point3("p0", point3!(0., 0., 0.))?,
+                    params.point3("p1", point3!(1., 1., 1.))?,
+                );
+                let emission = match params.floats("temperature")? {
+                    Some(_) if emission.is_some() => {
+                        let offset = params.get("temperature").unwrap().offset;
+                        return Err(ParseError::new(
+                            offset,
+                            "uniformgrid: `Le` and `temperature` are mutually exclusive",
+                        ));
+                    }
+                    Some(temperatures) => {
+                        // Temperatures may be given relative to an offset and in other units than Kelvin
+                        let temperature_offset =
+                            params.float("temperatureoffset", params.float("temperaturecutoff", 0.)?)?;
+                        let temperature_scale = params.float("temperaturescale", 1.)?;
+                        let temperatures = temperatures
+                            .into_iter()
+                            .map(|x| (x - temperature_offset) * temperature_scale)
+                            .collect();
+                        let grid = grid("temperature", Some(temperatures))?;
+                        Some((GridEmission::Temperature(grid), params.float("Lescale", 1.)?))
+                    }
+                    // Emission follows the density
+                    None => {
+                        emission.map(|(spectrum, scale)| (GridEmission::Spectrum(spectrum, density.clone()), scale))
+                    }
+                };
+                GridMedium::new(GridMediumConfig {
+                    bounds,
+                    medium_to_render: self.state.ctm,
+                    sigma_a,
+                    sigma_s,
+                    scale,
+                    g,
+                    density,
+                    emission,
+                })
+                .into()
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(Arc::new(medium)))
+    }
+
+    /// Interface of the current shapes, `None` unless they separate different media
+    fn medium_interface(&self, offset: usize, tokens: &Tokens) -> Option<MediumInterface> {
+        let (inside, outside) = (&self.state.inside_medium, &self.state.outside_medium);
+        if inside == outside {
+            return None;
+        }
+        let medium = |name: &Option<String>| {
+            let name = name.as_ref()?;
+            let medium = self.media.get(name).cloned();
+            if medium.is_none() {
+                warn!("{}: unknown medium `{name}`, using vacuum", tokens.location(offset));
+            }
+            medium
+        };
+        let (inside, outside) = (medium(inside), medium(outside));
+        Some(match self.state.reverse_orientation {
+            false => MediumInterface { inside, outside },
+            true => MediumInterface {
+                inside: outside,
+                outside: inside,
+            },
+        })
+    }
+}
+
+fn default_material() -> Arc<MaterialsEnum> {
+    Arc::new(MaterialsEnum::Matte(Matte {
+        reflectance: constant_texture(0.5),
+        bump: None,
+    }))
+}
+
+fn constant_texture(value: f32) -> Arc<SpectrumTextureEnum> {
+    Arc::new(
+        ConstantSpectrumTexture {
+            value: Arc::new(ConstantSpectrum::new(value).into()),
+        }
+        .into(),
+    )
+}
+
+/// Texture space of the mappings other than `uv` is placed by the transform at the texture definition
+fn texture_mapping(params: &ParamSet, texture_to_render: Transform<f32>) -> ParseResult<TextureMapping2DEnum> {
+    match params.string("mapping")?.unwrap_or("uv") {
+        "uv" => Ok(UVMapping {
+            su: params.float("uscale", 1.)?,
+            sv: params.float("vscale", 1.)?,
+            du: params.float("udelta", 0.)?,
+            dv: params.float("vdelta", 0.)?,
+        }
+        .into()),
+        "spherical" => Ok(SphericalMapping::new(texture_to_render).into()),
+        "cylindrical" => Ok(CylindricalMapping::new(texture_to_render).into()),
+        "planar" => Ok(PlanarMapping::new(
+            texture_to_render,
+            params.vec3("v1", vec3!(1., 0., 0.))?,
+            params.vec3("v2", vec3!(0., 1., 0.))?,
+            params.float("udelta", 0.)?,
+            params.float("vdelta", 0.)?,
+        )
+        .into()),
+        mapping => {
+            let offset = params.get("mapping").unwrap().offset;
+            Err(ParseError::new(
+                offset,
+                format!("unsupported texture mapping `{mapping}`"),
+            ))
+        }
+    }
+}
+
+/// PBRT matrices are specified column by column
+fn matrix_transform(values: &[f32]) -> Option<Transform<f32>> {
+    let [m00, m01, m02, m03, m10, m11, m12, m13, m20, m21, m22, m23, m30, m31, m32, m33] = values[..] else {
+        return None;
+    };
+    #[rustfmt::skip]
+    let mat = Matrix4::from_elements(
+        m00, m10, m20, m30,
+        m01, m11, m21, m31,
+        m02, m12, m22, m32,
+        m03, m13, m23, m33,
+    );
+    let inv = mat.invert()?;
+    Some(Transform { mat, inv })
+}
+
+fn triangle_mesh(params: &ParamSet, offset: usize, ctm: Transform<f32>) -> ParseResult<Vec<Triangle>> {
+    let positions = params
+        .point3s("P")?
+        .ok_or(ParseError::new(offset, "trianglemesh: missing `point3 P`"))?;
+    let indices = match params.ints("indices")? {
+        Some(indices) => indices,
+        None if positions.len() == 3 => vec![0, 1, 2],
+        None => return Err(ParseError::new(offset, "trianglemesh: missing `integer indices`")),
+    };
+    if indices.len() % 3 != 0 || indices.iter().any(|&x| x >= positions.len()) {
+        let offset = params.get("indices").map_or(offset, |x| x.offset);
+        return Err(ParseError::new(
+            offset,
+            "trianglemesh: indices must be triplets referencing existing vertices",
+        ));
+    }
+    let normals = params.vec3s("N")?;
+    if let Some(normals) = &normals
+        && normals.len() != positions.len()
+    {
+        let offset = params.get("N").unwrap().offset;
+        return Err(ParseError::new(
+            offset,
+            "trianglemesh: number of normals must match number of vertices",
+        ));
+    }
+
+    let uvs = params.point2s("uv")?;
+    if let Some(uvs) = &uvs
+        && uvs.len() != positions.len()
+    {
+        let offset = params.get("uv").unwrap().offset;
+        return Err(ParseError::new(
+            offset,
+            "trianglemesh: number of uvs must match number of vertices",
+        ));
+    }
+
+    let indices = indices.into_iter().map(|x| x as u32).collect();
+    Ok(TriangleMesh::new(positions, indices, normals, uvs, ctm).into_triangles())
+}
+
+/// Bilinear patch `p00 p10 p01 p11` is representable with a [Quad] if it is a parallelogram
+fn bilinear_quad(params: &ParamSet, offset: usize, ctm: Transform<f32>) -> ParseResult<Option<Quad>> {
+    let positions = params
+        .point3s("P")?
+        .ok_or(ParseError::new(offset, "bilinearmesh: missing `point3 P`"))?;
+    let indices = params.ints("indices")?.unwrap_or(vec![0, 1, 2, 3]);
+    let ([p00, p10, p01, p11], [0, 1, 2, 3]) = (positions.as_slice(), indices.as_slice()) else {
+        return Ok(None);
+    };
+    let (p00, p10, p01, p11) = (*p00, *p10, *p01, *p11);
+    let (ab, ac) = (p10 - p00, p01 - p00);
+    if (p11 - (p00 + ab + ac)).len() > 1e-4 * (ab.len() + ac.len()) {
+        return Ok(None);
+    }
+    Ok(Some(Quad::new(p00, ab, ac, ctm)))
+}
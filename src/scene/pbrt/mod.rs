@@ -0,0 +1,423 @@
+//! Importer for a subset of the [pbrt-v4 scene format](https://pbrt.org/fileformat-v4).
+//!
+//! Unsupported directives, shapes, materials and parameters are reported with [log::warn] and skipped, malformed
+//! input is reported as [SceneLoadError] pointing at the offending line.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    aggregates::BVH,
+    light::{LightEnum, LightSamplerKind},
+    math::Transform,
+    mediums::MediumEnum,
+    scene::{
+        cameras::{
+            default_screen_window, BaseCameraConfig, CameraType, OrthographicCamera, OrthographicCameraConfig,
+            PerspectiveCamera, PerspectiveCameraConfig,
+        },
+        film::RGBFilm,
+        filters::FilterEnum,
+        loader::SceneLoadError,
+        pbrt::builder::Importer,
+        primitives::PrimitiveEnum,
+        Scene, SceneError,
+    },
+    spectra::rgb::sRGB,
+    Bounds2f, Point2us,
+};
+
+mod builder;
+mod params;
+mod tokenizer;
+
+/// Imported scene along with rendering options. Options can be overridden before calling [PbrtScene::build].
+pub struct PbrtScene {
+    pub resolution: Point2us,
+    pub samples_per_pixel: u32,
+    pub max_depth: u32,
+    /// Sampler name as written in the file, e.g. `zsobol`
+    pub sampler: String,
+    /// Integrator name as written in the file, e.g. `volpath`
+    pub integrator: String,
+    pub light_sampler: LightSamplerKind,
+    pub output: Option<PathBuf>,
+    /// Defaults to pbrt's Gaussian filter
+    filter: FilterEnum,
+    camera: CameraDesc,
+    primitives: Vec<Arc<PrimitiveEnum>>,
+    lights: Vec<Arc<LightEnum>>,
+}
+
+#[derive(Debug, Clone)]
+struct CameraDesc {
+    projection: Projection,
+    camera_to_world: Transform<f32>,
+    screen_window: Option<Bounds2f>,
+    lens_radius: f32,
+    focal_distance: f32,
+    medium: Option<Arc<MediumEnum>>,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Projection {
+    Perspective { fov: f32 },
+    Orthographic,
+}
+
+impl PbrtScene {
+    pub fn build(self) -> Result<Scene, SceneError> {
+        let film = RGBFilm::new(self.resolution.x, self.resolution.y, self.filter, sRGB.clone());
+        let desc = self.camera;
+        let screen_window = desc.screen_window.unwrap_or(default_screen_window(self.resolution));
+        let base_config = BaseCameraConfig {
+            transform: desc.camera_to_world,
+            film,
+            medium: desc.medium,
+        };
+        let camera: CameraType = match desc.projection {
+            Projection::Perspective { fov } => PerspectiveCamera::new(PerspectiveCameraConfig {
+                base_config,
+                fov,
+                screen_window,
+                lens_radius: desc.lens_radius,
+                focal_distance: desc.focal_distance,
+            })
+            .into(),
+            Projection::Orthographic => OrthographicCamera::new(OrthographicCameraConfig {
+                base_config,
+                screen_window,
+                lens_radius: desc.lens_radius,
+                focal_distance: desc.focal_distance,
+            })
+            .into(),
+        };
+
+        Scene::new(camera, PrimitiveEnum::BVH(BVH::new(self.primitives, 8)), self.lights)
+    }
+}
+
+pub fn load_pbrt(path: impl AsRef<Path>) -> Result<PbrtScene, SceneLoadError> {
+    let mut importer = Importer::new();
+    importer.import_file(path.as_ref())?;
+    Ok(importer.finish())
+}
+
+/// Same as [load_pbrt], but reads the scene from a string. `path` is used for error messages and to resolve
+/// relative paths.
+pub fn parse_pbrt(source: String, path: PathBuf) -> Result<PbrtScene, SceneLoadError> {
+    let mut importer = Importer::new();
+    importer.import_source(source, path)?;
+    Ok(importer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::{
+        core::SurfaceInteraction,
+        light::Light,
+        mediums::MediumEnum,
+        point2,
+        scene::{
+            cameras::{Camera, CameraSample},
+            filters::Filter,
+        },
+        vec2, SampledWavelengths, Vec3f,
+    };
+
+    const SCENE: &str = r#"
+LookAt 0 0 -5  0 0 0  0 1 0
+Camera "perspective" "float fov" 45
+Film "rgb" "integer xresolution" 64 "integer yresolution" 32 "string filename" "out.exr"
+Sampler "halton" "integer pixelsamples" 4
+Integrator "path" "integer maxdepth" 3 "string lightsampler" "power"
+PixelFilter "gaussian" "float xradius" 2
+WorldBegin
+
+LightSource "point" "blackbody I" 5500 "point3 from" [0 5 0]
+LightSource "infinite" "blackbody L" 3000 "float scale" 0.1
+Texture "marble" "spectrum" "marble" "float scale" 2
+Texture "checks" "spectrum" "checkerboard" "string mapping" "spherical" "texture tex1" "marble" "rgb tex2" [.2 .2 .2]
+MakeNamedMaterial "gray" "string type" "diffuse" "spectrum reflectance" [400 0.5 700 0.5]
+MakeNamedMaterial "checks" "string type" "diffuse" "texture reflectance" "checks"
+Texture "bumps" "float" "wrinkled" "float roughness" 0.6
+Texture "rough" "float" "mix" "texture tex1" "bumps" "float tex2" 0.1 "float amount" 0.3
+MakeNamedMaterial "rough" "string type" "conductor" "texture roughness" "rough" "texture vroughness" "bumps"
+MakeNamedMaterial "bumpy" "string type" "diffuse" "texture displacement" "bumps"
+
+AttributeBegin
+    NamedMaterial "gray"
+    AreaLightSource "diffuse" "blackbody L" 6500
+    Translate 10 0 0
+    Shape "sphere" "float radius" 1
+AttributeEnd
+
+Shape "sphere" "float radius" 2 "float zmax" 1
+Shape "trianglemesh" "point3 P" [-1 -3 -1  1 -3 -1  0 -3 1] "integer indices" [0 1 2]
+"#;
+
+    fn parse(source: &str) -> Result<PbrtScene, SceneLoadError> {
+        parse_pbrt(source.to_string(), PathBuf::from("test.pbrt"))
+    }
+
+    #[test]
+    fn test_import() {
+        let imported = parse(SCENE).unwrap();
+        assert_eq!(imported.resolution, point2!(64, 32));
+        assert_eq!(imported.samples_per_pixel, 4);
+        assert_eq!(imported.max_depth, 3);
+        assert_eq!(imported.light_sampler, LightSamplerKind::Power);
+        assert_eq!(imported.output, Some(PathBuf::from("out.exr")));
+        let FilterEnum::Gaussian(filter) = &imported.filter else {
+            panic!("expected a Gaussian filter");
+        };
+        assert_eq!(filter.radius(), vec2!(2., 1.5));
+        assert_eq!(imported.primitives.len(), 3);
+        assert_eq!(imported.lights.len(), 3);
+
+        let scene = imported.build().unwrap();
+        let ray = scene.camera.generate_ray(CameraSample {
+            p_film: point2!(32., 16.),
+            p_lens: point2!(0.5, 0.5),
+            filter_weight: 1.,
+        });
+        let hit = scene.cast_ray(&ray).unwrap();
+        assert!((hit.hit.point.z + 2.).abs() < 1e-3);
+        assert_eq!(scene.infinite_lights.len(), 1);
+    }
+
+    #[test]
+    fn test_shared_infinite_light() {
+        let imported = parse("WorldBegin\nLightSource \"infinite\"\nShape \"sphere\"").unwrap();
+        let shared = imported.lights[0].clone();
+        assert!(matches!(imported.build(), Err(SceneError::SharedInfiniteLight)));
+        drop(shared);
+    }
+
+    #[test]
+    fn test_light_power() {
+        let imported = parse(
+            r#"
+Film "gbuffer" "integer xresolution" 20 "integer yresolution" 10
+WorldBegin
+LightSource "point" "float power" 100
+LightSource "point" "blackbody I" 3000 "float power" 100
+LightSource "point" "blackbody I" 3000
+"#,
+        )
+        .unwrap();
+        assert_eq!(imported.resolution, point2!(20, 10));
+
+        // Luminous power only depends on `power`, not on the spectrum
+        let luminance = |light: &LightEnum| {
+            (0..300)
+                .map(|x| {
+                    let lambda = SampledWavelengths::sample_visible((x as f32 + 0.5) / 300.);
+                    Vec3f::from(light.flux(&lambda).to_xyz(&lambda)).y
+                })
+                .sum::<f32>()
+                / 300.
+        };
+        let [d65, blackbody, unit] = [0, 1, 2].map(|i| luminance(&imported.lights[i]));
+        assert!((d65 / blackbody - 1.).abs() < 0.05, "{d65} {blackbody}");
+        assert!((blackbody / unit - 100. / (4. * PI)).abs() < 0.5, "{blackbody} {unit}");
+    }
+
+    #[test]
+    fn test_distant_light() {
+        let source = r#"
+WorldBegin
+LightSource "distant" "point3 from" [0 1 0] "point3 to" [0 0 0] "float illuminance" 10
+Shape "sphere" "float radius" 2
+"#;
+        let scene = parse(source).unwrap().build().unwrap();
+        assert!(scene.infinite_lights.is_empty());
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        let sample = scene.lights[0]
+            .sample(&SurfaceInteraction::default(), &lambda, point2!(0.5, 0.5))
+            .unwrap();
+        assert!((sample.incoming.y - 1.).abs() < 1e-5);
+        // Placed outside of the sphere's bounds
+        assert!(sample.point.y > 2. * 3f32.sqrt());
+        assert!(scene.lights[0].flux(&lambda).avg() > 0.);
+    }
+
+    #[test]
+    fn test_area_light() {
+        let flux = |params: &str| {
+            let source = format!(
+                r#"
+WorldBegin
+AreaLightSource "diffuse" "float power" 10 {params}
+Shape "bilinearmesh" "point3 P" [0 0 0  1 0 0  0 1 0  1 1 0]
+"#
+            );
+            let scene = parse(&source).unwrap().build().unwrap();
+            scene.lights[0].flux(&SampledWavelengths::sample_visible(0.5))[0]
+        };
+        // The power is spread over the emitting sides
+        let one_sided = flux("");
+        assert!(one_sided > 0.);
+        assert!((flux(r#""bool twosided" true"#) - one_sided).abs() < 1e-4 * one_sided);
+
+        // Every triangle of a mesh emits, sharing the power
+        let source = r#"
+WorldBegin
+AreaLightSource "diffuse" "float power" 10
+Shape "trianglemesh" "point3 P" [0 0 0  1 0 0  0 1 0  1 1 0] "integer indices" [0 1 2  2 1 3]
+"#;
+        let scene = parse(source).unwrap().build().unwrap();
+        assert_eq!(scene.lights.len(), 2);
+        let lambda = SampledWavelengths::sample_visible(0.5);
+        let mesh_flux: f32 = scene.lights.iter().map(|light| light.flux(&lambda)[0]).sum();
+        assert!((mesh_flux - one_sided).abs() < 1e-4 * one_sided);
+
+        let source = r#"
+WorldBegin
+AreaLightSource "diffuse" "rgb L" [1 1 1] "string filename" "emission.png"
+"#;
+        let err = parse(source).err().unwrap();
+        assert!(err.to_string().contains("can't have both `L` and `filename`"));
+    }
+
+    #[test]
+    fn test_goniometric_and_projection() {
+        let dir = std::env::temp_dir();
+        let ies = dir.join("rusttracer_test_light.ies");
+        let profile = "IESNA:LM-63-2002\nTILT=NONE\n1 1000 1 3 1 1 2 0 0 0\n1 1 10\n0 90 180\n0\n100 50 0\n";
+        std::fs::write(&ies, profile).unwrap();
+        let slide = dir.join("rusttracer_test_slide.png");
+        image::RgbImage::from_pixel(8, 4, image::Rgb([128, 128, 128]))
+            .save(&slide)
+            .unwrap();
+        let imported = parse(&format!(
+            r#"
+WorldBegin
+LightSource "goniometric" "string filename" "{}" "float power" 100
+LightSource "projection" "string filename" "{}" "float fov" 30 "float power" 100
+LightSource "goniometric" "string filename" "{}"
+"#,
+            ies.display(),
+            slide.display(),
+            slide.display()
+        ));
+        let err = imported.err().unwrap();
+        assert!(err.to_string().contains("must be square"));
+
+        let imported = parse(&format!(
+            r#"
+WorldBegin
+LightSource "point" "float power" 100
+LightSource "goniometric" "string filename" "{}" "float power" 100
+LightSource "projection" "string filename" "{}" "float fov" 30 "float power" 100
+"#,
+            ies.display(),
+            slide.display()
+        ))
+        .unwrap();
+        let luminance = |light: &LightEnum| {
+            (0..300)
+                .map(|x| {
+                    let lambda = SampledWavelengths::sample_visible((x as f32 + 0.5) / 300.);
+                    Vec3f::from(light.flux(&lambda).to_xyz(&lambda)).y
+                })
+                .sum::<f32>()
+                / 300.
+        };
+        // Same luminous power as a point light
+        let point = luminance(&imported.lights[0]);
+        for light in &imported.lights[1..] {
+            let luminance = luminance(light);
+            assert!((luminance / point - 1.).abs() < 0.05, "{luminance} {point}");
+        }
+    }
+
+    #[test]
+    fn test_media() {
+        let scene = parse(
+            r#"
+MakeNamedMedium "fog" "string type" "homogeneous" "rgb sigma_s" [0.1 0.2 0.3] "float g" 0.4
+MediumInterface "" "fog"
+LookAt 0 0 -5  0 0 0  0 1 0
+Camera "perspective" "float fov" 45
+Film "rgb" "integer xresolution" 64 "integer yresolution" 32
+WorldBegin
+MakeNamedMedium "smoke" "string type" "uniformgrid" "integer nx" 2 "float density" [0 1]
+AttributeBegin
+    MediumInterface "smoke" "fog"
+    Material "interface"
+    Shape "sphere" "float radius" 1
+AttributeEnd
+"#,
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        assert!(matches!(
+            scene.camera.medium().as_deref(),
+            Some(MediumEnum::Homogeneous(_))
+        ));
+
+        let ray = scene.camera.generate_ray(CameraSample {
+            p_film: point2!(32., 16.),
+            p_lens: point2!(0.5, 0.5),
+            filter_weight: 1.,
+        });
+        let hit = scene.cast_ray(&ray).unwrap();
+        assert!(hit.material.is_none());
+        let inside = hit.medium_towards(*ray.dir, None);
+        assert!(matches!(inside.as_deref(), Some(MediumEnum::Grid(_))));
+        let outside = hit.medium_towards(-*ray.dir, None);
+        assert!(matches!(outside.as_deref(), Some(MediumEnum::Homogeneous(_))));
+    }
+
+    #[test]
+    fn test_errors() {
+        let location = |source: &str| match parse(source) {
+            Err(SceneLoadError::Invalid { line, column, .. }) => (line, column),
+            _ => panic!("expected an error"),
+        };
+        assert_eq!(location("WorldBegin\n  NamedMaterial \"missing\""), (2, 3));
+        assert_eq!(location("AttributeBegin\nAttributeEnd\nAttributeEnd"), (3, 1));
+        assert_eq!(location("Shape \"sphere\" \"float radius\" [1 2]"), (1, 16));
+        assert_eq!(location("Translate 1 2"), (1, 14));
+        assert_eq!(
+            location("WorldBegin Shape \"plymesh\" \"string filename\" \"missing.ply\""),
+            (1, 12)
+        );
+        assert_eq!(
+            location("WorldBegin LightSource \"infinite\" \"string filename\" \"missing.exr\""),
+            (1, 35)
+        );
+        assert_eq!(
+            location("WorldBegin Texture \"t\" \"spectrum\" \"imagemap\" \"string filename\" \"missing.png\""),
+            (1, 46)
+        );
+        assert_eq!(
+            location("WorldBegin Texture \"t\" \"spectrum\" \"imagemap\" \"string wrap\" \"mirror\""),
+            (1, 46)
+        );
+        assert_eq!(
+            location("WorldBegin Texture \"t\" \"spectrum\" \"checkerboard\" \"integer dimension\" 4"),
+            (1, 50)
+        );
+        assert_eq!(
+            location("WorldBegin MakeNamedMaterial \"m\" \"string type\" \"dielectric\" \"texture eta\" \"missing\""),
+            (1, 61)
+        );
+        assert_eq!(
+            location("WorldBegin Material \"diffuse\" \"string normalmap\" \"missing.png\""),
+            (1, 31)
+        );
+        assert_eq!(
+            location("Integrator \"path\" \"string lightsampler\" \"best\""),
+            (1, 19)
+        );
+        assert_eq!(location("PixelFilter \"box\" \"float yradius\" 0"), (1, 19));
+    }
+}
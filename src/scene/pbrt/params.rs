@@ -0,0 +1,345 @@
+use std::{cell::Cell, sync::Arc};
+
+use log::warn;
+
+use crate::{
+    point2, point3,
+    scene::pbrt::tokenizer::{ParseError, ParseResult, TokenKind, Tokens},
+    spectra::{
+        named::NamedSpectra,
+        piecewise_linear::PiecewiseLinearSpectrum,
+        rgb::{sRGB, RGB},
+        BlackbodySpectrum, ConstantSpectrum, RGBAlbedoSpectrum, RGBIlluminantSpectrum, RGBUnboundedSpectrum,
+        SpectrumEnum,
+    },
+    vec3, Point2f, Point3f, Vec3f,
+};
+
+/// How `rgb` values are turned into spectra, see PBRT's `SpectrumType`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(super) enum SpectrumType {
+    Albedo,
+    Unbounded,
+    Illuminant,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum ParamValue {
+    Number(f32),
+    Str(String),
+    Bool(bool),
+}
+
+#[derive(Debug)]
+pub(super) struct Param {
+    /// Declared type, e.g. `float`, `rgb`, `point3`
+    pub ty: String,
+    pub name: String,
+    pub values: Vec<ParamValue>,
+    pub offset: usize,
+    used: Cell<bool>,
+}
+
+impl Param {
+    fn numbers(&self) -> ParseResult<Vec<f32>> {
+        self.values
+            .iter()
+            .map(|x| match x {
+                ParamValue::Number(x) => Ok(*x),
+                _ => Err(self.error("expected numeric values")),
+            })
+            .collect()
+    }
+
+    fn error(&self, message: impl AsRef<str>) -> ParseError {
+        ParseError::new(self.offset, format!("parameter `{}`: {}", self.name, message.as_ref()))
+    }
+}
+
+/// Parameter list following a directive, e.g. `"float radius" 1 "rgb reflectance" [.5 .5 .5]`
+#[derive(Debug, Default)]
+pub(super) struct ParamSet {
+    params: Vec<Param>,
+}
+
+impl ParamSet {
+    pub fn parse(tokens: &mut Tokens) -> ParseResult<Self> {
+        let mut params = vec![];
+        while let Some(TokenKind::Str(declaration)) = tokens.peek().map(|x| &x.kind) {
+            let offset = tokens.offset();
+            let mut declaration = declaration.split_whitespace();
+            let (Some(ty), Some(name), None) = (declaration.next(), declaration.next(), declaration.next()) else {
+                return Err(ParseError::new(
+                    offset,
+                    "expected parameter declaration `\"<type> <name>\"`",
+                ));
+            };
+            let (ty, name) = (ty.to_string(), name.to_string());
+            tokens.next();
+
+            let mut values = vec![];
+            let bracketed = matches!(tokens.peek().map(|x| &x.kind), Some(TokenKind::OpenBracket));
+            if bracketed {
+                tokens.next();
+            }
+            loop {
+                let value_offset = tokens.offset();
+                let value = match tokens.next().map(|x| x.kind) {
+                    Some(TokenKind::Number(x)) => ParamValue::Number(x),
+                    Some(TokenKind::Str(x)) if ty == "bool" => match x.as_str() {
+                        "true" => ParamValue::Bool(true),
+                        "false" => ParamValue::Bool(false),
+                        _ => return Err(ParseError::new(value_offset, "expected `true` or `false`")),
+                    },
+                    Some(TokenKind::Str(x)) => ParamValue::Str(x),
+                    Some(TokenKind::Identifier(x)) if x == "true" => ParamValue::Bool(true),
+                    Some(TokenKind::Identifier(x)) if x == "false" => ParamValue::Bool(false),
+                    Some(TokenKind::CloseBracket) if bracketed => break,
+                    _ => {
+                        return Err(ParseError::new(
+                            value_offset,
+                            format!("missing value for parameter `{name}`"),
+                        ))
+                    }
+                };
+                values.push(value);
+                if !bracketed {
+                    break;
+                }
+            }
+            params.push(Param {
+                ty,
+                name,
+                values,
+                offset,
+                used: Cell::new(false),
+            });
+        }
+        Ok(ParamSet { params })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Param> {
+        let param = self.params.iter().find(|x| x.name == name)?;
+        param.used.set(true);
+        Some(param)
+    }
+
+    fn single_number(&self, name: &str) -> ParseResult<Option<f32>> {
+        let Some(param) = self.get(name) else {
+            return Ok(None);
+        };
+        match param.numbers()?.as_slice() {
+            [x] => Ok(Some(*x)),
+            _ => Err(param.error("expected a single value")),
+        }
+    }
+
+    pub fn float(&self, name: &str, default: f32) -> ParseResult<f32> {
+        Ok(self.single_number(name)?.unwrap_or(default))
+    }
+
+    pub fn int(&self, name: &str, default: i32) -> ParseResult<i32> {
+        Ok(self.single_number(name)?.map_or(default, |x| x as i32))
+    }
+
+    pub fn floats(&self, name: &str) -> ParseResult<Option<Vec<f32>>> {
+        self.get(name).map(|param| param.numbers()).transpose()
+    }
+
+    pub fn ints(&self, name: &str) -> ParseResult<Option<Vec<usize>>> {
+        let Some(param) = self.get(name) else {
+            return Ok(None);
+        };
+        param
+            .numbers()?
+            .into_iter()
+            .map(|x| {
+                if x >= 0. && x.fract() == 0. {
+                    Ok(x as usize)
+                } else {
+                    Err(param.error("expected non-negative integers"))
+                }
+            })
+            .collect::<ParseResult<Vec<usize>>>()
+            .map(Some)
+    }
+
+    pub fn bool(&self, name: &str, default: bool) -> ParseResult<bool> {
+        let Some(param) = self.get(name) else {
+            return Ok(default);
+        };
+        match param.values.as_slice() {
+            [ParamValue::Bool(x)] => Ok(*x),
+            _ => Err(param.error("expected a single bool")),
+        }
+    }
+
+    pub fn string(&self, name: &str) -> ParseResult<Option<&str>> {
+        let Some(param) = self.get(name) else {
+            return Ok(None);
+        };
+        match param.values.as_slice() {
+            [ParamValue::Str(x)] => Ok(Some(x)),
+            _ => Err(param.error("expected a single string")),
+        }
+    }
+
+    fn triplets(&self, name: &str) -> ParseResult<Option<Vec<[f32; 3]>>> {
+        let Some(param) = self.get(name) else {
+            return Ok(None);
+        };
+        let numbers = param.numbers()?;
+        if numbers.len() % 3 != 0 {
+            return Err(param.error("number of values must be a multiple of 3"));
+        }
+        Ok(Some(numbers.chunks_exact(3).map(|x| [x[0], x[1], x[2]]).collect()))
+    }
+
+    pub fn point3s(&self, name: &str) -> ParseResult<Option<Vec<Point3f>>> {
+        Ok(self
+            .triplets(name)?
+            .map(|x| x.into_iter().map(|[x, y, z]| point3!(x, y, z)).collect()))
+    }
+
+    pub fn point3(&self, name: &str, default: Point3f) -> ParseResult<Point3f> {
+        let Some(param) = self.params.iter().find(|x| x.name == name) else {
+            return Ok(default);
+        };
+        match self.point3s(name)?.as_deref() {
+            Some([point]) => Ok(*point),
+            _ => Err(param.error("expected a single point")),
+        }
+    }
+
+    pub fn vec3(&self, name: &str, default: Vec3f) -> ParseResult<Vec3f> {
+        let Some(param) = self.params.iter().find(|x| x.name == name) else {
+            return Ok(default);
+        };
+        match self.vec3s(name)?.as_deref() {
+            Some([vec]) => Ok(*vec),
+            _ => Err(param.error("expected a single vector")),
+        }
+    }
+
+    pub fn vec3s(&self, name: &str) -> ParseResult<Option<Vec<Vec3f>>> {
+        Ok(self
+            .triplets(name)?
+            .map(|x| x.into_iter().map(|[x, y, z]| vec3!(x, y, z)).collect()))
+    }
+
+    pub fn point2s(&self, name: &str) -> ParseResult<Option<Vec<Point2f>>> {
+        let Some(param) = self.get(name) else {
+            return Ok(None);
+        };
+        let numbers = param.numbers()?;
+        if numbers.len() % 2 != 0 {
+            return Err(param.error("number of values must be a multiple of 2"));
+        }
+        Ok(Some(numbers.chunks_exact(2).map(|x| point2!(x[0], x[1])).collect()))
+    }
+
+    /// Name of a texture if the parameter is declared as `texture`
+    pub fn texture(&self, name: &str) -> ParseResult<Option<&str>> {
+        match self.params.iter().find(|x| x.name == name) {
+            Some(param) if param.ty == "texture" => self.string(name),
+            _ => Ok(None),
+        }
+    }
+
+    /// Spectrum declared as `rgb`, `spectrum`, `blackbody` or `float`
+    pub fn spectrum(&self, name: &str, spectrum_type: SpectrumType) -> ParseResult<Option<Arc<SpectrumEnum>>> {
+        let Some(param) = self.params.iter().find(|x| x.name == name && x.ty != "texture") else {
+            return Ok(None);
+        };
+        param.used.set(true);
+        let spectrum: SpectrumEnum = match param.ty.as_str() {
+            "rgb" | "color" => {
+                let [r, g, b] = param.numbers()?[..] else {
+                    return Err(param.error("expected 3 values"));
+                };
+                if [r, g, b].iter().any(|x| *x < 0.) {
+                    return Err(param.error("RGB components must be non-negative"));
+                }
+                let rgb = RGB::new(r, g, b);
+                match spectrum_type {
+                    SpectrumType::Albedo if [r, g, b].iter().any(|x| *x > 1.) => {
+                        return Err(param.error("reflectance RGB components must not exceed 1"))
+                    }
+                    SpectrumType::Albedo => RGBAlbedoSpectrum::new(&sRGB, rgb).into(),
+                    SpectrumType::Unbounded => RGBUnboundedSpectrum::new(&sRGB, rgb).into(),
+                    SpectrumType::Illuminant => RGBIlluminantSpectrum::new(&sRGB, rgb).into(),
+                }
+            }
+            "spectrum" => match param.values.as_slice() {
+                [ParamValue::Str(name)] => {
+                    return NamedSpectra::from_name(name)
+                        .map(|x| Some(x.get()))
+                        .ok_or_else(|| param.error(format!("unknown named spectrum `{name}`")))
+                }
+                _ => {
+                    let values = param.numbers()?;
+                    if values.len() < 2 || values.len() % 2 != 0 {
+                        return Err(param.error("expected (wavelength, value) pairs"));
+                    }
+                    if !values
+                        .iter()
+                        .step_by(2)
+                        .collect::<Vec<_>>()
+                        .windows(2)
+                        .all(|x| x[0] < x[1])
+                    {
+                        return Err(param.error("wavelengths must be strictly increasing"));
+                    }
+                    PiecewiseLinearSpectrum::from_interleaved(&values, spectrum_type == SpectrumType::Illuminant).into()
+                }
+            },
+            "blackbody" => match param.numbers()?[..] {
+                [temperature] if temperature > 0. => BlackbodySpectrum::new(temperature).into(),
+                _ => return Err(param.error("expected a single positive temperature")),
+            },
+            "float" => match param.numbers()?[..] {
+                [value] => ConstantSpectrum::new(value).into(),
+                _ => return Err(param.error("expected a single value")),
+            },
+            ty => return Err(param.error(format!("type `{ty}` can not be used as a spectrum"))),
+        };
+        Ok(Some(Arc::new(spectrum)))
+    }
+
+    /// Reports parameters that were never queried
+    pub fn warn_unused(&self, directive: &str, tokens: &Tokens) {
+        for param in self.params.iter().filter(|x| !x.used.get()) {
+            warn!(
+                "{}: {directive}: parameter `{} {}` is not supported, ignoring",
+                tokens.location(param.offset),
+                param.ty,
+                param.name
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let mut tokens = Tokens::new(
+            r#""float radius" 2 "point3 P" [0 0 0 1 0 0] "string filename" "a.ply" "bool twosided" true Shape"#
+                .to_string(),
+            PathBuf::from("test.pbrt"),
+        )
+        .unwrap();
+        let params = ParamSet::parse(&mut tokens).unwrap();
+        assert_eq!(params.float("radius", 1.).unwrap(), 2.);
+        assert_eq!(params.float("missing", 1.).unwrap(), 1.);
+        assert_eq!(params.point3s("P").unwrap().unwrap().len(), 2);
+        assert_eq!(params.string("filename").unwrap(), Some("a.ply"));
+        assert!(params.bool("twosided", false).unwrap());
+        assert!(params.float("filename", 0.).is_err());
+        assert!(matches!(tokens.peek().map(|x| &x.kind), Some(TokenKind::Identifier(_))));
+    }
+}
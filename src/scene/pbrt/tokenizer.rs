@@ -0,0 +1,254 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::scene::loader::{line_column, SceneLoadError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum TokenKind {
+    /// Bare word, i.e. a directive name or a `true`/`false` literal
+    Identifier(String),
+    Str(String),
+    Number(f32),
+    OpenBracket,
+    CloseBracket,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Token {
+    pub kind: TokenKind,
+    /// Byte offset in the source
+    pub offset: usize,
+}
+
+/// Error that is not yet bound to a file
+#[derive(Debug)]
+pub(super) struct ParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(offset: usize, message: impl Into<String>) -> Self {
+        ParseError {
+            offset,
+            message: message.into(),
+        }
+    }
+}
+
+pub(super) type ParseResult<T> = Result<T, ParseError>;
+
+/// Token stream of a single `.pbrt` file
+pub(super) struct Tokens {
+    pub path: PathBuf,
+    source: String,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Tokens {
+    pub fn load(path: &Path) -> Result<Self, SceneLoadError> {
+        let source = fs::read_to_string(path).map_err(|source| SceneLoadError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::new(source, path.to_path_buf())
+    }
+
+    pub fn new(source: String, path: PathBuf) -> Result<Self, SceneLoadError> {
+        match tokenize(&source) {
+            Ok(tokens) => Ok(Tokens {
+                path,
+                source,
+                tokens,
+                pos: 0,
+            }),
+            Err(err) => Err(SceneLoadError::invalid(
+                path,
+                &source,
+                Some(err.offset..err.offset),
+                err.message,
+            )),
+        }
+    }
+
+    pub fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    pub fn peek(&self) -> Option<&Token> { self.tokens.get(self.pos) }
+
+    /// Offset of the next token, or the end of the file
+    pub fn offset(&self) -> usize { self.peek().map_or(self.source.len(), |x| x.offset) }
+
+    pub fn error(&self, err: ParseError) -> SceneLoadError {
+        SceneLoadError::invalid(
+            self.path.clone(),
+            &self.source,
+            Some(err.offset..err.offset),
+            err.message,
+        )
+    }
+
+    /// `path:line:column` of a byte offset, used for warnings
+    pub fn location(&self, offset: usize) -> String {
+        let (line, column) = line_column(&self.source, offset);
+        format!("{}:{line}:{column}", self.path.display())
+    }
+
+    pub fn expect_number(&mut self) -> ParseResult<f32> {
+        let offset = self.offset();
+        match self.next() {
+            Some(Token {
+                kind: TokenKind::Number(x),
+                ..
+            }) => Ok(x),
+            _ => Err(ParseError::new(offset, "expected a number")),
+        }
+    }
+
+    pub fn expect_numbers<const N: usize>(&mut self) -> ParseResult<[f32; N]> {
+        let mut numbers = [0.; N];
+        for x in numbers.iter_mut() {
+            *x = self.expect_number()?;
+        }
+        Ok(numbers)
+    }
+
+    /// Numbers, optionally enclosed in brackets
+    pub fn expect_number_list(&mut self) -> ParseResult<Vec<f32>> {
+        if let Some(TokenKind::Number(_)) = self.peek().map(|x| &x.kind) {
+            return Ok(vec![self.expect_number()?]);
+        }
+        let offset = self.offset();
+        let Some(TokenKind::OpenBracket) = self.next().map(|x| x.kind) else {
+            return Err(ParseError::new(offset, "expected `[`"));
+        };
+        let mut numbers = vec![];
+        loop {
+            let offset = self.offset();
+            match self.next().map(|x| x.kind) {
+                Some(TokenKind::Number(x)) => numbers.push(x),
+                Some(TokenKind::CloseBracket) => return Ok(numbers),
+                _ => return Err(ParseError::new(offset, "expected a number or `]`")),
+            }
+        }
+    }
+
+    pub fn expect_string(&mut self) -> ParseResult<String> {
+        let offset = self.offset();
+        match self.next() {
+            Some(Token {
+                kind: TokenKind::Str(x),
+                ..
+            }) => Ok(x),
+            _ => Err(ParseError::new(offset, "expected a quoted string")),
+        }
+    }
+
+    /// Skips arguments of an unsupported directive
+    pub fn skip_arguments(&mut self) {
+        while let Some(token) = self.peek()
+            && !matches!(token.kind, TokenKind::Identifier(ref x) if x != "true" && x != "false")
+        {
+            self.pos += 1;
+        }
+    }
+}
+
+fn tokenize(source: &str) -> ParseResult<Vec<Token>> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let kind = match bytes[i] {
+            c if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'[' => {
+                i += 1;
+                TokenKind::OpenBracket
+            }
+            b']' => {
+                i += 1;
+                TokenKind::CloseBracket
+            }
+            b'"' => {
+                i += 1;
+                let mut value = String::new();
+                loop {
+                    match bytes.get(i) {
+                        None | Some(b'\n') => return Err(ParseError::new(start, "unterminated string")),
+                        Some(b'"') => break,
+                        Some(b'\\') => {
+                            i += 1;
+                            match bytes.get(i) {
+                                Some(b'n') => value.push('\n'),
+                                Some(b't') => value.push('\t'),
+                                Some(&c) => value.push(c as char),
+                                None => return Err(ParseError::new(start, "unterminated string")),
+                            }
+                            i += 1;
+                        }
+                        Some(_) => {
+                            // Copy a whole UTF-8 character
+                            let ch = source[i..].chars().next().unwrap();
+                            value.push(ch);
+                            i += ch.len_utf8();
+                        }
+                    }
+                }
+                i += 1;
+                TokenKind::Str(value)
+            }
+            _ => {
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !b"[]\"#".contains(&bytes[i]) {
+                    i += 1;
+                }
+                let word = &source[start..i];
+                match word.as_bytes()[0] {
+                    b'0'..=b'9' | b'-' | b'+' | b'.' => TokenKind::Number(
+                        word.parse()
+                            .map_err(|_| ParseError::new(start, format!("invalid number `{word}`")))?,
+                    ),
+                    _ => TokenKind::Identifier(word.to_string()),
+                }
+            }
+        };
+        tokens.push(Token { kind, offset: start });
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("Shape \"sphere\" # comment\n \"float radius\" [ -2.5e1 ]").unwrap();
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|x| x.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Identifier("Shape".to_string()),
+            TokenKind::Str("sphere".to_string()),
+            TokenKind::Str("float radius".to_string()),
+            TokenKind::OpenBracket,
+            TokenKind::Number(-25.),
+            TokenKind::CloseBracket,
+        ]);
+
+        assert_eq!(tokenize("Shape \"sphere").unwrap_err().offset, 6);
+    }
+}
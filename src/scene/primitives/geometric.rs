@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
 
 use derive_new::new;
 
@@ -7,38 +10,141 @@ use crate::{
     core::{Ray, SurfaceInteraction},
     light::LightEnum,
     material::MaterialsEnum,
+    mediums::MediumInterface,
     scene::primitives::{simple::SimplePrimitive, Primitive},
     shapes::{BoundedIntersectable, Intersectable},
+    textures::{FloatTexture, FloatTextureEnum},
 };
 
 #[derive(Debug)]
 #[derive(new)]
 pub struct GeometricPrimitive {
     pub shape: Arc<dyn BoundedIntersectable>,
-    pub material: Arc<MaterialsEnum>,
+    /// `None` for surfaces that only separate two media
+    pub material: Option<Arc<MaterialsEnum>>,
     pub light: Option<Arc<LightEnum>>,
-    // medium_interface
-    // alpha
+    pub medium_interface: Option<MediumInterface>,
+    /// Opacity in [0; 1], rays pass through the shape where it is below 1, e.g. for leaves and fences
+    pub alpha: Option<Arc<FloatTextureEnum>>,
 }
 
 unsafe impl Send for GeometricPrimitive {}
 unsafe impl Sync for GeometricPrimitive {}
 
+impl GeometricPrimitive {
+    /// Fractional alpha is compared to a hash of the ray, so the same ray is always masked the same way and shadow
+    /// rays agree with camera rays
+    fn is_masked(alpha: &FloatTextureEnum, interaction: &SurfaceInteraction, ray: &Ray) -> bool {
+        let alpha = alpha.evaluate(interaction);
+        if alpha >= 1. {
+            return false;
+        }
+        if alpha <= 0. {
+            return true;
+        }
+        let mut hasher = DefaultHasher::new();
+        [
+            ray.origin.x,
+            ray.origin.y,
+            ray.origin.z,
+            ray.dir.x,
+            ray.dir.y,
+            ray.dir.z,
+        ]
+        .map(f32::to_bits)
+        .hash(&mut hasher);
+        let u = (hasher.finish() >> 40) as f32 / (1 << 24) as f32;
+        u > alpha
+    }
+}
+
 impl Bounded<f32> for GeometricPrimitive {
     fn bound(&self) -> Aabb<f32> { self.shape.bound() }
 }
 
 impl Intersectable for GeometricPrimitive {
     fn intersect(&self, ray: &Ray, t_max: f32) -> Option<SurfaceInteraction> {
-        if let Some(mut interaction) = self.shape.intersect(ray, t_max) {
-            interaction.set_material_properties(&self.material, self.light.as_ref());
-            Some(interaction)
-        } else {
-            None
+        let mut interaction = self.shape.intersect(ray, t_max)?;
+        if let Some(alpha) = &self.alpha
+            && Self::is_masked(alpha, &interaction, ray)
+        {
+            // Continue past the masked out hit, the shape may be hit again further away
+            let t = interaction.hit.t;
+            let mut next = self.intersect(&interaction.spawn_ray(ray.dir), t_max - t)?;
+            next.hit.t += t;
+            return Some(next);
         }
+        interaction.set_material_properties(self.material.as_ref(), self.light.as_ref());
+        interaction.medium_interface.clone_from(&self.medium_interface);
+        Some(interaction)
     }
 
-    fn check_intersect(&self, ray: &Ray, t_max: f32) -> bool { self.shape.check_intersect(ray, t_max) }
+    fn check_intersect(&self, ray: &Ray, t_max: f32) -> bool {
+        match self.alpha {
+            Some(_) => self.intersect(ray, t_max).is_some(),
+            None => self.shape.check_intersect(ray, t_max),
+        }
+    }
 }
 
 impl Primitive for GeometricPrimitive {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        material::matte::Matte,
+        math::{Normed, Transform},
+        point3, ray,
+        shapes::quad::Quad,
+        spectra::ConstantSpectrum,
+        textures::constant::{ConstantFloatTexture, ConstantSpectrumTexture},
+        vec3,
+    };
+
+    fn primitive(alpha: f32) -> GeometricPrimitive {
+        let reflectance = Arc::new(
+            ConstantSpectrumTexture {
+                value: Arc::new(ConstantSpectrum::new(0.5).into()),
+            }
+            .into(),
+        );
+        GeometricPrimitive {
+            shape: Arc::new(Quad::new(
+                point3!(-1., -1., 0.),
+                vec3!(2., 0., 0.),
+                vec3!(0., 2., 0.),
+                Transform::id(),
+            )),
+            material: Some(Arc::new(MaterialsEnum::Matte(Matte {
+                reflectance,
+                bump: None,
+            }))),
+            light: None,
+            medium_interface: None,
+            alpha: Some(Arc::new(ConstantFloatTexture { value: alpha }.into())),
+        }
+    }
+
+    #[test]
+    fn test_alpha() {
+        let ray = |x: f32, y: f32| ray!(point3!(x, y, -1.), vec3!(0., 0., 1.).to_unit());
+        let opaque = primitive(1.);
+        assert!(opaque.intersect(&ray(0., 0.), f32::INFINITY).is_some());
+        let transparent = primitive(0.);
+        assert!(transparent.intersect(&ray(0., 0.), f32::INFINITY).is_none());
+        assert!(!transparent.check_intersect(&ray(0., 0.), f32::INFINITY));
+
+        // Rays are masked consistently, about half of them pass through
+        let half = primitive(0.5);
+        let n = 1000;
+        let mut hits = 0;
+        for i in 0..n {
+            let ray = ray(i as f32 / n as f32 - 0.5, 0.3);
+            let hit = half.intersect(&ray, f32::INFINITY).is_some();
+            assert_eq!(hit, half.check_intersect(&ray, f32::INFINITY));
+            hits += usize::from(hit);
+        }
+        assert!((hits as f32 / n as f32 - 0.5).abs() < 0.1, "{hits}");
+    }
+}
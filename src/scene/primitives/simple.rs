@@ -27,7 +27,7 @@ impl Bounded<f32> for SimplePrimitive {
 impl Intersectable for SimplePrimitive {
     fn intersect(&self, ray: &Ray, t_max: f32) -> Option<SurfaceInteraction> {
         if let Some(mut interaction) = self.shape.intersect(ray, t_max) {
-            interaction.set_material_properties(&self.material, None);
+            interaction.set_material_properties(Some(&self.material), None);
             Some(interaction)
         } else {
             None
@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
+use derive_more::{Display, Error};
 use image::Rgb;
 
 use crate::{
-    aggregates::BVH,
+    aggregates::{Bounded, BVH},
     core::{Ray, SurfaceInteraction},
-    light::{Light, LightEnum},
+    light::{Light, LightEnum, LightType},
     material::Material,
     math::{Normed, Unit},
     ray,
@@ -19,10 +20,46 @@ pub struct Scene {
     pub objects: PrimitiveEnum,
     // pub materials: ???
     pub lights: Vec<Arc<LightEnum>>,
+    /// Lights contributing to rays that escaped the scene, subset of `lights`
+    pub infinite_lights: Vec<Arc<LightEnum>>,
     // pub background_color: Rgb<f32>,
 }
 
+#[derive(Debug, Display, Error)]
+pub enum SceneError {
+    /// Infinite and distant lights are modified once the scene bounds are known, so nothing else may hold them yet
+    #[display("infinite light is shared and can't be preprocessed")]
+    SharedInfiniteLight,
+}
+
 impl Scene {
+    /// Preprocesses the lights. Infinite and distant lights must not be shared yet, since they are modified
+    pub fn new(
+        camera: CameraType,
+        objects: PrimitiveEnum,
+        mut lights: Vec<Arc<LightEnum>>,
+    ) -> Result<Self, SceneError> {
+        let bounds = objects.bound();
+        let mut infinite_lights = vec![];
+        for light in lights.iter_mut() {
+            let light_type = light.light_type();
+            if light_type.intersects(LightType::Infinite | LightType::DeltaDirection) {
+                Arc::get_mut(light)
+                    .ok_or(SceneError::SharedInfiniteLight)?
+                    .preprocess(&bounds);
+            }
+            if light_type.contains(LightType::Infinite) {
+                infinite_lights.push(light.clone());
+            }
+        }
+        Ok(Scene {
+            camera,
+            objects,
+            lights,
+            infinite_lights,
+        })
+    }
+
     pub fn cast_ray(&self, ray: &Ray) -> Option<SurfaceInteraction> { self.objects.intersect(ray, f32::INFINITY) }
 
     pub fn cast_bounded_ray(&self, ray: &Ray, t_max: f32) -> Option<SurfaceInteraction> {
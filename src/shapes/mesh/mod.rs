@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+pub use triangle::Triangle;
+
+use crate::{
+    math::{Normal3, Normed, Transform, Transformable, Unit},
+    Point2f, Point3f, Vec3f,
+};
+
+pub mod obj;
+pub mod ply;
+mod triangle;
+
+/// Vertex data shared by the triangles of a mesh. Vertices are stored in render space.
+#[derive(Debug)]
+pub struct TriangleMesh {
+    positions: Vec<Point3f>,
+    /// Three vertex indices per triangle
+    indices: Vec<u32>,
+    normals: Option<Vec<Unit<Vec3f>>>,
+    uvs: Option<Vec<Point2f>>,
+}
+
+impl TriangleMesh {
+    /// Panics if `indices` don't form triangles of existing vertices or if the number of normals or uvs doesn't
+    /// match the number of vertices
+    pub fn new(
+        positions: Vec<Point3f>,
+        indices: Vec<u32>,
+        normals: Option<Vec<Vec3f>>,
+        uvs: Option<Vec<Point2f>>,
+        transform: Transform<f32>,
+    ) -> Self {
+        assert_eq!(indices.len() % 3, 0, "number of indices must be a multiple of 3");
+        assert!(indices.iter().all(|&i| (i as usize) < positions.len()));
+        assert!(normals.as_ref().map_or(true, |x| x.len() == positions.len()));
+        assert!(uvs.as_ref().map_or(true, |x| x.len() == positions.len()));
+
+        let positions = positions.into_iter().map(|x| x.transform(&transform)).collect();
+        let normals = normals.map(|normals| {
+            normals
+                .into_iter()
+                .map(|x| Normal3::from(x).transform(&transform).value.to_unit())
+                .collect()
+        });
+        TriangleMesh {
+            positions,
+            indices,
+            normals,
+            uvs,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize { self.indices.len() / 3 }
+
+    /// Splits the mesh into triangles referencing it, skipping degenerate ones
+    pub fn into_triangles(self) -> Vec<Triangle> {
+        let mesh = Arc::new(self);
+        (0..mesh.triangle_count() as u32)
+            .map(|index| Triangle::new(mesh.clone(), index))
+            .filter(|triangle| !triangle.is_degenerate())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aggregates::Bounded, point3, vec3};
+
+    #[test]
+    fn test_into_triangles() {
+        let positions = vec![
+            point3!(0., 0., 0.),
+            point3!(1., 0., 0.),
+            point3!(0., 1., 0.),
+            point3!(2., 0., 0.),
+        ];
+        // The second triangle is degenerate
+        let indices = vec![0, 1, 2, 0, 1, 3];
+        let mesh = TriangleMesh::new(positions, indices, None, None, Transform::translate(vec3!(0., 0., 1.)));
+        assert_eq!(mesh.triangle_count(), 2);
+
+        let triangles = mesh.into_triangles();
+        assert_eq!(triangles.len(), 1);
+        let bound = triangles[0].bound();
+        assert_eq!(bound.min, point3!(0., 0., 1.));
+        assert_eq!(bound.max, point3!(1., 1., 1.));
+    }
+}
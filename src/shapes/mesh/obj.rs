@@ -0,0 +1,439 @@
+//! Wavefront OBJ loader. Polygons are triangulated as fans, MTL materials are mapped onto [Matte], [Metal] and
+//! [Glass], `map_Kd` becomes an image texture, `map_d` a cutout mask and non-zero `Ke` is reported as emission.
+//! Missing or malformed MTL files are reported with [log::warn], the groups using them are left without a material.
+
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ::obj::{IndexTuple, Material, Obj, ObjError, ObjMaterial};
+use derive_more::{Display, Error};
+use either::Either;
+use log::warn;
+
+use crate::{
+    material::{glass::Glass, matte::Matte, metal::Metal, MaterialsEnum, Roughness},
+    math::Transform,
+    point2, point3,
+    shapes::mesh::{Triangle, TriangleMesh},
+    spectra::{
+        rgb::{sRGB, RGB},
+        ConstantSpectrum, RGBAlbedoSpectrum, RGBIlluminantSpectrum, SpectrumEnum,
+    },
+    textures::{
+        constant::{ConstantFloatTexture, ConstantSpectrumTexture},
+        image::{FloatImageTexture, ImageChannel, RGBSpectrumKind, SpectrumImageTexture},
+        mappings::UVMapping,
+        mipmap::{FilterFunction, MIPMap, WrapMode},
+        FloatTextureEnum, SpectrumTextureEnum,
+    },
+    utils::{read_alpha, read_linear_rgb},
+    vec3, Point2f, Point3f, Vec3f,
+};
+
+#[derive(Debug, Display, Error)]
+pub enum ObjLoadError {
+    #[display("failed to load {}: {source}", path.display())]
+    Obj { path: PathBuf, source: ObjError },
+    /// Face references a vertex, texture coordinate or normal that is not defined
+    #[display("{}: face references missing {kind} {index}", path.display())]
+    InvalidIndex {
+        path: PathBuf,
+        kind: &'static str,
+        index: usize,
+    },
+}
+
+/// Triangulated contents of an OBJ file
+#[derive(Debug)]
+pub struct ObjMesh {
+    pub groups: Vec<ObjGroup>,
+}
+
+/// Triangles sharing a material
+#[derive(Debug)]
+pub struct ObjGroup {
+    pub name: String,
+    pub triangles: Vec<Triangle>,
+    /// `None` if the group has no `usemtl` or the material is not found in the MTL files
+    pub material: Option<MeshMaterial>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MeshMaterial {
+    pub material: Arc<MaterialsEnum>,
+    /// Emitted radiance, from a non-zero `Ke`
+    pub emission: Option<Arc<SpectrumEnum>>,
+    /// Cutout mask from `map_d`
+    pub alpha: Option<Arc<FloatTextureEnum>>,
+}
+
+impl ObjMesh {
+    /// All triangles regardless of their material
+    pub fn into_triangles(self) -> impl Iterator<Item = Triangle> {
+        self.groups.into_iter().flat_map(|group| group.triangles)
+    }
+}
+
+/// Loads an OBJ file along with the MTL files it references, applying `transform` to the vertices
+pub fn load_obj(path: impl AsRef<Path>, transform: Transform<f32>) -> Result<ObjMesh, ObjLoadError> {
+    let path = path.as_ref();
+    let mut obj = Obj::load(path).map_err(|source| ObjLoadError::Obj {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    if let Err(err) = obj.load_mtls() {
+        for (file, err) in err.0 {
+            warn!("{}: failed to load materials from {file}: {err}", path.display());
+        }
+    }
+
+    let data = &obj.data;
+    let positions: Vec<Point3f> = data.position.iter().map(|&[x, y, z]| point3!(x, y, z)).collect();
+    let normals: Vec<Vec3f> = data.normal.iter().map(|&[x, y, z]| vec3!(x, y, z)).collect();
+    let uvs: Vec<Point2f> = data.texture.iter().map(|&[u, v]| point2!(u, v)).collect();
+    let invalid_index = |kind, index| ObjLoadError::InvalidIndex {
+        path: path.to_path_buf(),
+        kind,
+        index,
+    };
+
+    // Groups often share materials, convert each one once
+    let mut materials: HashMap<String, Option<MeshMaterial>> = HashMap::new();
+    let mut groups = vec![];
+    for group in data.objects.iter().flat_map(|x| x.groups.iter()) {
+        let material = match &group.material {
+            None => None,
+            Some(ObjMaterial::Ref(name)) => materials
+                .entry(name.clone())
+                .or_insert_with(|| {
+                    warn!("{}: material `{name}` is not defined", path.display());
+                    None
+                })
+                .clone(),
+            Some(ObjMaterial::Mtl(mtl)) => materials
+                .entry(mtl.name.clone())
+                .or_insert_with(|| Some(mtl_material(mtl, path)))
+                .clone(),
+        };
+
+        // Faces with and without uvs or normals can't share a mesh, so they are split into up to four meshes
+        let mut builders: BTreeMap<(bool, bool), MeshBuilder> = BTreeMap::new();
+        for poly in group.polys.iter() {
+            let has_uvs = poly.0.iter().all(|x| x.1.is_some());
+            let has_normals = poly.0.iter().all(|x| x.2.is_some());
+            let builder = builders.entry((has_uvs, has_normals)).or_default();
+
+            let mut poly_ids = Vec::with_capacity(poly.0.len());
+            for &IndexTuple(position, uv, normal) in poly.0.iter() {
+                let position_value = *positions
+                    .get(position)
+                    .ok_or_else(|| invalid_index("vertex", position))?;
+                let uv_value = uv
+                    .map(|i| uvs.get(i).ok_or_else(|| invalid_index("texture coordinate", i)))
+                    .transpose()?;
+                let normal_value = normal
+                    .map(|i| normals.get(i).ok_or_else(|| invalid_index("normal", i)))
+                    .transpose()?;
+
+                let (uv, normal) = (uv.filter(|_| has_uvs), normal.filter(|_| has_normals));
+                let id = match builder.vertex_ids.entry((position, uv, normal)) {
+                    Entry::Occupied(entry) => *entry.get(),
+                    Entry::Vacant(entry) => {
+                        builder.positions.push(position_value);
+                        builder.uvs.extend(uv_value.filter(|_| has_uvs));
+                        builder.normals.extend(normal_value.filter(|_| has_normals));
+                        *entry.insert(builder.positions.len() as u32 - 1)
+                    }
+                };
+                poly_ids.push(id);
+            }
+
+            // Triangulate polygons as fans
+            for i in 2..poly_ids.len() {
+                builder.indices.extend([poly_ids[0], poly_ids[i - 1], poly_ids[i]]);
+            }
+        }
+        let mut triangles = vec![];
+        for ((has_uvs, has_normals), builder) in builders {
+            let mesh = TriangleMesh::new(
+                builder.positions,
+                builder.indices,
+                has_normals.then_some(builder.normals),
+                has_uvs.then_some(builder.uvs),
+                transform,
+            );
+            triangles.extend(mesh.into_triangles());
+        }
+
+        groups.push(ObjGroup {
+            name: group.name.clone(),
+            triangles,
+            material,
+        });
+    }
+    Ok(ObjMesh { groups })
+}
+
+/// Mesh vertices are unique combinations of OBJ position, uv and normal indices
+#[derive(Default)]
+struct MeshBuilder {
+    vertex_ids: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+    positions: Vec<Point3f>,
+    uvs: Vec<Point2f>,
+    normals: Vec<Vec3f>,
+    indices: Vec<u32>,
+}
+
+/// Approximates an MTL material, see the illumination models in the MTL spec
+fn mtl_material(mtl: &Material, path: &Path) -> MeshMaterial {
+    let is_black = |x: &[f32; 3]| x.iter().all(|&x| x == 0.);
+    let albedo = |rgb: [f32; 3]| -> Arc<SpectrumTextureEnum> {
+        let [r, g, b] = rgb.map(|x| x.clamp(0., 1.));
+        let rgb = RGB::new(r, g, b);
+        Arc::new(
+            ConstantSpectrumTexture {
+                value: Arc::new(RGBAlbedoSpectrum::new(&sRGB, rgb).into()),
+            }
+            .into(),
+        )
+    };
+    // `map_Kd` replaces `Kd`, images that fail to load fall back to it
+    let diffuse = |kd: [f32; 3]| -> Arc<SpectrumTextureEnum> {
+        let Some(file) = &mtl.map_kd else {
+            return albedo(kd);
+        };
+        let image_path = path.parent().unwrap_or(Path::new(".")).join(file);
+        match read_linear_rgb(&image_path) {
+            Ok(image) => Arc::new(
+                SpectrumImageTexture {
+                    mapping: UVMapping::default().into(),
+                    mipmap: Arc::new(MIPMap::new(image, WrapMode::Repeat, FilterFunction::Trilinear)),
+                    scale: 1.,
+                    invert: false,
+                    kind: RGBSpectrumKind::Albedo,
+                }
+                .into(),
+            ),
+            Err(err) => {
+                warn!(
+                    "{}: material `{}`: failed to load {}: {err}, using `Kd`",
+                    path.display(),
+                    mtl.name,
+                    image_path.display()
+                );
+                albedo(kd)
+            }
+        }
+    };
+
+    // Blender exports roughness as `Ns = (1 - roughness)^2 * 1000`
+    let roughness = || match mtl.ns {
+        Some(ns) => Roughness::isotropic(Arc::new(
+            ConstantFloatTexture {
+                value: 1. - (ns / 1000.).clamp(0., 1.).sqrt(),
+            }
+            .into(),
+        )),
+        None => Roughness::smooth(),
+    };
+
+    let illum = mtl.illum.unwrap_or(1);
+    let transparent = mtl.d.is_some_and(|d| d < 1.) || mtl.tr.is_some_and(|tr| tr > 0.);
+    let kd = mtl.kd.unwrap_or([0.5; 3]);
+    let ks = mtl.ks.filter(|x| !is_black(x));
+
+    let material = if matches!(illum, 4 | 6 | 7 | 9) || transparent {
+        MaterialsEnum::Glass(Glass {
+            ior: Either::Left(ConstantSpectrum::new(mtl.ni.unwrap_or(1.5)).into()),
+            roughness: roughness(),
+            spectrum: match mtl.tf.filter(|x| *x != [1.; 3]) {
+                Some(tf) => albedo(tf),
+                None => Arc::new(
+                    ConstantSpectrumTexture {
+                        value: Arc::new(ConstantSpectrum::new(1.).into()),
+                    }
+                    .into(),
+                ),
+            },
+            bump: None,
+        })
+    } else if let Some(ks) = ks
+        && (matches!(illum, 3 | 5) || is_black(&kd))
+    {
+        MaterialsEnum::Metal(Metal {
+            reflectance: Either::Left(albedo(ks)),
+            roughness: roughness(),
+            bump: None,
+        })
+    } else {
+        MaterialsEnum::Matte(Matte {
+            reflectance: diffuse(kd),
+            bump: None,
+        })
+    };
+
+    let emission = mtl
+        .ke
+        .filter(|x| !is_black(x))
+        .map(|[r, g, b]| Arc::new(RGBIlluminantSpectrum::new(&sRGB, RGB::new(r, g, b)).into()));
+    // Alpha channel of `map_d`, or the image itself if it has none
+    let alpha = mtl.map_d.as_ref().and_then(|file| {
+        let image_path = path.parent().unwrap_or(Path::new(".")).join(file);
+        match read_alpha(&image_path) {
+            Ok(image) => Some(Arc::new(
+                FloatImageTexture {
+                    mapping: UVMapping::default().into(),
+                    mipmap: Arc::new(MIPMap::new(image, WrapMode::Repeat, FilterFunction::Bilinear)),
+                    channel: ImageChannel::Average,
+                    scale: 1.,
+                    invert: false,
+                }
+                .into(),
+            )),
+            Err(err) => {
+                warn!(
+                    "{}: material `{}`: failed to load {}: {err}, ignoring `map_d`",
+                    path.display(),
+                    mtl.name,
+                    image_path.display()
+                );
+                None
+            }
+        }
+    });
+
+    MeshMaterial {
+        material: Arc::new(material),
+        emission,
+        alpha,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{core::Ray, math::Normed, ray, shapes::Intersectable};
+
+    /// Directory with an OBJ and an MTL file, removed on drop
+    struct TestFiles {
+        dir: PathBuf,
+    }
+
+    impl TestFiles {
+        fn new(obj: &str, mtl: &str) -> Self {
+            // Tests run in parallel, possibly in several processes
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+            let dir = env::temp_dir().join(format!("rusttracer_obj_test_{}_{id}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("mesh.mtl"), mtl).unwrap();
+            fs::write(dir.join("mesh.obj"), obj).unwrap();
+            TestFiles { dir }
+        }
+
+        fn obj_path(&self) -> PathBuf { self.dir.join("mesh.obj") }
+    }
+
+    impl Drop for TestFiles {
+        fn drop(&mut self) { let _ = fs::remove_dir_all(&self.dir); }
+    }
+
+    #[test]
+    fn test_load() {
+        let obj = "mtllib mesh.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+usemtl glass
+f 1/1/1 2/2/1 3/3/1 4/4/1
+g other
+f 1 2 3
+";
+        let mtl = "newmtl glass\nNi 1.33\nillum 7\n";
+        let files = TestFiles::new(obj, mtl);
+        let mesh = load_obj(files.obj_path(), Transform::id()).unwrap();
+        assert_eq!(mesh.groups.len(), 2);
+
+        let quad = &mesh.groups[0];
+        assert_eq!(quad.triangles.len(), 2);
+        let material = quad.material.as_ref().unwrap();
+        assert!(matches!(*material.material, MaterialsEnum::Glass(_)));
+        assert!(material.emission.is_none());
+        assert!(mesh.groups[1].material.is_none());
+        assert_eq!(mesh.into_triangles().count(), 3);
+    }
+
+    #[test]
+    fn test_diffuse_texture() {
+        let files = TestFiles::new(
+            "mtllib mesh.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl a\nf 1 2 3\nusemtl b\nf 1 2 3\n",
+            "newmtl a\nmap_Kd texture.png\nnewmtl b\nmap_Kd missing.png\n",
+        );
+        image::RgbImage::new(2, 2).save(files.dir.join("texture.png")).unwrap();
+        let mesh = load_obj(files.obj_path(), Transform::id()).unwrap();
+        let reflectance = |i: usize| match &*mesh.groups[i].material.as_ref().unwrap().material {
+            MaterialsEnum::Matte(matte) => matte.reflectance.clone(),
+            _ => panic!("expected a matte material"),
+        };
+        assert!(matches!(*reflectance(0), SpectrumTextureEnum::Image(_)));
+        assert!(matches!(*reflectance(1), SpectrumTextureEnum::Constant(_)));
+    }
+
+    #[test]
+    fn test_alpha_map() {
+        let files = TestFiles::new(
+            "mtllib mesh.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl a\nf 1 2 3\nusemtl b\nf 1 2 3\n",
+            "newmtl a\nmap_d leaves.png\nnewmtl b\nmap_d missing.png\n",
+        );
+        image::RgbaImage::new(2, 2).save(files.dir.join("leaves.png")).unwrap();
+        let mesh = load_obj(files.obj_path(), Transform::id()).unwrap();
+        let alpha = |i: usize| mesh.groups[i].material.as_ref().unwrap().alpha.is_some();
+        assert!(alpha(0));
+        assert!(!alpha(1));
+    }
+
+    #[test]
+    fn test_mixed_normals() {
+        // Only the first face has normals, it must keep them
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 2 0 0\nv 3 0 0\nv 2 1 0\nvn 1 0 1\nf 1//1 2//1 3//1\nf 4 5 6\n";
+        let files = TestFiles::new(obj, "");
+        let triangles: Vec<_> = load_obj(files.obj_path(), Transform::id())
+            .unwrap()
+            .into_triangles()
+            .collect();
+        assert_eq!(triangles.len(), 2);
+
+        let shading_normal = |x: f32| {
+            let ray = ray!(point3!(x, 0.2, -1.), vec3!(0., 0., 1.).to_unit());
+            let hit = triangles.iter().find_map(|x| x.intersect(&ray, 2.)).unwrap();
+            *hit.hit.normal
+        };
+        assert!((shading_normal(0.2).x.abs() - 0.5_f32.sqrt()).abs() < 1e-5);
+        assert!(shading_normal(2.2).x.abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_invalid_index() {
+        let files = TestFiles::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3/1\n", "");
+        let err = load_obj(files.obj_path(), Transform::id()).err().unwrap();
+        assert!(matches!(err, ObjLoadError::InvalidIndex {
+            kind: "texture coordinate",
+            index: 0,
+            ..
+        }));
+    }
+}
@@ -0,0 +1,479 @@
+//! Stanford PLY loader supporting ASCII and binary encodings. Vertex positions, normals (`nx`, `ny`, `nz`) and
+//! texture coordinates (`u`/`v`, `s`/`t` or `texture_u`/`texture_v`) are read, faces with more than three vertices
+//! are triangulated as fans. Other elements and properties are skipped.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::SplitAsciiWhitespace,
+};
+
+use derive_more::{Display, Error};
+
+use crate::{math::Transform, point2, point3, shapes::mesh::TriangleMesh, vec3, Point2f, Point3f, Vec3f};
+
+#[derive(Debug, Display, Error)]
+pub enum PlyLoadError {
+    #[display("failed to read {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[display("{}: {message}", path.display())]
+    Invalid {
+        path: PathBuf,
+        #[error(not(source))]
+        message: String,
+    },
+}
+
+/// Loads a PLY file, applying `transform` to the vertices
+pub fn load_ply(path: impl AsRef<Path>, transform: Transform<f32>) -> Result<TriangleMesh, PlyLoadError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|source| PlyLoadError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_ply(&bytes, transform).map_err(|message| PlyLoadError::Invalid {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+/// Upper bound on the number of vertices preallocated from the header's counts
+const MAX_RESERVED: usize = 1 << 20;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        let scalar = match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        };
+        Some(scalar)
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PropertyType {
+    Scalar(Scalar),
+    List { count: Scalar, item: Scalar },
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    ty: PropertyType,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+#[derive(Debug)]
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+/// Splits the file into the header and the offset of the body
+fn parse_header(bytes: &[u8]) -> Result<(Header, usize), String> {
+    // `end_header` must be a line of its own, it may appear in comments
+    let mut end = 0;
+    let body_start = loop {
+        if end >= bytes.len() {
+            return Err("missing `end_header`".to_string());
+        }
+        let line_end = bytes[end..]
+            .iter()
+            .position(|&x| x == b'\n')
+            .map_or(bytes.len(), |x| end + x);
+        if std::str::from_utf8(&bytes[end..line_end]).is_ok_and(|x| x.trim() == "end_header") {
+            break (line_end + 1).min(bytes.len());
+        }
+        end = line_end + 1;
+    };
+    let header = std::str::from_utf8(&bytes[..end]).map_err(|_| "header is not valid UTF-8")?;
+
+    let mut lines = header.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err("not a PLY file".to_string());
+    }
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    for line in lines {
+        let words: Vec<&str> = line.split_ascii_whitespace().collect();
+        match words.as_slice() {
+            [] | ["comment" | "obj_info", ..] => {}
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(format!("unknown format `{name}`")),
+                })
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| format!("invalid element count `{count}`"))?,
+                properties: vec![],
+            }),
+            ["property", rest @ ..] => {
+                let element = elements.last_mut().ok_or("property outside of an element")?;
+                let scalar = |name: &str| Scalar::parse(name).ok_or(format!("unknown property type `{name}`"));
+                let (ty, name) = match rest {
+                    ["list", count, item, name] => (
+                        PropertyType::List {
+                            count: scalar(count)?,
+                            item: scalar(item)?,
+                        },
+                        name,
+                    ),
+                    [ty, name] => (PropertyType::Scalar(scalar(ty)?), name),
+                    _ => return Err(format!("malformed property `{line}`")),
+                };
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    ty,
+                });
+            }
+            _ => return Err(format!("unexpected header line `{line}`")),
+        }
+    }
+    let format = format.ok_or("missing `format`")?;
+    Ok((Header { format, elements }, body_start))
+}
+
+/// Reads values of the file body regardless of the encoding
+enum Reader<'a> {
+    Ascii(SplitAsciiWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, scalar: Scalar) -> Result<f64, String> {
+        match self {
+            Reader::Ascii(words) => {
+                let word = words.next().ok_or("unexpected end of file")?;
+                word.parse().map_err(|_| format!("invalid number `{word}`"))
+            }
+            Reader::Binary { bytes, big_endian } => {
+                let size = scalar.size();
+                if bytes.len() < size {
+                    return Err("unexpected end of file".to_string());
+                }
+                let (value, rest) = bytes.split_at(size);
+                *bytes = rest;
+                let mut buffer = [0u8; 8];
+                buffer[..size].copy_from_slice(value);
+                if *big_endian {
+                    buffer[..size].reverse();
+                }
+                let value = match scalar {
+                    Scalar::I8 => buffer[0] as i8 as f64,
+                    Scalar::U8 => buffer[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+                    Scalar::U32 => u32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+                    Scalar::F32 => f32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+                    Scalar::F64 => f64::from_le_bytes(buffer),
+                };
+                Ok(value)
+            }
+        }
+    }
+
+    fn read_list(&mut self, count: Scalar, item: Scalar) -> Result<Vec<f64>, String> {
+        let count = self.read(count)?;
+        if count < 0. {
+            return Err("negative list length".to_string());
+        }
+        // The length comes from the file, don't preallocate for it
+        let mut list = vec![];
+        for _ in 0..count as usize {
+            list.push(self.read(item)?);
+        }
+        Ok(list)
+    }
+
+    fn skip(&mut self, property: &Property) -> Result<(), String> {
+        match property.ty {
+            PropertyType::Scalar(scalar) => self.read(scalar).map(|_| ()),
+            PropertyType::List { count, item } => self.read_list(count, item).map(|_| ()),
+        }
+    }
+}
+
+fn parse_ply(bytes: &[u8], transform: Transform<f32>) -> Result<TriangleMesh, String> {
+    let (header, body_start) = parse_header(bytes)?;
+    let body = &bytes[body_start..];
+    let mut reader = match header.format {
+        Format::Ascii => Reader::Ascii(
+            std::str::from_utf8(body)
+                .map_err(|_| "body is not valid UTF-8")?
+                .split_ascii_whitespace(),
+        ),
+        Format::BinaryLittleEndian => Reader::Binary {
+            bytes: body,
+            big_endian: false,
+        },
+        Format::BinaryBigEndian => Reader::Binary {
+            bytes: body,
+            big_endian: true,
+        },
+    };
+
+    let vertex_count = header
+        .elements
+        .iter()
+        .find(|x| x.name == "vertex")
+        .map_or(0, |x| x.count);
+    let mut positions: Vec<Point3f> = vec![];
+    let mut normals: Option<Vec<Vec3f>> = None;
+    let mut uvs: Option<Vec<Point2f>> = None;
+    let mut indices: Vec<u32> = vec![];
+    for element in header.elements.iter() {
+        match element.name.as_str() {
+            "vertex" => {
+                let find = |names: &[&str]| {
+                    element
+                        .properties
+                        .iter()
+                        .position(|x| names.contains(&x.name.as_str()) && matches!(x.ty, PropertyType::Scalar(_)))
+                };
+                let (Some(x), Some(y), Some(z)) = (find(&["x"]), find(&["y"]), find(&["z"])) else {
+                    return Err("vertices must have `x`, `y` and `z` properties".to_string());
+                };
+                let normal = match (find(&["nx"]), find(&["ny"]), find(&["nz"])) {
+                    (Some(nx), Some(ny), Some(nz)) => Some([nx, ny, nz]),
+                    _ => None,
+                };
+                let uv = match (
+                    find(&["u", "s", "texture_u", "texture_s"]),
+                    find(&["v", "t", "texture_v", "texture_t"]),
+                ) {
+                    (Some(u), Some(v)) => Some([u, v]),
+                    _ => None,
+                };
+                // Element counts come from the header and may be bogus
+                let capacity = element.count.min(MAX_RESERVED);
+                normals = normal.map(|_| Vec::with_capacity(capacity));
+                uvs = uv.map(|_| Vec::with_capacity(capacity));
+                positions.reserve(capacity);
+
+                let mut values = vec![0.; element.properties.len()];
+                for _ in 0..element.count {
+                    for (value, property) in values.iter_mut().zip(element.properties.iter()) {
+                        match property.ty {
+                            PropertyType::Scalar(scalar) => *value = reader.read(scalar)?,
+                            PropertyType::List { .. } => reader.skip(property)?,
+                        }
+                    }
+                    let value = |i: usize| values[i] as f32;
+                    positions.push(point3!(value(x), value(y), value(z)));
+                    if let (Some(normals), Some([nx, ny, nz])) = (&mut normals, normal) {
+                        normals.push(vec3!(value(nx), value(ny), value(nz)));
+                    }
+                    if let (Some(uvs), Some([u, v])) = (&mut uvs, uv) {
+                        uvs.push(point2!(value(u), value(v)));
+                    }
+                }
+            }
+            "face" => {
+                let vertex_indices = element.properties.iter().position(|x| {
+                    matches!(x.name.as_str(), "vertex_indices" | "vertex_index")
+                        && matches!(x.ty, PropertyType::List { .. })
+                });
+                let Some(vertex_indices) = vertex_indices else {
+                    return Err("faces must have a `vertex_indices` list".to_string());
+                };
+                for _ in 0..element.count {
+                    for (i, property) in element.properties.iter().enumerate() {
+                        match property.ty {
+                            PropertyType::List { count, item } if i == vertex_indices => {
+                                let list = reader.read_list(count, item)?;
+                                if let Some(index) = list.iter().find(|&&x| x < 0. || x >= vertex_count as f64) {
+                                    return Err(format!("face references missing vertex {index}"));
+                                }
+                                // Triangulate polygons as fans
+                                for j in 2..list.len() {
+                                    indices.extend([list[0], list[j - 1], list[j]].map(|x| x as u32));
+                                }
+                            }
+                            _ => reader.skip(property)?,
+                        }
+                    }
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    for property in element.properties.iter() {
+                        reader.skip(property)?;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(TriangleMesh::new(positions, indices, normals, uvs, transform))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregates::Bounded;
+
+    const HEADER: &str = "ply
+format {format} 1.0
+comment test
+element vertex 4
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property float u
+property float v
+element face 2
+property list uchar int vertex_indices
+property uchar flags
+end_header
+";
+
+    fn header(format: &str) -> Vec<u8> { HEADER.replace("{format}", format).into_bytes() }
+
+    #[test]
+    fn test_ascii() {
+        let mut bytes = header("ascii");
+        bytes.extend_from_slice(
+            b"0 0 0 0 0 1 0 0\n1 0 0 0 0 1 1 0\n1 1 0 0 0 1 1 1\n0 1 0 0 0 1 0 1\n4 0 1 2 3 7\n3 0 1 2 0\n",
+        );
+        let mesh = parse_ply(&bytes, Transform::id()).unwrap();
+        assert_eq!(mesh.triangle_count(), 3);
+        assert_eq!(mesh.normals.as_ref().unwrap().len(), 4);
+        assert_eq!(mesh.uvs.as_ref().unwrap()[2], point2!(1., 1.));
+    }
+
+    #[test]
+    fn test_binary() {
+        let vertices: [[f32; 8]; 4] = [
+            [0., 0., 0., 0., 0., 1., 0., 0.],
+            [2., 0., 0., 0., 0., 1., 1., 0.],
+            [2., 2., 0., 0., 0., 1., 1., 1.],
+            [0., 2., 0., 0., 0., 1., 0., 1.],
+        ];
+        let faces: [&[i32]; 2] = [&[0, 1, 2], &[0, 2, 3]];
+        for (format, big_endian) in [("binary_little_endian", false), ("binary_big_endian", true)] {
+            let mut bytes = header(format);
+            for value in vertices.iter().flatten() {
+                bytes.extend(if big_endian {
+                    value.to_be_bytes()
+                } else {
+                    value.to_le_bytes()
+                });
+            }
+            for face in faces {
+                bytes.push(face.len() as u8);
+                for index in face {
+                    bytes.extend(if big_endian {
+                        index.to_be_bytes()
+                    } else {
+                        index.to_le_bytes()
+                    });
+                }
+                bytes.push(0);
+            }
+
+            let triangles = parse_ply(&bytes, Transform::id()).unwrap().into_triangles();
+            assert_eq!(triangles.len(), 2);
+            assert_eq!(triangles[1].bound().max, point3!(2., 2., 0.));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut bytes = header("ascii");
+        bytes.extend_from_slice(b"0 0 0 0 0 1 0 0\n1 0 0 0 0 1 1 0\n");
+        assert_eq!(
+            parse_ply(&bytes, Transform::id()).err().unwrap(),
+            "unexpected end of file"
+        );
+
+        let mut bytes = header("ascii");
+        bytes.extend_from_slice(
+            b"0 0 0 0 0 1 0 0\n1 0 0 0 0 1 1 0\n1 1 0 0 0 1 1 1\n0 1 0 0 0 1 0 1\n3 0 1 5 0\n3 0 1 2 0\n",
+        );
+        assert_eq!(
+            parse_ply(&bytes, Transform::id()).err().unwrap(),
+            "face references missing vertex 5"
+        );
+        let mut bytes = header("ascii");
+        bytes.extend_from_slice(
+            b"0 0 0 0 0 1 0 0\n1 0 0 0 0 1 1 0\n1 1 0 0 0 1 1 1\n0 1 0 0 0 1 0 1\n3 0 -1 2 0\n3 0 1 2 0\n",
+        );
+        assert_eq!(
+            parse_ply(&bytes, Transform::id()).err().unwrap(),
+            "face references missing vertex -1"
+        );
+        assert!(parse_ply(
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n",
+            Transform::id()
+        )
+        .is_err());
+
+        // Huge counts fail on the missing data instead of allocating
+        let bytes = b"ply\nformat ascii 1.0\nelement vertex 4000000000\nproperty float x\nproperty float y\nproperty float z\nend_header\n0 0 0\n";
+        assert_eq!(
+            parse_ply(bytes, Transform::id()).err().unwrap(),
+            "unexpected end of file"
+        );
+    }
+
+    #[test]
+    fn test_end_header_in_comment() {
+        let mut bytes = HEADER
+            .replace("{format}", "ascii")
+            .replace("comment test", "comment written before end_header")
+            .into_bytes();
+        bytes.extend_from_slice(
+            b"0 0 0 0 0 1 0 0\n1 0 0 0 0 1 1 0\n1 1 0 0 0 1 1 1\n0 1 0 0 0 1 0 1\n4 0 1 2 3 7\n3 0 1 2 0\n",
+        );
+        assert_eq!(parse_ply(&bytes, Transform::id()).unwrap().triangle_count(), 3);
+    }
+}
@@ -0,0 +1,314 @@
+use std::sync::Arc;
+
+use num_traits::Pow;
+
+use crate::{
+    aggregates::Aabb,
+    core::{Interaction, Ray, SurfaceInteraction},
+    math::{
+        cross, dot, utils::local_normal, Cross, DirectionCone, Dot, Frame, Normed, Number, Transform, Transformable,
+        Unit,
+    },
+    point2, ray,
+    samplers::utils::{sample_spherical_triangle, sample_uniform_triangle, spherical_triangle_area},
+    shapes::{mesh::TriangleMesh, Bounded, Intersectable, Samplable, ShapeSample},
+    Point2f, Point3f, Vec3f,
+};
+
+/// Triangle of a [TriangleMesh]. Vertices are fetched from the mesh on demand
+#[derive(Debug, Clone)]
+pub struct Triangle {
+    mesh: Arc<TriangleMesh>,
+    index: u32,
+}
+
+impl Triangle {
+    const MAX_SPHERICAL_AREA: f32 = 6.22;
+    /// Solid angle sampling is numerically unstable for tiny triangles and ones seen nearly edge-on from close by,
+    /// those are sampled by area
+    const MIN_SPHERICAL_AREA: f32 = 3e-4;
+    const PADDING: f32 = 1e-4;
+
+    pub(super) fn new(mesh: Arc<TriangleMesh>, index: u32) -> Self { Triangle { mesh, index } }
+
+    fn vertex_indices(&self) -> [usize; 3] {
+        let first = 3 * self.index as usize;
+        [0, 1, 2].map(|i| self.mesh.indices[first + i] as usize)
+    }
+
+    /// First vertex and edges to the other two
+    fn vertices(&self) -> (Point3f, Vec3f, Vec3f) {
+        let [a, b, c] = self.vertex_indices().map(|i| self.mesh.positions[i]);
+        (a, b - a, c - a)
+    }
+
+    fn normal(&self) -> Unit<Vec3f> {
+        let (_, ab, ac) = self.vertices();
+        cross(&ab, &ac).to_unit()
+    }
+
+    /// Solid angle subtended at `point`
+    fn solid_angle(&self, point: Point3f) -> f32 {
+        let (a, ab, ac) = self.vertices();
+        let [a, b, c] = [a, a + ab, a + ac].map(|v| *(v - point).to_unit());
+        spherical_triangle_area(a, b, c)
+    }
+
+    pub(super) fn is_degenerate(&self) -> bool {
+        let (_, ab, ac) = self.vertices();
+        cross(&ab, &ac).len_squared() == 0.
+    }
+
+    /// Without texture coordinates, uses (0, 0), (1, 0), (0, 1) so that `dp_du` and `dp_dv` are `ab` and `ac`.
+    /// PBRT uses (0, 0), (1, 0), (1, 1) instead
+    fn uvs(&self) -> [Point2f; 3] {
+        match &self.mesh.uvs {
+            Some(uvs) => self.vertex_indices().map(|i| uvs[i]),
+            None => [point2!(0., 0.), point2!(1., 0.), point2!(0., 1.)],
+        }
+    }
+
+    fn uv_at(&self, alpha: f32, beta: f32) -> Point2f {
+        let [uv0, uv1, uv2] = self.uvs();
+        uv0 + (uv1 - uv0) * alpha + (uv2 - uv0) * beta
+    }
+
+    fn shading_normal(&self, alpha: f32, beta: f32) -> Vec3f {
+        match &self.mesh.normals {
+            Some(normals) => {
+                let [n0, n1, n2] = self.vertex_indices().map(|i| *normals[i]);
+                n0 * (1. - alpha - beta) + n1 * alpha + n2 * beta
+            }
+            None => *self.normal(),
+        }
+    }
+
+    /// Partial derivatives of the position with respect to the texture coordinates
+    fn dp_duv(&self, ab: Vec3f, ac: Vec3f) -> (Vec3f, Vec3f) {
+        let [uv0, uv1, uv2] = self.uvs();
+        let (duv1, duv2) = (uv1 - uv0, uv2 - uv0);
+        let det = duv1.x * duv2.y - duv1.y * duv2.x;
+        if det.abs() < 1e-9 {
+            return (ab, ac);
+        }
+        let dp_du = (ab * duv2.y - ac * duv1.y) / det;
+        let dp_dv = (ac * duv1.x - ab * duv2.x) / det;
+        (dp_du, dp_dv)
+    }
+
+    /// Distance along the ray and barycentric coordinates of `b` and `c`
+    fn hit(&self, ray: &Ray) -> Option<(f32, f32, f32)> {
+        let (a, ab, ac) = self.vertices();
+        let n = cross(&ab, &ac);
+        let normal = n.to_unit();
+        let denom = normal.dot(&ray.dir);
+        if denom.abs() < Self::PADDING {
+            return None;
+        }
+
+        let t = (normal.dot(&a.coords) - normal.dot(&ray.origin.coords)) / denom;
+        if t < 0.0 {
+            return None;
+        }
+
+        let planar_hit_point = ray.at(t) - a;
+        let w = -n / n.len_squared();
+        let alpha = dot(&w, &cross(&ac, &planar_hit_point));
+        let beta = dot(&w, &cross(&planar_hit_point, &ab));
+
+        if (0.0..=1.0).contains(&alpha) && (0.0..=1.0).contains(&beta) && alpha + beta <= 1.0 {
+            Some((t, alpha, beta))
+        } else {
+            None
+        }
+    }
+}
+
+impl Intersectable for Triangle {
+    fn intersect(&self, ray: &Ray, t_max: f32) -> Option<SurfaceInteraction> {
+        let (t, alpha, beta) = self.hit(ray)?;
+        let (_, ab, ac) = self.vertices();
+        let shading_normal = self.shading_normal(alpha, beta);
+        let normal = local_normal(shading_normal, ray).to_normal().to_unit();
+        let (dp_du, dp_dv) = self.dp_duv(ab, ac);
+        Some(SurfaceInteraction::new(
+            Interaction {
+                point: ray.at(t),
+                normal,
+                t,
+                outgoing: -ray.dir,
+                uv: self.uv_at(alpha, beta),
+                flipped: dot(&shading_normal, &ray.dir) >= 0.,
+            },
+            dp_du,
+            dp_dv,
+            Default::default(),
+            Default::default(),
+        ))
+    }
+
+    fn check_intersect(&self, ray: &Ray, t_max: f32) -> bool { self.hit(ray).is_some() }
+}
+
+impl Samplable for Triangle {
+    fn sample(&self, rnd_p: Point2f) -> Option<ShapeSample> {
+        let [_, alpha, beta] = sample_uniform_triangle(rnd_p);
+        let (a, ab, ac) = self.vertices();
+        Some(ShapeSample {
+            hit: Interaction {
+                point: a + alpha * ab + beta * ac,
+                normal: self.normal().cast(),
+                t: 0.0,
+                outgoing: Default::default(),
+                uv: self.uv_at(alpha, beta),
+                flipped: false,
+            },
+            pdf: self.area().recip(),
+        })
+    }
+
+    fn sample_from_point(&self, point: Point3f, rnd_p: Point2f) -> Option<ShapeSample> {
+        let solid_angle = self.solid_angle(point);
+        if !(Self::MIN_SPHERICAL_AREA..=Self::MAX_SPHERICAL_AREA).contains(&solid_angle) {
+            // Sample by area and convert the density to solid angle
+            let mut sample = self.sample(rnd_p)?;
+            let to_light = sample.hit.point - point;
+            let dist_squared = to_light.len_squared();
+            if dist_squared == 0. {
+                return None;
+            }
+            let cos = dot(&to_light.to_unit(), &self.normal()).abs();
+            if cos == 0. {
+                return None;
+            }
+            sample.pdf *= dist_squared / cos;
+            return Some(sample);
+        }
+
+        let (a, ab, ac) = self.vertices();
+        let ([_, alpha, beta], pdf) = sample_spherical_triangle([a, a + ab, a + ac], point, rnd_p)?;
+        Some(ShapeSample {
+            hit: Interaction {
+                point: a + alpha * ab + beta * ac,
+                normal: self.normal().cast(),
+                t: 0.0,
+                outgoing: Default::default(),
+                uv: self.uv_at(alpha, beta),
+                flipped: false,
+            },
+            pdf,
+        })
+    }
+
+    fn pdf(&self, interaction: &Interaction) -> f32 { self.area().recip() }
+
+    fn pdf_incoming(&self, interaction: &SurfaceInteraction, incoming: Unit<Vec3f>) -> f32 {
+        let ray = ray!(interaction.hit.point, incoming);
+        let Some((t, _, _)) = self.hit(&ray) else {
+            return 0.;
+        };
+        let solid_angle = self.solid_angle(interaction.hit.point);
+        if (Self::MIN_SPHERICAL_AREA..=Self::MAX_SPHERICAL_AREA).contains(&solid_angle) {
+            return solid_angle.recip();
+        }
+        let cos = dot(&incoming, &self.normal()).abs();
+        if cos == 0. {
+            return 0.;
+        }
+        t.pow(2) / (cos * self.area())
+    }
+
+    fn area(&self) -> f32 {
+        let (_, ab, ac) = self.vertices();
+        cross(&ab, &ac).len() / 2.
+    }
+
+    fn normal_bounds(&self) -> DirectionCone { DirectionCone::from_direction(self.normal()) }
+}
+
+impl Bounded<f32> for Triangle {
+    fn bound(&self) -> Aabb<f32> {
+        let (a, ab, ac) = self.vertices();
+        Aabb::from_points(a, a + ab) + Aabb::from_points(a, a + ac)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::{point3, vec3};
+
+    fn triangle(uvs: Option<Vec<Point2f>>) -> Triangle {
+        let positions = vec![point3!(0., 0., 0.), point3!(2., 0., 0.), point3!(0., 2., 0.)];
+        let mesh = TriangleMesh::new(positions, vec![0, 1, 2], None, uvs, Transform::id());
+        mesh.into_triangles().pop().unwrap()
+    }
+
+    #[test]
+    fn test_uv() {
+        let uvs = vec![point2!(0., 0.), point2!(1., 0.), point2!(1., 1.)];
+        let triangle = triangle(Some(uvs));
+        let ray = ray!(point3!(1.5, 0.5, -1.), vec3!(0., 0., 1.).to_unit());
+        let hit = triangle.intersect(&ray, f32::INFINITY).unwrap();
+        assert!((hit.hit.uv.x - 1.).abs() < 1e-5);
+        assert!((hit.hit.uv.y - 0.25).abs() < 1e-5);
+        assert!((hit.dp_du - vec3!(2., 0., 0.)).len() < 1e-5);
+        assert!((hit.dp_dv - vec3!(-2., 2., 0.)).len() < 1e-5);
+    }
+
+    #[test]
+    fn test_sample() {
+        let triangle = triangle(None);
+        assert_eq!(triangle.area(), 2.);
+        let origin = point3!(0.5, 0.5, -1.);
+        let sample = triangle.sample_from_point(origin, point2!(0.3, 0.6)).unwrap();
+        let point = sample.hit.point;
+        assert!(point.x >= 0. && point.y >= 0. && point.x + point.y <= 2. && point.z == 0.);
+
+        let interaction = SurfaceInteraction::new(
+            Interaction {
+                point: origin,
+                normal: vec3!(0., 0., 1.).to_normal().to_unit(),
+                t: 0.,
+                outgoing: vec3!(0., 0., 1.).to_unit(),
+                uv: Point2f::default(),
+                flipped: false,
+            },
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let pdf = triangle.pdf_incoming(&interaction, (point - origin).to_unit());
+        assert!((pdf - sample.pdf).abs() < 1e-4 * pdf);
+    }
+
+    #[test]
+    fn test_sample_solid_angle() {
+        // Covers an octant of the directions from the origin
+        let positions = vec![point3!(1., 0., 0.), point3!(0., 1., 0.), point3!(0., 0., 1.)];
+        let mesh = TriangleMesh::new(positions, vec![0, 1, 2], None, None, Transform::id());
+        let triangle = mesh.into_triangles().pop().unwrap();
+        let origin = point3!(0., 0., 0.);
+        assert!((triangle.solid_angle(origin) - PI / 2.).abs() < 1e-5);
+
+        // Stratified samples are spread evenly over the octant
+        let n = 16;
+        let mut mean = vec3!(0., 0., 0.);
+        for i in 0..n {
+            for j in 0..n {
+                let rnd_p = point2!((i as f32 + 0.5) / n as f32, (j as f32 + 0.5) / n as f32);
+                let sample = triangle.sample_from_point(origin, rnd_p).unwrap();
+                let point = sample.hit.point;
+                assert!((point.x + point.y + point.z - 1.).abs() < 1e-5);
+                assert!(point.x >= -1e-5 && point.y >= -1e-5 && point.z >= -1e-5);
+                assert!((sample.pdf - 2. / PI).abs() < 1e-4);
+                mean += *(point - origin).to_unit() / (n * n) as f32;
+            }
+        }
+        // By symmetry, the average direction is along (1, 1, 1) with z = 1/2 by Archimedes' theorem
+        assert!((mean - vec3!(0.5, 0.5, 0.5)).len() < 0.02);
+    }
+}
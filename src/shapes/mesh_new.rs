@@ -1,8 +0,0 @@
-// use crate::Point3f;
-//
-// pub struct Mesh{
-//     n_triangles: u32,
-//     n_vertices: u32,
-//     vertex_indices: Vec<u32>,
-//     vertices: Vec<Point3f>
-// }
\ No newline at end of file
@@ -3,14 +3,13 @@ use std::fmt::Debug;
 use crate::{
     aggregates::Bounded,
     core::{Interaction, Ray, SurfaceInteraction},
-    math::{Number, Unit},
+    math::{DirectionCone, Number, Unit},
     Point2f, Point3f, Vec3f,
 };
 
 pub mod mesh;
 pub mod quad;
 pub mod sphere;
-// mod mesh_new;
 
 pub trait Intersectable {
     fn intersect(&self, ray: &Ray, t_max: f32) -> Option<SurfaceInteraction>;
@@ -37,6 +36,9 @@ pub trait Samplable {
 
     /// Surface area of the object
     fn area(&self) -> f32;
+
+    /// Directions of the surface normals
+    fn normal_bounds(&self) -> DirectionCone;
 }
 
 pub trait BoundedIntersectable: Bounded<f32> + Intersectable + Samplable + Debug {}
@@ -3,8 +3,11 @@ use std::ops::Deref;
 use crate::{
     aggregates::Aabb,
     core::{Interaction, Ray, SurfaceInteraction},
-    math::{axis::Axis3, cross, dot, utils::local_normal, Cross, Dot, Frame, Normed, Transform, Transformable, Unit},
-    point3,
+    math::{
+        axis::Axis3, cross, dot, utils::local_normal, Cross, DirectionCone, Dot, Frame, Normed, Transform,
+        Transformable, Unit,
+    },
+    point2, point3, ray,
     shapes::{Bounded, Intersectable, Samplable, ShapeSample},
     unit_normal3_unchecked, Point2f, Point3f, Vec3f,
 };
@@ -41,6 +44,8 @@ impl Quad {
         }
     }
 
+    /// Axis-aligned box centered at the origin. Sides face outwards, so the inside of the box is the inside of a
+    /// medium interface
     pub fn quad_box(width: f32, height: f32, depth: f32, transform: Transform<f32>) -> Vec<Quad> {
         let mut sides = Vec::with_capacity(6);
 
@@ -51,11 +56,11 @@ impl Quad {
         let py = diag.only(Axis3::Y);
         let pz = diag.only(Axis3::Z);
 
-        sides.push(Quad::new(a, px, py, transform));
+        sides.push(Quad::new(a, py, px, transform));
         sides.push(Quad::new(a, px, pz, transform));
-        sides.push(Quad::new(a, py, pz, transform));
+        sides.push(Quad::new(a, pz, py, transform));
         sides.push(Quad::new(b, -px, -py, transform));
-        sides.push(Quad::new(b, -px, -pz, transform));
+        sides.push(Quad::new(b, -pz, -px, transform));
         sides.push(Quad::new(b, -py, -pz, transform));
 
         sides
@@ -89,7 +94,9 @@ impl Intersectable for Quad {
                     normal,
                     t,
                     outgoing: -ray.dir,
-                    uv: Point2f::default(),
+                    // hit_point = a + beta * ab + alpha * ac
+                    uv: point2!(beta, alpha),
+                    flipped: denom > 0.,
                 },
                 self.ab,
                 self.ac,
@@ -130,7 +137,6 @@ impl Bounded<f32> for Quad {
 
 impl Samplable for Quad {
     fn sample(&self, rnd_p: Point2f) -> Option<ShapeSample> {
-        // TODO: uv,pdf
         let point = self.a + rnd_p.x * self.ab + rnd_p.y * self.ac;
         Some(ShapeSample {
             hit: Interaction {
@@ -138,31 +144,80 @@ impl Samplable for Quad {
                 normal: self.normal.cast(),
                 t: 0.0,
                 outgoing: Default::default(),
-                uv: Default::default(),
+                // Same parametrization as in `intersect`
+                uv: rnd_p,
+                flipped: false,
             },
-            // pdf: self.area().recip(),
-            pdf: 1.,
+            pdf: self.area().recip(),
         })
     }
 
     fn sample_from_point(&self, point: Point3f, rnd_p: Point2f) -> Option<ShapeSample> {
-        // TODO: this
-        // let mut sample = self.sample(rnd_p).unwrap();
-        // let cos = dot(&((self.a + 0.5 * (self.ab + self.ac)) - point).to_unit(), &self.normal).abs();
-        // sample.pdf /= cos;
-        // Some(sample)
-        self.sample(rnd_p)
+        // Sample by area and convert the density to solid angle
+        let mut sample = self.sample(rnd_p)?;
+        let to_light = sample.hit.point - point;
+        let dist_squared = to_light.len_squared();
+        if dist_squared == 0. {
+            return None;
+        }
+        let cos = dot(&to_light.to_unit(), &self.normal).abs();
+        if cos == 0. {
+            return None;
+        }
+        sample.pdf *= dist_squared / cos;
+        Some(sample)
     }
 
-    fn pdf(&self, interaction: &Interaction) -> f32 { todo!() }
+    fn pdf(&self, interaction: &Interaction) -> f32 { self.area().recip() }
 
     fn pdf_incoming(&self, interaction: &SurfaceInteraction, incoming: Unit<Vec3f>) -> f32 {
-        // TODO: !!!
-        let dir = interaction.hit.point - self.a;
-        let cos = dot(&incoming, &dir).abs();
-        let dist = dir.len_squared();
-        self.area() * cos / dist
+        let ray = ray!(interaction.hit.point, incoming);
+        let Some(hit) = self.intersect(&ray, f32::INFINITY) else {
+            return 0.;
+        };
+        let cos = dot(&incoming, &self.normal).abs();
+        if cos == 0. {
+            return 0.;
+        }
+        hit.hit.t.powi(2) / (cos * self.area())
     }
 
     fn area(&self) -> f32 { cross(&self.ab, &self.ac).len() }
+
+    fn normal_bounds(&self) -> DirectionCone { DirectionCone::from_direction(self.normal) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3;
+
+    #[test]
+    fn test_sample() {
+        let quad = Quad::new(
+            point3!(0., 0., 0.),
+            vec3!(2., 0., 0.),
+            vec3!(0., 3., 0.),
+            Transform::id(),
+        );
+        assert_eq!(quad.area(), 6.);
+        let sample = quad.sample(point2!(0.25, 0.5)).unwrap();
+        assert_eq!(sample.hit.point, point3!(0.5, 1.5, 0.));
+        assert_eq!(sample.pdf, 1. / 6.);
+
+        // Sampled points are hit with the same uv and the solid angle densities agree
+        let mut interaction = SurfaceInteraction::default();
+        interaction.hit.point = point3!(1., 1., 2.);
+        let sample = quad
+            .sample_from_point(interaction.hit.point, point2!(0.25, 0.5))
+            .unwrap();
+        let incoming = (sample.hit.point - interaction.hit.point).to_unit();
+        let hit = quad
+            .intersect(&ray!(interaction.hit.point, incoming), f32::INFINITY)
+            .unwrap();
+        assert!((hit.hit.uv - sample.hit.uv).len() < 1e-5);
+        let pdf = quad.pdf_incoming(&interaction, incoming);
+        assert!((pdf - sample.pdf).abs() < 1e-4 * pdf);
+        assert_eq!(quad.pdf_incoming(&interaction, vec3!(0., 0., 1.).to_unit()), 0.);
+    }
 }
@@ -9,7 +9,7 @@ use crate::{
     math::{
         cross, dot,
         utils::spherical_coordinates::{spherical_direction, spherical_phi},
-        Dot, Frame, Normed, Number, Point3, Transform, Transformable, Unit,
+        DirectionCone, Dot, Frame, Normed, Number, Point3, Transform, Transformable, Unit,
     },
     point2, point3, ray,
     samplers::utils::{sample_uniform_cone, sample_uniform_sphere},
@@ -58,6 +58,7 @@ impl Sphere {
             t: root,
             outgoing: -ray.dir,
             uv: point2!(),
+            flipped: false,
         })
     }
 
@@ -126,7 +127,7 @@ impl Intersectable for Sphere {
 impl Samplable for Sphere {
     fn sample(&self, rnd_p: Point2f) -> Option<ShapeSample> {
         let point_obj = point3!(self.radius * *sample_uniform_sphere(rnd_p));
-        let normal = point_obj.to_normal().transform(&self.object_to_world).to_unit();
+        let normal = point_obj.to_normal().to_unit();
 
         let phi = spherical_phi(*point_obj);
         let theta = (point_obj.z / self.radius).acos();
@@ -140,6 +141,7 @@ impl Samplable for Sphere {
                 t: 0.,
                 outgoing: Default::default(),
                 uv: point2!(u, v),
+                flipped: false,
             }
             .transform(&self.object_to_world),
             pdf: self.area().recip(),
@@ -147,134 +149,66 @@ impl Samplable for Sphere {
     }
 
     fn sample_from_point(&self, origin: Point3f, rnd_p: Point2f) -> Option<ShapeSample> {
-        // TODO: doesn't work
         let point = origin.inv_transform(&self.object_to_world);
-        if point.len_squared() < self.radius.powi(2) + 1e-4 {
-            let mut ss = self.sample(rnd_p).unwrap();
-            let incoming = ss.hit.point - point;
-            ss.pdf /= dot(&ss.hit.normal, &-incoming).abs() / (point - ss.hit.point).len_squared();
-            return if ss.pdf.is_infinite() { None } else { Some(ss) };
+        if point.len_squared() <= self.radius.powi(2) {
+            // Sample by area and convert the density to solid angle
+            let mut sample = self.sample(rnd_p)?;
+            let incoming = sample.hit.point - origin;
+            let dist_squared = incoming.len_squared();
+            if dist_squared == 0. {
+                return None;
+            }
+            sample.pdf *= dist_squared / dot(&sample.hit.normal, &incoming.to_unit()).abs();
+            return sample.pdf.is_finite().then_some(sample);
         }
 
-        let max_sin_theta = self.radius / point.len();
-        let max_cos_theta = (1. - max_sin_theta.powi(2)).sqrt();
-        let sampled_dir = sample_uniform_cone(rnd_p, max_cos_theta).to_unit();
-        let interaction = self
-            .basic_intersect(ray!(point, sampled_dir), f32::INFINITY)
-            .unwrap()
-            .inv_transform(&self.object_to_world);
+        // Sample the cone of directions towards the visible cap
+        let sin2_theta_max = self.radius.powi(2) / point.len_squared();
+        let cos_theta_max = (1. - sin2_theta_max).sqrt();
+        let frame = Frame::from_z(*(-point.coords).to_unit());
+        let local_dir = sample_uniform_cone(rnd_p, cos_theta_max);
+        let sampled_dir = frame.from_local(local_dir).to_unit();
+        let interaction = self.basic_intersect(ray!(point, sampled_dir), f32::INFINITY)?;
+        let surf_int = self
+            .calculate_surface_interaction(interaction)
+            .transform(&self.object_to_world);
         Some(ShapeSample {
-            hit: interaction,
-            pdf: 1. / (2. * PI * (1. - max_cos_theta)),
+            hit: Interaction {
+                outgoing: Default::default(),
+                flipped: false,
+                ..surf_int.hit
+            },
+            pdf: (2. * PI * (1. - cos_theta_max)).recip(),
         })
-
-        // let p_center: Point3f = point3!().transform(&self.transform);
-        // let p_origin: Point3f = origin;
-        // // Sample uniformly on sphere if $\pt{}$ is inside it
-        // if (p_origin-p_center).len_squared() <= self.radius.powi(2) {
-        //     // Sample shape by area and compute incident direction _wi_
-        //     let mut ss = self.sample(rnd_p).expect("Sphere sample() failed!");
-        //     let wi = ss.hit.point - origin;
-        //     if wi.len_squared() == 0.0 {
-        //         return None;
-        //     }
-        //     let wi = wi.to_unit();
-        //
-        //     // Convert area sampling PDF in _ss_ to solid angle measure
-        //     ss.pdf /= dot(&ss.hit.normal, &-wi) / (origin - ss.hit.point).len_squared();
-        //     if ss.pdf.is_infinite() {
-        //         return None;
-        //     }
-        //     return Some(ss);
-        // }
-        //
-        // // Sample sphere uniformly inside subtended cone
-        // // Compute quantities related to the $\theta_\roman{max}$ for cone
-        // let sin_theta_max = self.radius / (origin - p_center).len();
-        // let sin2_theta_max = (sin_theta_max).powi(2);
-        // let cos_theta_max = (1.0 - sin2_theta_max).sqrt();
-        // let mut one_minus_cos_theta_max = 1.0 - cos_theta_max;
-        //
-        // // Compute $\theta$ and $\phi$ values for sample in cone
-        // let mut cos_theta = (cos_theta_max - 1.0) * rnd_p.x + 1.0;
-        // let mut sin2_theta = 1.0 - cos_theta.powi(2);
-        // /* sin^2(1.5 deg) */
-        // if sin2_theta_max < 0.00068523
-        // {
-        //     // Compute cone sample via Taylor series expansion for small angles
-        //     sin2_theta = sin2_theta_max * rnd_p.x;
-        //     cos_theta = (1.0 - sin2_theta).sqrt();
-        //     one_minus_cos_theta_max = sin2_theta_max / 2.0;
-        // }
-        //
-        // // Compute angle $\alpha$ from center of sphere to sampled point on surface
-        // let cos_alpha = sin2_theta / sin_theta_max
-        //     + cos_theta * (1.0 - sin2_theta / sin_theta_max.powi(2)).sqrt();
-        // let sin_alpha = (1.0 - cos_alpha.powi(2)).sqrt();
-        //
-        // // Compute surface normal and sampled point on sphere
-        // let phi = rnd_p.y * 2.0 * PI;
-        // let w = spherical_direction(sin_alpha, cos_alpha, phi);
-        // let sampling_frame = Frame::from_z(p_center - origin);
-        // let normal_sign = if /*self.reverse_orientation */ false { -1.0 } else { 1.0 };
-        // let n = Normal3f::new(sampling_frame.from_local(-w)* normal_sign) ;
-        // let p = p_center + vec3!(n.x, n.y, n.z) * self.radius;
-        //
-        // // Return _ShapeSample_ for sampled point on sphere
-        // // Compute $(u,v)$ coordinates for sampled point on sphere
-        // let p_obj = p.inv_transform(&self.transform);
-        // let theta = (p_obj.z / self.radius).acos();
-        // let mut sphere_phi = f32::atan2(p_obj.y, p_obj.x);
-        // if sphere_phi < 0.0 {
-        //     sphere_phi += 2.0 * PI;
-        // }
-        // let uv = Point2f::new(
-        //     sphere_phi,
-        //     theta,
-        // );
-        //
-        // debug_assert_ne!(one_minus_cos_theta_max, 0.0); // very small far away sphere
-        // let interaction = Interaction {
-        //     point: p,
-        //     t: 0.,
-        //     outgoing: Unit::from_unchecked(Vec3f::default()),
-        //     normal: n.to_unit(),
-        //     uv,
-        // };
-        // Some(ShapeSample {
-        //     hit: interaction,
-        //     pdf: 1.0 / (2.0 * PI * one_minus_cos_theta_max),
-        // })
     }
 
     fn pdf(&self, interaction: &Interaction) -> f32 { self.area().recip() }
 
     fn pdf_incoming(&self, interaction: &SurfaceInteraction, incoming: Unit<Vec3f>) -> f32 {
-        let center = point3!().inv_transform(&self.object_to_world);
+        let center = point3!().transform(&self.object_to_world);
         let origin = interaction.hit.point;
-        if (origin - center).len_squared() < self.radius.powi(2) {
+        if (origin - center).len_squared() <= self.radius.powi(2) {
             let ray = interaction.spawn_ray(incoming);
             let Some(int) = self.intersect(&ray, f32::INFINITY) else {
                 return 0.;
             };
-            let mut pdf = self.area().recip()
-                / dot(&int.hit.normal, &-incoming).abs()
-                / (interaction.hit.point - int.hit.point).len_squared();
+            let pdf = self.area().recip() * (interaction.hit.point - int.hit.point).len_squared()
+                / dot(&int.hit.normal, &incoming).abs();
             if pdf.is_infinite() {
                 0.
             } else {
                 pdf
             }
         } else {
-            let sin2_theta_max = self.radius.powi(2) / (interaction.hit.point - center).len_squared();
+            let sin2_theta_max = self.radius.powi(2) / (origin - center).len_squared();
             let cos_theta_max = (1. - sin2_theta_max).sqrt();
-            let one_minus_cos_theta_max = 1. - cos_theta_max;
-            // TODO: Compute more accurate oneMinusCosThetaMax for small solid angle
-            (2. * PI * one_minus_cos_theta_max).recip()
+            (2. * PI * (1. - cos_theta_max)).recip()
         }
     }
 
     fn area(&self) -> f32 { 4. * PI * self.radius.powi(2) }
+
+    fn normal_bounds(&self) -> DirectionCone { DirectionCone::entire_sphere() }
 }
 
 impl Bounded<f32> for Sphere {
@@ -307,6 +241,31 @@ mod tests {
         assert_eq!(aabb, expected)
     }
 
+    #[test]
+    fn test_sample_from_point() {
+        let center = point3!(1., 2., 3.);
+        let sphere = Sphere::new(1.0, Transform::translate(center.coords));
+        let mut interaction = SurfaceInteraction::default();
+        interaction.hit.point = point3!(1., 2., 6.);
+        for rnd_p in [point2!(0.1, 0.2), point2!(0.5, 0.5), point2!(0.9, 0.7)] {
+            let sample = sphere.sample_from_point(interaction.hit.point, rnd_p).unwrap();
+            let point = sample.hit.point;
+            assert!(((point - center).len() - 1.).abs() < 1e-4);
+            // On the cap visible from the point
+            assert!(dot(&sample.hit.normal, &(interaction.hit.point - point)) > 0.);
+            let pdf = sphere.pdf_incoming(&interaction, (point - interaction.hit.point).to_unit());
+            assert!((pdf - sample.pdf).abs() < 1e-4 * pdf);
+        }
+
+        // From the inside
+        interaction.hit.point = point3!(1., 2., 3.5);
+        let sample = sphere
+            .sample_from_point(interaction.hit.point, point2!(0.3, 0.6))
+            .unwrap();
+        let pdf = sphere.pdf_incoming(&interaction, (sample.hit.point - interaction.hit.point).to_unit());
+        assert!((pdf - sample.pdf).abs() < 1e-3 * pdf);
+    }
+
     #[test]
     fn test_aabb_translated_scaled() {
         let sphere = Sphere::new(
@@ -1,6 +1,7 @@
 use std::{array, env::var};
 
 use arrayvec::ArrayVec;
+pub use blackbody::BlackbodySpectrum;
 pub use constant::ConstantSpectrum;
 pub use densely_sampled::DenselySampledSpectrum;
 use named::NamedSpectra;
@@ -8,7 +9,10 @@ pub use rgb_spectrum::{RGBAlbedoSpectrum, RGBIlluminantSpectrum, RGBUnboundedSpe
 pub use sampled_spectrum::SampledSpectrum;
 pub use sampled_wavelengths::SampledWavelengths;
 
-use crate::spectra::{blackbody::BlackbodySpectrum, piecewise_linear::PiecewiseLinearSpectrum};
+use crate::spectra::{
+    cie::{CIE, CIE_Y_INTEGRAL},
+    piecewise_linear::PiecewiseLinearSpectrum,
+};
 
 mod blackbody;
 mod cie;
@@ -35,6 +39,13 @@ pub trait Spectrum {
         let values: [f32; N] = array::from_fn(|i| self.value(lambda[i]));
         SampledSpectrum::from(values)
     }
+
+    /// Maximum value over the supported wavelength range, found by sampling it every nanometer
+    fn max_value(&self) -> f32 {
+        (LAMBDA_MIN as i32..=LAMBDA_MAX as i32)
+            .map(|x| self.value(x as f32))
+            .fold(0., f32::max)
+    }
 }
 
 #[enum_delegate::implement(Spectrum)]
@@ -54,3 +65,14 @@ fn inner_product<F: Spectrum, G: Spectrum>(f: &F, g: &G) -> f32 {
         .map(|x| f.value(x as f32) * g.value(x as f32))
         .sum()
 }
+
+/// Luminance of an emission spectrum, relative to the normalized standard illuminants. For RGB illuminant spectra
+/// only the illuminant is taken into account, so that the RGB value still sets the brightness.
+/// SpectrumToPhotometric() in PBRT
+pub fn spectrum_to_photometric(spectrum: &SpectrumEnum) -> f32 {
+    let spectrum = match spectrum {
+        SpectrumEnum::RGBIlluminant(x) => x.illuminant(),
+        x => x,
+    };
+    inner_product(CIE::Y.get(), spectrum) / CIE_Y_INTEGRAL
+}
@@ -3,21 +3,149 @@ use std::sync::{Arc, LazyLock};
 use crate::spectra::{piecewise_linear::PiecewiseLinearSpectrum, SpectrumEnum};
 
 pub enum NamedSpectra {
+    IlluminantA,
+    IlluminantD50,
     IlluminantD65,
+    /// The ACES white point, a CIE daylight spectrum at 6000K
+    IlluminantAcesD60,
+    /// CIE fluorescent illuminants F1 to F12
+    IlluminantF(usize),
+    // Complex refractive indices of metals, real (eta) and imaginary (k) parts
+    AuEta,
+    AuK,
+    AgEta,
+    AgK,
+    AlEta,
+    AlK,
+    CrEta,
+    CrK,
+    CuEta,
+    CuK,
+    TiEta,
+    TiK,
 }
 
 impl NamedSpectra {
     pub fn get(&self) -> Arc<SpectrumEnum> {
         match self {
+            NamedSpectra::IlluminantA => ILLUMINANT_A.clone(),
+            NamedSpectra::IlluminantD50 => ILLUMINANT_D50.clone(),
             NamedSpectra::IlluminantD65 => ILLUMINANT_D65.clone(),
+            NamedSpectra::IlluminantAcesD60 => ILLUMINANT_ACES_D60.clone(),
+            NamedSpectra::IlluminantF(i) => ILLUMINANTS_F[i - 1].clone(),
+            NamedSpectra::AuEta => AU_ETA.clone(),
+            NamedSpectra::AuK => AU_K.clone(),
+            NamedSpectra::AgEta => AG_ETA.clone(),
+            NamedSpectra::AgK => AG_K.clone(),
+            NamedSpectra::AlEta => AL_ETA.clone(),
+            NamedSpectra::AlK => AL_K.clone(),
+            NamedSpectra::CrEta => CR_ETA.clone(),
+            NamedSpectra::CrK => CR_K.clone(),
+            NamedSpectra::CuEta => CU_ETA.clone(),
+            NamedSpectra::CuK => CU_K.clone(),
+            NamedSpectra::TiEta => TI_ETA.clone(),
+            NamedSpectra::TiK => TI_K.clone(),
         }
     }
+
+    /// Looks up a spectrum by its PBRT name, e.g. `stdillum-D65` or `metal-Au-eta`
+    pub fn from_name(name: &str) -> Option<NamedSpectra> {
+        if let Some(i) = name.strip_prefix("stdillum-F").and_then(|i| i.parse::<usize>().ok()) {
+            return (1..=CIE_ILLUMINANTS_F.len())
+                .contains(&i)
+                .then_some(NamedSpectra::IlluminantF(i));
+        }
+        match name {
+            "stdillum-A" => Some(NamedSpectra::IlluminantA),
+            "stdillum-D50" => Some(NamedSpectra::IlluminantD50),
+            "stdillum-D65" => Some(NamedSpectra::IlluminantD65),
+            "illum-acesD60" => Some(NamedSpectra::IlluminantAcesD60),
+            "metal-Au-eta" => Some(NamedSpectra::AuEta),
+            "metal-Au-k" => Some(NamedSpectra::AuK),
+            "metal-Ag-eta" => Some(NamedSpectra::AgEta),
+            "metal-Ag-k" => Some(NamedSpectra::AgK),
+            "metal-Al-eta" => Some(NamedSpectra::AlEta),
+            "metal-Al-k" => Some(NamedSpectra::AlK),
+            "metal-Cr-eta" => Some(NamedSpectra::CrEta),
+            "metal-Cr-k" => Some(NamedSpectra::CrK),
+            "metal-Cu-eta" => Some(NamedSpectra::CuEta),
+            "metal-Cu-k" => Some(NamedSpectra::CuK),
+            "metal-Ti-eta" => Some(NamedSpectra::TiEta),
+            "metal-Ti-k" => Some(NamedSpectra::TiK),
+            _ => None,
+        }
+    }
+}
+
+fn interleaved(data: &[f32], normalize: bool) -> Arc<SpectrumEnum> {
+    Arc::new(PiecewiseLinearSpectrum::from_interleaved(data, normalize).into())
+}
+
+/// CIE standard illuminant A, Planck's law at 2848K as given in the CIE 15 definition, sampled at 5nm
+fn illuminant_a() -> Vec<f32> {
+    const C2: f64 = 1.435e7;
+    const T: f64 = 2848.;
+    (300..=830)
+        .step_by(5)
+        .flat_map(|lambda| {
+            let lambda = lambda as f64;
+            let value =
+                100. * (560. / lambda).powi(5) * ((C2 / (T * 560.)).exp() - 1.) / ((C2 / (T * lambda)).exp() - 1.);
+            [lambda as f32, value as f32]
+        })
+        .collect()
+}
+
+/// CIE daylight spectrum for the correlated color temperature `cct`, built from the S0, S1 and S2 basis functions
+fn daylight(cct: f32) -> Vec<f32> {
+    let t = cct as f64;
+    let x = if t <= 7000. {
+        -4.6070e9 / t.powi(3) + 2.9678e6 / t.powi(2) + 0.09911e3 / t + 0.244063
+    } else {
+        -2.0064e9 / t.powi(3) + 1.9018e6 / t.powi(2) + 0.24748e3 / t + 0.237040
+    };
+    let y = -3. * x * x + 2.870 * x - 0.275;
+    let m = 0.0241 + 0.2562 * x - 0.7341 * y;
+    let m1 = (-1.3515 - 1.7703 * x + 5.9114 * y) / m;
+    let m2 = (0.0300 - 31.4424 * x + 30.0717 * y) / m;
+    (0..CIE_DAYLIGHT_S0.len())
+        .flat_map(|i| {
+            let value = CIE_DAYLIGHT_S0[i] as f64 + m1 * CIE_DAYLIGHT_S1[i] as f64 + m2 * CIE_DAYLIGHT_S2[i] as f64;
+            [(300 + 10 * i) as f32, value as f32]
+        })
+        .collect()
 }
 
-pub static ILLUMINANT_D65: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| {
-    let spectrum = PiecewiseLinearSpectrum::from_interleaved(&CIE_ILLUMINANT_D65, true).into();
-    Arc::new(spectrum)
+static ILLUMINANT_A: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&illuminant_a(), true));
+// The nominal temperatures predate the revised value of c2 in Planck's law, hence 5003K rather than 5000K
+static ILLUMINANT_D50: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&daylight(5003.), true));
+pub static ILLUMINANT_D65: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&CIE_ILLUMINANT_D65, true));
+static ILLUMINANT_ACES_D60: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&daylight(6000.), true));
+static ILLUMINANTS_F: LazyLock<Vec<Arc<SpectrumEnum>>> = LazyLock::new(|| {
+    CIE_ILLUMINANTS_F
+        .iter()
+        .map(|values| {
+            let data: Vec<f32> = values
+                .iter()
+                .enumerate()
+                .flat_map(|(i, value)| [(380 + 5 * i) as f32, *value])
+                .collect();
+            interleaved(&data, true)
+        })
+        .collect()
 });
+static AU_ETA: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_AU_ETA, false));
+static AU_K: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_AU_K, false));
+static AG_ETA: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_AG_ETA, false));
+static AG_K: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_AG_K, false));
+static AL_ETA: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_AL_ETA, false));
+static AL_K: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_AL_K, false));
+static CR_ETA: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_CR_ETA, false));
+static CR_K: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_CR_K, false));
+static CU_ETA: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_CU_ETA, false));
+static CU_K: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_CU_K, false));
+static TI_ETA: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_TI_ETA, false));
+static TI_K: LazyLock<Arc<SpectrumEnum>> = LazyLock::new(|| interleaved(&METAL_TI_K, false));
 
 #[rustfmt::skip]
 #[allow(clippy::all)]
@@ -39,3 +167,315 @@ static CIE_ILLUMINANT_D65: [f32; 214] = [
     790.000000, 64.304001,  795.000000, 61.877899,  800.000000, 59.451900,  805.000000, 55.705399,  810.000000, 51.959000,  815.000000, 54.699799,  820.000000, 57.440601,
     825.000000, 58.876499,  830.000000, 60.312500
 ];
+
+// Measured data of Johnson and Christy (1972) for Au, Ag and Cu, values for Al (Rakić 1995), Cr and Ti (Johnson and
+// Christy 1974) are rounded and sampled every 50 nm
+#[rustfmt::skip]
+static METAL_AU_ETA: [f32; 28] = [
+    381.49, 1.4600, 397.38, 1.4700, 413.28, 1.4600, 430.50, 1.4500, 450.85, 1.3800, 471.42, 1.3100, 495.94, 1.0400,
+    520.94, 0.6200, 548.60, 0.4300, 582.08, 0.2900, 616.84, 0.2100, 659.49, 0.1400, 704.45, 0.1300, 756.00, 0.1400
+];
+
+#[rustfmt::skip]
+static METAL_AU_K: [f32; 28] = [
+    381.49, 1.9330, 397.38, 1.9520, 413.28, 1.9580, 430.50, 1.9480, 450.85, 1.9140, 471.42, 1.8490, 495.94, 1.8330,
+    520.94, 2.0810, 548.60, 2.4550, 582.08, 2.8630, 616.84, 3.2720, 659.49, 3.6970, 704.45, 4.1030, 756.00, 4.5420
+];
+
+#[rustfmt::skip]
+static METAL_AG_ETA: [f32; 28] = [
+    381.49, 0.0500, 397.38, 0.0500, 413.28, 0.0500, 430.50, 0.0400, 450.85, 0.0400, 471.42, 0.0500, 495.94, 0.0500,
+    520.94, 0.0500, 548.60, 0.0600, 582.08, 0.0500, 616.84, 0.0600, 659.49, 0.0500, 704.45, 0.0400, 756.00, 0.0300
+];
+
+#[rustfmt::skip]
+static METAL_AG_K: [f32; 28] = [
+    381.49, 1.8640, 397.38, 2.0700, 413.28, 2.2750, 430.50, 2.4620, 450.85, 2.6570, 471.42, 2.8690, 495.94, 3.0930,
+    520.94, 3.3240, 548.60, 3.5860, 582.08, 3.8580, 616.84, 4.1520, 659.49, 4.4830, 704.45, 4.8380, 756.00, 5.2420
+];
+
+#[rustfmt::skip]
+static METAL_CU_ETA: [f32; 72] = [
+    381.49, 1.2000, 387.45, 1.1800, 393.60, 1.1744, 399.95, 1.1750, 406.51, 1.1775, 413.28, 1.1800, 420.29, 1.1781,
+    427.53, 1.1750, 435.03, 1.1728, 442.80, 1.1700, 450.85, 1.1653, 459.20, 1.1600, 467.86, 1.1553, 476.86, 1.1500,
+    486.21, 1.1428, 495.94, 1.1350, 506.06, 1.1316, 516.60, 1.1200, 527.59, 1.0924, 539.06, 1.0400, 551.04, 0.9504,
+    563.56, 0.8260, 576.67, 0.6459, 590.40, 0.4680, 604.80, 0.3513, 619.92, 0.2720, 635.82, 0.2308, 652.55, 0.2140,
+    670.18, 0.2092, 688.80, 0.2130, 708.48, 0.2162, 729.32, 0.2230, 751.42, 0.2365, 774.90, 0.2500, 799.90, 0.2542,
+    826.56, 0.2600
+];
+
+#[rustfmt::skip]
+static METAL_CU_K: [f32; 72] = [
+    381.49, 2.1216, 387.45, 2.2100, 393.60, 2.1772, 399.95, 2.1300, 406.51, 2.1601, 413.28, 2.2100, 420.29, 2.2499,
+    427.53, 2.2890, 435.03, 2.3260, 442.80, 2.3620, 450.85, 2.3976, 459.20, 2.4330, 467.86, 2.4692, 476.86, 2.5040,
+    486.21, 2.5359, 495.94, 2.5640, 506.06, 2.5896, 516.60, 2.6050, 527.59, 2.5956, 539.06, 2.5830, 551.04, 2.5765,
+    563.56, 2.5990, 576.67, 2.6781, 590.40, 2.8090, 604.80, 3.0107, 619.92, 3.2400, 635.82, 3.4582, 652.55, 3.6700,
+    670.18, 3.8631, 688.80, 4.0500, 708.48, 4.2396, 729.32, 4.4300, 751.42, 4.6196, 774.90, 4.8170, 799.90, 5.0341,
+    826.56, 5.2600
+];
+
+#[rustfmt::skip]
+static METAL_AL_ETA: [f32; 18] = [
+    400.00, 0.4900, 450.00, 0.6200, 500.00, 0.7700, 550.00, 0.9600, 600.00, 1.2000, 650.00, 1.4700, 700.00, 1.8300,
+    750.00, 2.4000, 800.00, 2.8000
+];
+
+#[rustfmt::skip]
+static METAL_AL_K: [f32; 18] = [
+    400.00, 4.8600, 450.00, 5.4700, 500.00, 6.0800, 550.00, 6.6900, 600.00, 7.2600, 650.00, 7.7900, 700.00, 8.3100,
+    750.00, 8.6200, 800.00, 8.4500
+];
+
+#[rustfmt::skip]
+static METAL_CR_ETA: [f32; 18] = [
+    400.00, 2.0500, 450.00, 2.4000, 500.00, 2.7500, 550.00, 3.0300, 600.00, 3.1900, 650.00, 3.3000, 700.00, 3.4500,
+    750.00, 3.5500, 800.00, 3.6200
+];
+
+#[rustfmt::skip]
+static METAL_CR_K: [f32; 18] = [
+    400.00, 3.2400, 450.00, 3.3000, 500.00, 3.3300, 550.00, 3.3200, 600.00, 3.3300, 650.00, 3.3500, 700.00, 3.3700,
+    750.00, 3.4200, 800.00, 3.4800
+];
+
+#[rustfmt::skip]
+static METAL_TI_ETA: [f32; 18] = [
+    400.00, 1.8700, 450.00, 1.9700, 500.00, 2.1000, 550.00, 2.2200, 600.00, 2.3900, 650.00, 2.5900, 700.00, 2.8000,
+    750.00, 3.0200, 800.00, 3.2100
+];
+
+#[rustfmt::skip]
+static METAL_TI_K: [f32; 18] = [
+    400.00, 2.6500, 450.00, 2.8000, 500.00, 2.9600, 550.00, 3.0600, 600.00, 3.2300, 650.00, 3.3900, 700.00, 3.5300,
+    750.00, 3.6500, 800.00, 3.7400
+];
+
+// CIE daylight basis functions from 300nm to 830nm in 10nm steps
+#[rustfmt::skip]
+static CIE_DAYLIGHT_S0: [f32; 54] = [
+    0.04, 6.0, 29.6, 55.3, 57.3, 61.8, 61.5, 68.8, 63.4, 65.8,
+    94.8, 104.8, 105.9, 96.8, 113.9, 125.6, 125.5, 121.3, 121.3, 113.5,
+    113.1, 110.8, 106.5, 108.8, 105.3, 104.4, 100.0, 96.0, 95.1, 89.1,
+    90.5, 90.3, 88.4, 84.0, 85.1, 81.9, 82.6, 84.9, 81.3, 71.9,
+    74.3, 76.4, 63.3, 71.7, 77.0, 65.2, 47.7, 68.6, 65.0, 66.0,
+    61.0, 53.3, 58.9, 61.9,
+];
+
+#[rustfmt::skip]
+static CIE_DAYLIGHT_S1: [f32; 54] = [
+    0.02, 4.5, 22.4, 42.0, 40.6, 41.6, 38.0, 42.4, 38.5, 35.0,
+    43.4, 46.3, 43.9, 37.1, 36.7, 35.9, 32.6, 27.9, 24.3, 20.1,
+    16.2, 13.2, 8.6, 6.1, 4.2, 1.9, 0.0, -1.6, -3.5, -3.5,
+    -5.8, -7.2, -8.6, -9.5, -10.9, -10.7, -12.0, -14.0, -13.6, -12.0,
+    -13.3, -12.9, -10.6, -11.6, -12.2, -10.2, -7.8, -11.2, -10.4, -10.6,
+    -9.7, -8.3, -9.3, -9.8,
+];
+
+#[rustfmt::skip]
+static CIE_DAYLIGHT_S2: [f32; 54] = [
+    0.0, 2.0, 4.0, 8.5, 7.8, 6.7, 5.3, 6.1, 2.0, 1.2,
+    -1.1, -0.5, -0.7, -1.2, -2.6, -2.9, -2.8, -2.6, -2.6, -1.8,
+    -1.5, -1.3, -1.2, -1.0, -0.5, -0.3, 0.0, 0.2, 0.5, 2.1,
+    3.2, 4.1, 4.7, 5.1, 6.7, 7.3, 8.6, 9.8, 10.2, 8.3,
+    9.6, 8.5, 7.0, 7.6, 8.0, 6.7, 5.2, 7.4, 6.8, 7.0,
+    6.4, 5.5, 6.1, 6.5,
+];
+
+// CIE fluorescent illuminants F1 to F12 from CIE 15, 380nm to 780nm in 5nm steps
+#[rustfmt::skip]
+#[allow(clippy::all)]
+static CIE_ILLUMINANTS_F: [[f32; 81]; 12] = [
+    // F1
+    [
+        1.87, 2.36, 2.94, 3.47, 5.17, 19.49, 6.13, 6.24, 7.01, 7.79, 8.56, 43.67, 16.94, 10.72,
+        11.35, 11.89, 12.37, 12.75, 13.00, 13.15, 13.23, 13.17, 13.13, 12.85, 12.52, 12.20, 11.83, 11.50,
+        11.22, 11.05, 11.03, 11.18, 11.53, 27.74, 17.05, 13.55, 14.33, 15.01, 15.52, 18.29, 19.55, 15.48,
+        14.91, 14.15, 13.22, 12.19, 11.12, 10.03, 8.95, 7.96, 7.02, 6.20, 5.42, 4.73, 4.15, 3.64,
+        3.20, 2.81, 2.47, 2.18, 1.93, 1.72, 1.67, 1.43, 1.29, 1.19, 1.08, 0.96, 0.88, 0.81,
+        0.77, 0.75, 0.73, 0.68, 0.69, 0.64, 0.68, 0.69, 0.61, 0.52, 0.43,
+    ],
+    // F2
+    [
+        1.18, 1.48, 1.84, 2.15, 3.44, 15.69, 3.85, 3.74, 4.19, 4.62, 5.06, 34.98, 11.81, 6.27,
+        6.63, 6.93, 7.19, 7.40, 7.54, 7.62, 7.65, 7.62, 7.62, 7.45, 7.28, 7.15, 7.05, 7.04,
+        7.16, 7.47, 8.04, 8.88, 10.01, 24.88, 16.64, 14.59, 16.16, 17.56, 18.62, 21.47, 22.79, 19.29,
+        18.66, 17.73, 16.54, 15.21, 13.80, 12.36, 10.95, 9.65, 8.40, 7.32, 6.31, 5.43, 4.68, 4.02,
+        3.45, 2.96, 2.55, 2.19, 1.89, 1.64, 1.53, 1.27, 1.10, 0.99, 0.88, 0.76, 0.68, 0.61,
+        0.56, 0.54, 0.51, 0.47, 0.47, 0.43, 0.46, 0.47, 0.40, 0.33, 0.27,
+    ],
+    // F3
+    [
+        0.82, 1.02, 1.26, 1.44, 2.57, 14.36, 2.70, 2.45, 2.73, 3.00, 3.28, 31.85, 9.47, 4.02,
+        4.25, 4.44, 4.59, 4.72, 4.80, 4.86, 4.87, 4.85, 4.88, 4.77, 4.67, 4.62, 4.62, 4.73,
+        4.99, 5.48, 6.25, 7.34, 8.78, 23.82, 16.14, 14.59, 16.63, 18.49, 19.95, 23.11, 24.69, 21.41,
+        20.85, 19.93, 18.67, 17.22, 15.65, 14.04, 12.45, 10.95, 9.51, 8.27, 7.11, 6.09, 5.22, 4.45,
+        3.80, 3.23, 2.75, 2.33, 1.99, 1.70, 1.55, 1.27, 1.09, 0.96, 0.83, 0.71, 0.62, 0.54,
+        0.49, 0.46, 0.43, 0.39, 0.39, 0.35, 0.38, 0.39, 0.33, 0.28, 0.21,
+    ],
+    // F4
+    [
+        0.57, 0.70, 0.87, 0.98, 2.01, 13.75, 1.95, 1.59, 1.76, 1.93, 2.10, 30.28, 8.03, 2.55,
+        2.70, 2.82, 2.91, 2.99, 3.04, 3.08, 3.09, 3.09, 3.14, 3.06, 3.00, 2.98, 3.01, 3.14,
+        3.41, 3.90, 4.69, 5.81, 7.32, 22.59, 15.11, 13.88, 16.33, 18.68, 20.64, 24.28, 26.26, 23.28,
+        22.94, 22.14, 20.91, 19.43, 17.74, 16.00, 14.42, 12.56, 10.93, 9.52, 8.18, 7.01, 6.00, 5.11,
+        4.36, 3.69, 3.13, 2.64, 2.24, 1.91, 1.70, 1.39, 1.18, 1.03, 0.88, 0.74, 0.64, 0.54,
+        0.49, 0.46, 0.42, 0.37, 0.37, 0.33, 0.35, 0.36, 0.31, 0.26, 0.19,
+    ],
+    // F5
+    [
+        1.87, 2.35, 2.92, 3.45, 5.10, 18.91, 6.00, 6.11, 6.85, 7.58, 8.31, 40.76, 16.06, 10.32,
+        10.91, 11.40, 11.83, 12.17, 12.40, 12.54, 12.58, 12.52, 12.47, 12.20, 11.89, 11.61, 11.33, 11.10,
+        10.96, 10.97, 11.16, 11.54, 12.12, 27.78, 17.73, 14.47, 15.20, 15.77, 16.10, 18.54, 19.50, 15.39,
+        14.64, 13.72, 12.69, 11.57, 10.45, 9.35, 8.29, 7.32, 6.41, 5.63, 4.90, 4.26, 3.72, 3.25,
+        2.83, 2.49, 2.19, 1.93, 1.71, 1.52, 1.43, 1.26, 1.13, 1.05, 0.96, 0.85, 0.78, 0.72,
+        0.68, 0.67, 0.65, 0.61, 0.62, 0.59, 0.62, 0.64, 0.55, 0.47, 0.40,
+    ],
+    // F6
+    [
+        1.05, 1.31, 1.63, 1.90, 3.11, 14.80, 3.43, 3.30, 3.68, 4.07, 4.45, 32.61, 10.74, 5.48,
+        5.78, 6.03, 6.25, 6.41, 6.52, 6.58, 6.59, 6.56, 6.56, 6.42, 6.28, 6.20, 6.19, 6.30,
+        6.60, 7.12, 7.94, 9.07, 10.49, 25.22, 17.46, 15.63, 17.22, 18.53, 19.43, 21.97, 23.01, 19.41,
+        18.56, 17.42, 16.09, 14.64, 13.15, 11.68, 10.25, 8.95, 7.74, 6.69, 5.71, 4.87, 4.16, 3.55,
+        3.02, 2.57, 2.20, 1.87, 1.60, 1.37, 1.29, 1.05, 0.91, 0.81, 0.71, 0.61, 0.54, 0.48,
+        0.44, 0.43, 0.40, 0.37, 0.38, 0.35, 0.39, 0.41, 0.33, 0.26, 0.21,
+    ],
+    // F7
+    [
+        2.56, 3.18, 3.84, 4.53, 6.15, 19.37, 7.37, 7.05, 7.71, 8.41, 9.15, 44.14, 17.52, 11.35,
+        12.00, 12.58, 13.08, 13.45, 13.71, 13.88, 13.95, 13.93, 13.82, 13.64, 13.43, 13.25, 13.08, 12.93,
+        12.78, 12.60, 12.44, 12.33, 12.26, 29.52, 17.05, 12.44, 12.58, 12.72, 12.83, 15.46, 16.75, 12.83,
+        12.67, 12.45, 12.19, 11.89, 11.60, 11.35, 11.12, 10.95, 10.76, 10.42, 10.11, 10.04, 10.02, 10.11,
+        9.87, 8.65, 7.27, 6.44, 5.83, 5.41, 5.04, 4.57, 4.12, 3.77, 3.46, 3.08, 2.73, 2.47,
+        2.25, 2.06, 1.90, 1.75, 1.62, 1.54, 1.45, 1.32, 1.17, 0.99, 0.81,
+    ],
+    // F8
+    [
+        1.21, 1.50, 1.81, 2.13, 3.17, 13.08, 3.83, 3.45, 3.86, 4.42, 5.09, 34.10, 12.42, 7.68,
+        8.60, 9.46, 10.24, 10.84, 11.33, 11.71, 11.98, 12.17, 12.28, 12.32, 12.35, 12.44, 12.55, 12.68,
+        12.77, 12.72, 12.60, 12.43, 12.22, 28.96, 16.51, 11.79, 11.76, 11.77, 11.84, 14.61, 16.11, 12.34,
+        12.53, 12.72, 12.92, 13.12, 13.34, 13.61, 13.87, 14.07, 14.20, 14.16, 14.13, 14.34, 14.50, 14.46,
+        14.00, 12.58, 10.99, 9.98, 9.22, 8.62, 8.07, 7.39, 6.71, 6.16, 5.63, 5.03, 4.46, 4.02,
+        3.66, 3.36, 3.09, 2.85, 2.65, 2.51, 2.37, 2.15, 1.89, 1.61, 1.32,
+    ],
+    // F9
+    [
+        0.90, 1.12, 1.36, 1.60, 2.59, 12.80, 3.05, 2.56, 2.86, 3.30, 3.82, 32.62, 10.77, 5.84,
+        6.57, 7.25, 7.86, 8.35, 8.75, 9.06, 9.31, 9.48, 9.61, 9.68, 9.74, 9.88, 10.04, 10.26,
+        10.48, 10.63, 10.78, 10.96, 11.18, 27.71, 16.29, 12.28, 12.74, 13.21, 13.65, 16.57, 18.14, 14.55,
+        14.65, 14.66, 14.61, 14.50, 14.39, 14.40, 14.47, 14.62, 14.72, 14.55, 14.40, 14.58, 14.88, 15.51,
+        15.47, 13.20, 10.57, 9.18, 8.25, 7.57, 7.03, 6.35, 5.72, 5.25, 4.80, 4.29, 3.80, 3.43,
+        3.12, 2.86, 2.64, 2.43, 2.26, 2.14, 2.02, 1.83, 1.61, 1.38, 1.12,
+    ],
+    // F10
+    [
+        1.11, 0.63, 0.62, 0.57, 1.48, 12.16, 2.12, 2.70, 3.74, 5.14, 6.75, 34.39, 14.86, 10.40,
+        10.76, 10.67, 10.11, 9.27, 8.29, 7.29, 7.91, 16.64, 16.73, 10.44, 5.94, 3.34, 2.35, 1.88,
+        1.59, 1.47, 1.80, 5.71, 40.98, 73.69, 33.61, 8.24, 3.38, 2.47, 2.14, 4.86, 11.45, 14.79,
+        12.16, 8.97, 6.52, 8.31, 44.12, 34.55, 12.09, 12.15, 10.52, 4.43, 1.95, 2.19, 3.19, 2.77,
+        2.29, 2.00, 1.52, 1.35, 1.47, 1.79, 1.74, 1.02, 1.14, 3.32, 4.49, 2.05, 0.49, 0.24,
+        0.21, 0.21, 0.24, 0.24, 0.21, 0.17, 0.21, 0.22, 0.17, 0.12, 0.09,
+    ],
+    // F11
+    [
+        0.91, 0.63, 0.46, 0.37, 1.29, 12.68, 1.59, 1.79, 2.46, 3.33, 4.49, 33.94, 12.13, 6.95,
+        7.19, 7.12, 6.72, 6.13, 5.46, 4.79, 5.66, 14.29, 14.96, 8.97, 4.72, 2.33, 1.47, 1.10,
+        0.89, 0.83, 1.18, 4.90, 39.59, 72.84, 32.61, 7.52, 2.83, 1.96, 1.67, 4.43, 11.28, 14.76,
+        12.73, 9.74, 7.33, 9.72, 55.27, 42.58, 13.18, 13.16, 12.26, 5.11, 2.07, 2.34, 3.58, 3.01,
+        2.48, 2.14, 1.54, 1.33, 1.46, 1.94, 2.00, 1.20, 1.35, 4.10, 5.58, 2.51, 0.57, 0.27,
+        0.23, 0.21, 0.24, 0.24, 0.20, 0.24, 0.32, 0.26, 0.16, 0.12, 0.09,
+    ],
+    // F12
+    [
+        0.96, 0.64, 0.40, 0.33, 1.19, 12.48, 1.12, 0.94, 1.08, 1.37, 1.78, 29.05, 7.90, 2.65,
+        2.71, 2.65, 2.49, 2.33, 2.10, 1.91, 3.01, 10.83, 11.88, 6.88, 3.43, 1.49, 0.92, 0.71,
+        0.60, 0.63, 1.10, 4.56, 34.40, 65.40, 29.48, 7.16, 3.08, 2.47, 2.27, 5.09, 11.96, 15.32,
+        14.27, 11.86, 9.28, 12.31, 68.53, 53.02, 14.67, 14.38, 14.71, 6.46, 2.57, 2.75, 4.18, 3.44,
+        2.81, 2.42, 1.64, 1.36, 1.49, 2.14, 2.34, 1.42, 1.61, 5.04, 6.98, 3.19, 0.71, 0.30,
+        0.26, 0.23, 0.28, 0.28, 0.21, 0.17, 0.21, 0.19, 0.15, 0.10, 0.05,
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spectra::{cie::CIE, Spectrum};
+
+    #[test]
+    fn test_metals() {
+        let reflectance = |eta: NamedSpectra, k: NamedSpectra, lambda: f32| {
+            let (eta, k) = (eta.get().value(lambda), k.get().value(lambda));
+            ((eta - 1.).powi(2) + k.powi(2)) / ((eta + 1.).powi(2) + k.powi(2))
+        };
+        for (eta, k) in [
+            (NamedSpectra::AgEta, NamedSpectra::AgK),
+            (NamedSpectra::AlEta, NamedSpectra::AlK),
+        ] {
+            assert!(reflectance(eta, k, 550.) > 0.9);
+        }
+        // Gold and copper reflect red more than blue
+        assert!(reflectance(NamedSpectra::AuEta, NamedSpectra::AuK, 650.) > 0.9);
+        assert!(reflectance(NamedSpectra::AuEta, NamedSpectra::AuK, 450.) < 0.5);
+        assert!(reflectance(NamedSpectra::CuEta, NamedSpectra::CuK, 650.) > 0.9);
+        assert!(reflectance(NamedSpectra::CuEta, NamedSpectra::CuK, 450.) < 0.7);
+        for (eta, k) in [
+            (NamedSpectra::CrEta, NamedSpectra::CrK),
+            (NamedSpectra::TiEta, NamedSpectra::TiK),
+        ] {
+            assert!((0.5..0.7).contains(&reflectance(eta, k, 550.)));
+        }
+        assert!(NamedSpectra::from_name("metal-Ti-k").is_some());
+    }
+
+    #[test]
+    fn test_illuminants() {
+        // The daylight model reproduces the tabulated D65
+        let d65 = PiecewiseLinearSpectrum::from_interleaved(&daylight(6504.), true);
+        for lambda in (400..=700).step_by(10) {
+            let (computed, table) = (d65.value(lambda as f32), ILLUMINANT_D65.value(lambda as f32));
+            assert!(
+                (computed / table - 1.).abs() < 0.02,
+                "{lambda}nm: {computed} vs {table}"
+            );
+        }
+        // Lower temperatures are redder
+        let ratio = |spectrum: NamedSpectra| spectrum.get().value(650.) / spectrum.get().value(450.);
+        assert!(ratio(NamedSpectra::IlluminantA) > 3.);
+        assert!(ratio(NamedSpectra::IlluminantD50) > ratio(NamedSpectra::IlluminantAcesD60));
+        assert!(ratio(NamedSpectra::IlluminantAcesD60) > ratio(NamedSpectra::IlluminantD65));
+        assert!(NamedSpectra::from_name("stdillum-A").is_some());
+        assert!(NamedSpectra::from_name("illum-acesD60").is_some());
+    }
+
+    #[test]
+    fn test_fluorescent_illuminants() {
+        // Chromaticities from CIE 15
+        let expected = [
+            (0.3131, 0.3371),
+            (0.3721, 0.3751),
+            (0.4091, 0.3941),
+            (0.4402, 0.4031),
+            (0.3138, 0.3452),
+            (0.3779, 0.3882),
+            (0.3129, 0.3292),
+            (0.3458, 0.3586),
+            (0.3741, 0.3727),
+            (0.3458, 0.3588),
+            (0.3805, 0.3769),
+            (0.4370, 0.4042),
+        ];
+        for (i, (x, y)) in expected.into_iter().enumerate() {
+            let name = format!("stdillum-F{}", i + 1);
+            let spectrum = NamedSpectra::from_name(&name).unwrap().get();
+            let xyz = [CIE::X, CIE::Y, CIE::Z].map(|cmf| {
+                (360..=830)
+                    .map(|lambda| spectrum.value(lambda as f32) * cmf.get().value(lambda as f32))
+                    .sum::<f32>()
+            });
+            let sum: f32 = xyz.iter().sum();
+            assert!((xyz[0] / sum - x).abs() < 1e-3, "{name}");
+            assert!((xyz[1] / sum - y).abs() < 1e-3, "{name}");
+        }
+        assert!(NamedSpectra::from_name("stdillum-F0").is_none());
+        assert!(NamedSpectra::from_name("stdillum-F13").is_none());
+    }
+}
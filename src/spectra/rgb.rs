@@ -40,6 +40,8 @@ impl RGB {
     pub fn has_nan(&self) -> bool { self.r.is_nan() || self.g.is_nan() || self.b.is_nan() }
 
     pub fn max(&self) -> f32 { self.r.max(self.g.max(self.b)) }
+
+    pub fn average(&self) -> f32 { (self.r + self.g + self.b) / 3. }
 }
 
 impl From<RGB> for Vec3f {
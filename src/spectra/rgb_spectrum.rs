@@ -64,6 +64,8 @@ impl RGBIlluminantSpectrum {
             illuminant: color_space.illuminant.clone(),
         }
     }
+
+    pub fn illuminant(&self) -> &SpectrumEnum { &self.illuminant }
 }
 
 impl Spectrum for RGBIlluminantSpectrum {
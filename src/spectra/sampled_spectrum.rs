@@ -27,11 +27,18 @@ impl<const N: usize> SampledSpectrum<N> {
 
     pub fn avg(&self) -> f32 { self.values.iter().sum::<f32>() / (self.values.len() as f32) }
 
+    pub fn max_value(&self) -> f32 { self.values.iter().copied().fold(f32::MIN, f32::max) }
+
     pub fn sqrt(mut self) -> Self {
         self.values.iter_mut().for_each(|x| *x = x.sqrt());
         self
     }
 
+    pub fn exp(mut self) -> Self {
+        self.values.iter_mut().for_each(|x| *x = x.exp());
+        self
+    }
+
     pub fn to_xyz(&self, lambda: &SampledWavelengths<N>) -> XYZ {
         let x = CIE::X.get().sample(lambda);
         let y = CIE::Y.get().sample(lambda);
@@ -125,5 +132,5 @@ gen_ops!(
     types SampledSpectrum<N>, f32;
 
     for *= call |x: &mut SampledSpectrum<N>, y: &f32| x.iter_mut().for_each(|x| *x *= y);
-    for /= call |x: &mut SampledSpectrum<N>, y: &f32| if *y!=0. {x.iter_mut().for_each(|x| *x *= y)} else {x.iter_mut().for_each(|x| *x = 0.)};
+    for /= call |x: &mut SampledSpectrum<N>, y: &f32| if *y!=0. {x.iter_mut().for_each(|x| *x /= y)} else {x.iter_mut().for_each(|x| *x = 0.)};
 );
@@ -1,16 +1,17 @@
-use std::sync::Arc;
+use std::{error::Error, sync::Arc};
 
 use either::Either;
 
 use crate::{
     aggregates::BVH,
-    light::{DiffuseAreaLight, LightEnum, PointLight, Spotlight},
-    material::{glass::Glass, matte::Matte, metal::Metal, MaterialsEnum},
+    light::{AreaEmission, DiffuseAreaLight, LightEnum, PointLight, Spotlight},
+    material::{glass::Glass, matte::Matte, metal::Metal, MaterialsEnum, Roughness},
     math::{axis::Axis3, Transform},
     point2, point3,
     scene::{
-        cameras::{BaseCameraConfig, CameraType, PerspectiveCamera, PerspectiveCameraConfig},
+        cameras::{default_screen_window, BaseCameraConfig, CameraType, PerspectiveCamera, PerspectiveCameraConfig},
         film::RGBFilm,
+        filters::FilterEnum,
         primitives::{geometric::GeometricPrimitive, simple::SimplePrimitive, PrimitiveEnum},
         Scene,
     },
@@ -23,7 +24,7 @@ use crate::{
     },
     test_scenes::teapot_triangles,
     textures::{constant::ConstantSpectrumTexture, SpectrumTextureEnum},
-    vec3, Bounds2f,
+    vec3, Bounds2f, Point2us,
 };
 
 fn base_box(
@@ -36,7 +37,7 @@ fn base_box(
         // left wall
         SimplePrimitive {
             shape: Arc::new(Quad::new(
-                point3!(1000., 0., 0.),
+                point3!(0., 0., 0.),
                 vec3!(0., 1000., 0.),
                 vec3!(0., 0., 1000.),
                 Transform::id(),
@@ -46,7 +47,7 @@ fn base_box(
         // right wall
         SimplePrimitive {
             shape: Arc::new(Quad::new(
-                point3!(0., 0., 0.),
+                point3!(1000., 0., 0.),
                 vec3!(0., 1000., 0.),
                 vec3!(0., 0., 1000.),
                 Transform::id(),
@@ -89,16 +90,15 @@ fn base_box(
     base_box
 }
 
-pub fn cornell_box() -> Scene {
+pub fn cornell_box(resolution: Point2us) -> Result<Scene, Box<dyn Error>> {
     let camera: CameraType = PerspectiveCamera::new(PerspectiveCameraConfig {
         base_config: BaseCameraConfig {
-            transform: Transform::id()
-                .then_rotate_degrees(Axis3::Y, 180.)
-                .then_translate(vec3!(500., 500., -1000.)),
-            film: RGBFilm::new(400, 400, sRGB.clone()),
+            transform: Transform::translate(vec3!(500., 500., -1000.)),
+            film: RGBFilm::new(resolution.x, resolution.y, FilterEnum::default(), sRGB.clone()),
+            medium: None,
         },
         fov: 55.0,
-        screen_window: Bounds2f::from_points(point2!(-1., -1.), point2!(1., 1.)),
+        screen_window: default_screen_window(resolution),
         lens_radius: 5.0,
         focal_distance: 1500.0,
     })
@@ -116,19 +116,29 @@ pub fn cornell_box() -> Scene {
 
     let matte_gray = Arc::new(MaterialsEnum::Matte(Matte {
         reflectance: const_gray.clone() as _,
+        bump: None,
     }));
     let matte_green = Arc::new(MaterialsEnum::Matte(Matte {
         reflectance: const_green.clone() as _,
+        bump: None,
     }));
     let matte_red = Arc::new(MaterialsEnum::Matte(Matte {
         reflectance: const_red.clone() as _,
+        bump: None,
     }));
     let metal = Arc::new(MaterialsEnum::Metal(Metal {
         reflectance: Either::Left(const_white.clone()),
+        roughness: Roughness::smooth(),
+        bump: None,
     }));
     let glass = Arc::new(MaterialsEnum::Glass(Glass {
         spectrum: const_gray.clone(),
-        ior: SpectrumEnum::PiecewiseLinear(PiecewiseLinearSpectrum::new(&[VISIBLE_MIN, VISIBLE_MAX], &[2.5, 1.5])),
+        ior: Either::Left(SpectrumEnum::PiecewiseLinear(PiecewiseLinearSpectrum::new(
+            &[VISIBLE_MIN, VISIBLE_MAX],
+            &[2.5, 1.5],
+        ))),
+        roughness: Roughness::smooth(),
+        bump: None,
     }));
 
     // let mut cornell_box = base_box(&matte_green, &matte_red, &metal, &matte_gray, &glass);
@@ -145,15 +155,18 @@ pub fn cornell_box() -> Scene {
     let d65 = NamedSpectra::IlluminantD65.get();
     let light_source = Arc::new(LightEnum::DiffuseArea(DiffuseAreaLight::new(
         // Arc::new(SpectrumEnum::RGBIlluminant(RGBIlluminantSpectrum::new(&sRGB, RGB::LIGHT_BLUE))),
-        d65.clone(),
-        1.5,
+        AreaEmission::Spectrum(d65.clone()),
+        50.,
+        false,
         Transform::id(),
         light_shape.clone(),
     )));
     let light = GeometricPrimitive {
         shape: light_shape.clone(),
-        material: matte_gray.clone(),
+        material: Some(matte_gray.clone()),
         light: Some(light_source.clone()),
+        medium_interface: None,
+        alpha: None,
     };
     cornell_box.push(Arc::new(PrimitiveEnum::Geometric(light)));
 
@@ -163,7 +176,7 @@ pub fn cornell_box() -> Scene {
             // .then_rotate_degrees(Axis3::X, 90.)
             .then_scale_uniform(150.)
             .then_translate(vec3!(500., 0., 500.)),
-    );
+    )?;
     let tri: Vec<Arc<PrimitiveEnum>> = tri
         .into_iter()
         .map(Arc::new)
@@ -199,11 +212,7 @@ pub fn cornell_box() -> Scene {
     //     Transform::translate(vec3!(500., 950., 500.)),
     // )))];
 
-    Scene {
-        camera,
-        objects,
-        lights,
-    }
+    Ok(Scene::new(camera, objects, lights)?)
     // for side in Quad::quad_box(
     //     165.0,
     //     165.0,
@@ -1,128 +1,121 @@
-use std::rc::Rc;
-
-use image::Rgb;
+use std::{error::Error, sync::Arc};
 
 use crate::{
     aggregates::BVH,
-    material::{diffuse_light::DiffuseLight, lambertian::Lambertian},
+    light::{AreaEmission, DiffuseAreaLight, LightEnum, UniformInfiniteLight},
+    material::{matte::Matte, MaterialsEnum},
+    math::Transform,
     point3,
-    scene::{CameraConfig, Primitive, Scene, SimpleCamera},
+    scene::{
+        cameras::{default_screen_window, BaseCameraConfig, CameraType, PerspectiveCamera, PerspectiveCameraConfig},
+        film::RGBFilm,
+        filters::FilterEnum,
+        primitives::{geometric::GeometricPrimitive, simple::SimplePrimitive, PrimitiveEnum},
+        Scene,
+    },
     shapes::quad::Quad,
-    utils::lerp,
-    vec3, Point3, Vec3,
+    spectra::{
+        named::NamedSpectra,
+        rgb::{sRGB, RGB},
+        RGBAlbedoSpectrum, SpectrumEnum,
+    },
+    textures::{constant::ConstantSpectrumTexture, SpectrumTextureEnum},
+    vec3, Point2us, Point3f,
 };
 
-pub fn cubes() -> Scene {
-    fn gen_cubes(
-        world: &mut Vec<Primitive>,
-        count: usize,
-        start: Point3,
-        size: f32,
-        offset: f32,
-        start_color: Rgb<f32>,
-        end_color: Rgb<f32>,
-    ) {
-        let diag = Vec3::ones() * size;
-        let offset = vec3!((size + offset) / 2., size + offset, (size + offset) / 2.);
-        for i in 0..count {
-            let start = start + offset * i as f32;
-            let sides = Quad::quad_box(start, start + diag);
+const SIZE: f32 = 4.;
+const GAP: f32 = 0.5;
+
+/// Staircase of `count` cubes going up from `start`, colored from `start_color` to `end_color`
+fn stairs(start: Point3f, count: usize, start_color: RGB, end_color: RGB) -> Vec<Arc<PrimitiveEnum>> {
+    let step = vec3!((SIZE + GAP) / 2., SIZE + GAP, (SIZE + GAP) / 2.);
+    let mut primitives = vec![];
+    for i in 0..count {
+        let t = i as f32 / count as f32;
+        let color = start_color * (1. - t) + end_color * t;
+        let albedo = Arc::new(SpectrumEnum::RGBAlbedo(RGBAlbedoSpectrum::new(&sRGB, color)));
+        let reflectance: Arc<SpectrumTextureEnum> = Arc::new(ConstantSpectrumTexture { value: albedo }.into());
+        let material = Arc::new(MaterialsEnum::Matte(Matte {
+            reflectance,
+            bump: None,
+        }));
 
-            let col = lerp(start_color, end_color, (i as f32 / count as f32));
-            for side in sides {
-                world.push(Primitive {
-                    shape: Box::new(side),
-                    material: Box::new(Lambertian { albedo: col }),
-                })
-            }
+        let center = start + step * i as f32 + vec3!(SIZE, SIZE, SIZE) / 2.;
+        for side in Quad::quad_box(SIZE, SIZE, SIZE, Transform::translate(center.coords)) {
+            primitives.push(Arc::new(PrimitiveEnum::Simple(SimplePrimitive {
+                shape: Arc::new(side),
+                material: material.clone(),
+            })));
         }
     }
+    primitives
+}
 
-    let mut world = vec![];
-    let size = 4.0;
-    let offset = 0.5;
-    let x = offset * 2.0 + 1.5 * size;
-    let off = vec3!(x, 0., -x);
-    let x2 = offset + size;
-    let z2 = -size / 2. - offset;
-    let off2 = vec3!(x2, z2, z2);
-    let off22 = vec3!(-x2, z2, -z2);
-    gen_cubes(
-        &mut world,
-        5,
-        point3!(0., 0., 0.),
-        4.,
-        0.5,
-        Rgb([0.06, 0.2, 0.07]),
-        Rgb([0.2, 0.9, 0.3]),
-    );
-    gen_cubes(
-        &mut world,
-        5,
-        point3!(0., 0., 0.) + off,
-        4.,
-        0.5,
-        Rgb([0.2, 0.02, 0.02]),
-        Rgb([0.9, 0.2, 0.2]),
-    );
-    gen_cubes(
-        &mut world,
-        5,
-        point3!(0., 0., 0.) + -off,
-        4.,
-        0.5,
-        Rgb([0.2, 0.02, 0.02]),
-        Rgb([0.9, 0.2, 0.2]),
-    );
-
-    gen_cubes(
-        &mut world,
-        5,
-        point3!(0., 0., 0.) + -off2,
-        4.,
-        0.5,
-        Rgb([0.02, 0.02, 0.2]),
-        Rgb([0.2, 0.2, 0.9]),
-    );
-    gen_cubes(
-        &mut world,
-        5,
-        point3!(0., 0., 0.) + -off22,
-        4.,
-        0.5,
-        Rgb([0.02, 0.02, 0.2]),
-        Rgb([0.2, 0.2, 0.9]),
-    );
-
-    world.push(Primitive {
-        shape: Box::new(Quad::new(
-            point3!(-100., 200., -100.),
-            vec3!(200., 0., 0.),
-            vec3!(0., 0., 200.),
-        )),
-        material: Box::new(DiffuseLight {
-            color: Rgb([10., 10., 10.]),
-        }),
-    });
+/// Five staircases of colored cubes under a large ceiling light
+pub fn cubes(resolution: Point2us) -> Result<Scene, Box<dyn Error>> {
+    let camera: CameraType = PerspectiveCamera::new(PerspectiveCameraConfig {
+        base_config: BaseCameraConfig {
+            transform: Transform::look_at(point3!(-20., 15., -5.), point3!(3., 10., 3.), vec3!(0., 1., 0.)).invert(),
+            film: RGBFilm::new(resolution.x, resolution.y, FilterEnum::default(), sRGB.clone()),
+            medium: None,
+        },
+        fov: 40.0,
+        screen_window: default_screen_window(resolution),
+        lens_radius: 0.,
+        focal_distance: 10.0,
+    })
+    .into();
 
-    let world = BVH::new(world.into_iter().map(Rc::new).collect(), 4);
+    let green = (RGB::new(0.06, 0.2, 0.07), RGB::new(0.2, 0.9, 0.3));
+    let red = (RGB::new(0.2, 0.02, 0.02), RGB::new(0.9, 0.2, 0.2));
+    let blue = (RGB::new(0.02, 0.02, 0.2), RGB::new(0.2, 0.2, 0.9));
+    let x = 2. * GAP + 1.5 * SIZE;
+    let side = vec3!(x, 0., -x);
+    let z = -SIZE / 2. - GAP;
+    let back = vec3!(SIZE + GAP, z, z);
+    let front = vec3!(-(SIZE + GAP), z, -z);
 
-    let materials = vec![];
+    let origin = point3!(0., 0., 0.);
+    let mut primitives = vec![];
+    for (start, (start_color, end_color)) in [
+        (origin, green),
+        (origin + side, red),
+        (origin + -side, red),
+        (origin + -back, blue),
+        (origin + -front, blue),
+    ] {
+        primitives.extend(stairs(start, 5, start_color, end_color));
+    }
 
-    let camera = SimpleCamera::from(CameraConfig {
-        position: point3!(-20., 15., -5.),
-        look_at: point3!(3., 10., 3.),
-        up: vec3!(0., 1., 0.),
-        aspect_ratio: 16.0 / 9.0,
-        vertical_fov: 40.0,
-        defocus_angle: 0.5,
-        focus_dist: 10.0,
-    });
+    let light_shape = Arc::new(Quad::new(
+        point3!(-100., 200., -100.),
+        vec3!(200., 0., 0.),
+        vec3!(0., 0., 200.),
+        Transform::id(),
+    ));
+    let light_source = Arc::new(LightEnum::DiffuseArea(DiffuseAreaLight::new(
+        AreaEmission::Spectrum(NamedSpectra::IlluminantD65.get()),
+        10.,
+        true,
+        Transform::id(),
+        light_shape.clone(),
+    )));
+    let white = Arc::new(SpectrumEnum::RGBAlbedo(RGBAlbedoSpectrum::new(&sRGB, RGB::WHITE)));
+    primitives.push(Arc::new(PrimitiveEnum::Geometric(GeometricPrimitive {
+        shape: light_shape,
+        material: Some(Arc::new(MaterialsEnum::Matte(Matte {
+            reflectance: Arc::new(ConstantSpectrumTexture { value: white }.into()),
+            bump: None,
+        }))),
+        light: Some(light_source.clone()),
+        medium_interface: None,
+        alpha: None,
+    })));
 
-    Scene {
-        camera,
-        objects: world,
-        materials,
-        background_color: Rgb([0.1, 0.1, 0.1]),
-    }
+    let sky = Arc::new(LightEnum::UniformInfinite(UniformInfiniteLight::new(
+        NamedSpectra::IlluminantD65.get(),
+        0.1,
+    )));
+    let objects = PrimitiveEnum::BVH(BVH::new(primitives, 4));
+    Ok(Scene::new(camera, objects, vec![light_source, sky])?)
 }
@@ -1,88 +1,110 @@
-use std::rc::Rc;
+use std::{error::Error, sync::Arc};
 
-use image::Rgb;
+use either::Either;
 
 use crate::{
     aggregates::BVH,
-    material::{diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal},
+    light::{AreaEmission, DiffuseAreaLight, LightEnum, UniformInfiniteLight},
+    material::{matte::Matte, metal::Metal, MaterialsEnum, Roughness},
+    math::Transform,
     point3,
-    scene::{CameraConfig, Primitive, Scene, SimpleCamera},
-    shapes::{mesh::Triangle, sphere::Sphere},
-    vec3, Point3, F,
+    scene::{
+        cameras::{default_screen_window, BaseCameraConfig, CameraType, PerspectiveCamera, PerspectiveCameraConfig},
+        film::RGBFilm,
+        filters::FilterEnum,
+        primitives::{geometric::GeometricPrimitive, simple::SimplePrimitive, PrimitiveEnum},
+        Scene,
+    },
+    shapes::quad::Quad,
+    spectra::{
+        named::NamedSpectra,
+        rgb::{sRGB, RGB},
+        RGBAlbedoSpectrum, SpectrumEnum,
+    },
+    test_scenes::teapot_triangles,
+    textures::{
+        constant::{ConstantFloatTexture, ConstantSpectrumTexture},
+        SpectrumTextureEnum,
+    },
+    vec3, Point2us,
 };
 
-pub fn teapot() -> Scene {
-    let obj = obj::Obj::load("./data/teapot.obj").unwrap();
-    let vertices: Vec<Point3> = obj.data.position.iter().map(|x| point3!(x[0], x[1], x[2])).collect();
-    let normals = obj.data.normal;
-    let group = obj.data.objects.first().unwrap().groups.first().unwrap();
-
-    let mut triangles: Vec<Triangle<F>> = vec![];
-    for x in group.polys.iter() {
-        let a = x.0[0].0;
-        let b = x.0[1].0;
-        let c = x.0[2].0;
-
-        triangles.push(Triangle::new(
-            vertices[a],
-            vertices[b] - vertices[a],
-            vertices[c] - vertices[a],
-        ));
-        // let na = normals[x.0[0].2.unwrap()];
-        // let nb = normals[x.0[1].2.unwrap()];
-        // let nc = normals[x.0[2].2.unwrap()];
-        // triangles.push(Triangle::new_with_normals(  vertices[a],  vertices[b] - vertices[a],  vertices[c] - vertices[a],
-        //     [
-        //         UnitVec::new(na[0], na[1], na[2]),
-        //         UnitVec::new(nb[0], nb[1], nb[2]),
-        //         UnitVec::new(nc[0], nc[1], nc[2]),
-        //     ]
-        // ))
-    }
-
-    let mut world = vec![
-        Primitive {
-            shape: Box::new(Sphere::new(point3!(5., 20.0, 10.), 10.0)),
-            material: Box::new(DiffuseLight {
-                color: Rgb([5., 5., 5.]),
-            }),
+/// Metal teapot on a gray floor, lit by a square light from above
+pub fn teapot(resolution: Point2us) -> Result<Scene, Box<dyn Error>> {
+    let camera: CameraType = PerspectiveCamera::new(PerspectiveCameraConfig {
+        base_config: BaseCameraConfig {
+            transform: Transform::look_at(point3!(4., 5., -7.), point3!(0., 1.2, 0.), vec3!(0., 1., 0.)).invert(),
+            film: RGBFilm::new(resolution.x, resolution.y, FilterEnum::default(), sRGB.clone()),
+            medium: None,
         },
-        Primitive {
-            shape: Box::new(Sphere::new(point3!(0., -1001., 0.), 1000.)),
-            material: Box::new(Lambertian {
-                albedo: Rgb([0.5, 0.5, 0.5]),
-            }),
-        },
-    ];
-    let material = Box::new(Metal {
-        albedo: Rgb([0.8, 0.5, 0.2]),
-        fuzz: 0.75,
-    });
-    for t in triangles {
-        world.push(Primitive {
-            shape: Box::new(t),
-            material: material.clone(),
-        })
-    }
+        fov: 40.0,
+        screen_window: default_screen_window(resolution),
+        lens_radius: 0.05,
+        focal_distance: 9.0,
+    })
+    .into();
 
-    let world = BVH::new(world.into_iter().map(Rc::new).collect(), 8);
+    let gray = Arc::new(SpectrumEnum::RGBAlbedo(RGBAlbedoSpectrum::new(&sRGB, RGB::DARK_GRAY)));
+    let orange = Arc::new(SpectrumEnum::RGBAlbedo(RGBAlbedoSpectrum::new(
+        &sRGB,
+        RGB::new(0.8, 0.5, 0.2),
+    )));
+    let const_gray: Arc<SpectrumTextureEnum> = Arc::new(ConstantSpectrumTexture { value: gray }.into());
+    let const_orange: Arc<SpectrumTextureEnum> = Arc::new(ConstantSpectrumTexture { value: orange }.into());
+    let matte_gray = Arc::new(MaterialsEnum::Matte(Matte {
+        reflectance: const_gray,
+        bump: None,
+    }));
+    let metal = Arc::new(MaterialsEnum::Metal(Metal {
+        reflectance: Either::Left(const_orange),
+        roughness: Roughness::isotropic(Arc::new(ConstantFloatTexture { value: 0.1 }.into())),
+        bump: None,
+    }));
 
-    let materials = vec![];
+    let mut primitives: Vec<Arc<PrimitiveEnum>> = teapot_triangles(Transform::id())?
+        .into_iter()
+        .map(|x| {
+            Arc::new(PrimitiveEnum::Simple(SimplePrimitive {
+                shape: Arc::new(x),
+                material: metal.clone(),
+            }))
+        })
+        .collect();
+    primitives.push(Arc::new(PrimitiveEnum::Simple(SimplePrimitive {
+        shape: Arc::new(Quad::new(
+            point3!(-50., 0., -50.),
+            vec3!(100., 0., 0.),
+            vec3!(0., 0., 100.),
+            Transform::id(),
+        )),
+        material: matte_gray.clone(),
+    })));
 
-    let camera = SimpleCamera::from(CameraConfig {
-        position: point3!(2., 5., 5.),
-        look_at: point3!(0.5, 1., 0.),
-        up: vec3!(0., 1., 0.),
-        aspect_ratio: 16.0 / 9.0,
-        vertical_fov: 40.0,
-        defocus_angle: 0.05,
-        focus_dist: 10.0,
-    });
+    let light_shape = Arc::new(Quad::new(
+        point3!(-2., 10., -2.),
+        vec3!(4., 0., 0.),
+        vec3!(0., 0., 4.),
+        Transform::id(),
+    ));
+    let light_source = Arc::new(LightEnum::DiffuseArea(DiffuseAreaLight::new(
+        AreaEmission::Spectrum(NamedSpectra::IlluminantD65.get()),
+        24.,
+        false,
+        Transform::id(),
+        light_shape.clone(),
+    )));
+    primitives.push(Arc::new(PrimitiveEnum::Geometric(GeometricPrimitive {
+        shape: light_shape,
+        material: Some(matte_gray),
+        light: Some(light_source.clone()),
+        medium_interface: None,
+        alpha: None,
+    })));
 
-    Scene {
-        camera,
-        objects: world,
-        materials,
-        background_color: Rgb([0.1, 0.1, 0.1]),
-    }
+    let sky = Arc::new(LightEnum::UniformInfinite(UniformInfiniteLight::new(
+        NamedSpectra::IlluminantD65.get(),
+        0.1,
+    )));
+    let objects = PrimitiveEnum::BVH(BVH::new(primitives, 8));
+    Ok(Scene::new(camera, objects, vec![light_source, sky])?)
 }
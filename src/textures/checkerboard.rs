@@ -1,24 +1,141 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
-use crate::{core::SurfaceInteraction, SampledWavelengths, textures::SpectrumTexture};
+use either::Either;
 
+use crate::{
+    core::SurfaceInteraction,
+    textures::{
+        mappings::{PointTransformMapping, TextureMapping2D, TextureMapping2DEnum, TextureMapping3D},
+        FloatTexture, FloatTextureEnum, SpectrumTexture, SpectrumTextureEnum,
+    },
+    SampledSpectrum, SampledWavelengths,
+};
+
+/// Checkers of unit size in texture space, either on a surface or filling the space
 #[derive(Debug)]
-pub struct CheckerboardTexture<T> {
-    pub dark: T,
-    pub light: T,
-    pub size: f32,
+pub struct CheckerboardTexture {
+    pub mapping: Either<TextureMapping2DEnum, PointTransformMapping>,
+    /// Texture of the checker at the origin and of its neighbours
+    pub tex: [Arc<SpectrumTextureEnum>; 2],
 }
 
-impl<T: Copy + Debug> SpectrumTexture<T> for CheckerboardTexture<T> {
-    fn evaluate(&self, surf_int: &SurfaceInteraction, lambda: SampledWavelengths) -> T {
-        let uv = surf_int.hit.uv;
-        let u = uv.x % (self.size * 2.);
-        let v = uv.y % (self.size * 2.);
-
-        if (u > self.size) ^ (v > self.size) {
-            self.dark
+/// Weight of the second texture, antialiased by integrating the checkerboard over a box filter as wide as the pixel
+/// footprint
+pub fn checkerboard(
+    mapping: &Either<TextureMapping2DEnum, PointTransformMapping>,
+    surf_int: &SurfaceInteraction,
+) -> f32 {
+    // Integral of the 1D checkerboard function that alternates between 1 and -1
+    let integral = |x: f32| {
+        let y = x / 2. - (x / 2.).floor() - 0.5;
+        x / 2. + y * (1. - 2. * y.abs())
+    };
+    // Box filtered checkerboard function
+    let filtered = |x: f32, r: f32| {
+        if (x - r).floor() == (x + r).floor() {
+            1. - 2. * (x.floor() as i64 & 1) as f32
         } else {
-            self.light
+            (integral(x + r) - 2. * integral(x) + integral(x - r)) / r.powi(2)
+        }
+    };
+
+    match mapping {
+        Either::Left(mapping) => {
+            let c = mapping.map(surf_int);
+            let ds = 1.5 * c.ds_dx.abs().max(c.ds_dy.abs());
+            let dt = 1.5 * c.dt_dx.abs().max(c.dt_dy.abs());
+            0.5 - filtered(c.st.x, ds) * filtered(c.st.y, dt) / 2.
+        }
+        Either::Right(mapping) => {
+            let c = mapping.map(surf_int);
+            let dx = 1.5 * c.dp_dx.x.abs().max(c.dp_dy.x.abs());
+            let dy = 1.5 * c.dp_dx.y.abs().max(c.dp_dy.y.abs());
+            let dz = 1.5 * c.dp_dx.z.abs().max(c.dp_dy.z.abs());
+            0.5 - filtered(c.p.x, dx) * filtered(c.p.y, dy) * filtered(c.p.z, dz) / 2.
+        }
+    }
+}
+
+impl SpectrumTexture for CheckerboardTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction, lambda: &SampledWavelengths) -> SampledSpectrum {
+        let weight = checkerboard(&self.mapping, surf_int);
+        let mut value = SampledSpectrum::from(0.);
+        if weight != 1. {
+            value += self.tex[0].evaluate(surf_int, lambda) * (1. - weight);
+        }
+        if weight != 0. {
+            value += self.tex[1].evaluate(surf_int, lambda) * weight;
+        }
+        value
+    }
+}
+
+/// Scalar counterpart of [CheckerboardTexture]
+#[derive(Debug)]
+pub struct FloatCheckerboardTexture {
+    pub mapping: Either<TextureMapping2DEnum, PointTransformMapping>,
+    pub tex: [Arc<FloatTextureEnum>; 2],
+}
+
+impl FloatTexture for FloatCheckerboardTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction) -> f32 {
+        let weight = checkerboard(&self.mapping, surf_int);
+        let mut value = 0.;
+        if weight != 1. {
+            value += self.tex[0].evaluate(surf_int) * (1. - weight);
+        }
+        if weight != 0. {
+            value += self.tex[1].evaluate(surf_int) * weight;
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{
+        core::Interaction,
+        math::Transform,
+        point2, point3,
+        textures::mappings::{TextureMapping2D, UVMapping},
+        vec3, Point2f,
+    };
+
+    fn interaction(uv: Point2f, footprint: f32) -> SurfaceInteraction {
+        let mut surf_int = SurfaceInteraction::new(
+            Interaction {
+                point: point3!(uv.x, uv.y, 0.5),
+                uv,
+                ..Default::default()
+            },
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        surf_int.du_dx = footprint;
+        surf_int.dv_dy = footprint;
+        surf_int.dp_dx = vec3!(footprint, 0., 0.);
+        surf_int.dp_dy = vec3!(0., footprint, 0.);
+        surf_int
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        for mapping in [
+            Either::Left(UVMapping::default().into()),
+            Either::Right(PointTransformMapping::new(Transform::id())),
+        ] {
+            // Sharp without a footprint
+            assert_eq!(checkerboard(&mapping, &interaction(point2!(0.5, 0.5), 0.)), 0.);
+            assert_eq!(checkerboard(&mapping, &interaction(point2!(1.5, 0.5), 0.)), 1.);
+            assert_eq!(checkerboard(&mapping, &interaction(point2!(-0.5, 0.5), 0.)), 1.);
+            // Wide footprints average the checkers
+            let blurred = checkerboard(&mapping, &interaction(point2!(0.3, 0.7), 10.));
+            assert_abs_diff_eq!(blurred, 0.5, epsilon = 0.05);
         }
     }
 }
@@ -3,7 +3,7 @@ use std::{fmt::Debug, sync::Arc};
 use crate::{
     core::SurfaceInteraction,
     spectra::{Spectrum, SpectrumEnum},
-    textures::SpectrumTexture,
+    textures::{FloatTexture, SpectrumTexture},
     SampledSpectrum, SampledWavelengths,
 };
 
@@ -17,3 +17,12 @@ impl SpectrumTexture for ConstantSpectrumTexture {
         self.value.sample(lambda)
     }
 }
+
+#[derive(Debug)]
+pub struct ConstantFloatTexture {
+    pub value: f32,
+}
+
+impl FloatTexture for ConstantFloatTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction) -> f32 { self.value }
+}
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use crate::{
+    core::SurfaceInteraction,
+    math::Normed,
+    point2, point3,
+    textures::{
+        mappings::{TextureMapping2D, TextureMapping2DEnum},
+        noise::noise,
+        SpectrumTexture, SpectrumTextureEnum,
+    },
+    SampledSpectrum, SampledWavelengths,
+};
+
+/// Randomly placed dots, at most one per unit cell of the texture space
+#[derive(Debug)]
+pub struct DotsTexture {
+    pub mapping: TextureMapping2DEnum,
+    pub inside: Arc<SpectrumTextureEnum>,
+    pub outside: Arc<SpectrumTextureEnum>,
+}
+
+impl DotsTexture {
+    const RADIUS: f32 = 0.35;
+
+    pub fn is_inside(&self, surf_int: &SurfaceInteraction) -> bool {
+        let st = self.mapping.map(surf_int).st;
+        let s_cell = (st.x + 0.5).floor();
+        let t_cell = (st.y + 0.5).floor();
+        // Noise decides whether the cell has a dot and where its center is
+        if noise(point3!(s_cell + 0.5, t_cell + 0.5, 0.5)) <= 0. {
+            return false;
+        }
+        let max_shift = 0.5 - Self::RADIUS;
+        let center = point2!(
+            s_cell + max_shift * noise(point3!(s_cell + 1.5, t_cell + 2.8, 0.5)),
+            t_cell + max_shift * noise(point3!(s_cell + 4.5, t_cell + 9.8, 0.5))
+        );
+        (st - center).len_squared() < Self::RADIUS.powi(2)
+    }
+}
+
+impl SpectrumTexture for DotsTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction, lambda: &SampledWavelengths) -> SampledSpectrum {
+        if self.is_inside(surf_int) {
+            self.inside.evaluate(surf_int, lambda)
+        } else {
+            self.outside.evaluate(surf_int, lambda)
+        }
+    }
+}
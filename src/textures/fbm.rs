@@ -0,0 +1,38 @@
+use crate::{
+    core::SurfaceInteraction,
+    textures::{
+        mappings::{PointTransformMapping, TextureMapping3D},
+        noise::{fbm, turbulence},
+        FloatTexture,
+    },
+};
+
+/// Solid [fbm] noise, values are roughly in [-1; 1]
+#[derive(Debug)]
+pub struct FBmTexture {
+    pub mapping: PointTransformMapping,
+    pub omega: f32,
+    pub octaves: u32,
+}
+
+impl FloatTexture for FBmTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction) -> f32 {
+        let c = self.mapping.map(surf_int);
+        fbm(c.p, c.dp_dx, c.dp_dy, self.omega, self.octaves)
+    }
+}
+
+/// Solid [turbulence], non-negative
+#[derive(Debug)]
+pub struct WrinkledTexture {
+    pub mapping: PointTransformMapping,
+    pub omega: f32,
+    pub octaves: u32,
+}
+
+impl FloatTexture for WrinkledTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction) -> f32 {
+        let c = self.mapping.map(surf_int);
+        turbulence(c.p, c.dp_dx, c.dp_dy, self.omega, self.octaves)
+    }
+}
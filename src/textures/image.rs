@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use crate::{
+    core::SurfaceInteraction,
+    point2,
+    spectra::{
+        rgb::{sRGB, RGB},
+        RGBAlbedoSpectrum, RGBIlluminantSpectrum, RGBUnboundedSpectrum, Spectrum,
+    },
+    textures::{
+        mappings::{TextureMapping2D, TextureMapping2DEnum},
+        mipmap::MIPMap,
+        FloatTexture, SpectrumTexture,
+    },
+    SampledSpectrum, SampledWavelengths,
+};
+
+/// How texel colors are turned into spectra
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RGBSpectrumKind {
+    /// Reflectance, clamped to [0; 1]
+    Albedo,
+    Unbounded,
+    /// Emission relative to the color space illuminant
+    Illuminant,
+}
+
+/// sRGB image texture. `t` grows upwards, while image rows go from the top
+#[derive(Debug)]
+pub struct SpectrumImageTexture {
+    pub mapping: TextureMapping2DEnum,
+    pub mipmap: Arc<MIPMap>,
+    pub scale: f32,
+    /// Use `1 - value` instead of the texel value
+    pub invert: bool,
+    pub kind: RGBSpectrumKind,
+}
+
+impl SpectrumImageTexture {
+    pub fn rgb(&self, surf_int: &SurfaceInteraction) -> RGB {
+        let coords = self.mapping.map(surf_int);
+        let st = point2!(coords.st.x, 1. - coords.st.y);
+        let rgb = self.mipmap.filter(st, coords.dst_dx(), coords.dst_dy()) * self.scale;
+        let rgb = if self.invert { RGB::WHITE - rgb } else { rgb };
+        let [r, g, b] = <[f32; 3]>::from(rgb).map(|x| x.max(0.));
+        RGB::new(r, g, b)
+    }
+}
+
+impl SpectrumTexture for SpectrumImageTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction, lambda: &SampledWavelengths) -> SampledSpectrum {
+        let rgb = self.rgb(surf_int);
+        match self.kind {
+            RGBSpectrumKind::Albedo => {
+                let [r, g, b] = <[f32; 3]>::from(rgb).map(|x| x.min(1.));
+                RGBAlbedoSpectrum::new(&sRGB, RGB::new(r, g, b)).sample(lambda)
+            }
+            RGBSpectrumKind::Unbounded => RGBUnboundedSpectrum::new(&sRGB, rgb).sample(lambda),
+            RGBSpectrumKind::Illuminant => RGBIlluminantSpectrum::new(&sRGB, rgb).sample(lambda),
+        }
+    }
+}
+
+/// Which part of the texel color a [FloatImageTexture] reads
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ImageChannel {
+    Red,
+    Green,
+    Blue,
+    /// Mean of the three channels
+    #[default]
+    Average,
+}
+
+/// Single channel of an image texture, e.g. a bump or roughness map
+#[derive(Debug)]
+pub struct FloatImageTexture {
+    pub mapping: TextureMapping2DEnum,
+    pub mipmap: Arc<MIPMap>,
+    pub channel: ImageChannel,
+    pub scale: f32,
+    /// Use `1 - value` instead of the texel value
+    pub invert: bool,
+}
+
+impl FloatTexture for FloatImageTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction) -> f32 {
+        let coords = self.mapping.map(surf_int);
+        let st = point2!(coords.st.x, 1. - coords.st.y);
+        let [r, g, b] = self.mipmap.filter(st, coords.dst_dx(), coords.dst_dy()).into();
+        let value = self.scale
+            * match self.channel {
+                ImageChannel::Red => r,
+                ImageChannel::Green => g,
+                ImageChannel::Blue => b,
+                ImageChannel::Average => (r + g + b) / 3.,
+            };
+        if self.invert {
+            (1. - value).max(0.)
+        } else {
+            value
+        }
+    }
+}
@@ -1,47 +1,96 @@
-use std::{f32::consts::FRAC_1_PI, ops::Deref};
+use std::{
+    f32::consts::{FRAC_1_PI, PI},
+    ops::Deref,
+};
 
 use crate::{
     core::SurfaceInteraction,
     math::{
+        dot,
         utils::spherical_coordinates::{spherical_phi, spherical_theta},
         Normed, Transform, Transformable,
     },
-    point2, Point2f, Point3f,
+    point2, vec2, vec3, Point2f, Point3f, Vec2f, Vec3f,
 };
 
+#[enum_delegate::register]
 pub trait TextureMapping2D {
-    fn map(&self, surf_int: SurfaceInteraction) -> TextureCoords2D;
+    fn map(&self, surf_int: &SurfaceInteraction) -> TextureCoords2D;
+}
+
+#[enum_delegate::implement(TextureMapping2D)]
+#[derive(Debug)]
+pub enum TextureMapping2DEnum {
+    UV(UVMapping),
+    Spherical(SphericalMapping),
+    Cylindrical(CylindricalMapping),
+    Planar(PlanarMapping),
 }
 
 pub trait TextureMapping3D {
-    fn map(&self, surf_int: SurfaceInteraction) -> TextureCoords3D;
+    fn map(&self, surf_int: &SurfaceInteraction) -> TextureCoords3D;
 }
 
+/// Texture coordinates and their screen space derivatives used for filtering
+#[derive(Debug, Copy, Clone)]
 pub struct TextureCoords2D {
-    st: Point2f,
-    // ds_dx: f32,
-    // ds_dy: f32,
-    // dt_dx: f32,
-    // dt_dy: f32,
+    pub st: Point2f,
+    pub ds_dx: f32,
+    pub ds_dy: f32,
+    pub dt_dx: f32,
+    pub dt_dy: f32,
+}
+
+impl TextureCoords2D {
+    /// Derivatives come from the chain rule, `ds_dp` and `dt_dp` are gradients of `st` in texture space
+    fn from_gradients(st: Point2f, ds_dp: Vec3f, dt_dp: Vec3f, dp_dx: Vec3f, dp_dy: Vec3f) -> Self {
+        TextureCoords2D {
+            st,
+            ds_dx: dot(&ds_dp, &dp_dx),
+            ds_dy: dot(&ds_dp, &dp_dy),
+            dt_dx: dot(&dt_dp, &dp_dx),
+            dt_dy: dot(&dt_dp, &dp_dy),
+        }
+    }
+
+    pub fn dst_dx(&self) -> Vec2f { vec2!(self.ds_dx, self.dt_dx) }
+
+    pub fn dst_dy(&self) -> Vec2f { vec2!(self.ds_dy, self.dt_dy) }
 }
 
+/// Texture space point and its screen space derivatives
+#[derive(Debug, Copy, Clone)]
 pub struct TextureCoords3D {
-    st: Point3f,
+    pub p: Point3f,
+    pub dp_dx: Vec3f,
+    pub dp_dy: Vec3f,
 }
 
+#[derive(Debug, Copy, Clone)]
 pub struct UVMapping {
     /// Scale u
-    su: f32,
+    pub su: f32,
     /// Scale v
-    sv: f32,
+    pub sv: f32,
     /// Offset u
-    du: f32,
+    pub du: f32,
     /// Offset v
-    dv: f32,
+    pub dv: f32,
+}
+
+impl Default for UVMapping {
+    fn default() -> Self {
+        UVMapping {
+            su: 1.,
+            sv: 1.,
+            du: 0.,
+            dv: 0.,
+        }
+    }
 }
 
 impl TextureMapping2D for UVMapping {
-    fn map(&self, surf_int: SurfaceInteraction) -> TextureCoords2D {
+    fn map(&self, surf_int: &SurfaceInteraction) -> TextureCoords2D {
         let ds_dx = self.su * surf_int.du_dx;
         let ds_dy = self.su * surf_int.du_dy;
         let dt_dx = self.sv * surf_int.dv_dx;
@@ -51,19 +100,168 @@ impl TextureMapping2D for UVMapping {
             self.su * surf_int.hit.uv.x + self.du,
             self.sv * surf_int.hit.uv.y + self.dv
         );
-        TextureCoords2D { st } //, ds_dx, ds_dy, dt_dx, dt_dy };
+        TextureCoords2D {
+            st,
+            ds_dx,
+            ds_dy,
+            dt_dx,
+            dt_dy,
+        }
+    }
+}
+
+/// Texture space point and its screen space derivatives, shared by the mappings defined in texture space
+fn texture_space(render_to_texture: &Transform<f32>, surf_int: &SurfaceInteraction) -> TextureCoords3D {
+    TextureCoords3D {
+        p: surf_int.hit.point.transform(render_to_texture),
+        dp_dx: surf_int.dp_dx.transform(render_to_texture),
+        dp_dy: surf_int.dp_dy.transform(render_to_texture),
     }
 }
 
+/// `s` is the azimuth around the texture space z axis and `t` is the polar angle from it, both in [0; 1]
+#[derive(Debug, Copy, Clone)]
 pub struct SphericalMapping {
     render_to_texture: Transform<f32>,
 }
 
+impl SphericalMapping {
+    pub fn new(texture_to_render: Transform<f32>) -> Self {
+        SphericalMapping {
+            render_to_texture: texture_to_render.invert(),
+        }
+    }
+}
+
 impl TextureMapping2D for SphericalMapping {
-    fn map(&self, surf_int: SurfaceInteraction) -> TextureCoords2D {
-        let texture_point = surf_int.hit.point.transform(&self.render_to_texture);
-        let vec = texture_point.deref().to_unit();
-        let st = point2!(spherical_theta(*vec) * FRAC_1_PI, spherical_phi(*vec) * FRAC_1_PI / 2.);
-        TextureCoords2D { st }
+    fn map(&self, surf_int: &SurfaceInteraction) -> TextureCoords2D {
+        let TextureCoords3D { p, dp_dx, dp_dy } = texture_space(&self.render_to_texture, surf_int);
+        let x2y2 = p.x.powi(2) + p.y.powi(2);
+        let sqrt_x2y2 = x2y2.sqrt();
+        let ds_dp = vec3!(-p.y, p.x, 0.) / (2. * PI * x2y2);
+        let dt_dp = vec3!(p.x * p.z / sqrt_x2y2, p.y * p.z / sqrt_x2y2, -sqrt_x2y2) / (PI * (x2y2 + p.z.powi(2)));
+
+        let vec = p.deref().to_unit();
+        let st = point2!(spherical_phi(*vec) / (2. * PI), spherical_theta(*vec) * FRAC_1_PI);
+        TextureCoords2D::from_gradients(st, ds_dp, dt_dp, dp_dx, dp_dy)
+    }
+}
+
+/// `s` is the azimuth around the texture space z axis in [0; 1] and `t` is the height along it
+#[derive(Debug, Copy, Clone)]
+pub struct CylindricalMapping {
+    render_to_texture: Transform<f32>,
+}
+
+impl CylindricalMapping {
+    pub fn new(texture_to_render: Transform<f32>) -> Self {
+        CylindricalMapping {
+            render_to_texture: texture_to_render.invert(),
+        }
+    }
+}
+
+impl TextureMapping2D for CylindricalMapping {
+    fn map(&self, surf_int: &SurfaceInteraction) -> TextureCoords2D {
+        let TextureCoords3D { p, dp_dx, dp_dy } = texture_space(&self.render_to_texture, surf_int);
+        let x2y2 = p.x.powi(2) + p.y.powi(2);
+        let ds_dp = vec3!(-p.y, p.x, 0.) / (2. * PI * x2y2);
+        let dt_dp = vec3!(0., 0., 1.);
+
+        let st = point2!((PI + f32::atan2(p.y, p.x)) / (2. * PI), p.z);
+        TextureCoords2D::from_gradients(st, ds_dp, dt_dp, dp_dx, dp_dy)
+    }
+}
+
+/// Projection of the texture space point onto two vectors
+#[derive(Debug, Copy, Clone)]
+pub struct PlanarMapping {
+    render_to_texture: Transform<f32>,
+    vs: Vec3f,
+    vt: Vec3f,
+    /// Offset s
+    ds: f32,
+    /// Offset t
+    dt: f32,
+}
+
+impl PlanarMapping {
+    pub fn new(texture_to_render: Transform<f32>, vs: Vec3f, vt: Vec3f, ds: f32, dt: f32) -> Self {
+        PlanarMapping {
+            render_to_texture: texture_to_render.invert(),
+            vs,
+            vt,
+            ds,
+            dt,
+        }
+    }
+}
+
+impl TextureMapping2D for PlanarMapping {
+    fn map(&self, surf_int: &SurfaceInteraction) -> TextureCoords2D {
+        let TextureCoords3D { p, dp_dx, dp_dy } = texture_space(&self.render_to_texture, surf_int);
+        let st = point2!(self.ds + dot(&self.vs, &p.coords), self.dt + dot(&self.vt, &p.coords));
+        TextureCoords2D::from_gradients(st, self.vs, self.vt, dp_dx, dp_dy)
+    }
+}
+
+/// Texture space is the render space moved by a transform, used by solid textures
+#[derive(Debug, Copy, Clone)]
+pub struct PointTransformMapping {
+    render_to_texture: Transform<f32>,
+}
+
+impl PointTransformMapping {
+    pub fn new(texture_to_render: Transform<f32>) -> Self {
+        PointTransformMapping {
+            render_to_texture: texture_to_render.invert(),
+        }
+    }
+}
+
+impl TextureMapping3D for PointTransformMapping {
+    fn map(&self, surf_int: &SurfaceInteraction) -> TextureCoords3D { texture_space(&self.render_to_texture, surf_int) }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{core::Interaction, point3};
+
+    fn interaction(point: Point3f, dp_dx: Vec3f, dp_dy: Vec3f) -> SurfaceInteraction {
+        let mut surf_int = SurfaceInteraction::new(
+            Interaction {
+                point,
+                ..Default::default()
+            },
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        surf_int.dp_dx = dp_dx;
+        surf_int.dp_dy = dp_dy;
+        surf_int
+    }
+
+    #[test]
+    fn test_derivatives() {
+        // Analytic derivatives match finite differences
+        let mappings: [TextureMapping2DEnum; 3] = [
+            SphericalMapping::new(Transform::translate(vec3!(0.1, 0.2, 0.3))).into(),
+            CylindricalMapping::new(Transform::id()).into(),
+            PlanarMapping::new(Transform::id(), vec3!(1., 0.5, 0.), vec3!(0., 0., 2.), 0.1, 0.).into(),
+        ];
+        let point = point3!(0.6, -0.4, 0.5);
+        let (dp_dx, dp_dy) = (vec3!(1e-3, 0., 2e-3), vec3!(0., -1e-3, 1e-3));
+        for mapping in mappings {
+            let coords = mapping.map(&interaction(point, dp_dx, dp_dy));
+            let moved_x = mapping.map(&interaction(point + dp_dx, dp_dx, dp_dy));
+            let moved_y = mapping.map(&interaction(point + dp_dy, dp_dx, dp_dy));
+            assert_abs_diff_eq!(moved_x.st - coords.st, coords.dst_dx(), epsilon = 2e-5);
+            assert_abs_diff_eq!(moved_y.st - coords.st, coords.dst_dy(), epsilon = 2e-5);
+        }
     }
 }
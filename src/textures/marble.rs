@@ -0,0 +1,62 @@
+use crate::{
+    core::SurfaceInteraction,
+    spectra::{
+        rgb::{sRGB, RGB},
+        RGBAlbedoSpectrum, Spectrum,
+    },
+    textures::{
+        mappings::{PointTransformMapping, TextureMapping3D},
+        noise::fbm,
+        SpectrumTexture,
+    },
+    SampledSpectrum, SampledWavelengths,
+};
+
+/// Bands along the texture space y axis distorted with [fbm], colored with a fixed palette
+#[derive(Debug)]
+pub struct MarbleTexture {
+    pub mapping: PointTransformMapping,
+    /// Frequency of the bands
+    pub scale: f32,
+    /// Strength of the distortion
+    pub variation: f32,
+    pub omega: f32,
+    pub octaves: u32,
+}
+
+impl MarbleTexture {
+    #[rustfmt::skip]
+    const COLORS: [[f32; 3]; 9] = [
+        [0.58, 0.58, 0.6], [0.58, 0.58, 0.6], [0.58, 0.58, 0.6],
+        [0.5, 0.5, 0.5], [0.6, 0.59, 0.58], [0.58, 0.58, 0.6],
+        [0.58, 0.58, 0.6], [0.2, 0.2, 0.33], [0.58, 0.58, 0.6],
+    ];
+
+    pub fn rgb(&self, surf_int: &SurfaceInteraction) -> RGB {
+        let c = self.mapping.map(surf_int);
+        let p = c.p * self.scale;
+        let marble =
+            p.y + self.variation * fbm(p, c.dp_dx * self.scale, c.dp_dy * self.scale, self.omega, self.octaves);
+        let t = 0.5 + 0.5 * marble.sin();
+
+        // Cubic Bezier segment of the palette evaluated with de Casteljau's algorithm
+        let n_segments = Self::COLORS.len() - 3;
+        let first = ((t * n_segments as f32).floor() as usize).min(n_segments - 1);
+        let t = t * n_segments as f32 - first as f32;
+        let mut points = Self::COLORS[first..first + 4].to_vec();
+        while points.len() > 1 {
+            points = points
+                .windows(2)
+                .map(|x| [0, 1, 2].map(|i| (1. - t) * x[0][i] + t * x[1][i]))
+                .collect();
+        }
+        let [r, g, b] = points[0].map(|x| (1.5 * x).clamp(0., 1.));
+        RGB::new(r, g, b)
+    }
+}
+
+impl SpectrumTexture for MarbleTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction, lambda: &SampledWavelengths) -> SampledSpectrum {
+        RGBAlbedoSpectrum::new(&sRGB, self.rgb(surf_int)).sample(lambda)
+    }
+}
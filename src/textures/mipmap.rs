@@ -0,0 +1,184 @@
+use image::{Rgb, Rgb32FImage};
+
+use crate::{spectra::rgb::RGB, Point2f, Vec2f};
+
+/// How lookups outside of [0; 1] are handled
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    Clamp,
+    Black,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum FilterFunction {
+    /// Nearest texel of the closest level
+    Point,
+    /// Bilinear interpolation on the closest level
+    Bilinear,
+    /// Bilinear interpolation between two closest levels
+    #[default]
+    Trilinear,
+}
+
+/// Image pyramid, level 0 is the original image and each next level halves the resolution
+#[derive(Debug)]
+pub struct MIPMap {
+    pyramid: Vec<Rgb32FImage>,
+    wrap: WrapMode,
+    filter: FilterFunction,
+}
+
+impl MIPMap {
+    pub fn new(image: Rgb32FImage, wrap: WrapMode, filter: FilterFunction) -> Self {
+        let mut pyramid = vec![image];
+        while let Some(level) = pyramid.last()
+            && level.width().max(level.height()) > 1
+        {
+            pyramid.push(downsample(level));
+        }
+        MIPMap { pyramid, wrap, filter }
+    }
+
+    pub fn levels(&self) -> usize { self.pyramid.len() }
+
+    pub fn resolution(&self, level: usize) -> (u32, u32) { self.pyramid[level].dimensions() }
+
+    /// Filtered lookup of a footprint given by the derivatives of `st` along the screen x and y
+    pub fn filter(&self, st: Point2f, dst_dx: Vec2f, dst_dy: Vec2f) -> RGB {
+        // Level whose texel spacing matches the widest extent of the footprint
+        let width = 2.
+            * dst_dx
+                .x
+                .abs()
+                .max(dst_dx.y.abs())
+                .max(dst_dy.x.abs())
+                .max(dst_dy.y.abs());
+        let n_levels = self.levels();
+        let level = (n_levels - 1) as f32 + width.max(1e-8).log2();
+        if level >= (n_levels - 1) as f32 {
+            return self.texel(n_levels - 1, 0, 0);
+        }
+        let i_level = level.floor().max(0.) as usize;
+
+        match self.filter {
+            FilterFunction::Point => {
+                let (width, height) = self.resolution(i_level);
+                let x = (st.x * width as f32 - 0.5).round() as i32;
+                let y = (st.y * height as f32 - 0.5).round() as i32;
+                self.texel(i_level, x, y)
+            }
+            FilterFunction::Bilinear => self.bilerp(i_level, st),
+            FilterFunction::Trilinear if level <= 0. => self.bilerp(0, st),
+            FilterFunction::Trilinear => {
+                let t = level - i_level as f32;
+                self.bilerp(i_level, st) * (1. - t) + self.bilerp(i_level + 1, st) * t
+            }
+        }
+    }
+
+    /// Bilinear interpolation of the 4 texels around `st` with texel centers at half-integer coordinates
+    pub fn bilerp(&self, level: usize, st: Point2f) -> RGB {
+        let (width, height) = self.resolution(level);
+        let x = st.x * width as f32 - 0.5;
+        let y = st.y * height as f32 - 0.5;
+        let (xi, yi) = (x.floor(), y.floor());
+        let (dx, dy) = (x - xi, y - yi);
+        let (xi, yi) = (xi as i32, yi as i32);
+        self.texel(level, xi, yi) * ((1. - dx) * (1. - dy))
+            + self.texel(level, xi + 1, yi) * (dx * (1. - dy))
+            + self.texel(level, xi, yi + 1) * ((1. - dx) * dy)
+            + self.texel(level, xi + 1, yi + 1) * (dx * dy)
+    }
+
+    pub fn texel(&self, level: usize, x: i32, y: i32) -> RGB {
+        let image = &self.pyramid[level];
+        let (width, height) = (image.width() as i32, image.height() as i32);
+        let (x, y) = match self.wrap {
+            WrapMode::Repeat => (x.rem_euclid(width), y.rem_euclid(height)),
+            WrapMode::Clamp => (x.clamp(0, width - 1), y.clamp(0, height - 1)),
+            WrapMode::Black if !(0..width).contains(&x) || !(0..height).contains(&y) => return RGB::BLACK,
+            WrapMode::Black => (x, y),
+        };
+        let [r, g, b] = image.get_pixel(x as u32, y as u32).0;
+        RGB::new(r, g, b)
+    }
+}
+
+/// Halves the resolution with a box filter, the last row or column of odd-sized images is folded into the previous one
+fn downsample(image: &Rgb32FImage) -> Rgb32FImage {
+    let (width, height) = image.dimensions();
+    let (new_width, new_height) = ((width / 2).max(1), (height / 2).max(1));
+    Rgb32FImage::from_fn(new_width, new_height, |x, y| {
+        let xs = 2 * x..(2 * x + 2 + u32::from(x == new_width - 1 && width % 2 == 1)).min(width);
+        let ys = 2 * y..(2 * y + 2 + u32::from(y == new_height - 1 && height % 2 == 1)).min(height);
+        let count = (xs.len() * ys.len()) as f32;
+        let mut sum = [0.; 3];
+        for y in ys {
+            for x in xs.clone() {
+                let pixel = image.get_pixel(x, y).0;
+                (0..3).for_each(|i| sum[i] += pixel[i]);
+            }
+        }
+        Rgb(sum.map(|x| x / count))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{point2, vec2};
+
+    fn checker(size: u32) -> Rgb32FImage { Rgb32FImage::from_fn(size, size, |x, y| Rgb([((x + y) % 2) as f32; 3])) }
+
+    #[test]
+    fn test_pyramid() {
+        let mipmap = MIPMap::new(checker(8), WrapMode::Repeat, FilterFunction::Trilinear);
+        assert_eq!(mipmap.levels(), 4);
+        assert_eq!(mipmap.resolution(3), (1, 1));
+        // Box filtering preserves the average
+        assert_abs_diff_eq!(mipmap.texel(1, 0, 0), RGB::new(0.5, 0.5, 0.5));
+        assert_abs_diff_eq!(mipmap.texel(3, 0, 0), RGB::new(0.5, 0.5, 0.5));
+
+        let odd = MIPMap::new(
+            Rgb32FImage::from_pixel(5, 3, Rgb([1.; 3])),
+            WrapMode::Clamp,
+            FilterFunction::Point,
+        );
+        assert_eq!(odd.resolution(1), (2, 1));
+        assert_abs_diff_eq!(odd.texel(odd.levels() - 1, 0, 0), RGB::WHITE);
+    }
+
+    #[test]
+    fn test_filter() {
+        let mipmap = MIPMap::new(checker(8), WrapMode::Repeat, FilterFunction::Trilinear);
+        // Texel-sized footprint reads the original image
+        let texel = 1. / 8.;
+        let st = point2!(1.5 * texel, 0.5 * texel);
+        assert_abs_diff_eq!(
+            mipmap.filter(st, vec2!(texel / 2., 0.), vec2!(0., texel / 2.)),
+            RGB::WHITE
+        );
+        // Footprint wider than a checker cell averages it out
+        let filtered = mipmap.filter(st, vec2!(2. * texel, 0.), vec2!(0., 2. * texel));
+        assert_abs_diff_eq!(filtered, RGB::new(0.5, 0.5, 0.5), epsilon = 1e-6);
+        // Whole texture
+        assert_abs_diff_eq!(mipmap.filter(st, vec2!(1., 0.), vec2!(0., 1.)), RGB::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_wrap() {
+        let image = Rgb32FImage::from_fn(2, 1, |x, _| Rgb([x as f32; 3]));
+        let repeat = MIPMap::new(image.clone(), WrapMode::Repeat, FilterFunction::Point);
+        assert_abs_diff_eq!(repeat.texel(0, -1, 0), RGB::WHITE);
+        assert_abs_diff_eq!(repeat.texel(0, 2, 3), RGB::BLACK);
+        let clamp = MIPMap::new(image.clone(), WrapMode::Clamp, FilterFunction::Point);
+        assert_abs_diff_eq!(clamp.texel(0, 5, 0), RGB::WHITE);
+        let black = MIPMap::new(image, WrapMode::Black, FilterFunction::Point);
+        assert_abs_diff_eq!(black.texel(0, 1, 0), RGB::WHITE);
+        assert_abs_diff_eq!(black.texel(0, 2, 0), RGB::BLACK);
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use crate::{
+    core::SurfaceInteraction,
+    math::utils::lerp,
+    textures::{FloatTexture, FloatTextureEnum},
+};
+
+/// Product of two textures
+#[derive(Debug)]
+pub struct FloatScaledTexture {
+    pub tex: Arc<FloatTextureEnum>,
+    pub scale: Arc<FloatTextureEnum>,
+}
+
+impl FloatTexture for FloatScaledTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction) -> f32 {
+        let scale = self.scale.evaluate(surf_int);
+        if scale == 0. {
+            return 0.;
+        }
+        scale * self.tex.evaluate(surf_int)
+    }
+}
+
+/// Linear interpolation between two textures
+#[derive(Debug)]
+pub struct FloatMixTexture {
+    pub tex: [Arc<FloatTextureEnum>; 2],
+    /// Weight of the second texture
+    pub amount: Arc<FloatTextureEnum>,
+}
+
+impl FloatTexture for FloatMixTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction) -> f32 {
+        let amount = self.amount.evaluate(surf_int);
+        let t0 = if amount != 1. {
+            self.tex[0].evaluate(surf_int)
+        } else {
+            0.
+        };
+        let t1 = if amount != 0. {
+            self.tex[1].evaluate(surf_int)
+        } else {
+            0.
+        };
+        lerp(t0, t1, amount)
+    }
+}
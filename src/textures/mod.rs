@@ -1,12 +1,31 @@
 use std::fmt::Debug;
 
 use crate::{
-    core::SurfaceInteraction, textures::constant::ConstantSpectrumTexture, SampledSpectrum, SampledWavelengths,
+    core::SurfaceInteraction,
+    textures::{
+        checkerboard::{CheckerboardTexture, FloatCheckerboardTexture},
+        constant::{ConstantFloatTexture, ConstantSpectrumTexture},
+        dots::DotsTexture,
+        fbm::{FBmTexture, WrinkledTexture},
+        image::{FloatImageTexture, SpectrumImageTexture},
+        marble::MarbleTexture,
+        mix::{FloatMixTexture, FloatScaledTexture},
+        wood::WoodTexture,
+    },
+    SampledSpectrum, SampledWavelengths,
 };
 
-// pub mod checkerboard;
+pub mod checkerboard;
 pub mod constant;
+pub mod dots;
+pub mod fbm;
+pub mod image;
 pub mod mappings;
+pub mod marble;
+pub mod mipmap;
+pub mod mix;
+pub mod noise;
+pub mod wood;
 
 #[enum_delegate::register]
 pub trait SpectrumTexture {
@@ -17,4 +36,26 @@ pub trait SpectrumTexture {
 #[derive(Debug)]
 pub enum SpectrumTextureEnum {
     Constant(ConstantSpectrumTexture),
+    Image(SpectrumImageTexture),
+    Checkerboard(CheckerboardTexture),
+    Dots(DotsTexture),
+    Marble(MarbleTexture),
+    Wood(WoodTexture),
+}
+
+#[enum_delegate::register]
+pub trait FloatTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction) -> f32;
+}
+
+#[enum_delegate::implement(FloatTexture)]
+#[derive(Debug)]
+pub enum FloatTextureEnum {
+    Constant(ConstantFloatTexture),
+    Image(FloatImageTexture),
+    Checkerboard(FloatCheckerboardTexture),
+    FBm(FBmTexture),
+    Wrinkled(WrinkledTexture),
+    Scaled(FloatScaledTexture),
+    Mix(FloatMixTexture),
 }
@@ -0,0 +1,147 @@
+use crate::{
+    math::{utils::smooth_step, Normed},
+    Point3f, Vec3f,
+};
+
+/// Ken Perlin's permutation of [0; 255], lookups wrap around it
+#[rustfmt::skip]
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225,
+    140, 36, 103, 30, 69, 142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148,
+    247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219, 203, 117, 35, 11, 32,
+    57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122,
+    60, 211, 133, 230, 220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54,
+    65, 25, 63, 161, 1, 216, 80, 73, 209, 76, 132, 187, 208, 89, 18, 169,
+    200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173, 186, 3, 64,
+    52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212,
+    207, 206, 59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213,
+    119, 248, 152, 2, 44, 154, 163, 70, 221, 153, 101, 155, 167, 43, 172, 9,
+    129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232, 178, 185, 112, 104,
+    218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162, 241,
+    81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157,
+    184, 84, 204, 176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93,
+    222, 114, 67, 29, 24, 72, 243, 141, 128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn perm(i: usize) -> usize { PERMUTATION[i & 255] as usize }
+
+/// Improved Perlin noise in [-1; 1], zero at integer lattice points
+pub fn noise(p: Point3f) -> f32 {
+    let (ix, iy, iz) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (dx, dy, dz) = (p.x - ix, p.y - iy, p.z - iz);
+    // The noise is periodic with the permutation size
+    let wrap = |i: f32| (i as i64 & 255) as usize;
+    let (ix, iy, iz) = (wrap(ix), wrap(iy), wrap(iz));
+
+    // Gradient weights at the corners of the cell
+    let grad = |x: usize, y: usize, z: usize, dx: f32, dy: f32, dz: f32| {
+        let h = perm(perm(perm(ix + x) + iy + y) + iz + z) & 15;
+        let u = if h < 8 || h == 12 || h == 13 { dx } else { dy };
+        let v = if h < 4 || h == 12 || h == 13 { dy } else { dz };
+        (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+    };
+    let w000 = grad(0, 0, 0, dx, dy, dz);
+    let w100 = grad(1, 0, 0, dx - 1., dy, dz);
+    let w010 = grad(0, 1, 0, dx, dy - 1., dz);
+    let w110 = grad(1, 1, 0, dx - 1., dy - 1., dz);
+    let w001 = grad(0, 0, 1, dx, dy, dz - 1.);
+    let w101 = grad(1, 0, 1, dx - 1., dy, dz - 1.);
+    let w011 = grad(0, 1, 1, dx, dy - 1., dz - 1.);
+    let w111 = grad(1, 1, 1, dx - 1., dy - 1., dz - 1.);
+
+    // Trilinear interpolation with a smooth falloff
+    let weight = |t: f32| t * t * t * (t * (t * 6. - 15.) + 10.);
+    let lerp = |t: f32, a: f32, b: f32| a + t * (b - a);
+    let (wx, wy, wz) = (weight(dx), weight(dy), weight(dz));
+    let x00 = lerp(wx, w000, w100);
+    let x10 = lerp(wx, w010, w110);
+    let x01 = lerp(wx, w001, w101);
+    let x11 = lerp(wx, w011, w111);
+    let y0 = lerp(wy, x00, x10);
+    let y1 = lerp(wy, x01, x11);
+    lerp(wz, y0, y1)
+}
+
+/// Number of octaves that are not smaller than the pixel footprint
+fn octaves(dp_dx: Vec3f, dp_dy: Vec3f, max_octaves: u32) -> f32 {
+    let len2 = dp_dx.len_squared().max(dp_dy.len_squared());
+    (-1. - len2.log2() / 2.).clamp(0., max_octaves as f32)
+}
+
+/// Fractional Brownian motion, sum of noise octaves with frequencies doubling and amplitudes scaled by `omega`.
+/// Octaves finer than the pixel footprint given by `dp_dx` and `dp_dy` are skipped to avoid aliasing
+pub fn fbm(p: Point3f, dp_dx: Vec3f, dp_dy: Vec3f, omega: f32, max_octaves: u32) -> f32 {
+    let n = octaves(dp_dx, dp_dy, max_octaves);
+    let n_int = n.floor() as u32;
+
+    let (mut sum, mut lambda, mut o) = (0., 1., 1.);
+    for _ in 0..n_int {
+        sum += o * noise(p * lambda);
+        lambda *= 1.99;
+        o *= omega;
+    }
+    // Fade in the partial octave
+    sum + o * smooth_step(n - n_int as f32, 0.3, 0.7) * noise(p * lambda)
+}
+
+/// Same as [fbm], but sums absolute values of noise. Filtered out octaves are replaced by their average
+pub fn turbulence(p: Point3f, dp_dx: Vec3f, dp_dy: Vec3f, omega: f32, max_octaves: u32) -> f32 {
+    let n = octaves(dp_dx, dp_dy, max_octaves);
+    let n_int = n.floor() as u32;
+
+    let (mut sum, mut lambda, mut o) = (0., 1., 1.);
+    for _ in 0..n_int {
+        sum += o * noise(p * lambda).abs();
+        lambda *= 1.99;
+        o *= omega;
+    }
+    let partial = smooth_step(n - n_int as f32, 0.3, 0.7);
+    sum += o * (0.2 + partial * (noise(p * lambda).abs() - 0.2));
+    for _ in n_int + 1..max_octaves {
+        o *= omega;
+        sum += o * 0.2;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{point3, vec3};
+
+    #[test]
+    fn test_noise() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut sum = 0.;
+        for _ in 0..10_000 {
+            let p = point3!(
+                rng.gen_range(-50. ..50.),
+                rng.gen_range(-50. ..50.),
+                rng.gen_range(-50. ..50.)
+            );
+            let value = noise(p);
+            assert!(value.abs() <= 1.);
+            // Continuous
+            assert_abs_diff_eq!(value, noise(p + vec3!(1e-4, -1e-4, 1e-4)), epsilon = 1e-2);
+            sum += value;
+        }
+        assert_abs_diff_eq!(sum / 10_000., 0., epsilon = 0.02);
+        assert_eq!(noise(point3!(3., -7., 12.)), 0.);
+    }
+
+    #[test]
+    fn test_octaves() {
+        let p = point3!(0.3, 1.7, -2.2);
+        let none = vec3!(0., 0., 0.);
+        // A single octave is plain noise
+        assert_abs_diff_eq!(fbm(p, none, none, 0.5, 1), noise(p));
+        // Footprints larger than the noise period filter it out completely
+        let wide = vec3!(2., 0., 0.);
+        assert_eq!(fbm(p, wide, none, 0.5, 8), 0.);
+        assert_abs_diff_eq!(turbulence(p, wide, none, 0.5, 2), 0.2 * 1.5);
+    }
+}
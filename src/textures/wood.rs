@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use crate::{
+    core::SurfaceInteraction,
+    math::{utils::smooth_step, Normed},
+    textures::{
+        mappings::{PointTransformMapping, TextureMapping3D},
+        noise::fbm,
+        SpectrumTexture, SpectrumTextureEnum,
+    },
+    SampledSpectrum, SampledWavelengths,
+};
+
+/// Growth rings around the texture space z axis, distorted with [fbm]
+#[derive(Debug)]
+pub struct WoodTexture {
+    pub mapping: PointTransformMapping,
+    pub light: Arc<SpectrumTextureEnum>,
+    pub dark: Arc<SpectrumTextureEnum>,
+    /// Rings per unit of distance from the axis
+    pub rings: f32,
+    /// Strength of the distortion, in rings
+    pub variation: f32,
+    pub omega: f32,
+    pub octaves: u32,
+}
+
+impl WoodTexture {
+    /// Average of the ring profile
+    const AVERAGE: f32 = (1. - Self::DARK_END) + (Self::DARK_END - Self::DARK_START) / 2.;
+    const DARK_END: f32 = 0.9;
+    /// Each ring fades from light to dark and sharply returns to light
+    const DARK_START: f32 = 0.5;
+
+    /// Weight of the dark texture
+    pub fn darkness(&self, surf_int: &SurfaceInteraction) -> f32 {
+        let c = self.mapping.map(surf_int);
+        let distance = (c.p.x.powi(2) + c.p.y.powi(2)).sqrt();
+        let rings = distance * self.rings + self.variation * fbm(c.p, c.dp_dx, c.dp_dy, self.omega, self.octaves);
+        let profile = smooth_step(rings - rings.floor(), Self::DARK_START, Self::DARK_END);
+
+        // Rings narrower than the pixel footprint blend into their average
+        let footprint = self.rings * c.dp_dx.len().max(c.dp_dy.len());
+        let blur = smooth_step(footprint, 0.25, 1.);
+        profile + blur * (Self::AVERAGE - profile)
+    }
+}
+
+impl SpectrumTexture for WoodTexture {
+    fn evaluate(&self, surf_int: &SurfaceInteraction, lambda: &SampledWavelengths) -> SampledSpectrum {
+        let darkness = self.darkness(surf_int);
+        self.light.evaluate(surf_int, lambda) * (1. - darkness) + self.dark.evaluate(surf_int, lambda) * darkness
+    }
+}
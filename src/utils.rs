@@ -1,11 +1,52 @@
-use std::time::Instant;
+use std::{path::Path, time::Instant};
 
-use image::{Pixel, Rgb};
+use image::{DynamicImage, ImageResult, Pixel, Rgb, Rgb32FImage};
 
 pub fn lerp(a: Rgb<f32>, b: Rgb<f32>, t: f32) -> Rgb<f32> { a.map2(&b, |a, b| (1. - t) * a + t * b) }
 
 pub(crate) fn linear_to_gamma(linear: Rgb<f32>) -> Rgb<f32> { linear.map(|x| if x > 0. { x.sqrt() } else { x }) }
 
+/// Inverse of the sRGB transfer function
+pub(crate) fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Reads an image with linear RGB values. Floating point images (EXR, HDR, PFM) are assumed to be linear already,
+/// integer ones are assumed to be sRGB-encoded
+pub fn read_linear_rgb(path: &Path) -> ImageResult<Rgb32FImage> {
+    let image = image::open(path)?;
+    let is_float = matches!(image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_));
+    let mut image = image.into_rgb32f();
+    if !is_float {
+        image.pixels_mut().for_each(|pixel| pixel.apply(srgb_to_linear));
+    }
+    Ok(image)
+}
+
+/// Reads an image as is, without decoding the transfer function. Used for data such as normal maps
+pub fn read_raw_rgb(path: &Path) -> ImageResult<Rgb32FImage> { Ok(image::open(path)?.into_rgb32f()) }
+
+/// Reads the alpha channel replicated into RGB, e.g. for cutout masks. Images without alpha are averaged instead,
+/// without decoding the transfer function
+pub fn read_alpha(path: &Path) -> ImageResult<Rgb32FImage> {
+    let image = image::open(path)?;
+    if !image.color().has_alpha() {
+        let image = image.into_rgb32f();
+        return Ok(Rgb32FImage::from_fn(image.width(), image.height(), |x, y| {
+            let [r, g, b] = image.get_pixel(x, y).0;
+            Rgb([(r + g + b) / 3.; 3])
+        }));
+    }
+    let image = image.into_rgba32f();
+    Ok(Rgb32FImage::from_fn(image.width(), image.height(), |x, y| {
+        Rgb([image.get_pixel(x, y).0[3]; 3])
+    }))
+}
+
 pub fn time_it<F, Out>(f: F) -> (Out, f32)
 where F: FnOnce() -> Out {
     let start = Instant::now();